@@ -44,6 +44,14 @@ pub fn free_pmem_regions() -> Vec<Range<PhysAddr>> {
     FREE_PMEM_REGIONS.clone()
 }
 
+/// The base ISA has no instruction to write back data cache lines, which are
+/// coherent on the supported platforms, so only the prior memory accesses are ordered.
 pub fn frame_flush(_target: crate::PhysAddr) {
-    unimplemented!()
+    unsafe { core::arch::asm!("fence rw, rw") };
+}
+
+/// Execute `FENCE.I` on the current hart, then on all other harts.
+pub fn icache_flush_all() {
+    unsafe { core::arch::asm!("fence.i") };
+    super::sbi::remote_fence_i(0);
 }
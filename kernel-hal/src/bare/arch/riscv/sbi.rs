@@ -74,6 +74,13 @@ pub fn send_ipi(sipi_value: usize) -> usize {
     sbi_call(SBI_SEND_IPI, 0, sipi_value, 0, 0)
 }
 
+/// Instructs remote harts to execute `FENCE.I`.
+///
+/// `hart_mask` is the address of a mask of harts, all harts if it is 0.
+pub fn remote_fence_i(hart_mask: usize) -> usize {
+    sbi_call(SBI_REMOTE_FENCE_I, 0, hart_mask, 0, 0)
+}
+
 pub fn shutdown() -> ! {
     sbi_call(SBI_SHUTDOWN, 0, 0, 0, 0);
     unreachable!();
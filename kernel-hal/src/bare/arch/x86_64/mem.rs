@@ -37,3 +37,6 @@ pub fn frame_flush(target: PhysAddr) {
         _mm_mfence();
     }
 }
+
+/// The instruction cache is coherent with stores on x86, nothing to do.
+pub fn icache_flush_all() {}
@@ -45,5 +45,9 @@ hal_fn_impl! {
         fn frame_flush(target: PhysAddr) {
             super::arch::mem::frame_flush(target)
         }
+
+        fn icache_flush_all() {
+            super::arch::mem::icache_flush_all()
+        }
     }
 }
@@ -10,6 +10,8 @@ cfg_if! {
     }
 }
 
+mod sched;
+
 pub mod boot;
 pub mod mem;
 pub mod net;
@@ -0,0 +1,251 @@
+//! Scheduler of the kernel threads (futures), with per-CPU run queues.
+//!
+//! - Deadline threads run before the fair ones, the earliest deadline first,
+//!   until they have run for their capacity in the current period.
+//! - Fair threads run by priority, in FIFO order for the same priority.
+//! - A thread is queued on a CPU in its affinity mask, preferring the CPU it
+//!   ran on last time. A CPU with an empty queue steals from the others.
+//!
+//! Threads are never preempted, so the parameters are applied when a thread is queued.
+
+use alloc::{boxed::Box, collections::BTreeMap, sync::Arc, task::Wake, vec::Vec};
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use core::task::{Context, Waker};
+use core::time::Duration;
+
+use spin::Mutex;
+
+use crate::thread::{SchedClass, SchedHandle, HIGHEST_PRIORITY};
+use crate::{cpu::cpu_id, timer::timer_now};
+
+/// The maximum number of CPUs, limited by the width of affinity masks.
+const MAX_CPU_NUM: usize = 64;
+
+/// The key of a queued thread, the smaller runs first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct SchedKey {
+    /// 0 for deadline threads within the capacity, 1 for the others
+    band: u8,
+    /// The absolute deadline in ns for deadline threads, or the inverted priority
+    rank: u64,
+    /// The order of being queued
+    seq: u64,
+}
+
+type RunQueue = BTreeMap<SchedKey, Arc<Task>>;
+
+lazy_static! {
+    static ref RUN_QUEUES: Vec<Mutex<RunQueue>> = (0..MAX_CPU_NUM)
+        .map(|_| Mutex::new(RunQueue::new()))
+        .collect();
+}
+
+/// The CPUs running the scheduler.
+static ONLINE_CPUS: AtomicU64 = AtomicU64::new(0);
+/// The number of unfinished threads.
+static TASK_COUNT: AtomicUsize = AtomicUsize::new(0);
+static NEXT_SEQ: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    /// Waiting to be woken up.
+    Idle,
+    /// In a run queue.
+    Queued,
+    /// Being polled.
+    Running,
+    /// Woken up while being polled, to be queued again.
+    Woken,
+    /// Finished.
+    Done,
+}
+
+struct TaskState {
+    status: Status,
+    /// The CPU the thread ran on last time.
+    last_cpu: Option<usize>,
+    /// The end of the current period of a deadline thread.
+    period_end: Duration,
+    /// The time a deadline thread has run in the current period.
+    consumed: Duration,
+}
+
+struct Task {
+    future: Mutex<Option<Pin<Box<dyn Future<Output = ()> + Send>>>>,
+    sched: Arc<SchedHandle>,
+    state: Mutex<TaskState>,
+}
+
+impl Task {
+    /// Put the thread into a run queue, with its `state` locked.
+    fn enqueue(self: &Arc<Self>, state: &mut TaskState) {
+        let info = self.sched.get();
+        let (band, rank) = match info.class {
+            SchedClass::Deadline {
+                capacity,
+                relative_deadline,
+                period,
+            } => {
+                let now = timer_now();
+                if now >= state.period_end {
+                    state.period_end = now + period;
+                    state.consumed = Duration::ZERO;
+                }
+                if state.consumed < capacity {
+                    let deadline = state.period_end - period + relative_deadline;
+                    (0, deadline.as_nanos() as u64)
+                } else {
+                    // run after all fair threads until the next period
+                    (1, HIGHEST_PRIORITY as u64 + 1)
+                }
+            }
+            SchedClass::Fair { priority } => (1, HIGHEST_PRIORITY.saturating_sub(priority) as u64),
+        };
+        let key = SchedKey {
+            band,
+            rank,
+            seq: NEXT_SEQ.fetch_add(1, Ordering::Relaxed),
+        };
+        let cpu = select_cpu(info.affinity, state.last_cpu);
+        RUN_QUEUES[cpu].lock().insert(key, self.clone());
+    }
+
+    /// Poll the thread once on `cpu`.
+    fn run(self: Arc<Self>, cpu: usize) {
+        {
+            let mut state = self.state.lock();
+            state.status = Status::Running;
+            state.last_cpu = Some(cpu);
+        }
+        let waker = Waker::from(self.clone());
+        let start = timer_now();
+        let ready = {
+            let mut future = self.future.lock();
+            let ready = match future.as_mut() {
+                Some(future) => future
+                    .as_mut()
+                    .poll(&mut Context::from_waker(&waker))
+                    .is_ready(),
+                None => true,
+            };
+            if ready {
+                *future = None;
+            }
+            ready
+        };
+        let mut state = self.state.lock();
+        state.consumed += timer_now() - start;
+        if ready {
+            state.status = Status::Done;
+            TASK_COUNT.fetch_sub(1, Ordering::SeqCst);
+        } else if state.status == Status::Woken {
+            state.status = Status::Queued;
+            self.enqueue(&mut state);
+        } else {
+            state.status = Status::Idle;
+        }
+    }
+}
+
+impl Wake for Task {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let mut state = self.state.lock();
+        match state.status {
+            Status::Idle => {
+                state.status = Status::Queued;
+                self.enqueue(&mut state);
+            }
+            Status::Running => state.status = Status::Woken,
+            _ => {}
+        }
+    }
+}
+
+fn current_cpu() -> usize {
+    // CPUs beyond the affinity mask share the queues
+    cpu_id() as usize % MAX_CPU_NUM
+}
+
+/// Select a CPU in `affinity` to queue a thread, preferring `last_cpu`
+/// and then the current CPU.
+fn select_cpu(affinity: u64, last_cpu: Option<usize>) -> usize {
+    let cpu = current_cpu();
+    let allowed = affinity & ONLINE_CPUS.load(Ordering::SeqCst);
+    match last_cpu {
+        Some(last) if allowed & (1 << last) != 0 => last,
+        _ if allowed & (1 << cpu) != 0 => cpu,
+        _ if allowed != 0 => allowed.trailing_zeros() as usize,
+        // no online CPU is allowed, or the scheduler is not running yet
+        _ => cpu,
+    }
+}
+
+/// Remove the first thread in `queue` that `f` returns true for.
+fn pop_first(queue: &Mutex<RunQueue>, f: impl Fn(&Task) -> bool) -> Option<Arc<Task>> {
+    let mut queue = queue.lock();
+    let key = *queue.iter().find(|(_, task)| f(task))?.0;
+    queue.remove(&key)
+}
+
+/// Pick the next thread to run on `cpu`, from its own queue or stolen from the others.
+fn pick_next(cpu: usize) -> Option<Arc<Task>> {
+    while let Some(task) = pop_first(&RUN_QUEUES[cpu], |_| true) {
+        if select_cpu(task.sched.get().affinity, Some(cpu)) == cpu {
+            return Some(task);
+        }
+        // the affinity has been changed since it was queued
+        let mut state = task.state.lock();
+        task.enqueue(&mut state);
+    }
+    let mask = 1 << cpu;
+    let allowed = |task: &Task| task.sched.get().affinity & mask != 0;
+    loop {
+        // steal the first allowed thread among all queues
+        let (_, victim) = (0..MAX_CPU_NUM)
+            .filter(|&i| i != cpu)
+            .filter_map(|i| {
+                let queue = RUN_QUEUES[i].lock();
+                let key = *queue.iter().find(|(_, task)| allowed(task))?.0;
+                Some((key, i))
+            })
+            .min()?;
+        if let Some(task) = pop_first(&RUN_QUEUES[victim], allowed) {
+            return Some(task);
+        }
+    }
+}
+
+/// Spawn a new thread scheduled with the parameters in `sched`.
+pub(super) fn spawn(future: impl Future<Output = ()> + Send + 'static, sched: Arc<SchedHandle>) {
+    TASK_COUNT.fetch_add(1, Ordering::SeqCst);
+    let task = Arc::new(Task {
+        future: Mutex::new(Some(Box::pin(future))),
+        sched,
+        state: Mutex::new(TaskState {
+            status: Status::Queued,
+            last_cpu: None,
+            period_end: Duration::ZERO,
+            consumed: Duration::ZERO,
+        }),
+    });
+    let mut state = task.state.lock();
+    task.enqueue(&mut state);
+}
+
+/// Run the threads on the current CPU until there are no runnable ones.
+///
+/// Returns whether there are unfinished threads.
+pub fn run_until_idle() -> bool {
+    let cpu = current_cpu();
+    ONLINE_CPUS.fetch_or(1 << cpu, Ordering::SeqCst);
+    while let Some(task) = pick_next(cpu) {
+        task.run(cpu);
+    }
+    TASK_COUNT.load(Ordering::SeqCst) != 0
+}
@@ -1,11 +1,20 @@
 //! Thread spawning.
 
+use alloc::sync::Arc;
 use core::future::Future;
 
+use crate::hal_fn::thread::SchedHandle;
+
+pub use super::sched::run_until_idle;
+
 hal_fn_impl! {
     impl mod crate::hal_fn::thread {
         fn spawn(future: impl Future<Output = ()> + Send + 'static) {
-            executor::spawn(future);
+            super::sched::spawn(future, Arc::new(SchedHandle::default()));
+        }
+
+        fn spawn_sched(future: impl Future<Output = ()> + Send + 'static, sched: Arc<SchedHandle>) {
+            super::sched::spawn(future, sched);
         }
 
         fn set_tid(_tid: u64, _pid: u64) {}
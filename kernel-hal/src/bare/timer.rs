@@ -1,6 +1,6 @@
 //! Time and clock functions.
 
-use alloc::boxed::Box;
+use alloc::{boxed::Box, vec::Vec};
 use core::time::Duration;
 
 use naive_timer::Timer;
@@ -11,8 +11,12 @@ pub(super) const TICKS_PER_SEC: u64 = 100;
 
 lazy_static! {
     static ref NAIVE_TIMER: Mutex<Timer> = Mutex::new(Timer::default());
+    /// Timers set since the last tick, kept apart as callbacks may set new timers.
+    static ref NEW_TIMERS: Mutex<Vec<(Duration, TimerCallback)>> = Mutex::new(Vec::new());
 }
 
+type TimerCallback = Box<dyn FnOnce(Duration) + Send + Sync>;
+
 hal_fn_impl! {
     impl mod crate::hal_fn::timer {
         fn timer_now() -> Duration {
@@ -20,11 +24,15 @@ hal_fn_impl! {
         }
 
         fn timer_set(deadline: Duration, callback: Box<dyn FnOnce(Duration) + Send + Sync>) {
-            NAIVE_TIMER.lock().add(deadline, callback);
+            NEW_TIMERS.lock().push((deadline, callback));
         }
 
         fn timer_tick() {
-            NAIVE_TIMER.lock().expire(timer_now());
+            let mut timer = NAIVE_TIMER.lock();
+            for (deadline, callback) in NEW_TIMERS.lock().drain(..) {
+                timer.add(deadline, callback);
+            }
+            timer.expire(timer_now());
         }
     }
 }
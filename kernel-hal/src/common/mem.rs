@@ -83,6 +83,11 @@ impl Drop for PhysFrame {
     }
 }
 
+/// Returns the number of total and free physical frames.
+pub fn frame_stats() -> (usize, usize) {
+    KHANDLER.frame_stats()
+}
+
 lazy_static! {
     /// The global physical frame contains all zeros.
     pub static ref ZERO_FRAME: PhysFrame = PhysFrame::new_zero().expect("failed to alloc zero frame");
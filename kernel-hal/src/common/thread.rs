@@ -1,5 +1,7 @@
 use core::time::Duration;
 
+use spin::Mutex;
+
 use super::future::{SleepFuture, YieldFuture};
 
 /// Sleeps until the specified of time.
@@ -11,3 +13,84 @@ pub async fn sleep_until(deadline: Duration) {
 pub async fn yield_now() {
     YieldFuture::default().await
 }
+
+/// The lowest priority of a thread.
+pub const LOWEST_PRIORITY: u8 = 0;
+
+/// The priority of a thread by default.
+pub const DEFAULT_PRIORITY: u8 = 16;
+
+/// The highest priority of a thread.
+pub const HIGHEST_PRIORITY: u8 = 31;
+
+/// The affinity mask allowing a thread to run on all CPUs.
+pub const CPU_MASK_ALL: u64 = u64::MAX;
+
+/// The scheduling class of a thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchedClass {
+    /// Time-sharing threads, the ones of higher priority run first.
+    Fair {
+        /// From [`LOWEST_PRIORITY`] to [`HIGHEST_PRIORITY`].
+        priority: u8,
+    },
+    /// Threads run before the fair ones, the earliest deadline first,
+    /// for at most `capacity` in each `period`.
+    Deadline {
+        /// The execution time in each period.
+        capacity: Duration,
+        /// The deadline relative to the start of each period.
+        relative_deadline: Duration,
+        /// The length of a period.
+        period: Duration,
+    },
+}
+
+impl Default for SchedClass {
+    fn default() -> Self {
+        SchedClass::Fair {
+            priority: DEFAULT_PRIORITY,
+        }
+    }
+}
+
+/// Scheduling parameters of a thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchedInfo {
+    /// The scheduling class.
+    pub class: SchedClass,
+    /// The CPUs the thread can run on, bit `i` for the CPU `i`.
+    pub affinity: u64,
+}
+
+impl Default for SchedInfo {
+    fn default() -> Self {
+        SchedInfo {
+            class: SchedClass::default(),
+            affinity: CPU_MASK_ALL,
+        }
+    }
+}
+
+/// Scheduling parameters of a thread shared with the scheduler.
+///
+/// The changes take effect the next time the thread is scheduled.
+#[derive(Debug, Default)]
+pub struct SchedHandle(Mutex<SchedInfo>);
+
+impl SchedHandle {
+    /// Create a new handle with `info`.
+    pub fn new(info: SchedInfo) -> Self {
+        SchedHandle(Mutex::new(info))
+    }
+
+    /// Get the scheduling parameters.
+    pub fn get(&self) -> SchedInfo {
+        *self.0.lock()
+    }
+
+    /// Update the scheduling parameters with `f`.
+    pub fn update(&self, f: impl FnOnce(&mut SchedInfo)) {
+        f(&mut *self.0.lock());
+    }
+}
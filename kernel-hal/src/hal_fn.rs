@@ -1,4 +1,4 @@
-use alloc::{boxed::Box, string::String, vec::Vec};
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
 use core::{future::Future, ops::Range, time::Duration};
 
 use crate::drivers::prelude::{IrqHandler, IrqPolarity, IrqTriggerMode};
@@ -67,6 +67,10 @@ hal_fn_def! {
 
         /// Flush the physical frame.
         pub fn frame_flush(target: PhysAddr);
+
+        /// Synchronize the instruction cache of all CPUs with the stores to
+        /// instruction memory, e.g. after code is modified.
+        pub fn icache_flush_all() {}
     }
 
     /// Virtual memory operations.
@@ -139,6 +143,11 @@ hal_fn_def! {
         /// Spawn a new thread.
         pub fn spawn(future: impl Future<Output = ()> + Send + 'static);
 
+        /// Spawn a new thread scheduled with the parameters in `sched`.
+        pub fn spawn_sched(future: impl Future<Output = ()> + Send + 'static, _sched: Arc<SchedHandle>) {
+            spawn(future)
+        }
+
         /// Set tid and pid of current task.
         pub fn set_tid(tid: u64, pid: u64);
 
@@ -19,6 +19,13 @@ pub trait KernelHandler: Send + Sync + 'static {
         unimplemented!()
     }
 
+    /// Returns the number of total and free physical frames.
+    ///
+    /// Reports no frames if the kernel does not count them.
+    fn frame_stats(&self) -> (usize, usize) {
+        (0, 0)
+    }
+
     /// Handle kernel mode page fault.
     fn handle_page_fault(&self, _fault_vaddr: VirtAddr, _access_flags: MMUFlags) {
         // do nothing
@@ -1,13 +1,21 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+
 use bitmap_allocator::BitAlloc;
 
-use super::mem::FRAME_ALLOCATOR;
+use super::mem::{FRAME_ALLOCATOR, PMEM_SIZE};
 use crate::kernel_handler::{DummyKernelHandler, KernelHandler};
 use crate::{PhysAddr, PAGE_SIZE};
 
+/// Number of allocated physical frames
+static ALLOCATED_FRAMES: AtomicUsize = AtomicUsize::new(0);
+
 impl KernelHandler for DummyKernelHandler {
     fn frame_alloc(&self) -> Option<PhysAddr> {
         let ret = FRAME_ALLOCATOR.lock().alloc().map(|id| id * PAGE_SIZE);
         trace!("Allocate frame: {:x?}", ret);
+        if ret.is_some() {
+            ALLOCATED_FRAMES.fetch_add(1, Ordering::Relaxed);
+        }
         ret
     }
 
@@ -21,11 +29,21 @@ impl KernelHandler for DummyKernelHandler {
             ret,
             ret.map(|x| x + frame_count)
         );
+        if ret.is_some() {
+            ALLOCATED_FRAMES.fetch_add(frame_count, Ordering::Relaxed);
+        }
         ret
     }
 
     fn frame_dealloc(&self, paddr: PhysAddr) {
         trace!("Deallocate frame: {:x}", paddr);
         FRAME_ALLOCATOR.lock().dealloc(paddr / PAGE_SIZE);
+        ALLOCATED_FRAMES.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    fn frame_stats(&self) -> (usize, usize) {
+        // the first frame is never allocated
+        let total = PMEM_SIZE / PAGE_SIZE - 1;
+        (total, total - ALLOCATED_FRAMES.load(Ordering::Relaxed))
     }
 }
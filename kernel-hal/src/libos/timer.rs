@@ -13,7 +13,7 @@ hal_fn_impl! {
 
         fn timer_set(deadline: Duration, callback: Box<dyn FnOnce(Duration) + Send + Sync>) {
             task::spawn(async move {
-                let dur = deadline - timer_now();
+                let dur = deadline.saturating_sub(timer_now());
                 task::sleep(dur).await;
                 callback(timer_now());
             });
@@ -0,0 +1,314 @@
+//! Linux user and group credentials
+#![deny(missing_docs)]
+
+use alloc::vec::Vec;
+
+use rcore_fs::vfs::{FileType, Metadata};
+
+use crate::error::{LxError, LxResult};
+
+/// User ID or group ID
+pub type Id = u32;
+
+/// The ID passed as `-1`, which leaves the corresponding ID unchanged.
+pub const ID_UNCHANGED: Id = Id::MAX;
+
+/// Max number of supplementary groups
+pub const NGROUPS_MAX: usize = 65536;
+
+/// Set-user-ID bit of file mode
+const S_ISUID: u16 = 0o4000;
+/// Set-group-ID bit of file mode
+const S_ISGID: u16 = 0o2000;
+/// Execute permission of group
+const S_IXGRP: u16 = 0o010;
+/// Execute permission of anyone
+const S_IXUGO: u16 = 0o111;
+
+bitflags::bitflags! {
+    /// File access permissions, the same as `mode` of `access`
+    pub struct Access: u16 {
+        /// Read permission
+        const READ = 4;
+        /// Write permission
+        const WRITE = 2;
+        /// Execute or search permission
+        const EXEC = 1;
+    }
+}
+
+/// The real, effective, saved set and file system IDs
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Ids {
+    /// real ID
+    pub real: Id,
+    /// effective ID
+    pub effective: Id,
+    /// saved set ID
+    pub saved: Id,
+    /// file system ID, follows the effective ID
+    pub fs: Id,
+}
+
+impl Ids {
+    /// Whether `id` is one of the real, effective and saved set IDs.
+    fn is_current(&self, id: Id) -> bool {
+        id == self.real || id == self.effective || id == self.saved
+    }
+
+    /// Check that an unprivileged caller only switches between its current IDs.
+    fn check(&self, id: Id, privileged: bool) -> LxResult {
+        if id == ID_UNCHANGED || privileged || self.is_current(id) {
+            Ok(())
+        } else {
+            Err(LxError::EPERM)
+        }
+    }
+
+    /// Semantics of `setuid`
+    fn set(&mut self, id: Id, privileged: bool) -> LxResult {
+        if privileged {
+            self.real = id;
+            self.saved = id;
+        } else if id != self.real && id != self.saved {
+            return Err(LxError::EPERM);
+        }
+        self.effective = id;
+        self.fs = id;
+        Ok(())
+    }
+
+    /// Semantics of `setreuid`
+    fn set_re(&mut self, real: Id, effective: Id, privileged: bool) -> LxResult {
+        if real != ID_UNCHANGED && !privileged && real != self.real && real != self.effective {
+            return Err(LxError::EPERM);
+        }
+        self.check(effective, privileged)?;
+        let old_real = self.real;
+        if real != ID_UNCHANGED {
+            self.real = real;
+        }
+        if effective != ID_UNCHANGED {
+            self.effective = effective;
+        }
+        if real != ID_UNCHANGED || (effective != ID_UNCHANGED && effective != old_real) {
+            self.saved = self.effective;
+        }
+        self.fs = self.effective;
+        Ok(())
+    }
+
+    /// Semantics of `setresuid`
+    fn set_res(&mut self, real: Id, effective: Id, saved: Id, privileged: bool) -> LxResult {
+        self.check(real, privileged)?;
+        self.check(effective, privileged)?;
+        self.check(saved, privileged)?;
+        if real != ID_UNCHANGED {
+            self.real = real;
+        }
+        if effective != ID_UNCHANGED {
+            self.effective = effective;
+        }
+        if saved != ID_UNCHANGED {
+            self.saved = saved;
+        }
+        self.fs = self.effective;
+        Ok(())
+    }
+
+    /// Semantics of `setfsuid`, returns the previous file system ID.
+    fn set_fs(&mut self, id: Id, privileged: bool) -> Id {
+        let old = self.fs;
+        if id != ID_UNCHANGED && (privileged || self.is_current(id) || id == self.fs) {
+            self.fs = id;
+        }
+        old
+    }
+}
+
+/// Credentials of a process
+///
+/// A process with effective user ID 0 is privileged to change its IDs,
+/// and one with file system user ID 0 bypasses file permission checks.
+#[derive(Debug, Default, Clone)]
+pub struct Credentials {
+    /// user IDs
+    pub uid: Ids,
+    /// group IDs
+    pub gid: Ids,
+    /// supplementary group IDs
+    pub groups: Vec<Id>,
+}
+
+impl Credentials {
+    /// Whether the process may change its user and group IDs arbitrarily.
+    pub fn privileged(&self) -> bool {
+        self.uid.effective == 0
+    }
+
+    /// Set the effective user ID, and also the real and saved set user ID if privileged.
+    pub fn set_uid(&mut self, uid: Id) -> LxResult {
+        let privileged = self.privileged();
+        self.uid.set(uid, privileged)
+    }
+
+    /// Set the real and effective user IDs.
+    pub fn set_reuid(&mut self, ruid: Id, euid: Id) -> LxResult {
+        let privileged = self.privileged();
+        self.uid.set_re(ruid, euid, privileged)
+    }
+
+    /// Set the real, effective and saved set user IDs.
+    pub fn set_resuid(&mut self, ruid: Id, euid: Id, suid: Id) -> LxResult {
+        let privileged = self.privileged();
+        self.uid.set_res(ruid, euid, suid, privileged)
+    }
+
+    /// Set the file system user ID, returns the previous one.
+    pub fn set_fsuid(&mut self, fsuid: Id) -> Id {
+        let privileged = self.privileged();
+        self.uid.set_fs(fsuid, privileged)
+    }
+
+    /// Set the effective group ID, and also the real and saved set group ID if privileged.
+    pub fn set_gid(&mut self, gid: Id) -> LxResult {
+        let privileged = self.privileged();
+        self.gid.set(gid, privileged)
+    }
+
+    /// Set the real and effective group IDs.
+    pub fn set_regid(&mut self, rgid: Id, egid: Id) -> LxResult {
+        let privileged = self.privileged();
+        self.gid.set_re(rgid, egid, privileged)
+    }
+
+    /// Set the real, effective and saved set group IDs.
+    pub fn set_resgid(&mut self, rgid: Id, egid: Id, sgid: Id) -> LxResult {
+        let privileged = self.privileged();
+        self.gid.set_res(rgid, egid, sgid, privileged)
+    }
+
+    /// Set the file system group ID, returns the previous one.
+    pub fn set_fsgid(&mut self, fsgid: Id) -> Id {
+        let privileged = self.privileged();
+        self.gid.set_fs(fsgid, privileged)
+    }
+
+    /// Set the supplementary group IDs.
+    pub fn set_groups(&mut self, groups: Vec<Id>) -> LxResult {
+        if !self.privileged() {
+            return Err(LxError::EPERM);
+        }
+        if groups.len() > NGROUPS_MAX {
+            return Err(LxError::EINVAL);
+        }
+        self.groups = groups;
+        Ok(())
+    }
+
+    /// Credentials used by `access`, which checks with the real IDs instead of the effective ones.
+    pub fn real(&self) -> Self {
+        let mut cred = self.clone();
+        cred.uid.fs = cred.uid.real;
+        cred.gid.fs = cred.gid.real;
+        cred
+    }
+
+    /// Whether the process is a member of group `gid`.
+    pub fn in_group(&self, gid: usize) -> bool {
+        self.gid.fs as usize == gid || self.groups.iter().any(|&g| g as usize == gid)
+    }
+
+    /// Whether the process bypasses file permission checks.
+    fn fs_privileged(&self) -> bool {
+        self.uid.fs == 0
+    }
+
+    /// Check the permission to `access` a file with metadata `meta`.
+    pub fn check_access(&self, meta: &Metadata, access: Access) -> LxResult {
+        if self.fs_privileged() {
+            // even root needs an execute bit to execute a file
+            if access.contains(Access::EXEC)
+                && meta.type_ != FileType::Dir
+                && meta.mode & S_IXUGO == 0
+            {
+                return Err(LxError::EACCES);
+            }
+            return Ok(());
+        }
+        let shift = if meta.uid == self.uid.fs as usize {
+            6
+        } else if self.in_group(meta.gid) {
+            3
+        } else {
+            0
+        };
+        let granted = Access::from_bits_truncate((meta.mode >> shift) & 0o7);
+        if granted.contains(access) {
+            Ok(())
+        } else {
+            Err(LxError::EACCES)
+        }
+    }
+
+    /// Change the permission bits in `meta` to `mode`, the process must own the file.
+    pub fn chmod(&self, meta: &mut Metadata, mode: u16) -> LxResult {
+        if !self.fs_privileged() && meta.uid != self.uid.fs as usize {
+            return Err(LxError::EPERM);
+        }
+        let mut mode = mode & 0o7777;
+        if !self.fs_privileged() && !self.in_group(meta.gid) {
+            mode &= !S_ISGID;
+        }
+        meta.mode = mode;
+        Ok(())
+    }
+
+    /// Change the owner and group in `meta`, an ID of [`ID_UNCHANGED`] is ignored.
+    ///
+    /// Only a privileged process can change the owner. The owner can change the group
+    /// to one of its groups. The set-user-ID and set-group-ID bits are cleared as Linux does.
+    pub fn chown(&self, meta: &mut Metadata, uid: Id, gid: Id) -> LxResult {
+        let privileged = self.fs_privileged();
+        let owner = meta.uid == self.uid.fs as usize;
+        if uid != ID_UNCHANGED && uid as usize != meta.uid && !privileged {
+            return Err(LxError::EPERM);
+        }
+        if gid != ID_UNCHANGED
+            && gid as usize != meta.gid
+            && !(privileged || (owner && self.in_group(gid as usize)))
+        {
+            return Err(LxError::EPERM);
+        }
+        if uid == ID_UNCHANGED && gid == ID_UNCHANGED {
+            return Ok(());
+        }
+        if uid != ID_UNCHANGED {
+            meta.uid = uid as usize;
+        }
+        if gid != ID_UNCHANGED {
+            meta.gid = gid as usize;
+        }
+        if meta.type_ != FileType::Dir {
+            meta.mode &= !S_ISUID;
+            if meta.mode & S_IXGRP != 0 {
+                meta.mode &= !S_ISGID;
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the set-user-ID and set-group-ID bits of the program on `execve`.
+    pub fn exec(&mut self, meta: &Metadata) {
+        if meta.mode & S_ISUID != 0 {
+            self.uid.effective = meta.uid as Id;
+        }
+        if meta.mode & S_ISGID != 0 && meta.mode & S_IXGRP != 0 {
+            self.gid.effective = meta.gid as Id;
+        }
+        self.uid.saved = self.uid.effective;
+        self.uid.fs = self.uid.effective;
+        self.gid.saved = self.gid.effective;
+        self.gid.fs = self.gid.effective;
+    }
+}
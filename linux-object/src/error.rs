@@ -97,14 +97,28 @@ pub enum LxError {
     ELOOP = 40,
     /// Identifier removed
     EIDRM = 43,
+    /// Value too large for defined data type
+    EOVERFLOW = 75,
     /// Socket operation on non-socket
     ENOTSOCK = 88,
+    /// Message too long
+    EMSGSIZE = 90,
+    /// Protocol wrong type for socket
+    EPROTOTYPE = 91,
     /// Protocol not available
     ENOPROTOOPT = 92,
+    /// Protocol not supported
+    EPROTONOSUPPORT = 93,
+    /// Socket type not supported
+    ESOCKTNOSUPPORT = 94,
+    /// Operation not supported on transport endpoint
+    EOPNOTSUPP = 95,
     /// Protocol family not supported
     EPFNOSUPPORT = 96,
     /// Address family not supported by protocol
     EAFNOSUPPORT = 97,
+    /// Address already in use
+    EADDRINUSE = 98,
     /// No buffer space available
     ENOBUFS = 105,
     /// Transport endpoint is already connected
@@ -161,10 +175,17 @@ impl fmt::Display for LxError {
             ENOTEMPTY => "Directory not empty",
             ELOOP => "Too many symbolic links encountered",
             EIDRM => "Identifier removed",
+            EOVERFLOW => "Value too large for defined data type",
             ENOTSOCK => "Socket operation on non-socket",
+            EMSGSIZE => "Message too long",
+            EPROTOTYPE => "Protocol wrong type for socket",
             ENOPROTOOPT => "Protocol not available",
+            EPROTONOSUPPORT => "Protocol not supported",
+            ESOCKTNOSUPPORT => "Socket type not supported",
+            EOPNOTSUPP => "Operation not supported on transport endpoint",
             EPFNOSUPPORT => "Protocol family not supported",
             EAFNOSUPPORT => "Address family not supported by protocol",
+            EADDRINUSE => "Address already in use",
             ENOBUFS => "No buffer space available",
             EISCONN => "Transport endpoint is already connected",
             ENOTCONN => "Transport endpoint is not connected",
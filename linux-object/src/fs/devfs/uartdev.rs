@@ -4,11 +4,14 @@ use rcore_fs::vfs::{make_rdev, FileType, FsError, INode, Metadata, PollStatus, R
 use rcore_fs_devfs::DevFS;
 use zcore_drivers::{scheme::UartScheme, DeviceError};
 
+use crate::fs::Tty;
+
 /// Uart device.
 pub struct UartDev {
     index: usize,
     port: Arc<dyn UartScheme>,
     inode_id: usize,
+    tty: Arc<Tty>,
 }
 
 impl UartDev {
@@ -17,8 +20,14 @@ impl UartDev {
             index,
             port,
             inode_id: DevFS::new_inode_id(),
+            tty: Default::default(),
         }
     }
+
+    /// Job control state of the terminal.
+    pub fn tty(&self) -> &Arc<Tty> {
+        &self.tty
+    }
 }
 
 impl INode for UartDev {
@@ -30,10 +39,12 @@ impl INode for UartDev {
         );
 
         let mut len = 0;
-        for b in buf.iter_mut() {
+        while len < buf.len() {
             match self.port.try_recv() {
-                Ok(Some(b_)) => {
-                    *b = b_;
+                // special characters generate signals instead of input
+                Ok(Some(b)) if self.tty.handle_input(b) => {}
+                Ok(Some(b)) => {
+                    buf[len] = b;
                     len += 1;
                 }
                 Ok(None) => break,
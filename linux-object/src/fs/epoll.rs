@@ -0,0 +1,494 @@
+//! Implement epoll instance as a file
+#![deny(missing_docs)]
+
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    sync::{Arc, Weak},
+    task::Wake,
+    vec::Vec,
+};
+use core::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll, Waker},
+};
+
+use async_trait::async_trait;
+use rcore_fs::vfs::PollStatus;
+use spin::{Mutex, MutexGuard};
+use zircon_object::object::*;
+use zircon_object::vm::VmObject;
+
+use super::{FileDesc, FileLike, OpenFlags};
+use crate::error::{LxError, LxResult};
+use crate::net::{register_socket_waker, Socket};
+
+bitflags::bitflags! {
+    /// Event types and input flags of `struct epoll_event`
+    pub struct EpollEvents: u32 {
+        /// The associated file is available for read operations.
+        const IN = 0x001;
+        /// There is an exceptional condition on the file descriptor.
+        const PRI = 0x002;
+        /// The associated file is available for write operations.
+        const OUT = 0x004;
+        /// Error condition happened on the associated file descriptor.
+        const ERR = 0x008;
+        /// Hang up happened on the associated file descriptor.
+        const HUP = 0x010;
+        /// Normal data can be read.
+        const RDNORM = 0x040;
+        /// Priority band data can be read.
+        const RDBAND = 0x080;
+        /// Normal data can be written.
+        const WRNORM = 0x100;
+        /// Priority data can be written.
+        const WRBAND = 0x200;
+        /// Message available.
+        const MSG = 0x400;
+        /// Stream socket peer closed connection.
+        const RDHUP = 0x2000;
+        /// Sets an exclusive wakeup mode.
+        const EXCLUSIVE = 1 << 28;
+        /// Prevent system suspend while the event is pending.
+        const WAKEUP = 1 << 29;
+        /// Disable the file descriptor after one event is reported.
+        const ONESHOT = 1 << 30;
+        /// Edge-triggered notification.
+        const ET = 1 << 31;
+    }
+}
+
+impl EpollEvents {
+    /// Events that are always reported, even if not requested.
+    const ALWAYS: Self = Self::from_bits_truncate(Self::ERR.bits() | Self::HUP.bits());
+    /// Input flags which are not event types.
+    const INPUT_FLAGS: Self = Self::from_bits_truncate(
+        Self::EXCLUSIVE.bits() | Self::WAKEUP.bits() | Self::ONESHOT.bits() | Self::ET.bits(),
+    );
+
+    /// Convert poll status to ready events.
+    fn from_status(status: PollStatus) -> Self {
+        let mut events = EpollEvents::empty();
+        if status.read {
+            events |= EpollEvents::IN | EpollEvents::RDNORM;
+        }
+        if status.write {
+            events |= EpollEvents::OUT | EpollEvents::WRNORM;
+        }
+        if status.error {
+            events |= EpollEvents::ERR;
+        }
+        events
+    }
+}
+
+/// Linux struct epoll_event
+#[cfg_attr(target_arch = "x86_64", repr(C, packed))]
+#[cfg_attr(not(target_arch = "x86_64"), repr(C))]
+#[derive(Debug, Copy, Clone)]
+pub struct EpollEvent {
+    /// Epoll events
+    pub events: EpollEvents,
+    /// User data variable
+    pub data: u64,
+}
+
+/// The file watched by an epoll instance.
+///
+/// The entry is dropped once the file is closed.
+#[derive(Clone)]
+pub enum EpollTarget {
+    /// A file descriptor
+    File(Weak<dyn FileLike>),
+    /// A socket, which is not a [`FileLike`] yet
+    Socket(Weak<Mutex<dyn Socket>>),
+}
+
+impl EpollTarget {
+    /// Returns whether the target has been closed.
+    fn is_closed(&self) -> bool {
+        match self {
+            EpollTarget::File(file) => file.strong_count() == 0,
+            EpollTarget::Socket(socket) => socket.strong_count() == 0,
+        }
+    }
+
+    /// Check the ready status of the target.
+    fn poll(&self) -> LxResult<PollStatus> {
+        match self {
+            EpollTarget::File(file) => match file.upgrade() {
+                Some(file) => file.poll(),
+                None => Ok(PollStatus {
+                    read: false,
+                    write: false,
+                    error: false,
+                }),
+            },
+            EpollTarget::Socket(socket) => {
+                let (read, write, error) = match socket.upgrade() {
+                    Some(socket) => socket.lock().poll(),
+                    None => (false, false, false),
+                };
+                Ok(PollStatus { read, write, error })
+            }
+        }
+    }
+
+    /// Register `waker` to be woken up once, when the status of the target changes.
+    ///
+    /// Returns `false` if the target can not notify its status changes.
+    fn register_waker(&self, waker: &Waker) -> bool {
+        match self {
+            EpollTarget::File(file) => file.upgrade().map_or(false, |f| f.register_waker(waker)),
+            EpollTarget::Socket(socket) => socket
+                .upgrade()
+                .map_or(false, |s| s.lock().register_waker(waker)),
+        }
+    }
+
+    /// Register the waker of `cx` to be woken up when the status of the target may change.
+    fn register(&self, cx: &mut Context) {
+        match self {
+            EpollTarget::File(file) => {
+                if let Some(file) = file.upgrade() {
+                    if !file.register_waker(cx.waker()) {
+                        // the waker is registered by `async_poll` if the file is not ready
+                        let _ = Box::pin(file.async_poll()).as_mut().poll(cx);
+                    }
+                }
+            }
+            EpollTarget::Socket(socket) => {
+                if let Some(socket) = socket.upgrade() {
+                    register_socket_waker(&*socket.lock(), cx.waker());
+                }
+            }
+        }
+    }
+}
+
+/// The edge of an edge-triggered entry, which records changes of the target.
+///
+/// It is registered on the target as a one-shot waker, and is armed again once woken up.
+struct Edge {
+    /// Whether the target changed since the entry was checked last time
+    changed: AtomicBool,
+    /// Whether the edge is registered on the target and not woken up yet
+    armed: AtomicBool,
+    /// The waker of the task waiting on the epoll instance
+    waker: Mutex<Option<Waker>>,
+}
+
+impl Edge {
+    fn new() -> Arc<Self> {
+        Arc::new(Edge {
+            // a ready target is reported once after it is added
+            changed: AtomicBool::new(true),
+            armed: AtomicBool::new(false),
+            waker: Mutex::new(None),
+        })
+    }
+}
+
+impl Wake for Edge {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.changed.store(true, Ordering::SeqCst);
+        self.armed.store(false, Ordering::SeqCst);
+        if let Some(waker) = self.waker.lock().take() {
+            waker.wake();
+        }
+    }
+}
+
+/// An entry in the interest list of an epoll instance.
+struct EpollEntry {
+    /// The watched file
+    target: EpollTarget,
+    /// Requested events and input flags
+    event: EpollEvent,
+    /// Events ready last time, used by edge-triggered mode
+    last_ready: EpollEvents,
+    /// Changes of the target, used by edge-triggered mode
+    edge: Arc<Edge>,
+    /// Whether the entry is disabled after `EPOLLONESHOT` is triggered
+    disabled: bool,
+}
+
+impl EpollEntry {
+    fn new(target: EpollTarget, event: EpollEvent) -> Self {
+        EpollEntry {
+            target,
+            event,
+            last_ready: EpollEvents::empty(),
+            edge: Edge::new(),
+            disabled: false,
+        }
+    }
+
+    fn is_edge_triggered(&self) -> bool {
+        self.event.events.contains(EpollEvents::ET)
+    }
+
+    /// Returns the events to report.
+    ///
+    /// An edge-triggered entry is reported when the target changed and is still ready,
+    /// or when new events become ready.
+    ///
+    /// The state of the entry is updated if `consume` is true.
+    fn check(&mut self, consume: bool) -> LxResult<EpollEvents> {
+        if self.disabled {
+            return Ok(EpollEvents::empty());
+        }
+        let flags = self.event.events;
+        let interest = (flags - EpollEvents::INPUT_FLAGS) | EpollEvents::ALWAYS;
+        let report = if self.is_edge_triggered() {
+            let changed = if consume {
+                let changed = self.edge.changed.swap(false, Ordering::SeqCst);
+                // arm the edge before polling, so that no change is missed
+                if !self.edge.armed.swap(true, Ordering::SeqCst)
+                    && !self.target.register_waker(&Waker::from(self.edge.clone()))
+                {
+                    self.edge.armed.store(false, Ordering::SeqCst);
+                }
+                changed
+            } else {
+                self.edge.changed.load(Ordering::SeqCst)
+            };
+            let ready = EpollEvents::from_status(self.target.poll()?) & interest;
+            let report = if changed || !(ready - self.last_ready).is_empty() {
+                ready
+            } else {
+                EpollEvents::empty()
+            };
+            if consume {
+                self.last_ready = ready;
+            }
+            report
+        } else {
+            EpollEvents::from_status(self.target.poll()?) & interest
+        };
+        if consume && !report.is_empty() && flags.contains(EpollEvents::ONESHOT) {
+            self.disabled = true;
+        }
+        Ok(report)
+    }
+
+    /// Register the waker of `cx` to be woken up when the entry may have events to report.
+    fn register(&self, cx: &mut Context) {
+        if self.disabled {
+            return;
+        }
+        if self.is_edge_triggered() && self.edge.armed.load(Ordering::SeqCst) {
+            *self.edge.waker.lock() = Some(cx.waker().clone());
+        } else {
+            self.target.register(cx);
+        }
+    }
+}
+
+/// Operations of `epoll_ctl`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EpollCtlOp {
+    /// Add an entry to the interest list.
+    Add,
+    /// Remove an entry from the interest list.
+    Del,
+    /// Change the settings of an entry in the interest list.
+    Mod,
+}
+
+/// Epoll instance
+pub struct EpollInstance {
+    /// object base
+    base: KObjectBase,
+    /// open flags
+    flags: Mutex<OpenFlags>,
+    /// interest list, shared between duplicated file descriptors
+    interests: Arc<Mutex<BTreeMap<FileDesc, EpollEntry>>>,
+}
+
+impl_kobject!(EpollInstance);
+
+impl EpollInstance {
+    /// Create a new epoll instance.
+    pub fn new(flags: OpenFlags) -> Arc<Self> {
+        Arc::new(EpollInstance {
+            base: KObjectBase::new(),
+            flags: Mutex::new(flags),
+            interests: Default::default(),
+        })
+    }
+
+    /// Add, modify or remove an entry in the interest list.
+    ///
+    /// `target` and `event` are ignored when `op` is [`EpollCtlOp::Del`].
+    pub fn control(
+        &self,
+        op: EpollCtlOp,
+        fd: FileDesc,
+        target: EpollTarget,
+        event: EpollEvent,
+    ) -> LxResult {
+        let mut interests = self.interests();
+        match op {
+            EpollCtlOp::Add => {
+                if interests.contains_key(&fd) {
+                    return Err(LxError::EEXIST);
+                }
+                interests.insert(fd, EpollEntry::new(target, event));
+            }
+            EpollCtlOp::Mod => {
+                let entry = interests.get_mut(&fd).ok_or(LxError::ENOENT)?;
+                *entry = EpollEntry::new(entry.target.clone(), event);
+            }
+            EpollCtlOp::Del => {
+                interests.remove(&fd).ok_or(LxError::ENOENT)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Lock the interest list, dropping entries of closed files.
+    fn interests(&self) -> MutexGuard<BTreeMap<FileDesc, EpollEntry>> {
+        let mut interests = self.interests.lock();
+        interests.retain(|_, entry| !entry.target.is_closed());
+        interests
+    }
+
+    /// Collect at most `max_events` ready events.
+    ///
+    /// The waker of `cx` is registered on every watched file if no event is ready.
+    pub fn poll_events(&self, cx: &mut Context, max_events: usize) -> LxResult<Vec<EpollEvent>> {
+        let mut interests = self.interests();
+        let events = Self::collect(&mut interests, max_events)?;
+        if !events.is_empty() {
+            return Ok(events);
+        }
+        for entry in interests.values() {
+            entry.register(cx);
+        }
+        // check again, in case something changed before the waker was registered
+        Self::collect(&mut interests, max_events)
+    }
+
+    fn collect(
+        interests: &mut BTreeMap<FileDesc, EpollEntry>,
+        max_events: usize,
+    ) -> LxResult<Vec<EpollEvent>> {
+        let mut events = Vec::new();
+        for entry in interests.values_mut() {
+            if events.len() >= max_events {
+                break;
+            }
+            let report = entry.check(true)?;
+            if !report.is_empty() {
+                events.push(EpollEvent {
+                    events: report,
+                    data: entry.event.data,
+                });
+            }
+        }
+        Ok(events)
+    }
+
+    /// Returns whether some event is ready, without consuming it.
+    ///
+    /// If `cx` is given, the waker is registered on every watched file if no event is ready.
+    fn has_events(&self, cx: Option<&mut Context>) -> LxResult<bool> {
+        let mut interests = self.interests();
+        for entry in interests.values_mut() {
+            if !entry.check(false)?.is_empty() {
+                return Ok(true);
+            }
+        }
+        if let Some(cx) = cx {
+            for entry in interests.values() {
+                entry.register(cx);
+            }
+            for entry in interests.values_mut() {
+                if !entry.check(false)?.is_empty() {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[async_trait]
+impl FileLike for EpollInstance {
+    fn flags(&self) -> OpenFlags {
+        *self.flags.lock()
+    }
+
+    fn set_flags(&self, f: OpenFlags) -> LxResult {
+        let flags = &mut self.flags.lock();
+        flags.set(OpenFlags::NON_BLOCK, f.contains(OpenFlags::NON_BLOCK));
+        flags.set(OpenFlags::CLOEXEC, f.contains(OpenFlags::CLOEXEC));
+        Ok(())
+    }
+
+    async fn read(&self, _buf: &mut [u8]) -> LxResult<usize> {
+        Err(LxError::EINVAL)
+    }
+
+    async fn write(&self, _buf: &[u8]) -> LxResult<usize> {
+        Err(LxError::EINVAL)
+    }
+
+    async fn read_at(&self, _offset: u64, _buf: &mut [u8]) -> LxResult<usize> {
+        Err(LxError::ESPIPE)
+    }
+
+    fn write_at(&self, _offset: u64, _buf: &[u8]) -> LxResult<usize> {
+        Err(LxError::ESPIPE)
+    }
+
+    /// An epoll instance is readable if some event is ready.
+    fn poll(&self) -> LxResult<PollStatus> {
+        Ok(PollStatus {
+            read: self.has_events(None)?,
+            write: false,
+            error: false,
+        })
+    }
+
+    async fn async_poll(&self) -> LxResult<PollStatus> {
+        #[must_use = "future does nothing unless polled/`await`-ed"]
+        struct EpollFuture<'a> {
+            epoll: &'a EpollInstance,
+        }
+
+        impl<'a> Future for EpollFuture<'a> {
+            type Output = LxResult<PollStatus>;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+                match self.epoll.has_events(Some(cx)) {
+                    Ok(true) => Poll::Ready(Ok(PollStatus {
+                        read: true,
+                        write: false,
+                        error: false,
+                    })),
+                    Ok(false) => Poll::Pending,
+                    Err(err) => Poll::Ready(Err(err)),
+                }
+            }
+        }
+
+        EpollFuture { epoll: self }.await
+    }
+
+    fn ioctl(&self, _request: usize, _arg1: usize, _arg2: usize, _arg3: usize) -> LxResult<usize> {
+        Err(LxError::ENOTTY)
+    }
+
+    fn get_vmo(&self, _offset: usize, _len: usize) -> LxResult<Arc<VmObject>> {
+        Err(LxError::ENODEV)
+    }
+}
@@ -0,0 +1,255 @@
+//! Implement eventfd as a file
+#![deny(missing_docs)]
+
+use alloc::{boxed::Box, collections::BTreeMap, sync::Arc};
+use core::convert::TryInto;
+use core::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    task::{Context, Poll, Waker},
+};
+
+use async_trait::async_trait;
+use rcore_fs::vfs::PollStatus;
+use spin::Mutex;
+use zircon_object::object::*;
+use zircon_object::vm::VmObject;
+
+use super::{FileLike, OpenFlags};
+use crate::error::{LxError, LxResult};
+
+/// The max value of the counter
+const MAX_COUNT: u64 = u64::MAX - 1;
+
+/// An eventfd, a counter used as a wait/notify mechanism
+pub struct EventFd {
+    /// object base
+    base: KObjectBase,
+    /// `O_CLOEXEC`, the only flag of the file descriptor itself
+    cloexec: AtomicBool,
+    /// the counter, shared between duplicated file descriptors
+    counter: Arc<Counter>,
+}
+
+/// The counter of an eventfd, with the state of its open file description
+struct Counter {
+    /// current value
+    value: Mutex<u64>,
+    /// `EFD_SEMAPHORE`: a read decreases the counter by 1
+    semaphore: bool,
+    /// `O_NONBLOCK` of the open file description
+    non_block: AtomicBool,
+    /// wakers of the tasks waiting for the value to change, by waiter ID
+    waiters: Mutex<BTreeMap<usize, Waker>>,
+    /// ID of the next waiter
+    next_waiter: AtomicUsize,
+}
+
+impl_kobject!(EventFd);
+
+impl EventFd {
+    /// Create a new eventfd with the initial value `value`.
+    pub fn new(value: u32, semaphore: bool, flags: OpenFlags) -> Arc<Self> {
+        let counter = Counter {
+            value: Mutex::new(value as u64),
+            semaphore,
+            non_block: AtomicBool::new(flags.non_block()),
+            waiters: Mutex::new(BTreeMap::new()),
+            next_waiter: AtomicUsize::new(0),
+        };
+        Arc::new(EventFd {
+            base: KObjectBase::new(),
+            cloexec: AtomicBool::new(flags.close_on_exec()),
+            counter: Arc::new(counter),
+        })
+    }
+
+    /// Wait until `ready`, called with the counter locked, returns `Some`.
+    ///
+    /// Returns `EAGAIN` instead of waiting if the file is non-blocking.
+    async fn wait_for<T>(&self, ready: impl Fn(&mut u64) -> Option<T> + Unpin) -> LxResult<T> {
+        let non_block = self.flags().non_block();
+        CounterFuture {
+            counter: &self.counter,
+            id: None,
+            ready: move |value: &mut u64| match ready(value) {
+                None if non_block => Some(Err(LxError::EAGAIN)),
+                result => result.map(Ok),
+            },
+        }
+        .await
+    }
+}
+
+impl Counter {
+    /// Set the value, and wake up the waiters.
+    fn update(&self, value: &mut u64, new: u64) {
+        *value = new;
+        let waiters = core::mem::take(&mut *self.waiters.lock());
+        for waker in waiters.into_values() {
+            waker.wake();
+        }
+    }
+
+    /// The poll status with the counter of `value`.
+    fn status(value: u64) -> PollStatus {
+        PollStatus {
+            read: value != 0,
+            write: value < MAX_COUNT,
+            error: false,
+        }
+    }
+}
+
+/// A future waiting for the counter of an eventfd
+#[must_use = "future does nothing unless polled/`await`-ed"]
+struct CounterFuture<'a, F> {
+    counter: &'a Counter,
+    /// ID of the waker in the waiters, if it is registered
+    id: Option<usize>,
+    ready: F,
+}
+
+impl<T, F: Fn(&mut u64) -> Option<T> + Unpin> Future for CounterFuture<'_, F> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let counter = self.counter;
+        let mut value = counter.value.lock();
+        if let Some(output) = (self.ready)(&mut value) {
+            return Poll::Ready(output);
+        }
+        // register with the value locked, so no update is missed
+        let id = *self
+            .id
+            .get_or_insert_with(|| counter.next_waiter.fetch_add(1, Ordering::Relaxed));
+        counter.waiters.lock().insert(id, cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<F> Drop for CounterFuture<'_, F> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.counter.waiters.lock().remove(&id);
+        }
+    }
+}
+
+#[async_trait]
+impl FileLike for EventFd {
+    fn flags(&self) -> OpenFlags {
+        let mut flags = OpenFlags::empty();
+        flags.set(
+            OpenFlags::NON_BLOCK,
+            self.counter.non_block.load(Ordering::SeqCst),
+        );
+        flags.set(OpenFlags::CLOEXEC, self.cloexec.load(Ordering::SeqCst));
+        flags
+    }
+
+    fn set_flags(&self, f: OpenFlags) -> LxResult {
+        self.counter
+            .non_block
+            .store(f.non_block(), Ordering::SeqCst);
+        self.cloexec.store(f.close_on_exec(), Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Read the counter as an 8-byte integer, and reset it to zero,
+    /// or decrease it by 1 with `EFD_SEMAPHORE`.
+    ///
+    /// Blocks until the counter is not zero, unless the file is non-blocking.
+    async fn read(&self, buf: &mut [u8]) -> LxResult<usize> {
+        if buf.len() < 8 {
+            return Err(LxError::EINVAL);
+        }
+        let semaphore = self.counter.semaphore;
+        let read = self
+            .wait_for(|value| {
+                let read = match *value {
+                    0 => return None,
+                    _ if semaphore => 1,
+                    count => count,
+                };
+                let new = *value - read;
+                self.counter.update(value, new);
+                Some(read)
+            })
+            .await?;
+        buf[..8].copy_from_slice(&read.to_ne_bytes());
+        Ok(8)
+    }
+
+    /// Add an 8-byte integer to the counter.
+    ///
+    /// Blocks until a read makes room for it, unless the file is non-blocking.
+    async fn write(&self, buf: &[u8]) -> LxResult<usize> {
+        let add = buf
+            .get(..8)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_ne_bytes)
+            .ok_or(LxError::EINVAL)?;
+        if add == u64::MAX {
+            return Err(LxError::EINVAL);
+        }
+        self.wait_for(|value| {
+            if add > MAX_COUNT - *value {
+                return None;
+            }
+            let new = *value + add;
+            self.counter.update(value, new);
+            Some(())
+        })
+        .await?;
+        Ok(8)
+    }
+
+    async fn read_at(&self, _offset: u64, _buf: &mut [u8]) -> LxResult<usize> {
+        Err(LxError::ESPIPE)
+    }
+
+    fn write_at(&self, _offset: u64, _buf: &[u8]) -> LxResult<usize> {
+        Err(LxError::ESPIPE)
+    }
+
+    /// An eventfd is readable if the counter is not zero,
+    /// and writable if a value of at least 1 can be added.
+    fn poll(&self) -> LxResult<PollStatus> {
+        Ok(Counter::status(*self.counter.value.lock()))
+    }
+
+    /// Wait until the eventfd is readable or writable.
+    async fn async_poll(&self) -> LxResult<PollStatus> {
+        let status = CounterFuture {
+            counter: &self.counter,
+            id: None,
+            ready: |value: &mut u64| {
+                let status = Counter::status(*value);
+                if status.read || status.write {
+                    Some(status)
+                } else {
+                    None
+                }
+            },
+        }
+        .await;
+        Ok(status)
+    }
+
+    /// Register `waker` to be woken up once, when the counter changes.
+    fn register_waker(&self, waker: &Waker) -> bool {
+        let id = self.counter.next_waiter.fetch_add(1, Ordering::Relaxed);
+        self.counter.waiters.lock().insert(id, waker.clone());
+        true
+    }
+
+    fn ioctl(&self, _request: usize, _arg1: usize, _arg2: usize, _arg3: usize) -> LxResult<usize> {
+        Err(LxError::ENOTTY)
+    }
+
+    fn get_vmo(&self, _offset: usize, _len: usize) -> LxResult<Arc<VmObject>> {
+        Err(LxError::ENODEV)
+    }
+}
@@ -1,15 +1,18 @@
 //! File handle for process
 
 use alloc::{boxed::Box, string::String, sync::Arc};
+use core::task::Waker;
 
 use async_trait::async_trait;
 use spin::RwLock;
 
 use rcore_fs::vfs::{FileType, FsError, INode, Metadata, PollStatus};
 use zircon_object::object::*;
-use zircon_object::vm::{pages, VmObject};
+use zircon_object::vm::{pages, VmObject, PAGE_SIZE};
 
-use super::FileLike;
+use super::lock::{self, LockOwner};
+use super::page_cache::{copy_page_cache, read_page_cache, update_page_cache};
+use super::{get_page_cache, stdio::Stdin, FileLike, Pipe, Tty};
 use crate::error::{LxError, LxResult};
 
 bitflags::bitflags! {
@@ -31,6 +34,10 @@ bitflags::bitflags! {
         const APPEND = 1 << 10;
         /// non block open
         const NON_BLOCK = 1 << 11;
+        /// direct I/O, or packet mode for a pipe
+        const DIRECT = 1 << 14;
+        /// fail if the last component of the path is a symbolic link
+        const NOFOLLOW = 1 << 17;
         /// close on exec
         const CLOEXEC = 1 << 19;
     }
@@ -95,6 +102,14 @@ pub struct File {
 
 impl_kobject!(File);
 
+impl Drop for File {
+    /// Release the locks owned by the open file description.
+    fn drop(&mut self) {
+        let owner = LockOwner::file(self);
+        lock::release_locks(&self.inner.get_mut().inode, owner);
+    }
+}
+
 impl FileInner {
     /// read from file
     async fn read(&mut self, buf: &mut [u8]) -> LxResult<usize> {
@@ -108,6 +123,10 @@ impl FileInner {
         if !self.flags.readable() {
             return Err(LxError::EBADF);
         }
+        // mappings of the file may have changed the cached content
+        if let Some(len) = read_page_cache(&self.inode, offset as usize, buf)? {
+            return Ok(len);
+        }
         if !self.flags.non_block() {
             // block
             loop {
@@ -142,6 +161,7 @@ impl FileInner {
             return Err(LxError::EBADF);
         }
         let len = self.inode.write_at(offset as usize, buf)?;
+        update_page_cache(&self.inode, offset as usize, &buf[..len])?;
         Ok(len)
     }
 }
@@ -224,6 +244,22 @@ impl File {
     pub fn inode(&self) -> Arc<dyn INode> {
         self.inner.read().inode.clone()
     }
+
+    /// Returns the page cache shared by mappings of the file, which covers at least `len` bytes.
+    ///
+    /// Returns `None` if this is not a regular file.
+    pub fn page_cache(&self, len: usize) -> LxResult<Option<Arc<VmObject>>> {
+        let inode = self.inode();
+        match inode.metadata()?.type_ {
+            FileType::File => Ok(Some(get_page_cache(&inode, len)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the terminal if this file is a terminal device.
+    pub fn tty(&self) -> Option<Arc<Tty>> {
+        Tty::of(&*self.inner.read().inode)
+    }
 }
 
 #[async_trait]
@@ -240,19 +276,11 @@ impl FileLike for File {
         Ok(())
     }
 
-    fn dup(&self) -> Arc<dyn FileLike> {
-        Arc::new(Self {
-            base: KObjectBase::new(),
-            path: self.path.clone(),
-            inner: RwLock::new(self.inner.read().clone()),
-        })
-    }
-
     async fn read(&self, buf: &mut [u8]) -> LxResult<usize> {
         self.inner.write().read(buf).await
     }
 
-    fn write(&self, buf: &[u8]) -> LxResult<usize> {
+    async fn write(&self, buf: &[u8]) -> LxResult<usize> {
         self.inner.write().write(buf)
     }
 
@@ -272,6 +300,18 @@ impl FileLike for File {
         Ok(self.inner.read().inode.async_poll().await?)
     }
 
+    fn register_waker(&self, waker: &Waker) -> bool {
+        let inode = self.inode();
+        if let Some(pipe) = inode.downcast_ref::<Pipe>() {
+            pipe.register_waker(waker);
+        } else if let Some(stdin) = inode.downcast_ref::<Stdin>() {
+            stdin.register_waker(waker);
+        } else {
+            return false;
+        }
+        true
+    }
+
     fn ioctl(&self, request: usize, arg1: usize, _arg2: usize, _arg3: usize) -> LxResult<usize> {
         // ioctl syscall
         self.inner.read().inode.io_control(request as u32, arg1)?;
@@ -283,12 +323,8 @@ impl FileLike for File {
         let inner = self.inner.read();
         match inner.inode.metadata()?.type_ {
             FileType::File => {
-                // TODO: better implementation
-                let mut buf = alloc::vec![0; len];
-                let len = inner.inode.read_at(offset, &mut buf)?;
-                let vmo = VmObject::new_paged(pages(len));
-                vmo.write(0, &buf[..len])?;
-                Ok(vmo)
+                let len = pages(len) * PAGE_SIZE;
+                copy_page_cache(&inner.inode, offset, len)
             }
             FileType::CharDevice => {
                 use super::devfs::FbDev;
@@ -9,6 +9,11 @@ pub const TCGETS: usize = 0x5401;
 #[cfg(target_arch = "mips")]
 pub const TCGETS: usize = 0x540D;
 
+#[cfg(not(target_arch = "mips"))]
+pub const TIOCSCTTY: usize = 0x540E;
+#[cfg(target_arch = "mips")]
+pub const TIOCSCTTY: usize = 0x5480;
+
 #[cfg(not(target_arch = "mips"))]
 pub const TIOCGPGRP: usize = 0x540F;
 // _IOR('t', 119, int)
@@ -0,0 +1,377 @@
+//! Advisory file locks
+//!
+//! - `flock` locks of a whole file, owned by an open file description
+//! - POSIX record locks of byte ranges, owned by a process
+//! - open file description record locks (`F_OFD_*`), owned by an open file description
+//!
+//! Record locks of both owners conflict with each other, but not with `flock` locks.
+#![deny(missing_docs)]
+
+use alloc::{collections::BTreeMap, sync::Arc, vec, vec::Vec};
+use core::{
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+use lazy_static::lazy_static;
+use rcore_fs::vfs::INode;
+use spin::Mutex;
+use zircon_object::object::KoID;
+
+use super::{inode_key, File};
+use crate::error::{LxError, LxResult};
+
+/// The owner of a lock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockOwner {
+    /// An open file description, by the address of the `File`
+    File(usize),
+    /// A process, by its ID
+    Process(KoID),
+}
+
+impl LockOwner {
+    /// The open file description `file` as an owner.
+    pub fn file(file: &File) -> Self {
+        LockOwner::File(file as *const File as usize)
+    }
+}
+
+/// The type of a lock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockType {
+    /// A shared lock
+    Read,
+    /// An exclusive lock
+    Write,
+}
+
+/// The kind of a lock, locks of different kinds do not conflict
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockKind {
+    /// A `flock` lock of the whole file
+    Flock,
+    /// A record lock of a byte range
+    Record,
+}
+
+/// A lock on the bytes in `[start, end]` of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileLock {
+    /// owner of the lock
+    pub owner: LockOwner,
+    /// type of the lock
+    pub type_: LockType,
+    /// the first byte
+    pub start: u64,
+    /// the last byte, `u64::MAX` to lock up to the end of file however it grows
+    pub end: u64,
+}
+
+impl FileLock {
+    fn overlaps(&self, other: &FileLock) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    fn conflicts(&self, other: &FileLock) -> bool {
+        self.owner != other.owner
+            && self.overlaps(other)
+            && (self.type_ == LockType::Write || other.type_ == LockType::Write)
+    }
+
+    /// Whether the ranges of two locks overlap or are adjacent.
+    fn touches(&self, other: &FileLock) -> bool {
+        self.start <= other.end.saturating_add(1) && other.start <= self.end.saturating_add(1)
+    }
+}
+
+/// Locks on an inode
+struct InodeLocks {
+    /// Keeps the inode alive, as an inode out of a file system is keyed by its address
+    _inode: Arc<dyn INode>,
+    /// `flock` locks
+    flocks: Vec<FileLock>,
+    /// Record locks, the ranges of an owner never overlap or touch each other
+    records: Vec<FileLock>,
+    /// Wakers of the waiters for a conflicting lock to be released, by waiter ID
+    waiters: BTreeMap<usize, Waker>,
+}
+
+impl InodeLocks {
+    fn locks(&self, kind: LockKind) -> &Vec<FileLock> {
+        match kind {
+            LockKind::Flock => &self.flocks,
+            LockKind::Record => &self.records,
+        }
+    }
+
+    /// Returns a lock conflicting with `lock`, if any.
+    fn conflict(&self, kind: LockKind, lock: &FileLock) -> Option<FileLock> {
+        self.locks(kind).iter().find(|l| l.conflicts(lock)).cloned()
+    }
+
+    /// Add `lock`, replacing the locks of its owner in the range.
+    fn insert(&mut self, kind: LockKind, lock: FileLock) {
+        match kind {
+            LockKind::Flock => {
+                self.flocks.retain(|l| l.owner != lock.owner);
+                self.flocks.push(lock);
+            }
+            LockKind::Record => {
+                self.remove_range(lock.owner, lock.start, lock.end);
+                // merge with adjacent locks of the same type
+                let mut merged = lock;
+                self.records.retain(|l| {
+                    if l.owner == merged.owner && l.type_ == merged.type_ && l.touches(&merged) {
+                        merged.start = merged.start.min(l.start);
+                        merged.end = merged.end.max(l.end);
+                        false
+                    } else {
+                        true
+                    }
+                });
+                self.records.push(merged);
+            }
+        }
+    }
+
+    /// Remove the range `[start, end]` from the record locks of `owner`,
+    /// splitting the locks partly in the range.
+    fn remove_range(&mut self, owner: LockOwner, start: u64, end: u64) {
+        for lock in mem::take(&mut self.records) {
+            if lock.owner != owner || lock.end < start || lock.start > end {
+                self.records.push(lock);
+                continue;
+            }
+            if lock.start < start {
+                self.records.push(FileLock {
+                    end: start - 1,
+                    ..lock
+                });
+            }
+            if lock.end > end {
+                self.records.push(FileLock {
+                    start: end + 1,
+                    ..lock
+                });
+            }
+        }
+    }
+
+    fn wake_all(&mut self) {
+        for waker in mem::take(&mut self.waiters).into_values() {
+            waker.wake();
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.flocks.is_empty() && self.records.is_empty() && self.waiters.is_empty()
+    }
+}
+
+/// All locks in the system
+#[derive(Default)]
+struct LockTable {
+    /// Locks keyed by the file system and inode number of the inode
+    inodes: BTreeMap<(usize, usize), InodeLocks>,
+    /// The process of each blocked waiter and the owner of the lock it is waiting for,
+    /// by waiter ID, to detect deadlocks
+    waits_for: BTreeMap<usize, (KoID, KoID)>,
+    /// ID of the next waiter
+    next_waiter: usize,
+}
+
+impl LockTable {
+    fn get(&mut self, inode: &Arc<dyn INode>) -> &mut InodeLocks {
+        self.inodes
+            .entry(inode_key(inode))
+            .or_insert_with(|| InodeLocks {
+                _inode: inode.clone(),
+                flocks: Vec::new(),
+                records: Vec::new(),
+                waiters: BTreeMap::new(),
+            })
+    }
+
+    /// Apply `f` to the locks of `inode` if there is any, then wake up the waiters.
+    fn release(&mut self, key: (usize, usize), f: impl FnOnce(&mut InodeLocks)) {
+        if let Some(locks) = self.inodes.get_mut(&key) {
+            f(locks);
+            locks.wake_all();
+        }
+        self.remove_if_empty(key);
+    }
+
+    fn remove_if_empty(&mut self, key: (usize, usize)) {
+        if self.inodes.get(&key).map_or(false, InodeLocks::is_empty) {
+            self.inodes.remove(&key);
+        }
+    }
+
+    /// Whether the process `pid` waiting for `owner` would wait for itself,
+    /// through the threads of the processes blocked on the way.
+    fn would_deadlock(&self, pid: KoID, owner: KoID) -> bool {
+        let mut visited = Vec::new();
+        let mut owners = vec![owner];
+        while let Some(owner) = owners.pop() {
+            if owner == pid {
+                return true;
+            }
+            if visited.contains(&owner) {
+                continue;
+            }
+            visited.push(owner);
+            owners.extend(
+                self.waits_for
+                    .values()
+                    .filter(|&&(waiter, _)| waiter == owner)
+                    .map(|&(_, next)| next),
+            );
+        }
+        false
+    }
+}
+
+lazy_static! {
+    static ref LOCKS: Mutex<LockTable> = Mutex::new(LockTable::default());
+}
+
+/// Returns a lock conflicting with `lock` on `inode`, if any.
+pub fn test_lock(inode: &Arc<dyn INode>, kind: LockKind, lock: &FileLock) -> Option<FileLock> {
+    LOCKS
+        .lock()
+        .inodes
+        .get(&inode_key(inode))
+        .and_then(|locks| locks.conflict(kind, lock))
+}
+
+/// Acquire `lock` on `inode`.
+///
+/// If a conflicting lock is held, fails with `EAGAIN` unless `wait` is true,
+/// in which case it waits for the conflicting locks to be released. A waiting
+/// process fails with `EDEADLK` if the owner of the conflicting record lock is
+/// waiting for it, directly or not, and with `EINTR` once `interrupted` returns
+/// true. `interrupted` is only called when blocked, and should register the waker
+/// to be woken when it would return true.
+pub async fn lock<F>(
+    inode: &Arc<dyn INode>,
+    kind: LockKind,
+    lock: FileLock,
+    wait: bool,
+    interrupted: F,
+) -> LxResult
+where
+    F: FnMut(&mut Context) -> bool + Unpin,
+{
+    LockFuture {
+        inode,
+        kind,
+        lock,
+        wait,
+        interrupted,
+        id: None,
+    }
+    .await
+}
+
+/// Unlock the bytes in `[start, end]` of record locks, or the `flock` lock of `owner` on `inode`.
+pub fn unlock(inode: &Arc<dyn INode>, kind: LockKind, owner: LockOwner, start: u64, end: u64) {
+    LOCKS.lock().release(inode_key(inode), |locks| match kind {
+        LockKind::Flock => locks.flocks.retain(|l| l.owner != owner),
+        LockKind::Record => locks.remove_range(owner, start, end),
+    });
+}
+
+/// Release all locks of `owner` on `inode`, when the last descriptor of an open file
+/// description is closed, or a process closes any descriptor of the file.
+pub fn release_locks(inode: &Arc<dyn INode>, owner: LockOwner) {
+    LOCKS.lock().release(inode_key(inode), |locks| {
+        locks.flocks.retain(|l| l.owner != owner);
+        locks.records.retain(|l| l.owner != owner);
+    });
+}
+
+/// Release all record locks of the process `pid`, when it exits.
+pub fn release_process_locks(pid: KoID) {
+    let mut table = LOCKS.lock();
+    let owner = LockOwner::Process(pid);
+    let keys = table
+        .inodes
+        .iter()
+        .filter(|(_, locks)| locks.records.iter().any(|l| l.owner == owner))
+        .map(|(&key, _)| key)
+        .collect::<Vec<_>>();
+    for key in keys {
+        table.release(key, |locks| locks.records.retain(|l| l.owner != owner));
+    }
+}
+
+#[must_use = "future does nothing unless polled/`await`-ed"]
+struct LockFuture<'a, F> {
+    inode: &'a Arc<dyn INode>,
+    kind: LockKind,
+    lock: FileLock,
+    wait: bool,
+    interrupted: F,
+    /// ID of the waiter, once the future has been blocked
+    id: Option<usize>,
+}
+
+impl<F: FnMut(&mut Context) -> bool + Unpin> Future for LockFuture<'_, F> {
+    type Output = LxResult;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // a woken waiter checks for signals first, so a killed process does not take the lock
+        if self.id.is_some() && (self.interrupted)(cx) {
+            return Poll::Ready(Err(LxError::EINTR));
+        }
+        let mut table = LOCKS.lock();
+        let (kind, lock) = (self.kind, self.lock);
+        let locks = table.get(self.inode);
+        let conflict = match locks.conflict(kind, &lock) {
+            Some(conflict) => conflict,
+            None => {
+                locks.insert(kind, lock);
+                return Poll::Ready(Ok(()));
+            }
+        };
+        if !self.wait {
+            table.remove_if_empty(inode_key(self.inode));
+            return Poll::Ready(Err(LxError::EAGAIN));
+        }
+        let blocked = self.id.is_some();
+        let id = *self.id.get_or_insert_with(|| {
+            table.next_waiter += 1;
+            table.next_waiter
+        });
+        if let (LockOwner::Process(pid), LockOwner::Process(owner)) = (lock.owner, conflict.owner) {
+            if table.would_deadlock(pid, owner) {
+                return Poll::Ready(Err(LxError::EDEADLK));
+            }
+            table.waits_for.insert(id, (pid, owner));
+        }
+        table.get(self.inode).waiters.insert(id, cx.waker().clone());
+        drop(table);
+        if !blocked && (self.interrupted)(cx) {
+            return Poll::Ready(Err(LxError::EINTR));
+        }
+        Poll::Pending
+    }
+}
+
+impl<F> Drop for LockFuture<'_, F> {
+    /// Remove the waiter, also when the wait is interrupted or cancelled.
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            let mut table = LOCKS.lock();
+            table.waits_for.remove(&id);
+            let key = inode_key(self.inode);
+            if let Some(locks) = table.inodes.get_mut(&key) {
+                locks.waiters.remove(&id);
+            }
+            table.remove_if_empty(key);
+        }
+    }
+}
@@ -1,28 +1,41 @@
 //! Linux file objects
 
 mod devfs;
+mod epoll;
+mod eventfd;
 mod file;
 mod ioctl;
+mod mount;
+mod page_cache;
 mod pipe;
+mod procfs;
 mod pseudo;
+mod signalfd;
 mod stdio;
+mod timerfd;
+mod tty;
 
+pub mod lock;
 pub mod rcore_fs_wrapper;
 
-use alloc::{boxed::Box, string::ToString, sync::Arc, vec::Vec};
-use core::convert::TryFrom;
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use core::{convert::TryFrom, task::Waker};
 
 use async_trait::async_trait;
 use downcast_rs::impl_downcast;
 
 use kernel_hal::drivers;
-use rcore_fs::vfs::{FileSystem, FileType, INode, PollStatus, Result};
+use rcore_fs::vfs::{FileType, INode, PollStatus, Result};
 use rcore_fs_devfs::{
     special::{NullINode, ZeroINode},
     DevFS,
 };
-use rcore_fs_mountfs::MountFS;
-use rcore_fs_ramfs::RamFS;
+use rcore_fs_mountfs::MNode;
 use zircon_object::{object::KernelObject, vm::VmObject};
 
 use crate::error::{LxError, LxResult};
@@ -30,10 +43,23 @@ use crate::process::LinuxProcess;
 use devfs::RandomINode;
 use pseudo::Pseudo;
 
+pub use epoll::{EpollCtlOp, EpollEvent, EpollEvents, EpollInstance, EpollTarget};
+pub use eventfd::EventFd;
 pub use file::{File, OpenFlags, SeekFrom};
+pub use mount::{
+    find_fs_type, register_fs_type, FsConstructor, FsType, Mount, MountFlags, MountTable,
+    UmountFlags,
+};
+pub use page_cache::{get_page_cache, sync_page_cache};
 pub use pipe::Pipe;
+pub use procfs::ProcFS;
 pub use rcore_fs::vfs;
+pub use signalfd::SignalFd;
 pub use stdio::{STDIN, STDOUT};
+pub use timerfd::TimerFd;
+pub use tty::Tty;
+
+pub(crate) use tty::CONSOLE_TTY;
 
 #[async_trait]
 /// Generic file interface
@@ -46,12 +72,10 @@ pub trait FileLike: KernelObject {
     fn flags(&self) -> OpenFlags;
     /// Set open flags.
     fn set_flags(&self, f: OpenFlags) -> LxResult;
-    /// Duplicate the file.
-    fn dup(&self) -> Arc<dyn FileLike>;
     /// read to buffer
     async fn read(&self, buf: &mut [u8]) -> LxResult<usize>;
     /// write from buffer
-    fn write(&self, buf: &[u8]) -> LxResult<usize>;
+    async fn write(&self, buf: &[u8]) -> LxResult<usize>;
     /// read to buffer at given offset
     async fn read_at(&self, offset: u64, buf: &mut [u8]) -> LxResult<usize>;
     /// write from buffer at given offset
@@ -60,6 +84,12 @@ pub trait FileLike: KernelObject {
     fn poll(&self) -> LxResult<PollStatus>;
     /// wait for some event on a file descriptor use async
     async fn async_poll(&self) -> LxResult<PollStatus>;
+    /// Register `waker` to be woken up once, when the status of the file changes.
+    ///
+    /// Returns `false` if the file can not notify its status changes.
+    fn register_waker(&self, _waker: &Waker) -> bool {
+        false
+    }
     /// manipulates the underlying device parameters of special files
     fn ioctl(&self, request: usize, arg1: usize, arg2: usize, arg3: usize) -> LxResult<usize>;
     /// Returns the [`VmObject`] representing the file with given `offset` and `len`.
@@ -109,12 +139,8 @@ impl From<FileDesc> for i32 {
     }
 }
 
-/// create root filesystem, mount DevFS and RamFS
-pub fn create_root_fs(rootfs: Arc<dyn FileSystem>) -> Arc<dyn INode> {
-    let rootfs = MountFS::new(rootfs);
-    let root = rootfs.mountpoint_root_inode();
-
-    // create DevFS
+/// create DevFS with device files
+fn create_devfs() -> Arc<DevFS> {
     let devfs = DevFS::new();
     let devfs_root = devfs.root();
     devfs_root
@@ -167,22 +193,7 @@ pub fn create_root_fs(rootfs: Arc<dyn FileSystem>) -> Arc<dyn INode> {
         }
     }
 
-    // mount DevFS at /dev
-    let dev = root.find(true, "dev").unwrap_or_else(|_| {
-        root.create("dev", FileType::Dir, 0o666)
-            .expect("failed to mkdir /dev")
-    });
-    dev.mount(devfs).expect("failed to mount DevFS");
-
-    // mount RamFS at /tmp
-    let ramfs = RamFS::new();
-    let tmp = root.find(true, "tmp").unwrap_or_else(|_| {
-        root.create("tmp", FileType::Dir, 0o666)
-            .expect("failed to mkdir /tmp")
-    });
-    tmp.mount(ramfs).expect("failed to mount RamFS");
-
-    root
+    devfs
 }
 
 /// extension for INode
@@ -204,6 +215,22 @@ impl INodeExt for dyn INode {
     }
 }
 
+/// Returns a key identifying the file of `inode`.
+///
+/// `MountFS` wraps each lookup in a new `MNode`, so a file in the tree is identified
+/// by its file system and inode number. Other inodes are identified by their address.
+pub(crate) fn inode_key(inode: &Arc<dyn INode>) -> (usize, usize) {
+    if let Some(mnode) = inode.downcast_ref::<MNode>() {
+        if let Ok(metadata) = mnode.metadata() {
+            return (
+                Arc::as_ptr(&mnode.fs()) as *const u8 as usize,
+                metadata.inode,
+            );
+        }
+    }
+    (0, Arc::as_ptr(inode) as *const u8 as usize)
+}
+
 impl LinuxProcess {
     /// Lookup INode from the process.
     ///
@@ -215,7 +242,8 @@ impl LinuxProcess {
     ///
     /// - If `path` is absolute, then `dirfd` is ignored.
     ///
-    /// - If `follow` is true, then dereference `path` if it is a symbolic link.
+    /// - Symbolic links in the intermediate components of `path` are always followed.
+    ///   If `follow` is true, then dereference `path` if it is a symbolic link.
     pub fn lookup_inode_at(
         &self,
         dirfd: FileDesc,
@@ -229,30 +257,122 @@ impl LinuxProcess {
             path,
             follow
         );
-        // hard code special path
-        if path == "/proc/self/exe" {
-            return Ok(Arc::new(Pseudo::new(
-                &self.execute_path(),
-                FileType::SymLink,
-            )));
-        }
-        let (fd_dir_path, fd_name) = split_path(path);
-        if fd_dir_path == "/proc/self/fd" {
-            let fd = FileDesc::try_from(fd_name)?;
-            let file = self.get_file(fd)?;
-            return Ok(Arc::new(Pseudo::new(file.path(), FileType::SymLink)));
+        // procfs does not know the calling process, so `/proc/self` is resolved here
+        let self_path;
+        let mut path = path;
+        if dirfd == FileDesc::CWD || path.starts_with('/') {
+            let abs_path = self.absolute_path(path);
+            if let Some(rest) = abs_path.strip_prefix("/proc/self") {
+                if rest.is_empty() && !follow {
+                    let pid = self.pid().to_string();
+                    return Ok(Arc::new(Pseudo::new(&pid, FileType::SymLink)));
+                }
+                if rest.is_empty() || rest.starts_with('/') {
+                    self_path = format!("/proc/{}{}", self.pid(), rest);
+                    path = &self_path;
+                }
+            }
         }
 
-        let follow_max_depth = if follow { FOLLOW_MAX_DEPTH } else { 0 };
-        if dirfd == FileDesc::CWD {
-            Ok(self
-                .root_inode()
-                .lookup(&self.current_working_directory())?
-                .lookup_follow(path, follow_max_depth)?)
+        let start = if path.starts_with('/') {
+            self.root_inode().clone()
+        } else if dirfd == FileDesc::CWD {
+            let cwd = self.current_working_directory();
+            self.walk_path(self.root_inode().clone(), &cwd, true)?
         } else {
-            let file = self.get_file(dirfd)?;
-            Ok(file.lookup_follow(path, follow_max_depth)?)
+            self.get_file(dirfd)?.inode()
+        };
+        self.walk_path(start, path, follow)
+    }
+
+    /// Walk `path` from the directory `start`, which should be the root for an
+    /// absolute `path`, following symbolic links in the
+    /// intermediate components, and in the last one if `follow` is true or
+    /// `path` ends with a slash.
+    ///
+    /// Fails with `ELOOP` if more than [`FOLLOW_MAX_DEPTH`] links are followed.
+    fn walk_path(
+        &self,
+        start: Arc<dyn INode>,
+        path: &str,
+        follow: bool,
+    ) -> LxResult<Arc<dyn INode>> {
+        let mut inode = start;
+        let must_be_dir = path.ends_with('/');
+        let follow = follow || must_be_dir;
+        // components to walk, the next one at the end
+        let mut rest = Vec::new();
+        push_components(&mut rest, path);
+        let mut links = 0;
+        while let Some(name) = rest.pop() {
+            if inode.metadata()?.type_ != FileType::Dir {
+                return Err(LxError::ENOTDIR);
+            }
+            let next = inode.find(&name)?;
+            if next.metadata()?.type_ == FileType::SymLink && (follow || !rest.is_empty()) {
+                links += 1;
+                if links > FOLLOW_MAX_DEPTH {
+                    return Err(LxError::ELOOP);
+                }
+                let target = String::from_utf8(next.read_as_vec()?).map_err(|_| LxError::EINVAL)?;
+                if target.is_empty() {
+                    return Err(LxError::ENOENT);
+                }
+                // an absolute target restarts from the root, a relative one from the directory
+                if target.starts_with('/') {
+                    inode = self.root_inode().clone();
+                }
+                push_components(&mut rest, &target);
+                continue;
+            }
+            inode = next;
+        }
+        if must_be_dir && inode.metadata()?.type_ != FileType::Dir {
+            return Err(LxError::ENOTDIR);
         }
+        Ok(inode)
+    }
+
+    /// Lookup the directory in which the file `path` is created, and the name of the file.
+    ///
+    /// If `follow` is true and the last component of `path` is a symbolic link,
+    /// the file is created at the target of the link, which may not exist.
+    pub fn lookup_parent_at(
+        &self,
+        dirfd: FileDesc,
+        path: &str,
+        follow: bool,
+    ) -> LxResult<(Arc<dyn INode>, String)> {
+        let (dir_path, file_name) = split_path(path);
+        let mut dir = self.lookup_inode_at(dirfd, dir_path, true)?;
+        let mut name = String::from(file_name);
+        let mut links = 0;
+        while follow {
+            let inode = match dir.find(&name) {
+                Ok(inode) => inode,
+                Err(_) => break,
+            };
+            if inode.metadata()?.type_ != FileType::SymLink {
+                break;
+            }
+            links += 1;
+            if links > FOLLOW_MAX_DEPTH {
+                return Err(LxError::ELOOP);
+            }
+            let target = String::from_utf8(inode.read_as_vec()?).map_err(|_| LxError::EINVAL)?;
+            if target.is_empty() {
+                return Err(LxError::ENOENT);
+            }
+            let (target_dir, target_name) = split_path(&target);
+            let start = if target.starts_with('/') {
+                self.root_inode().clone()
+            } else {
+                dir
+            };
+            dir = self.walk_path(start, target_dir, true)?;
+            name = String::from(target_name);
+        }
+        Ok((dir, name))
     }
 
     /// Lookup INode from the process.
@@ -261,6 +381,47 @@ impl LinuxProcess {
     pub fn lookup_inode(&self, path: &str) -> LxResult<Arc<dyn INode>> {
         self.lookup_inode_at(FileDesc::CWD, path, true)
     }
+
+    /// Mount a file system of type `fs_type` from `source` on the directory `target`.
+    ///
+    /// With [`MountFlags::REMOUNT`], only the flags of the mounted file system are changed.
+    pub fn mount(&self, source: &str, target: &str, fs_type: &str, flags: MountFlags) -> LxResult {
+        if !self.cred().privileged() {
+            return Err(LxError::EPERM);
+        }
+        let mountpoint = self.lookup_inode(target)?;
+        let target = self.absolute_path(target);
+        if flags.contains(MountFlags::REMOUNT) {
+            return self.mounts().remount(&target, flags);
+        }
+        if flags.intersects(MountFlags::BIND | MountFlags::MOVE) {
+            warn!("mount: bind and move are not supported");
+            return Err(LxError::EINVAL);
+        }
+        let fs_type = find_fs_type(fs_type)?;
+        let device = if fs_type.requires_device {
+            let device = self.lookup_inode(source)?;
+            match device.metadata()?.type_ {
+                FileType::File | FileType::BlockDevice => Some(device),
+                _ => return Err(LxError::ENOTBLK),
+            }
+        } else {
+            None
+        };
+        let fs = (fs_type.new)(device, self.mounts().job())?;
+        self.mounts()
+            .mount(mountpoint, target, source.into(), fs_type, flags, fs)
+    }
+
+    /// Unmount the file system mounted on `target`.
+    pub fn umount(&self, target: &str, flags: UmountFlags) -> LxResult {
+        if !self.cred().privileged() {
+            return Err(LxError::EPERM);
+        }
+        let follow = !flags.contains(UmountFlags::NOFOLLOW);
+        self.lookup_inode_at(FileDesc::CWD, target, follow)?;
+        self.mounts().umount(&self.absolute_path(target))
+    }
 }
 
 /// Split a `path` str to `(base_path, file_name)`
@@ -274,5 +435,13 @@ pub fn split_path(path: &str) -> (&str, &str) {
     (dir_path, file_name)
 }
 
-/// the max depth for following a link
-const FOLLOW_MAX_DEPTH: usize = 1;
+/// Push the components of `path` to `stack` in reverse order, so the first one is popped first.
+fn push_components(stack: &mut Vec<String>, path: &str) {
+    let components = path
+        .split('/')
+        .filter(|name| !name.is_empty() && *name != ".");
+    stack.extend(components.rev().map(String::from));
+}
+
+/// the max number of symbolic links followed in a path lookup
+const FOLLOW_MAX_DEPTH: usize = 40;
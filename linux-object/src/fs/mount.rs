@@ -0,0 +1,488 @@
+//! Mount table and file system types
+#![deny(missing_docs)]
+
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+
+use lazy_static::lazy_static;
+use rcore_fs::dev::{self, DevError, Device};
+use rcore_fs::vfs::{self, FileSystem, FileType, FsInfo, INode};
+use rcore_fs_mountfs::{MNode, MountFS};
+use rcore_fs_ramfs::RamFS;
+use rcore_fs_sfs::SimpleFileSystem;
+use spin::RwLock;
+use zircon_object::task::Job;
+
+use super::{create_devfs, procfs::ProcFS};
+use crate::error::{LxError, LxResult};
+
+bitflags::bitflags! {
+    /// Flags of `mount`
+    pub struct MountFlags: usize {
+        /// Mount read-only.
+        const RDONLY = 1;
+        /// Ignore set-user-ID and set-group-ID bits.
+        const NOSUID = 1 << 1;
+        /// Disallow access to device files.
+        const NODEV = 1 << 2;
+        /// Disallow program execution.
+        const NOEXEC = 1 << 3;
+        /// Writes are synced at once.
+        const SYNCHRONOUS = 1 << 4;
+        /// Alter flags of a mounted file system.
+        const REMOUNT = 1 << 5;
+        /// Allow mandatory locks.
+        const MANDLOCK = 1 << 6;
+        /// Directory modifications are synchronous.
+        const DIRSYNC = 1 << 7;
+        /// Do not update access times.
+        const NOATIME = 1 << 10;
+        /// Do not update directory access times.
+        const NODIRATIME = 1 << 11;
+        /// Bind a directory to another place.
+        const BIND = 1 << 12;
+        /// Move a subtree.
+        const MOVE = 1 << 13;
+        /// Apply recursively.
+        const REC = 1 << 14;
+        /// Suppress some warnings.
+        const SILENT = 1 << 15;
+        /// Update access times relative to modification times.
+        const RELATIME = 1 << 21;
+        /// Always update access times.
+        const STRICTATIME = 1 << 24;
+        /// Update times lazily.
+        const LAZYTIME = 1 << 25;
+    }
+}
+
+bitflags::bitflags! {
+    /// Flags of `umount2`
+    pub struct UmountFlags: usize {
+        /// Force unmounting even if busy.
+        const FORCE = 1;
+        /// Detach from the tree now, clean up when no longer busy.
+        const DETACH = 2;
+        /// Mark the mount point as expired.
+        const EXPIRE = 4;
+        /// Do not dereference `target` if it is a symbolic link.
+        const NOFOLLOW = 8;
+    }
+}
+
+impl MountFlags {
+    /// Flags that are kept by a mount point, the others are operations.
+    const PER_MOUNT: Self = Self::from_bits_truncate(
+        Self::RDONLY.bits()
+            | Self::NOSUID.bits()
+            | Self::NODEV.bits()
+            | Self::NOEXEC.bits()
+            | Self::SYNCHRONOUS.bits()
+            | Self::MANDLOCK.bits()
+            | Self::DIRSYNC.bits()
+            | Self::NOATIME.bits()
+            | Self::NODIRATIME.bits()
+            | Self::RELATIME.bits()
+            | Self::STRICTATIME.bits()
+            | Self::LAZYTIME.bits(),
+    );
+
+    /// Returns `f_flags` of `statfs`.
+    pub fn statfs_flags(self) -> usize {
+        // ST_RDONLY ... ST_NODIRATIME share the bits with MS_*
+        let same = Self::RDONLY
+            | Self::NOSUID
+            | Self::NODEV
+            | Self::NOEXEC
+            | Self::SYNCHRONOUS
+            | Self::MANDLOCK
+            | Self::NOATIME
+            | Self::NODIRATIME;
+        let mut flags = (self & same).bits();
+        if self.contains(Self::RELATIME) {
+            flags |= 1 << 12; // ST_RELATIME
+        }
+        flags
+    }
+
+    /// Returns the mount options shown in `/proc/mounts`.
+    fn options(self) -> String {
+        let mut options = vec![if self.contains(Self::RDONLY) {
+            "ro"
+        } else {
+            "rw"
+        }];
+        for &(flag, name) in [
+            (Self::NOSUID, "nosuid"),
+            (Self::NODEV, "nodev"),
+            (Self::NOEXEC, "noexec"),
+            (Self::SYNCHRONOUS, "sync"),
+            (Self::MANDLOCK, "mand"),
+            (Self::DIRSYNC, "dirsync"),
+            (Self::NOATIME, "noatime"),
+            (Self::NODIRATIME, "nodiratime"),
+            (Self::RELATIME, "relatime"),
+        ]
+        .iter()
+        {
+            if self.contains(flag) {
+                options.push(name);
+            }
+        }
+        options.join(",")
+    }
+}
+
+/// Constructor of a file system, with the device file if the type requires one,
+/// and the job whose processes share the file system tree.
+pub type FsConstructor =
+    fn(device: Option<Arc<dyn INode>>, job: &Weak<Job>) -> LxResult<Arc<dyn FileSystem>>;
+
+/// A file system type which can be mounted.
+#[derive(Clone, Copy)]
+pub struct FsType {
+    /// Name used by `mount` and shown in `/proc/mounts`
+    pub name: &'static str,
+    /// Magic number reported by `statfs`
+    pub magic: i64,
+    /// Whether the file system is loaded from a device
+    pub requires_device: bool,
+    /// Create a file system of this type
+    pub new: FsConstructor,
+}
+
+/// The file system at `/`, which is not created by `mount`.
+const ROOTFS: FsType = FsType {
+    name: "rootfs",
+    magic: 0x8584_58f6,
+    requires_device: false,
+    new: new_ramfs,
+};
+
+const TMPFS: FsType = FsType {
+    name: "tmpfs",
+    magic: 0x0102_1994,
+    requires_device: false,
+    new: new_ramfs,
+};
+
+const RAMFS: FsType = FsType {
+    name: "ramfs",
+    magic: 0x8584_58f6,
+    requires_device: false,
+    new: new_ramfs,
+};
+
+const DEVFS: FsType = FsType {
+    name: "devfs",
+    magic: 0x1373,
+    requires_device: false,
+    new: new_devfs,
+};
+
+const SFS: FsType = FsType {
+    name: "sfs",
+    magic: 0x2f8d_be2a,
+    requires_device: true,
+    new: new_sfs,
+};
+
+const PROC: FsType = FsType {
+    name: "proc",
+    magic: 0x9fa0,
+    requires_device: false,
+    new: new_procfs,
+};
+
+// TODO: sysfs is empty for now
+const SYSFS: FsType = FsType {
+    name: "sysfs",
+    magic: 0x6265_6572,
+    requires_device: false,
+    new: new_ramfs,
+};
+
+fn new_ramfs(_device: Option<Arc<dyn INode>>, _job: &Weak<Job>) -> LxResult<Arc<dyn FileSystem>> {
+    Ok(RamFS::new())
+}
+
+fn new_devfs(_device: Option<Arc<dyn INode>>, _job: &Weak<Job>) -> LxResult<Arc<dyn FileSystem>> {
+    Ok(create_devfs())
+}
+
+fn new_procfs(_device: Option<Arc<dyn INode>>, job: &Weak<Job>) -> LxResult<Arc<dyn FileSystem>> {
+    Ok(ProcFS::new(job.clone()))
+}
+
+fn new_sfs(device: Option<Arc<dyn INode>>, _job: &Weak<Job>) -> LxResult<Arc<dyn FileSystem>> {
+    let device = device.ok_or(LxError::ENOTBLK)?;
+    let sfs = SimpleFileSystem::open(Arc::new(FileDevice(device)))?;
+    Ok(sfs)
+}
+
+lazy_static! {
+    /// Registered file system types
+    static ref FS_TYPES: RwLock<BTreeMap<&'static str, FsType>> = {
+        let mut types = BTreeMap::new();
+        for &fs_type in [TMPFS, RAMFS, DEVFS, SFS, PROC, SYSFS].iter() {
+            types.insert(fs_type.name, fs_type);
+        }
+        RwLock::new(types)
+    };
+}
+
+/// Register a file system type, replacing the one with the same name.
+pub fn register_fs_type(fs_type: FsType) {
+    FS_TYPES.write().insert(fs_type.name, fs_type);
+}
+
+/// Find a registered file system type by `name`.
+pub fn find_fs_type(name: &str) -> LxResult<FsType> {
+    FS_TYPES.read().get(name).copied().ok_or(LxError::ENODEV)
+}
+
+/// A file used as the device of a file system, such as a disk image.
+struct FileDevice(Arc<dyn INode>);
+
+impl Device for FileDevice {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> dev::Result<usize> {
+        self.0.read_at(offset, buf).map_err(|_| DevError)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> dev::Result<usize> {
+        self.0.write_at(offset, buf).map_err(|_| DevError)
+    }
+
+    fn sync(&self) -> dev::Result<()> {
+        self.0.sync_all().map_err(|_| DevError)
+    }
+}
+
+/// A file system mounted on a directory.
+///
+/// `MountFS` can not remove a mount point, so the mounted file system is wrapped in a layer,
+/// which shows the covered directory again after the file system is detached.
+struct Layer {
+    /// The mounted file system, `None` if detached
+    fs: RwLock<Option<Arc<dyn FileSystem>>>,
+    /// The directory under the mount point
+    covered: Arc<dyn INode>,
+}
+
+impl FileSystem for Layer {
+    fn sync(&self) -> vfs::Result<()> {
+        match &*self.fs.read() {
+            Some(fs) => fs.sync(),
+            None => Ok(()),
+        }
+    }
+
+    fn root_inode(&self) -> Arc<dyn INode> {
+        match &*self.fs.read() {
+            Some(fs) => fs.root_inode(),
+            None => self.covered.clone(),
+        }
+    }
+
+    fn info(&self) -> FsInfo {
+        match &*self.fs.read() {
+            Some(fs) => fs.info(),
+            None => self.covered.fs().info(),
+        }
+    }
+}
+
+/// A mounted file system
+#[derive(Clone)]
+pub struct Mount {
+    /// Source device or name
+    pub source: String,
+    /// Absolute path of the mount point
+    pub target: String,
+    /// File system type
+    pub fs_type: FsType,
+    /// Mount flags
+    pub flags: MountFlags,
+    /// The file system in the tree
+    mountfs: Arc<MountFS>,
+    /// The detachable layer, `None` for the root
+    layer: Option<Arc<Layer>>,
+}
+
+/// The mount table of a file system tree, shared by the processes with the same root.
+pub struct MountTable {
+    /// The root directory
+    root: Arc<MNode>,
+    /// Mounted file systems, in the order of mounting
+    mounts: RwLock<Vec<Mount>>,
+    /// Layers detached by `umount`, which are reused when mounting at the same path again
+    detached: RwLock<BTreeMap<String, (Arc<MountFS>, Arc<Layer>)>>,
+    /// The job whose processes share the tree
+    job: Weak<Job>,
+}
+
+impl MountTable {
+    /// Create a tree of processes in `job`, with `rootfs` at `/`,
+    /// DevFS at `/dev`, ProcFS at `/proc` and RamFS at `/tmp`.
+    pub fn new(rootfs: Arc<dyn FileSystem>, job: Weak<Job>) -> Arc<Self> {
+        let mountfs = MountFS::new(rootfs);
+        let root = mountfs.mountpoint_root_inode();
+        let table = MountTable {
+            root: root.clone(),
+            mounts: RwLock::new(vec![Mount {
+                source: ROOTFS.name.to_string(),
+                target: "/".to_string(),
+                fs_type: ROOTFS,
+                flags: MountFlags::empty(),
+                mountfs,
+                layer: None,
+            }]),
+            detached: Default::default(),
+            job,
+        };
+        for &(name, fs_type) in [("dev", DEVFS), ("proc", PROC), ("tmp", TMPFS)].iter() {
+            let dir = root.find(true, name).unwrap_or_else(|_| {
+                root.create(name, FileType::Dir, 0o666)
+                    .unwrap_or_else(|_| panic!("failed to mkdir /{}", name))
+            });
+            let fs = (fs_type.new)(None, &table.job).expect("failed to create file system");
+            table
+                .mount(
+                    dir,
+                    format!("/{}", name),
+                    fs_type.name.to_string(),
+                    fs_type,
+                    MountFlags::empty(),
+                    fs,
+                )
+                .unwrap_or_else(|_| panic!("failed to mount /{}", name));
+        }
+        Arc::new(table)
+    }
+
+    /// Returns the root directory.
+    pub fn root(&self) -> Arc<dyn INode> {
+        self.root.clone()
+    }
+
+    /// Returns the job whose processes share the tree.
+    pub fn job(&self) -> &Weak<Job> {
+        &self.job
+    }
+
+    /// Mount `fs` on the directory `mountpoint`, whose absolute path is `target`.
+    pub fn mount(
+        &self,
+        mountpoint: Arc<dyn INode>,
+        target: String,
+        source: String,
+        fs_type: FsType,
+        flags: MountFlags,
+        fs: Arc<dyn FileSystem>,
+    ) -> LxResult {
+        if mountpoint.metadata()?.type_ != FileType::Dir {
+            return Err(LxError::ENOTDIR);
+        }
+        let mut mounts = self.mounts.write();
+        if mounts.iter().any(|m| m.target == target) {
+            return Err(LxError::EBUSY);
+        }
+        let detached = self.detached.write().remove(&target);
+        let (mountfs, layer) = match detached {
+            Some((mountfs, layer)) => {
+                *layer.fs.write() = Some(fs);
+                (mountfs, layer)
+            }
+            None => {
+                let mnode = mountpoint.downcast_ref::<MNode>().ok_or(LxError::EINVAL)?;
+                let layer = Arc::new(Layer {
+                    fs: RwLock::new(Some(fs)),
+                    covered: mountpoint.clone(),
+                });
+                (mnode.mount(layer.clone())?, layer)
+            }
+        };
+        mounts.push(Mount {
+            source,
+            target,
+            fs_type,
+            flags: flags & MountFlags::PER_MOUNT,
+            mountfs,
+            layer: Some(layer),
+        });
+        Ok(())
+    }
+
+    /// Change the flags of the file system mounted at `target`.
+    pub fn remount(&self, target: &str, flags: MountFlags) -> LxResult {
+        let mut mounts = self.mounts.write();
+        let mount = mounts
+            .iter_mut()
+            .find(|m| m.target == target)
+            .ok_or(LxError::EINVAL)?;
+        mount.flags = flags & MountFlags::PER_MOUNT;
+        Ok(())
+    }
+
+    /// Detach the file system mounted at `target`.
+    ///
+    /// Fails if `target` is not a mount point, or other file systems are mounted under it.
+    pub fn umount(&self, target: &str) -> LxResult {
+        let mut mounts = self.mounts.write();
+        let index = mounts
+            .iter()
+            .position(|m| m.target == target)
+            .ok_or(LxError::EINVAL)?;
+        let layer = mounts[index].layer.clone().ok_or(LxError::EBUSY)?;
+        let prefix = format!("{}/", target);
+        if mounts.iter().any(|m| m.target.starts_with(&prefix)) {
+            return Err(LxError::EBUSY);
+        }
+        let mount = mounts.remove(index);
+        if let Some(fs) = layer.fs.write().take() {
+            fs.sync().ok();
+        }
+        self.detached
+            .write()
+            .insert(mount.target, (mount.mountfs, layer));
+        Ok(())
+    }
+
+    /// Returns the mounted file system containing `inode`.
+    pub fn find(&self, inode: &Arc<dyn INode>) -> Option<Mount> {
+        let fs = inode.fs();
+        let ptr = Arc::as_ptr(&fs) as *const u8;
+        self.mounts
+            .read()
+            .iter()
+            .find(|m| Arc::as_ptr(&m.mountfs) as *const u8 == ptr)
+            .cloned()
+    }
+
+    /// Fail with `EROFS` if `inode` is on a file system mounted read-only.
+    pub fn check_writable(&self, inode: &Arc<dyn INode>) -> LxResult {
+        match self.find(inode) {
+            Some(m) if m.flags.contains(MountFlags::RDONLY) => Err(LxError::EROFS),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the content of `/proc/mounts`.
+    pub fn proc_mounts(&self) -> String {
+        let mut content = String::new();
+        for m in self.mounts.read().iter() {
+            content += &format!(
+                "{} {} {} {} 0 0\n",
+                m.source,
+                m.target,
+                m.fs_type.name,
+                m.flags.options()
+            );
+        }
+        content
+    }
+}
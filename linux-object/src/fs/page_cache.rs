@@ -0,0 +1,166 @@
+//! Page cache of regular files shared by `MAP_SHARED` mappings
+//!
+//! Each inode mapped as shared has a single VMO caching its content,
+//! so that all processes mapping the file see the same pages.
+//! The cache is filled when it is created or grown, and written back to the
+//! inode by [`sync_page_cache`], i.e. on `msync` and `munmap`.
+//! While the cache exists, `read` takes the content from it, and `write` updates
+//! both the inode and the cache.
+
+use alloc::{
+    collections::BTreeMap,
+    sync::{Arc, Weak},
+};
+
+use lazy_static::lazy_static;
+use rcore_fs::vfs::INode;
+use spin::Mutex;
+use zircon_object::vm::{pages, VmObject, PAGE_SIZE};
+
+use super::inode_key;
+use crate::error::LxResult;
+
+/// The page cache of an inode
+struct PageCache {
+    inode: Arc<dyn INode>,
+    vmo: Weak<VmObject>,
+}
+
+lazy_static! {
+    /// Page caches keyed by [`inode_key`]
+    static ref PAGE_CACHES: Mutex<BTreeMap<(usize, usize), PageCache>> = Mutex::new(BTreeMap::new());
+}
+
+/// Returns the page cache of `inode`, if it is mapped.
+fn find_page_cache(inode: &Arc<dyn INode>) -> Option<Arc<VmObject>> {
+    let caches = PAGE_CACHES.lock();
+    if caches.is_empty() {
+        return None;
+    }
+    caches
+        .get(&inode_key(inode))
+        .and_then(|cache| cache.vmo.upgrade())
+}
+
+/// Get the page cache of `inode` which covers at least `len` bytes.
+///
+/// The cache is only kept while some mapping refers to it.
+pub fn get_page_cache(inode: &Arc<dyn INode>, len: usize) -> LxResult<Arc<VmObject>> {
+    let mut caches = PAGE_CACHES.lock();
+    let key = inode_key(inode);
+    let len = pages(len) * PAGE_SIZE;
+    if let Some(vmo) = caches.get(&key).and_then(|cache| cache.vmo.upgrade()) {
+        let old_len = vmo.len();
+        if old_len < len {
+            vmo.set_len(len)?;
+            fill(inode, &vmo, old_len, len - old_len)?;
+        }
+        return Ok(vmo);
+    }
+    caches.retain(|_, cache| cache.vmo.strong_count() > 0);
+    let vmo = VmObject::new_paged_with_resizable(true, pages(len));
+    fill(inode, &vmo, 0, len)?;
+    caches.insert(
+        key,
+        PageCache {
+            inode: inode.clone(),
+            vmo: Arc::downgrade(&vmo),
+        },
+    );
+    Ok(vmo)
+}
+
+/// Read the content of `inode` in `[offset, offset + len)` into `vmo`.
+fn fill(inode: &Arc<dyn INode>, vmo: &VmObject, offset: usize, len: usize) -> LxResult {
+    let mut buf = alloc::vec![0; len];
+    let len = inode.read_at(offset, &mut buf)?;
+    vmo.write(offset, &buf[..len])?;
+    Ok(())
+}
+
+/// Write the range `[offset, offset + len)` of `vmo` back to its inode,
+/// if `vmo` is a page cache.
+///
+/// Only the content within the file size is written, the file is not extended.
+pub fn sync_page_cache(vmo: &Arc<VmObject>, offset: usize, len: usize) -> LxResult {
+    let inode = {
+        let caches = PAGE_CACHES.lock();
+        let cache = caches
+            .values()
+            .find(|cache| cache.vmo.as_ptr() == Arc::as_ptr(vmo));
+        match cache {
+            Some(cache) => cache.inode.clone(),
+            None => return Ok(()),
+        }
+    };
+    let size = inode.metadata()?.size;
+    if offset >= size {
+        return Ok(());
+    }
+    let len = len.min(size - offset).min(vmo.len() - offset);
+    let mut buf = alloc::vec![0; len];
+    vmo.read(offset, &mut buf)?;
+    inode.write_at(offset, &buf)?;
+    Ok(())
+}
+
+/// Read the content of `inode` at `offset` from its page cache.
+///
+/// Returns `None` if the inode is not cached.
+pub(super) fn read_page_cache(
+    inode: &Arc<dyn INode>,
+    offset: usize,
+    buf: &mut [u8],
+) -> LxResult<Option<usize>> {
+    let vmo = match find_page_cache(inode) {
+        Some(vmo) => vmo,
+        None => return Ok(None),
+    };
+    let size = inode.metadata()?.size;
+    if offset >= size {
+        return Ok(Some(0));
+    }
+    let len = buf.len().min(size - offset);
+    // the part beyond the cache is read from the inode
+    let cached = len.min(vmo.len().saturating_sub(offset));
+    vmo.read(offset, &mut buf[..cached])?;
+    if cached < len {
+        inode.read_at(offset + cached, &mut buf[cached..len])?;
+    }
+    Ok(Some(len))
+}
+
+/// Returns a private copy of the content of `inode` in `[offset, offset + len)`.
+///
+/// The copy is a copy-on-write clone of the page cache if it covers the range,
+/// otherwise only the range is read, through the page cache if there is one.
+pub(super) fn copy_page_cache(
+    inode: &Arc<dyn INode>,
+    offset: usize,
+    len: usize,
+) -> LxResult<Arc<VmObject>> {
+    if let Some(vmo) = find_page_cache(inode) {
+        if offset <= vmo.len() && len <= vmo.len() - offset {
+            return Ok(vmo.create_child(false, offset, len)?);
+        }
+    }
+    let vmo = VmObject::new_paged(pages(len));
+    let mut buf = alloc::vec![0; len];
+    let read = match read_page_cache(inode, offset, &mut buf)? {
+        Some(read) => read,
+        None => inode.read_at(offset, &mut buf)?,
+    };
+    vmo.write(0, &buf[..read])?;
+    Ok(vmo)
+}
+
+/// Update the page cache of `inode` after `buf` is written to the inode at `offset`.
+pub(super) fn update_page_cache(inode: &Arc<dyn INode>, offset: usize, buf: &[u8]) -> LxResult {
+    if let Some(vmo) = find_page_cache(inode) {
+        if offset < vmo.len() {
+            let len = buf.len().min(vmo.len() - offset);
+            vmo.write(offset, &buf[..len])?;
+        }
+    }
+    Ok(())
+}
@@ -7,7 +7,7 @@ use core::{any::Any, cmp::min};
 use core::{
     future::Future,
     pin::Pin,
-    task::{Context, Poll},
+    task::{Context, Poll, Waker},
 };
 use rcore_fs::vfs::*;
 use spin::Mutex;
@@ -22,10 +22,15 @@ pub enum PipeEnd {
     Write,
 }
 
+/// The max size of a packet of a pipe in packet mode
+const PIPE_BUF: usize = 4096;
+
 /// Pipe inner data
 pub struct PipeData {
     /// pipe buffer
     buf: VecDeque<u8>,
+    /// lengths of the packets in the buffer, if the pipe is in packet mode
+    packets: Option<VecDeque<usize>>,
     /// event bus for pipe
     eventbus: EventBus,
     /// number of pipe ends
@@ -52,8 +57,21 @@ impl Drop for Pipe {
 impl Pipe {
     /// Create a pair of INode: (read, write)
     pub fn create_pair() -> (Pipe, Pipe) {
+        Self::create(false)
+    }
+
+    /// Create a pair of INode in packet mode: (read, write)
+    ///
+    /// Each write is a separate packet of at most `PIPE_BUF` bytes,
+    /// and each read returns at most one packet, discarding the rest of it.
+    pub fn create_packet_pair() -> (Pipe, Pipe) {
+        Self::create(true)
+    }
+
+    fn create(packet: bool) -> (Pipe, Pipe) {
         let inner = PipeData {
             buf: VecDeque::new(),
+            packets: if packet { Some(VecDeque::new()) } else { None },
             eventbus: EventBus::default(),
             end_cnt: 2, // one read, one write
         };
@@ -69,6 +87,16 @@ impl Pipe {
             },
         )
     }
+
+    /// Register `waker` to be woken up once, when the status of the pipe changes.
+    pub fn register_waker(&self, waker: &Waker) {
+        let waker = waker.clone();
+        self.data.lock().eventbus.subscribe(Box::new(move |_| {
+            waker.wake_by_ref();
+            true
+        }));
+    }
+
     /// whether the pipe struct is readable
     fn can_read(&self) -> bool {
         if let PipeEnd::Read = self.direction {
@@ -101,10 +129,15 @@ impl INode for Pipe {
             if data.buf.is_empty() && data.end_cnt == 2 {
                 Err(FsError::Again)
             } else {
-                let len = min(buf.len(), data.buf.len());
+                let packet = data.packets.as_mut().and_then(VecDeque::pop_front);
+                let len = min(buf.len(), packet.unwrap_or(data.buf.len()));
                 for item in buf.iter_mut().take(len) {
                     *item = data.buf.pop_front().unwrap();
                 }
+                // the rest of a packet is discarded
+                if let Some(packet) = packet {
+                    data.buf.drain(..packet - len);
+                }
                 if data.buf.is_empty() {
                     data.eventbus.clear(Event::READABLE);
                 }
@@ -119,6 +152,9 @@ impl INode for Pipe {
     fn write_at(&self, _offset: usize, buf: &[u8]) -> Result<usize> {
         if let PipeEnd::Write = self.direction {
             let mut data = self.data.lock();
+            if let Some(packets) = &mut data.packets {
+                packets.extend(buf.chunks(PIPE_BUF).map(|chunk| chunk.len()));
+            }
             for c in buf {
                 data.buf.push_back(*c);
             }
@@ -0,0 +1,260 @@
+//! Content of the files in procfs
+
+use alloc::{string::String, vec::Vec};
+use core::time::Duration;
+
+use zircon_object::{
+    object::KernelObject,
+    task::{Job, Process, Status},
+    vm::{MMUFlags, PAGE_SIZE},
+};
+
+use crate::fs::CONSOLE_TTY;
+use crate::process::{job_processes, ProcessExt};
+
+/// Clock ticks per second, the unit of CPU times
+const USER_HZ: u64 = 100;
+
+/// Device number of `/dev/console`
+const CONSOLE_TTY_NR: u32 = (5 << 8) | 1;
+
+fn ticks(time: Duration) -> u64 {
+    time.as_nanos() as u64 / (1_000_000_000 / USER_HZ)
+}
+
+fn kb(bytes: usize) -> usize {
+    bytes / 1024
+}
+
+fn num_cpus() -> usize {
+    kernel_hal::vdso::vdso_constants().max_num_cpus as usize
+}
+
+/// Strings terminated by NUL, as `cmdline` and `environ`.
+pub(super) fn nul_separated(strings: &[String]) -> Vec<u8> {
+    let mut content = Vec::new();
+    for s in strings {
+        content.extend_from_slice(s.as_bytes());
+        content.push(0);
+    }
+    content
+}
+
+/// Returns the time `proc` has run in user mode.
+fn user_time(proc: &Process) -> Duration {
+    proc.linux().user_time()
+}
+
+/// Returns the time `proc` has run in kernel mode.
+fn system_time(proc: &Process) -> Duration {
+    proc.linux().usage().system_time
+}
+
+/// Returns the file name of the program, truncated as the `comm` of Linux.
+fn comm(proc: &Process) -> String {
+    let path = proc.linux().execute_path();
+    let name = path.rsplit('/').next().unwrap_or_default();
+    name.chars().take(15).collect()
+}
+
+/// Returns the state code and name of `proc`.
+fn state(proc: &Process) -> (char, &'static str) {
+    match proc.status() {
+        Status::Exited(_) => ('Z', "zombie"),
+        _ if proc.linux().is_stopped() => ('T', "stopped"),
+        Status::Running => ('R', "running"),
+        Status::Init => ('S', "sleeping"),
+    }
+}
+
+/// Returns the virtual memory size and resident set size of `proc`.
+fn memory_size(proc: &Process) -> (usize, usize) {
+    let mappings = proc.vmar().get_mappings();
+    let vsize = mappings.iter().map(|m| m.size).sum();
+    let rss = mappings.iter().map(|m| m.committed_bytes).sum();
+    (vsize, rss)
+}
+
+fn ppid(proc: &Process) -> u64 {
+    proc.linux().parent().map_or(0, |p| p.id())
+}
+
+/// `/proc/<pid>/stat`
+pub(super) fn process_stat(proc: &Process) -> String {
+    let linux = proc.linux();
+    let (tty_nr, tpgid) = match CONSOLE_TTY.foreground(proc) {
+        Ok(pgid) => (CONSOLE_TTY_NR, pgid as i64),
+        Err(_) => (0, -1),
+    };
+    let (vsize, rss) = memory_size(proc);
+    let children = linux.children_usage();
+    let mut content = format!(
+        "{} ({}) {} {} {} {} {} {} 0 {} {} 0 0 {} {} {} {} 20 0 {} 0 0 {} {} {}",
+        proc.id(),
+        comm(proc),
+        state(proc).0,
+        ppid(proc),
+        linux.pgid(),
+        linux.sid(),
+        tty_nr,
+        tpgid,
+        linux.usage().minor_faults,
+        children.minor_faults,
+        ticks(user_time(proc)),
+        ticks(system_time(proc)),
+        ticks(children.user_time),
+        ticks(children.system_time),
+        proc.thread_ids().len(),
+        vsize,
+        rss / PAGE_SIZE,
+        u64::MAX,
+    );
+    // the remaining fields about addresses, signals and scheduling are not tracked
+    for _ in 26..=52 {
+        content += " 0";
+    }
+    content.push('\n');
+    content
+}
+
+/// `/proc/<pid>/status`
+pub(super) fn process_status(proc: &Process) -> String {
+    let linux = proc.linux();
+    let cred = linux.cred();
+    let (code, name) = state(proc);
+    let (vsize, rss) = memory_size(proc);
+    let groups: String = cred.groups.iter().map(|g| format!("{} ", g)).collect();
+    format!(
+        "Name:\t{}\n\
+         State:\t{} ({})\n\
+         Tgid:\t{}\n\
+         Pid:\t{}\n\
+         PPid:\t{}\n\
+         Uid:\t{}\t{}\t{}\t{}\n\
+         Gid:\t{}\t{}\t{}\t{}\n\
+         Groups:\t{}\n\
+         VmSize:\t{:8} kB\n\
+         VmRSS:\t{:8} kB\n\
+         Threads:\t{}\n",
+        comm(proc),
+        code,
+        name,
+        proc.id(),
+        proc.id(),
+        ppid(proc),
+        cred.uid.real,
+        cred.uid.effective,
+        cred.uid.saved,
+        cred.uid.fs,
+        cred.gid.real,
+        cred.gid.effective,
+        cred.gid.saved,
+        cred.gid.fs,
+        groups,
+        kb(vsize),
+        kb(rss),
+        proc.thread_ids().len(),
+    )
+}
+
+/// `/proc/<pid>/maps`
+pub(super) fn process_maps(proc: &Process) -> String {
+    let mut content = String::new();
+    for m in proc.vmar().get_mappings() {
+        let perm = |flag, c| if m.flags.contains(flag) { c } else { '-' };
+        let mut line = format!(
+            "{:08x}-{:08x} {}{}{}p {:08x} 00:00 0",
+            m.addr,
+            m.addr + m.size,
+            perm(MMUFlags::READ, 'r'),
+            perm(MMUFlags::WRITE, 'w'),
+            perm(MMUFlags::EXECUTE, 'x'),
+            m.vmo_offset,
+        );
+        // the name starts at the same column as Linux
+        let name = m.vmo.name();
+        if !name.is_empty() {
+            let pad = 73usize.saturating_sub(line.len()).max(1);
+            line.extend(core::iter::repeat(' ').take(pad));
+            line += &name;
+        }
+        content += &line;
+        content.push('\n');
+    }
+    content
+}
+
+/// `/proc/cpuinfo`
+pub(super) fn cpuinfo() -> String {
+    let mut content = String::new();
+    for id in 0..num_cpus() {
+        content += &format!(
+            "processor\t: {}\ncpu MHz\t\t: {}.000\n\n",
+            id,
+            kernel_hal::cpu::cpu_frequency()
+        );
+    }
+    content
+}
+
+/// `/proc/meminfo`
+pub(super) fn meminfo() -> String {
+    let (total_frames, free_frames) = kernel_hal::mem::frame_stats();
+    let (total, free) = (total_frames * PAGE_SIZE, free_frames * PAGE_SIZE);
+    let mut content = String::new();
+    for &(name, bytes) in [
+        ("MemTotal", total),
+        ("MemFree", free),
+        ("MemAvailable", free),
+        ("Buffers", 0),
+        ("Cached", 0),
+        ("SwapTotal", 0),
+        ("SwapFree", 0),
+    ]
+    .iter()
+    {
+        content += &format!("{:<16}{:>8} kB\n", format!("{}:", name), kb(bytes));
+    }
+    content
+}
+
+/// `/proc/uptime`
+pub(super) fn uptime() -> String {
+    let now = kernel_hal::timer::timer_now();
+    format!("{}.{:02} 0.00\n", now.as_secs(), now.subsec_millis() / 10)
+}
+
+/// `/proc/loadavg`
+pub(super) fn loadavg(job: &Job) -> String {
+    let procs: Vec<_> = job_processes(job).collect();
+    let threads: usize = procs.iter().map(|p| p.thread_ids().len()).sum();
+    let running = procs.iter().filter(|p| state(p).0 == 'R').count();
+    let last_pid = procs.iter().map(|p| p.id()).max().unwrap_or(0);
+    format!("0.00 0.00 0.00 {}/{} {}\n", running, threads, last_pid)
+}
+
+/// `/proc/stat`
+pub(super) fn stat(job: &Job) -> String {
+    let procs: Vec<_> = job_processes(job).collect();
+    let cpus = num_cpus() as u64;
+    let user = procs.iter().map(|p| ticks(user_time(p))).sum::<u64>();
+    let system = procs.iter().map(|p| ticks(system_time(p))).sum::<u64>();
+    let idle = (ticks(kernel_hal::timer::timer_now()) * cpus).saturating_sub(user + system);
+    let mut content = format!("cpu  {} 0 {} {} 0 0 0 0 0 0\n", user, system, idle);
+    for id in 0..cpus {
+        content += &format!(
+            "cpu{} {} 0 {} {} 0 0 0 0 0 0\n",
+            id,
+            user / cpus,
+            system / cpus,
+            idle / cpus
+        );
+    }
+    let running = procs.iter().filter(|p| state(p).0 == 'R').count();
+    content += &format!(
+        "btime 0\nprocesses {}\nprocs_running {}\nprocs_blocked 0\n",
+        procs.len(),
+        running
+    );
+    content
+}
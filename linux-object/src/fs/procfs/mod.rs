@@ -0,0 +1,417 @@
+//! Process information pseudo-filesystem mounted at `/proc`
+#![deny(missing_docs)]
+
+use alloc::{
+    string::{String, ToString},
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use core::any::Any;
+use core::convert::TryFrom;
+
+use rcore_fs::vfs::*;
+use zircon_object::{
+    object::{KernelObject, KoID},
+    task::{Job, Process},
+    vm::PAGE_SIZE,
+};
+
+use super::{File, FileDesc, FileLike};
+use crate::process::{job_processes, ProcessExt};
+
+mod content;
+
+/// Files under `/proc` about the whole system
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InfoFile {
+    CpuInfo,
+    LoadAvg,
+    MemInfo,
+    Mounts,
+    Stat,
+    Uptime,
+}
+
+const INFO_FILES: &[(&str, InfoFile)] = &[
+    ("cpuinfo", InfoFile::CpuInfo),
+    ("loadavg", InfoFile::LoadAvg),
+    ("meminfo", InfoFile::MemInfo),
+    ("mounts", InfoFile::Mounts),
+    ("stat", InfoFile::Stat),
+    ("uptime", InfoFile::Uptime),
+];
+
+/// Files under `/proc/<pid>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProcessFile {
+    Cmdline,
+    Cwd,
+    Environ,
+    Exe,
+    Maps,
+    Mounts,
+    Stat,
+    Status,
+}
+
+const PROCESS_FILES: &[(&str, ProcessFile)] = &[
+    ("cmdline", ProcessFile::Cmdline),
+    ("cwd", ProcessFile::Cwd),
+    ("environ", ProcessFile::Environ),
+    ("exe", ProcessFile::Exe),
+    ("maps", ProcessFile::Maps),
+    ("mounts", ProcessFile::Mounts),
+    ("stat", ProcessFile::Stat),
+    ("status", ProcessFile::Status),
+];
+
+/// Name of the file descriptor directory under `/proc/<pid>`
+const FD_DIR: &str = "fd";
+
+/// A node in the tree, whose content is generated when read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Node {
+    /// `/proc`
+    Root,
+    /// `/proc/<name>`
+    Info(InfoFile),
+    /// `/proc/<pid>`
+    Process(KoID),
+    /// `/proc/<pid>/<name>`
+    ProcessFile(KoID, ProcessFile),
+    /// `/proc/<pid>/fd`
+    FdDir(KoID),
+    /// `/proc/<pid>/fd/<fd>`, a link to the opened file
+    Fd(KoID, FileDesc),
+}
+
+/// Process information file system.
+///
+/// Directories of processes are listed from the job, and files are generated
+/// from live process data every time they are read.
+pub struct ProcFS {
+    /// The job containing the listed processes
+    job: Weak<Job>,
+    /// Reference to self, for the inodes
+    self_ref: Weak<ProcFS>,
+}
+
+impl ProcFS {
+    /// Create a file system showing the processes in `job`.
+    pub fn new(job: Weak<Job>) -> Arc<Self> {
+        Arc::new_cyclic(|self_ref| ProcFS {
+            job,
+            self_ref: self_ref.clone(),
+        })
+    }
+
+    fn inode(&self, node: Node) -> Arc<dyn INode> {
+        Arc::new(ProcINode {
+            fs: self.self_ref.upgrade().unwrap(),
+            node,
+        })
+    }
+
+    fn job(&self) -> Result<Arc<Job>> {
+        self.job.upgrade().ok_or(FsError::EntryNotFound)
+    }
+
+    /// Returns the process `pid`, which may have exited since the inode was found.
+    fn process(&self, pid: KoID) -> Result<Arc<Process>> {
+        self.job()?
+            .get_child(pid)
+            .ok()
+            .and_then(|obj| obj.downcast_arc::<Process>().ok())
+            .ok_or(FsError::EntryNotFound)
+    }
+
+    /// Returns the IDs of live processes in ascending order.
+    fn pids(&self) -> Vec<KoID> {
+        let mut pids: Vec<_> = match self.job.upgrade() {
+            Some(job) => job_processes(&job).map(|p| p.id()).collect(),
+            None => Vec::new(),
+        };
+        pids.sort_unstable();
+        pids
+    }
+}
+
+impl FileSystem for ProcFS {
+    fn sync(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn INode> {
+        self.inode(Node::Root)
+    }
+
+    fn info(&self) -> FsInfo {
+        FsInfo {
+            bsize: PAGE_SIZE,
+            frsize: PAGE_SIZE,
+            blocks: 0,
+            bfree: 0,
+            bavail: 0,
+            files: 0,
+            ffree: 0,
+            namemax: 255,
+        }
+    }
+}
+
+/// An inode of [`ProcFS`]
+pub struct ProcINode {
+    fs: Arc<ProcFS>,
+    node: Node,
+}
+
+impl ProcINode {
+    /// Returns the child nodes of a directory.
+    fn children(&self) -> Result<Vec<(String, Node)>> {
+        let children = match self.node {
+            Node::Root => {
+                let mut children: Vec<_> = self
+                    .fs
+                    .pids()
+                    .into_iter()
+                    .map(|pid| (pid.to_string(), Node::Process(pid)))
+                    .collect();
+                children.extend(
+                    INFO_FILES
+                        .iter()
+                        .map(|&(name, file)| (name.to_string(), Node::Info(file))),
+                );
+                children
+            }
+            Node::Process(pid) => {
+                self.fs.process(pid)?;
+                let mut children = vec![(FD_DIR.to_string(), Node::FdDir(pid))];
+                children.extend(
+                    PROCESS_FILES
+                        .iter()
+                        .map(|&(name, file)| (name.to_string(), Node::ProcessFile(pid, file))),
+                );
+                children
+            }
+            Node::FdDir(pid) => {
+                let files = self
+                    .fs
+                    .process(pid)?
+                    .linux()
+                    .get_files()
+                    .unwrap_or_default();
+                let mut fds: Vec<_> = files.keys().copied().collect();
+                fds.sort_unstable();
+                fds.into_iter()
+                    .map(|fd| (usize::from(fd).to_string(), Node::Fd(pid, fd)))
+                    .collect()
+            }
+            _ => return Err(FsError::NotDir),
+        };
+        Ok(children)
+    }
+
+    /// Returns the parent node of a directory.
+    fn parent(&self) -> Node {
+        match self.node {
+            Node::FdDir(pid) => Node::Process(pid),
+            _ => Node::Root,
+        }
+    }
+
+    /// Generates the content of a file, or the target of a link.
+    fn content(&self) -> Result<Vec<u8>> {
+        let content = match self.node {
+            Node::Root | Node::Process(_) | Node::FdDir(_) => return Err(FsError::IsDir),
+            Node::Info(file) => match file {
+                InfoFile::CpuInfo => content::cpuinfo(),
+                InfoFile::LoadAvg => content::loadavg(&self.fs.job()?),
+                InfoFile::MemInfo => content::meminfo(),
+                InfoFile::Mounts => {
+                    // all processes in the job share the same mount table
+                    let job = self.fs.job()?;
+                    let proc = job_processes(&job).next().ok_or(FsError::EntryNotFound)?;
+                    proc.linux().mounts().proc_mounts()
+                }
+                InfoFile::Stat => content::stat(&self.fs.job()?),
+                InfoFile::Uptime => content::uptime(),
+            },
+            Node::ProcessFile(pid, file) => {
+                let proc = self.fs.process(pid)?;
+                let linux = proc.linux();
+                match file {
+                    ProcessFile::Cmdline => return Ok(content::nul_separated(&linux.args().0)),
+                    ProcessFile::Environ => return Ok(content::nul_separated(&linux.args().1)),
+                    ProcessFile::Cwd => linux.current_working_directory(),
+                    ProcessFile::Exe => linux.execute_path(),
+                    ProcessFile::Maps => content::process_maps(&proc),
+                    ProcessFile::Mounts => linux.mounts().proc_mounts(),
+                    ProcessFile::Stat => content::process_stat(&proc),
+                    ProcessFile::Status => content::process_status(&proc),
+                }
+            }
+            Node::Fd(pid, fd) => {
+                let file = self
+                    .fs
+                    .process(pid)?
+                    .linux()
+                    .get_file_like(fd)
+                    .map_err(|_| FsError::EntryNotFound)?;
+                fd_target(&file)
+            }
+        };
+        Ok(content.into_bytes())
+    }
+
+    fn inode_id(&self) -> usize {
+        // the low 32 bits identify the file in the directory of a process
+        let (pid, index) = match self.node {
+            Node::Root => (0, 1),
+            Node::Info(file) => (0, 2 + INFO_FILES.iter().position(|f| f.1 == file).unwrap()),
+            Node::Process(pid) => (pid, 0),
+            Node::ProcessFile(pid, file) => (
+                pid,
+                1 + PROCESS_FILES.iter().position(|f| f.1 == file).unwrap(),
+            ),
+            Node::FdDir(pid) => (pid, 0x100),
+            Node::Fd(pid, fd) => (pid, 0x1000 + usize::from(fd)),
+        };
+        ((pid as usize) << 32) | index
+    }
+}
+
+/// Returns the target of a link under `/proc/<pid>/fd`.
+fn fd_target(file: &Arc<dyn FileLike>) -> String {
+    match file.clone().downcast_arc::<File>() {
+        Ok(file) => file.path().clone(),
+        Err(_) => format!("anon_inode:[{}]", file.type_name()),
+    }
+}
+
+impl INode for ProcINode {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize> {
+        let content = self.content()?;
+        if offset >= content.len() {
+            return Ok(0);
+        }
+        let len = (content.len() - offset).min(buf.len());
+        buf[..len].copy_from_slice(&content[offset..offset + len]);
+        Ok(len)
+    }
+
+    fn write_at(&self, _offset: usize, _buf: &[u8]) -> Result<usize> {
+        Err(FsError::NotSupported)
+    }
+
+    fn poll(&self) -> Result<PollStatus> {
+        Ok(PollStatus {
+            read: true,
+            write: false,
+            error: false,
+        })
+    }
+
+    fn metadata(&self) -> Result<Metadata> {
+        let (type_, mode, size) = match self.node {
+            Node::Root | Node::Process(_) => (FileType::Dir, 0o555, 0),
+            Node::FdDir(_) => (FileType::Dir, 0o500, 0),
+            Node::ProcessFile(_, ProcessFile::Cwd)
+            | Node::ProcessFile(_, ProcessFile::Exe)
+            | Node::Fd(..) => (FileType::SymLink, 0o777, self.content()?.len()),
+            Node::ProcessFile(_, ProcessFile::Environ) => (FileType::File, 0o400, 0),
+            Node::Info(_) | Node::ProcessFile(..) => (FileType::File, 0o444, 0),
+        };
+        // files of a process are owned by its effective user
+        let (uid, gid) = match self.node {
+            Node::Process(pid)
+            | Node::ProcessFile(pid, _)
+            | Node::FdDir(pid)
+            | Node::Fd(pid, _) => {
+                let cred = self.fs.process(pid)?.linux().cred();
+                (cred.uid.effective as usize, cred.gid.effective as usize)
+            }
+            _ => (0, 0),
+        };
+        Ok(Metadata {
+            dev: 0,
+            inode: self.inode_id(),
+            size,
+            blk_size: 0,
+            blocks: 0,
+            atime: Timespec { sec: 0, nsec: 0 },
+            mtime: Timespec { sec: 0, nsec: 0 },
+            ctime: Timespec { sec: 0, nsec: 0 },
+            type_,
+            mode,
+            nlinks: if type_ == FileType::Dir { 2 } else { 1 },
+            uid,
+            gid,
+            rdev: 0,
+        })
+    }
+
+    fn find(&self, name: &str) -> Result<Arc<dyn INode>> {
+        match name {
+            "." => return Ok(self.fs.inode(self.node)),
+            ".." => return Ok(self.fs.inode(self.parent())),
+            _ => {}
+        }
+        let node = match self.node {
+            Node::Root => match name.parse::<KoID>() {
+                Ok(pid) => {
+                    self.fs.process(pid)?;
+                    Node::Process(pid)
+                }
+                Err(_) => INFO_FILES
+                    .iter()
+                    .find(|f| f.0 == name)
+                    .map(|f| Node::Info(f.1))
+                    .ok_or(FsError::EntryNotFound)?,
+            },
+            Node::Process(pid) => {
+                self.fs.process(pid)?;
+                if name == FD_DIR {
+                    Node::FdDir(pid)
+                } else {
+                    PROCESS_FILES
+                        .iter()
+                        .find(|f| f.0 == name)
+                        .map(|f| Node::ProcessFile(pid, f.1))
+                        .ok_or(FsError::EntryNotFound)?
+                }
+            }
+            Node::FdDir(pid) => {
+                let fd = FileDesc::try_from(name).map_err(|_| FsError::EntryNotFound)?;
+                self.fs
+                    .process(pid)?
+                    .linux()
+                    .get_file_like(fd)
+                    .map_err(|_| FsError::EntryNotFound)?;
+                Node::Fd(pid, fd)
+            }
+            _ => return Err(FsError::NotDir),
+        };
+        Ok(self.fs.inode(node))
+    }
+
+    fn get_entry(&self, id: usize) -> Result<String> {
+        match id {
+            0 => Ok(String::from(".")),
+            1 => Ok(String::from("..")),
+            i => self
+                .children()?
+                .into_iter()
+                .nth(i - 2)
+                .map(|(name, _)| name)
+                .ok_or(FsError::EntryNotFound),
+        }
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.clone()
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
@@ -0,0 +1,230 @@
+//! Implement signalfd as a file
+#![deny(missing_docs)]
+
+use alloc::{
+    boxed::Box,
+    sync::{Arc, Weak},
+};
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_trait::async_trait;
+use rcore_fs::vfs::PollStatus;
+use spin::Mutex;
+use zircon_object::object::*;
+use zircon_object::task::Process;
+use zircon_object::vm::VmObject;
+
+use super::{FileLike, OpenFlags};
+use crate::error::{LxError, LxResult};
+use crate::process::ProcessExt;
+use crate::signal::{SigInfo, Signal, SignalCode, Sigset};
+use crate::sync::Event;
+
+/// Size of `struct signalfd_siginfo`
+const SIGINFO_SIZE: usize = 128;
+
+/// A signalfd, which accepts signals in its mask by reading the file
+///
+/// Only process-directed signals of the process creating the signalfd are read,
+/// thread-directed signals are delivered as usual.
+pub struct SignalFd {
+    /// object base
+    base: KObjectBase,
+    /// open flags
+    flags: Mutex<OpenFlags>,
+    /// the process whose signals are read
+    proc: Weak<Process>,
+    /// signals to accept, shared between duplicated file descriptors
+    mask: Arc<Mutex<Sigset>>,
+}
+
+impl_kobject!(SignalFd);
+
+impl SignalFd {
+    /// Create a new signalfd accepting signals of `proc` in `mask`.
+    pub fn new(proc: &Arc<Process>, mask: Sigset, flags: OpenFlags) -> Arc<Self> {
+        Arc::new(SignalFd {
+            base: KObjectBase::new(),
+            flags: Mutex::new(flags),
+            proc: Arc::downgrade(proc),
+            mask: Arc::new(Mutex::new(Self::valid_mask(mask))),
+        })
+    }
+
+    /// Replace the signals to accept.
+    pub fn set_mask(&self, mask: Sigset) {
+        *self.mask.lock() = Self::valid_mask(mask);
+    }
+
+    /// `SIGKILL` and `SIGSTOP` can not be accepted.
+    fn valid_mask(mut mask: Sigset) -> Sigset {
+        mask.remove(Signal::SIGKILL);
+        mask.remove(Signal::SIGSTOP);
+        mask
+    }
+
+    /// Dequeue a pending signal in the mask.
+    fn dequeue(&self) -> Option<SigInfo> {
+        let proc = self.proc.upgrade()?;
+        let blocked = self.mask.lock().complement();
+        proc.linux().dequeue_signal(&blocked)
+    }
+
+    /// Whether a signal in the mask is pending.
+    ///
+    /// If `cx` is given, the waker is registered when no signal is pending.
+    fn has_signal(&self, cx: Option<&mut Context>) -> bool {
+        let proc = match self.proc.upgrade() {
+            Some(proc) => proc,
+            None => return false,
+        };
+        let mask = *self.mask.lock();
+        match cx {
+            Some(cx) => {
+                let waker = cx.waker().clone();
+                proc.linux().subscribe_signal(
+                    &mask,
+                    Box::new(move |event| {
+                        if event.contains(Event::RECEIVE_SIGNAL) {
+                            waker.wake_by_ref();
+                            return true;
+                        }
+                        false
+                    }),
+                )
+            }
+            None => !proc
+                .linux()
+                .pending_signals()
+                .difference(&mask.complement())
+                .is_empty(),
+        }
+    }
+}
+
+/// Encode `info` as `struct signalfd_siginfo`.
+#[allow(unsafe_code)]
+fn encode_siginfo(info: &SigInfo, buf: &mut [u8]) {
+    buf[..SIGINFO_SIZE].fill(0);
+    let mut put = |offset: usize, bytes: &[u8]| {
+        buf[offset..offset + bytes.len()].copy_from_slice(bytes);
+    };
+    put(0, &info.signo.to_ne_bytes());
+    put(4, &info.errno.to_ne_bytes());
+    put(8, &info.code.to_ne_bytes());
+    // SAFETY: the active field of the union is decided by the signal and the code
+    unsafe {
+        if info.code == SignalCode::TIMER as i32 {
+            let timer = info.field.timer;
+            put(24, &timer.tid.to_ne_bytes());
+            put(32, &timer.overrun.to_ne_bytes());
+            put(44, &(timer.value as i32).to_ne_bytes());
+            put(48, &(timer.value as u64).to_ne_bytes());
+        } else if info.signo == Signal::SIGCHLD as i32 && info.code > 0 {
+            let child = info.field.child;
+            put(12, &child.pid.to_ne_bytes());
+            put(16, &child.uid.to_ne_bytes());
+            put(40, &child.status.to_ne_bytes());
+            put(56, &(child.utime as u64).to_ne_bytes());
+            put(64, &(child.stime as u64).to_ne_bytes());
+        } else {
+            let kill = info.field.kill;
+            put(12, &kill.pid.to_ne_bytes());
+            put(16, &kill.uid.to_ne_bytes());
+            put(44, &(kill.value as i32).to_ne_bytes());
+            put(48, &(kill.value as u64).to_ne_bytes());
+        }
+    }
+}
+
+#[async_trait]
+impl FileLike for SignalFd {
+    fn flags(&self) -> OpenFlags {
+        *self.flags.lock()
+    }
+
+    fn set_flags(&self, f: OpenFlags) -> LxResult {
+        let flags = &mut self.flags.lock();
+        flags.set(OpenFlags::NON_BLOCK, f.contains(OpenFlags::NON_BLOCK));
+        flags.set(OpenFlags::CLOEXEC, f.contains(OpenFlags::CLOEXEC));
+        Ok(())
+    }
+
+    /// Accept pending signals in the mask, each as a `struct signalfd_siginfo`.
+    ///
+    /// Blocks until a signal is pending, unless the file is non-blocking.
+    async fn read(&self, buf: &mut [u8]) -> LxResult<usize> {
+        if buf.len() < SIGINFO_SIZE {
+            return Err(LxError::EINVAL);
+        }
+        let mut len = 0;
+        while len + SIGINFO_SIZE <= buf.len() {
+            match self.dequeue() {
+                Some(info) => {
+                    encode_siginfo(&info, &mut buf[len..]);
+                    len += SIGINFO_SIZE;
+                }
+                None if len > 0 => break,
+                None if self.flags().non_block() => return Err(LxError::EAGAIN),
+                None => {
+                    self.async_poll().await?;
+                }
+            }
+        }
+        Ok(len)
+    }
+
+    async fn write(&self, _buf: &[u8]) -> LxResult<usize> {
+        Err(LxError::EINVAL)
+    }
+
+    async fn read_at(&self, _offset: u64, _buf: &mut [u8]) -> LxResult<usize> {
+        Err(LxError::ESPIPE)
+    }
+
+    fn write_at(&self, _offset: u64, _buf: &[u8]) -> LxResult<usize> {
+        Err(LxError::ESPIPE)
+    }
+
+    /// A signalfd is readable if a signal in the mask is pending.
+    fn poll(&self) -> LxResult<PollStatus> {
+        Ok(PollStatus {
+            read: self.has_signal(None),
+            write: false,
+            error: false,
+        })
+    }
+
+    async fn async_poll(&self) -> LxResult<PollStatus> {
+        #[must_use = "future does nothing unless polled/`await`-ed"]
+        struct SignalFdFuture<'a> {
+            signalfd: &'a SignalFd,
+        }
+
+        impl<'a> Future for SignalFdFuture<'a> {
+            type Output = LxResult<PollStatus>;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+                if self.signalfd.has_signal(Some(cx)) {
+                    Poll::Ready(self.signalfd.poll())
+                } else {
+                    Poll::Pending
+                }
+            }
+        }
+
+        SignalFdFuture { signalfd: self }.await
+    }
+
+    fn ioctl(&self, _request: usize, _arg1: usize, _arg2: usize, _arg3: usize) -> LxResult<usize> {
+        Err(LxError::ENOTTY)
+    }
+
+    fn get_vmo(&self, _offset: usize, _len: usize) -> LxResult<Arc<VmObject>> {
+        Err(LxError::ENODEV)
+    }
+}
@@ -2,14 +2,16 @@
 #![allow(unsafe_code)]
 
 use super::ioctl::*;
-use crate::{sync::Event, sync::EventBus};
+use super::tty::CONSOLE_TTY;
+use crate::sync::{wait_for_event, Event, EventBus};
 use alloc::boxed::Box;
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::any::Any;
 use core::future::Future;
 use core::pin::Pin;
-use core::task::{Context, Poll};
+use core::task::{Context, Poll, Waker};
 use kernel_hal::console::{self, ConsoleWinSize};
 use lazy_static::lazy_static;
 use rcore_fs::vfs::*;
@@ -19,16 +21,26 @@ lazy_static! {
     /// STDIN global reference
     pub static ref STDIN: Arc<Stdin> = {
         let stdin = Arc::new(Stdin::default());
-        let cloned = stdin.clone();
         if let Some(uart) = kernel_hal::drivers::all_uart().first() {
+            let input = Arc::new(Mutex::new(VecDeque::new()));
+            let eventbus = EventBus::new();
             uart.clone().subscribe(
-                Box::new(move |_| {
-                    while let Some(c) = uart.try_recv().unwrap_or(None) {
-                        cloned.push(c as char);
+                Box::new({
+                    let input = input.clone();
+                    let eventbus = eventbus.clone();
+                    move |_| {
+                        // job control takes the locks of processes, so the input is
+                        // only queued here and handled by `console_input`
+                        let mut input = input.lock();
+                        while let Some(c) = uart.try_recv().unwrap_or(None) {
+                            input.push_back(c);
+                        }
+                        eventbus.lock().set(Event::READABLE);
                     }
                 }),
                 false,
             );
+            kernel_hal::thread::spawn(console_input(input, eventbus, stdin.clone()));
         }
         stdin
     };
@@ -36,6 +48,27 @@ lazy_static! {
     pub static ref STDOUT: Arc<Stdout> = Default::default();
 }
 
+/// Handle the input characters received by the UART interrupt handler in task context.
+async fn console_input(
+    input: Arc<Mutex<VecDeque<u8>>>,
+    eventbus: Arc<Mutex<EventBus>>,
+    stdin: Arc<Stdin>,
+) {
+    loop {
+        wait_for_event(eventbus.clone(), Event::READABLE).await;
+        let chars: Vec<u8> = {
+            let mut input = input.lock();
+            eventbus.lock().clear(Event::READABLE);
+            input.drain(..).collect()
+        };
+        for c in chars {
+            if !CONSOLE_TTY.handle_input(c) {
+                stdin.push(c as char);
+            }
+        }
+    }
+}
+
 /// Stdin struct, for Stdin buffer
 #[derive(Default)]
 pub struct Stdin {
@@ -58,6 +91,14 @@ impl Stdin {
         }
         c
     }
+    /// Register `waker` to be woken up once, when the Stdin buffer changes.
+    pub fn register_waker(&self, waker: &Waker) {
+        let waker = waker.clone();
+        self.eventbus.lock().subscribe(Box::new(move |_| {
+            waker.wake_by_ref();
+            true
+        }));
+    }
     /// specify whether the Stdin buffer is readable
     pub fn can_read(&self) -> bool {
         self.buf.lock().len() > 0
@@ -124,18 +165,11 @@ impl INode for Stdin {
                 unsafe { *winsize = console::console_win_size() };
                 Ok(0)
             }
-            TCGETS | TIOCSPGRP => {
-                warn!("stdin TCGETS | TIOCSPGRP, pretend to be tty.");
+            TCGETS => {
+                warn!("stdin TCGETS, pretend to be tty.");
                 // pretend to be tty
                 Ok(0)
             }
-            TIOCGPGRP => {
-                warn!("stdin TIOCGPGRP, pretend to be have a tty process group.");
-                // pretend to be have a tty process group
-                // TODO: verify pointer
-                unsafe { *(data as *mut u32) = 0 };
-                Ok(0)
-            }
             _ => Err(FsError::NotSupported),
         }
     }
@@ -169,17 +203,11 @@ impl INode for Stdout {
                 unsafe { *winsize = console::console_win_size() };
                 Ok(0)
             }
-            TCGETS | TIOCSPGRP => {
-                warn!("stdout TCGETS | TIOCSPGRP, pretend to be tty.");
+            TCGETS => {
+                warn!("stdout TCGETS, pretend to be tty.");
                 // pretend to be tty
                 Ok(0)
             }
-            TIOCGPGRP => {
-                // pretend to be have a tty process group
-                // TODO: verify pointer
-                unsafe { *(data as *mut u32) = 0 };
-                Ok(0)
-            }
             _ => Err(FsError::NotSupported),
         }
     }
@@ -0,0 +1,206 @@
+//! Implement timerfd as a file
+#![deny(missing_docs)]
+
+use alloc::{
+    boxed::Box,
+    sync::{Arc, Weak},
+};
+use core::time::Duration;
+
+use async_trait::async_trait;
+use rcore_fs::vfs::PollStatus;
+use spin::Mutex;
+use zircon_object::object::*;
+use zircon_object::vm::VmObject;
+
+use super::{FileLike, OpenFlags};
+use crate::error::{LxError, LxResult};
+use crate::sync::{wait_for_event, Event, EventBus};
+
+/// A timerfd, a timer notifying expirations by the file
+pub struct TimerFd {
+    /// object base
+    base: KObjectBase,
+    /// open flags
+    flags: Mutex<OpenFlags>,
+    /// the timer, shared between duplicated file descriptors
+    timer: Arc<Timer>,
+}
+
+/// The timer of a timerfd
+struct Timer {
+    inner: Mutex<TimerInner>,
+    /// `READABLE` if the timer has expired since the last read
+    eventbus: Arc<Mutex<EventBus>>,
+}
+
+#[derive(Default)]
+struct TimerInner {
+    /// Time of the next expiration, unset if disarmed
+    deadline: Option<Duration>,
+    /// Period of a periodic timer, zero for a one-shot timer
+    interval: Duration,
+    /// Number of expirations since the last read
+    expirations: u64,
+    /// Increased every time the timer is set, to ignore HAL timers set before
+    generation: usize,
+}
+
+impl_kobject!(TimerFd);
+
+impl TimerFd {
+    /// Create a new disarmed timerfd.
+    pub fn new(flags: OpenFlags) -> Arc<Self> {
+        Arc::new(TimerFd {
+            base: KObjectBase::new(),
+            flags: Mutex::new(flags),
+            timer: Arc::new(Timer {
+                inner: Mutex::default(),
+                eventbus: EventBus::new(),
+            }),
+        })
+    }
+
+    /// Returns the time until the next expiration and the interval,
+    /// the former is zero if the timer is disarmed.
+    pub fn get(&self) -> (Duration, Duration) {
+        let now = kernel_hal::timer::timer_now();
+        let inner = self.timer.inner.lock();
+        let remaining = inner
+            .deadline
+            .map(|deadline| deadline.saturating_sub(now))
+            .unwrap_or_default();
+        (remaining, inner.interval)
+    }
+
+    /// Arm the timer to expire at `deadline`, then every `interval`,
+    /// or disarm it if `deadline` is `None`. The expiration count is reset.
+    ///
+    /// Returns the time until the next expiration and the interval before.
+    pub fn set(&self, deadline: Option<Duration>, interval: Duration) -> (Duration, Duration) {
+        let old = self.get();
+        let mut inner = self.timer.inner.lock();
+        inner.deadline = deadline;
+        inner.interval = interval;
+        inner.expirations = 0;
+        inner.generation += 1;
+        self.timer.eventbus.lock().clear(Event::READABLE);
+        if let Some(deadline) = deadline {
+            Timer::arm(&self.timer, deadline, inner.generation);
+        }
+        old
+    }
+
+    /// Take the expiration count, fail with `EAGAIN` if it is zero.
+    fn try_read(&self) -> LxResult<u64> {
+        let mut inner = self.timer.inner.lock();
+        if inner.expirations == 0 {
+            return Err(LxError::EAGAIN);
+        }
+        self.timer.eventbus.lock().clear(Event::READABLE);
+        Ok(core::mem::take(&mut inner.expirations))
+    }
+}
+
+impl Timer {
+    /// Set a HAL timer expiring at `deadline`.
+    fn arm(timer: &Arc<Self>, deadline: Duration, generation: usize) {
+        let timer = Arc::downgrade(timer);
+        kernel_hal::timer::timer_set(
+            deadline,
+            Box::new(move |now| Self::expire(timer, generation, now)),
+        );
+    }
+
+    /// Count the expirations at `now`, and rearm the timer if it is periodic.
+    fn expire(timer: Weak<Self>, generation: usize, now: Duration) {
+        let timer = match timer.upgrade() {
+            Some(timer) => timer,
+            None => return,
+        };
+        let mut inner = timer.inner.lock();
+        let deadline = match inner.deadline {
+            Some(deadline) if inner.generation == generation && deadline <= now => deadline,
+            _ => return,
+        };
+        if inner.interval == Duration::ZERO {
+            inner.deadline = None;
+            inner.expirations += 1;
+        } else {
+            let interval = inner.interval.as_nanos();
+            let count = (now - deadline).as_nanos() / interval + 1;
+            let next = Duration::from_nanos((deadline.as_nanos() + count * interval) as u64);
+            inner.deadline = Some(next);
+            inner.expirations = inner.expirations.saturating_add(count as u64);
+            Self::arm(&timer, next, generation);
+        }
+        timer.eventbus.lock().set(Event::READABLE);
+    }
+}
+
+#[async_trait]
+impl FileLike for TimerFd {
+    fn flags(&self) -> OpenFlags {
+        *self.flags.lock()
+    }
+
+    fn set_flags(&self, f: OpenFlags) -> LxResult {
+        let flags = &mut self.flags.lock();
+        flags.set(OpenFlags::NON_BLOCK, f.contains(OpenFlags::NON_BLOCK));
+        flags.set(OpenFlags::CLOEXEC, f.contains(OpenFlags::CLOEXEC));
+        Ok(())
+    }
+
+    /// Read the number of expirations since the last read as an 8-byte integer.
+    ///
+    /// Blocks until the timer expires, unless the file is non-blocking.
+    async fn read(&self, buf: &mut [u8]) -> LxResult<usize> {
+        if buf.len() < 8 {
+            return Err(LxError::EINVAL);
+        }
+        let count = loop {
+            match self.try_read() {
+                Err(LxError::EAGAIN) if !self.flags().non_block() => {
+                    wait_for_event(self.timer.eventbus.clone(), Event::READABLE).await;
+                }
+                result => break result?,
+            }
+        };
+        buf[..8].copy_from_slice(&count.to_ne_bytes());
+        Ok(8)
+    }
+
+    async fn write(&self, _buf: &[u8]) -> LxResult<usize> {
+        Err(LxError::EINVAL)
+    }
+
+    async fn read_at(&self, _offset: u64, _buf: &mut [u8]) -> LxResult<usize> {
+        Err(LxError::ESPIPE)
+    }
+
+    fn write_at(&self, _offset: u64, _buf: &[u8]) -> LxResult<usize> {
+        Err(LxError::ESPIPE)
+    }
+
+    /// A timerfd is readable if the timer has expired since the last read.
+    fn poll(&self) -> LxResult<PollStatus> {
+        Ok(PollStatus {
+            read: self.timer.inner.lock().expirations != 0,
+            write: false,
+            error: false,
+        })
+    }
+
+    async fn async_poll(&self) -> LxResult<PollStatus> {
+        wait_for_event(self.timer.eventbus.clone(), Event::READABLE).await;
+        self.poll()
+    }
+
+    fn ioctl(&self, _request: usize, _arg1: usize, _arg2: usize, _arg3: usize) -> LxResult<usize> {
+        Err(LxError::ENOTTY)
+    }
+
+    fn get_vmo(&self, _offset: usize, _len: usize) -> LxResult<Arc<VmObject>> {
+        Err(LxError::ENODEV)
+    }
+}
@@ -0,0 +1,200 @@
+//! Controlling terminal and job control
+#![deny(missing_docs)]
+
+use alloc::sync::{Arc, Weak};
+
+use kernel_hal::user::{UserInPtr, UserOutPtr};
+use lazy_static::lazy_static;
+use rcore_fs::vfs::INode;
+use spin::Mutex;
+use zircon_object::{
+    object::KoID,
+    task::{Job, Process},
+};
+
+use super::devfs::UartDev;
+use super::ioctl::*;
+use super::stdio::{Stdin, Stdout};
+use crate::error::{LxError, LxResult};
+use crate::process::{process_group, session, ProcessExt};
+use crate::signal::{SigInfo, Signal, SignalCode, SIG_IGN};
+
+lazy_static! {
+    /// The terminal of stdin and stdout
+    pub(crate) static ref CONSOLE_TTY: Arc<Tty> = Default::default();
+}
+
+/// Job control state of a terminal.
+///
+/// A terminal is the controlling terminal of at most one session, and one process group
+/// in the session is the foreground group which receives the signals generated by
+/// special characters. Reading from the terminal is only allowed in the foreground group.
+#[derive(Default)]
+pub struct Tty {
+    inner: Mutex<TtyInner>,
+}
+
+#[derive(Default)]
+struct TtyInner {
+    /// The job containing the processes of the session
+    job: Weak<Job>,
+    /// ID of the controlled session
+    session: Option<KoID>,
+    /// ID of the foreground process group
+    foreground: KoID,
+}
+
+impl Tty {
+    /// Returns the terminal of `inode`, if it is a terminal device.
+    pub fn of(inode: &dyn INode) -> Option<Arc<Tty>> {
+        if inode.downcast_ref::<Stdin>().is_some() || inode.downcast_ref::<Stdout>().is_some() {
+            Some(CONSOLE_TTY.clone())
+        } else {
+            inode
+                .downcast_ref::<UartDev>()
+                .map(|uart| uart.tty().clone())
+        }
+    }
+
+    /// Make the terminal the controlling terminal of the session of `proc`,
+    /// with the process group of `proc` in the foreground.
+    pub(crate) fn attach(&self, proc: &Process) {
+        let linux = proc.linux();
+        let (sid, pgid) = (linux.sid(), linux.pgid());
+        let mut inner = self.inner.lock();
+        inner.job = Arc::downgrade(&proc.job());
+        inner.session = Some(sid);
+        inner.foreground = pgid;
+    }
+
+    /// Make the terminal the controlling terminal of the session led by `proc`, on `TIOCSCTTY`.
+    ///
+    /// A terminal controlling another session can only be stolen by a privileged process
+    /// with `steal` set.
+    pub fn set_controlling(&self, proc: &Process, steal: bool) -> LxResult {
+        let linux = proc.linux();
+        if linux.sid() != proc.id() {
+            return Err(LxError::EPERM);
+        }
+        let (current, job) = {
+            let inner = self.inner.lock();
+            (inner.session, inner.job.upgrade())
+        };
+        match (current, job) {
+            (Some(sid), _) if sid == proc.id() => return Ok(()),
+            (Some(sid), Some(job)) if !session(&job, sid).is_empty() => {
+                if !(steal && linux.cred().privileged()) {
+                    return Err(LxError::EPERM);
+                }
+            }
+            _ => {}
+        }
+        self.attach(proc);
+        Ok(())
+    }
+
+    /// Returns the foreground process group, on `TIOCGPGRP`.
+    ///
+    /// The terminal must be the controlling terminal of the caller `proc`.
+    pub fn foreground(&self, proc: &Process) -> LxResult<KoID> {
+        let sid = proc.linux().sid();
+        let inner = self.inner.lock();
+        if inner.session != Some(sid) {
+            return Err(LxError::ENOTTY);
+        }
+        Ok(inner.foreground)
+    }
+
+    /// Move the process group `pgid` to the foreground, on `TIOCSPGRP`.
+    ///
+    /// The terminal must be the controlling terminal of the caller `proc`,
+    /// and the group must be in the same session.
+    pub fn set_foreground(&self, proc: &Process, pgid: KoID) -> LxResult {
+        let sid = proc.linux().sid();
+        self.foreground(proc)?;
+        if !process_group(&proc.job(), pgid)
+            .iter()
+            .any(|p| p.linux().sid() == sid)
+        {
+            return Err(LxError::EPERM);
+        }
+        let mut inner = self.inner.lock();
+        if inner.session != Some(sid) {
+            return Err(LxError::ENOTTY);
+        }
+        inner.foreground = pgid;
+        Ok(())
+    }
+
+    /// Handle the job control requests of `ioctl` from `proc`.
+    ///
+    /// Returns `None` if `cmd` is not a job control request.
+    pub fn ioctl(&self, proc: &Process, cmd: usize, data: usize) -> Option<LxResult<usize>> {
+        let ret = match cmd {
+            TIOCSCTTY => self.set_controlling(proc, data == 1),
+            TIOCGPGRP => self.foreground(proc).and_then(|pgid| {
+                UserOutPtr::<i32>::from(data).write(pgid as i32)?;
+                Ok(())
+            }),
+            TIOCSPGRP => UserInPtr::<i32>::from(data)
+                .read()
+                .map_err(LxError::from)
+                .and_then(|pgid| match pgid {
+                    pgid if pgid < 0 => Err(LxError::EINVAL),
+                    pgid => self.set_foreground(proc, pgid as KoID),
+                }),
+            _ => return None,
+        };
+        Some(ret.map(|_| 0))
+    }
+
+    /// Check that `proc` may read from the terminal.
+    ///
+    /// A process in a background group of the controlled session sends `SIGTTIN` to its
+    /// group and gets `EINTR`, or gets `EIO` if it ignores `SIGTTIN`.
+    pub fn check_read(&self, proc: &Process) -> LxResult {
+        let linux = proc.linux();
+        let (sid, pgid) = (linux.sid(), linux.pgid());
+        {
+            let inner = self.inner.lock();
+            if inner.session != Some(sid) || inner.foreground == pgid {
+                return Ok(());
+            }
+        }
+        if linux.signal_action(Signal::SIGTTIN).handler == SIG_IGN {
+            return Err(LxError::EIO);
+        }
+        for p in process_group(&proc.job(), pgid) {
+            p.send_signal(SigInfo::new(Signal::SIGTTIN, SignalCode::KERNEL));
+        }
+        Err(LxError::EINTR)
+    }
+
+    /// Handle an input character `c`.
+    ///
+    /// Returns true if `c` generates a signal to the foreground group and is consumed.
+    ///
+    /// It takes the locks of the foreground processes, so it must not be called in
+    /// interrupt context.
+    pub fn handle_input(&self, c: u8) -> bool {
+        let signal = match c {
+            0x03 => Signal::SIGINT,  // ^C
+            0x1a => Signal::SIGTSTP, // ^Z
+            0x1c => Signal::SIGQUIT, // ^\
+            _ => return false,
+        };
+        let (job, pgid) = {
+            let inner = self.inner.lock();
+            if inner.session.is_none() {
+                return false;
+            }
+            (inner.job.upgrade(), inner.foreground)
+        };
+        if let Some(job) = job {
+            for p in process_group(&job, pgid) {
+                p.send_signal(SigInfo::new(signal, SignalCode::KERNEL));
+            }
+        }
+        true
+    }
+}
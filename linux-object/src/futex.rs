@@ -0,0 +1,184 @@
+//! Linux futex support
+//!
+//! Private futexes are kept by each process and keyed by the address, see
+//! [`LinuxProcess::get_futex`]. Futexes shared by processes are keyed by the
+//! page of the VMO mapped at the address, so that processes mapping the same
+//! memory at different addresses find the same futex.
+//!
+//! [`LinuxProcess::get_futex`]: crate::process::LinuxProcess::get_futex
+
+use alloc::{
+    collections::BTreeMap,
+    sync::{Arc, Weak},
+};
+use core::sync::atomic::{AtomicI32, Ordering};
+
+use kernel_hal::user::UserInPtr;
+use lazy_static::lazy_static;
+use spin::Mutex;
+use zircon_object::{
+    object::{KernelObject, KoID},
+    signal::Futex,
+    task::Process,
+    vm::{VirtAddr, VmAddressRegion},
+};
+
+use crate::error::{LxError, LxResult};
+use crate::process::ProcessExt;
+
+/// The owner of a robust futex has waiters.
+pub const FUTEX_WAITERS: i32 = 0x8000_0000u32 as i32;
+/// The owner of a robust futex died.
+pub const FUTEX_OWNER_DIED: i32 = 0x4000_0000;
+/// Mask of the owner TID in a robust futex.
+pub const FUTEX_TID_MASK: i32 = 0x3fff_ffff;
+/// The bitset matching all waiters.
+pub const FUTEX_BITSET_MATCH_ANY: u32 = u32::MAX;
+
+/// Max number of entries walked in a robust list, which may be circular.
+const ROBUST_LIST_LIMIT: usize = 2048;
+
+lazy_static! {
+    /// Futexes shared by processes, keyed by the VMO ID and the offset
+    static ref SHARED_FUTEXES: Mutex<BTreeMap<(KoID, usize), Weak<Futex>>> =
+        Mutex::new(BTreeMap::new());
+}
+
+/// Get the futex word at `uaddr`, which must be 4-byte aligned.
+#[allow(unsafe_code)]
+pub fn futex_word(uaddr: VirtAddr) -> LxResult<&'static AtomicI32> {
+    if uaddr == 0 {
+        return Err(LxError::EFAULT);
+    }
+    if uaddr % 4 != 0 {
+        return Err(LxError::EINVAL);
+    }
+    // FIXME: check address
+    Ok(unsafe { &*(uaddr as *const AtomicI32) })
+}
+
+/// Get the futex shared by processes at `uaddr` mapped in `vmar`.
+///
+/// A futex is only kept while some thread is waiting on it.
+pub fn get_shared_futex(vmar: &VmAddressRegion, uaddr: VirtAddr) -> LxResult<Arc<Futex>> {
+    let word = futex_word(uaddr)?;
+    let (vmo, offset) = vmar.get_vmo_offset(uaddr).ok_or(LxError::EFAULT)?;
+    let mut futexes = SHARED_FUTEXES.lock();
+    let key = (vmo.id(), offset);
+    if let Some(futex) = futexes.get(&key).and_then(Weak::upgrade) {
+        return Ok(futex);
+    }
+    futexes.retain(|_, futex| futex.strong_count() > 0);
+    let futex = Futex::new(word);
+    futexes.insert(key, Arc::downgrade(&futex));
+    Ok(futex)
+}
+
+/// Apply the operation encoded in `encoded` by `FUTEX_WAKE_OP` to the word at `uaddr`,
+/// and returns whether the old value satisfies the comparison.
+pub fn futex_wake_op(uaddr: VirtAddr, encoded: u32) -> LxResult<bool> {
+    const FUTEX_OP_OPARG_SHIFT: u32 = 8;
+    let word = futex_word(uaddr)?;
+    let mut op = encoded >> 28;
+    let cmp = (encoded >> 24) & 0xf;
+    // both arguments are 12-bit signed integers
+    let mut oparg = ((encoded << 8) as i32) >> 20;
+    let cmparg = ((encoded << 20) as i32) >> 20;
+    if op & FUTEX_OP_OPARG_SHIFT != 0 {
+        op &= !FUTEX_OP_OPARG_SHIFT;
+        oparg = 1 << (oparg & 31);
+    }
+    if op > 4 || cmp > 5 {
+        return Err(LxError::ENOSYS);
+    }
+    let old = word
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |old| {
+            Some(match op {
+                0 => oparg,
+                1 => old.wrapping_add(oparg),
+                2 => old | oparg,
+                3 => old & !oparg,
+                _ => old ^ oparg,
+            })
+        })
+        .unwrap();
+    Ok(match cmp {
+        0 => old == cmparg,
+        1 => old != cmparg,
+        2 => old < cmparg,
+        3 => old <= cmparg,
+        4 => old > cmparg,
+        _ => old >= cmparg,
+    })
+}
+
+/// `struct robust_list_head` registered by `set_robust_list`
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RobustListHead {
+    /// The first entry of the list, which points to the head itself if empty
+    pub list: usize,
+    /// Offset of the futex word from the address of an entry
+    pub futex_offset: isize,
+    /// The entry being acquired or released
+    pub list_op_pending: usize,
+}
+
+/// Release the robust futexes held by the exiting thread `tid`,
+/// whose robust list is at `head`.
+///
+/// Each futex still owned by the thread is marked with `FUTEX_OWNER_DIED`,
+/// and one waiter is woken if there is any.
+pub fn exit_robust_list(proc: &Process, head: UserInPtr<RobustListHead>, tid: KoID) {
+    let head_addr = head.as_addr();
+    let head = match head.read() {
+        Ok(head) => head,
+        Err(_) => return,
+    };
+    let futex_addr = |entry: usize| (entry as isize + head.futex_offset) as usize;
+    // the lowest bit of an entry marks a PI futex
+    let mut entry = head.list & !1;
+    for _ in 0..ROBUST_LIST_LIMIT {
+        if entry == head_addr || entry == 0 {
+            break;
+        }
+        // the entry may be released when the futex is handled
+        let next = match UserInPtr::<usize>::from(entry).read() {
+            Ok(next) => next & !1,
+            Err(_) => break,
+        };
+        if entry != head.list_op_pending & !1 {
+            handle_futex_death(proc, futex_addr(entry), tid);
+        }
+        entry = next;
+    }
+    if head.list_op_pending & !1 != 0 {
+        handle_futex_death(proc, futex_addr(head.list_op_pending & !1), tid);
+    }
+}
+
+/// Mark the futex at `uaddr` owned by the exiting thread `tid`, and wake a waiter.
+fn handle_futex_death(proc: &Process, uaddr: VirtAddr, tid: KoID) {
+    let word = match futex_word(uaddr) {
+        Ok(word) => word,
+        Err(_) => return,
+    };
+    let old = match word.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |value| {
+        if value & FUTEX_TID_MASK == tid as i32 {
+            Some((value & FUTEX_WAITERS) | FUTEX_OWNER_DIED)
+        } else {
+            None
+        }
+    }) {
+        Ok(old) => old,
+        Err(_) => return,
+    };
+    if old & FUTEX_WAITERS != 0 {
+        // the waiters may use either a private or a shared futex
+        if proc.linux().get_futex(uaddr).wake(1) == 0 {
+            if let Ok(futex) = get_shared_futex(&proc.vmar(), uaddr) {
+                futex.wake(1);
+            }
+        }
+    }
+}
@@ -17,9 +17,11 @@ extern crate log;
 pub mod error;
 
 // layer 1
+pub mod cred;
 pub mod fs;
 
 // layer 2
+pub mod futex;
 pub mod ipc;
 pub mod loader;
 pub mod net;
@@ -28,3 +30,4 @@ pub mod signal;
 pub mod sync;
 pub mod thread;
 pub mod time;
+pub mod timer;
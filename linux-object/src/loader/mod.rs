@@ -4,10 +4,11 @@
 use {
     crate::error::LxResult,
     crate::fs::INodeExt,
+    crate::signal::SIGRETURN_CODE,
     alloc::{collections::BTreeMap, string::String, sync::Arc, vec::Vec},
     rcore_fs::vfs::INode,
     xmas_elf::{program::ProgramHeader, ElfFile},
-    zircon_object::{util::elf_loader::*, vm::*, ZxError},
+    zircon_object::{object::KernelObject, util::elf_loader::*, vm::*, ZxError},
 };
 
 mod abi;
@@ -22,8 +23,15 @@ pub struct LinuxElfLoader {
     pub root_inode: Arc<dyn INode>,
 }
 
+/// The size of the region reserved for the heap after the main program.
+const MAX_HEAP_SIZE: usize = 0x1000_0000;
+
 impl LinuxElfLoader {
-    /// load a Linux ElfFile and return a tuple of (entry,sp)
+    /// load a Linux ElfFile and return a tuple of (entry, sp, heap_start, vdso_base)
+    ///
+    /// A dynamically linked program is loaded together with its interpreter.
+    /// The heap starts right after the highest loaded segment of the main program,
+    /// and a region is reserved for it before anything else is mapped.
     pub fn load(
         &self,
         vmar: &Arc<VmAddressRegion>,
@@ -31,7 +39,7 @@ impl LinuxElfLoader {
         args: Vec<String>,
         envs: Vec<String>,
         path: String,
-    ) -> LxResult<(VirtAddr, VirtAddr)> {
+    ) -> LxResult<(VirtAddr, VirtAddr, VirtAddr, VirtAddr)> {
         debug!(
             "load: vmar.addr & size: {:#x?}, data {:#x?}, args: {:?}, envs: {:?}",
             vmar.get_info(),
@@ -44,20 +52,13 @@ impl LinuxElfLoader {
 
         debug!("elf info:  {:#x?}", elf.header.pt2);
 
-        if let Ok(interp) = elf.get_interpreter() {
-            info!("interp: {:?}, path: {:?}", interp, path);
-            let inode = self.root_inode.lookup(interp)?;
-            let data = inode.read_as_vec()?;
-            let mut new_args = vec![interp.into(), path.clone()];
-            new_args.extend_from_slice(&args[1..]);
-            return self.load(vmar, &data, new_args, envs, path);
-        }
-
-        let size = elf.load_segment_size();
-        let image_vmar = vmar.allocate(None, size, VmarFlags::CAN_MAP_RXW, PAGE_SIZE)?;
+        let image_vmar = self.load_image(vmar, &elf)?;
         let mut base = image_vmar.addr();
-        let vmo = image_vmar.load_from_elf(&elf)?;
+        let heap_start = image_vmar.addr() + elf.load_segment_size();
         let entry = base + elf.header.pt2.entry_point() as usize;
+        let flags =
+            VmarFlags::CAN_MAP_READ | VmarFlags::CAN_MAP_WRITE | VmarFlags::CAN_MAP_SPECIFIC;
+        vmar.allocate_at(heap_start - vmar.addr(), MAX_HEAP_SIZE, flags, PAGE_SIZE)?;
 
         // for static exec program
         let ph: ProgramHeader = elf.program_iter().next().unwrap();
@@ -69,20 +70,45 @@ impl LinuxElfLoader {
             entry
         );
 
-        // fill syscall entry
-        if let Some(offset) = elf.get_symbol_address("rcore_syscall_entry") {
-            vmo.write(offset as usize, &self.syscall_entry.to_ne_bytes())?;
-        }
-
-        match elf.relocate(image_vmar) {
-            Ok(()) => info!("elf relocate passed !"),
-            Err(error) => {
-                base = static_prog_base;
-                warn!("elf relocate Err:{:?}, base {:x?}", error, base);
+        // the interpreter relocates a dynamically linked program
+        let interp = elf.get_interpreter().ok();
+        if interp.is_none() {
+            match elf.relocate(image_vmar) {
+                Ok(()) => info!("elf relocate passed !"),
+                Err(error) => {
+                    base = static_prog_base;
+                    warn!("elf relocate Err:{:?}, base {:x?}", error, base);
+                }
             }
         }
+        let interp_base_entry = match interp {
+            Some(interp) => {
+                info!("interp: {:?}, path: {:?}", interp, path);
+                let inode = self.root_inode.lookup(interp)?;
+                let data = inode.read_as_vec()?;
+                let interp_elf = ElfFile::new(&data).map_err(|_| ZxError::INVALID_ARGS)?;
+                let interp_vmar = self.load_image(vmar, &interp_elf)?;
+                let interp_base = interp_vmar.addr();
+                if let Err(error) = interp_elf.relocate(interp_vmar) {
+                    warn!("interp relocate Err:{:?}", error);
+                }
+                Some((
+                    interp_base,
+                    interp_base + interp_elf.header.pt2.entry_point() as usize,
+                ))
+            }
+            None => None,
+        };
+
+        // the vDSO page holds the code returning from signal handlers
+        let vdso_vmo = VmObject::new_paged(1);
+        vdso_vmo.write(0, &SIGRETURN_CODE)?;
+        vdso_vmo.set_name("[vdso]");
+        let flags = MMUFlags::READ | MMUFlags::EXECUTE | MMUFlags::USER;
+        let vdso_base = vmar.map(None, vdso_vmo, 0, PAGE_SIZE, flags)?;
 
         let stack_vmo = VmObject::new_paged(self.stack_pages);
+        stack_vmo.set_name("[stack]");
         let flags = MMUFlags::READ | MMUFlags::WRITE | MMUFlags::USER;
         let stack_bottom = vmar.map(None, stack_vmo.clone(), 0, stack_vmo.len(), flags)?;
         let mut sp = stack_bottom + stack_vmo.len();
@@ -106,6 +132,10 @@ impl LinuxElfLoader {
                 map.insert(abi::AT_PHENT, elf.header.pt2.ph_entry_size() as usize);
                 map.insert(abi::AT_PHNUM, elf.header.pt2.ph_count() as usize);
                 map.insert(abi::AT_PAGESZ, PAGE_SIZE);
+                if let Some((interp_base, _)) = interp_base_entry {
+                    map.insert(abi::AT_BASE, interp_base);
+                    map.insert(abi::AT_ENTRY, entry);
+                }
                 map
             },
         };
@@ -113,11 +143,31 @@ impl LinuxElfLoader {
         stack_vmo.write(self.stack_pages * PAGE_SIZE - init_stack.len(), &init_stack)?;
         sp -= init_stack.len();
 
+        // start from the interpreter if there is one
+        let entry = interp_base_entry.map_or(entry, |(_, interp_entry)| interp_entry);
         debug!(
-            "ProcInitInfo auxv: {:#x?}\nentry:{:#x}, sp:{:#x}",
-            info.auxv, entry, sp
+            "ProcInitInfo auxv: {:#x?}\nentry:{:#x}, sp:{:#x}, heap:{:#x}, vdso:{:#x}",
+            info.auxv, entry, sp, heap_start, vdso_base
         );
 
-        Ok((entry, sp))
+        Ok((entry, sp, heap_start, vdso_base))
+    }
+
+    /// Map the loadable segments of `elf` into a new sub-region of `vmar`.
+    fn load_image(
+        &self,
+        vmar: &Arc<VmAddressRegion>,
+        elf: &ElfFile,
+    ) -> LxResult<Arc<VmAddressRegion>> {
+        let size = elf.load_segment_size();
+        let flags = VmarFlags::CAN_MAP_RXW | VmarFlags::CAN_MAP_SPECIFIC;
+        let image_vmar = vmar.allocate(None, size, flags, PAGE_SIZE)?;
+        let vmo = image_vmar.load_from_elf(elf)?;
+
+        // fill syscall entry
+        if let Some(offset) = elf.get_symbol_address("rcore_syscall_entry") {
+            vmo.write(offset as usize, &self.syscall_entry.to_ne_bytes())?;
+        }
+        Ok(image_vmar)
     }
 }
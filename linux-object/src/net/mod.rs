@@ -14,6 +14,14 @@ pub use tcp::*;
 pub mod udp;
 pub use udp::*;
 
+/// Messages and ancillary data of `sendmsg` and `recvmsg`
+pub mod msg;
+pub use msg::*;
+
+/// Unix domain sockets
+pub mod unix;
+pub use unix::*;
+
 use spin::Mutex;
 /// missing documentation
 // pub mod raw;
@@ -175,7 +183,9 @@ use crate::error::*;
 use alloc::boxed::Box;
 use alloc::fmt::Debug;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use async_trait::async_trait;
+use core::{task::Waker, time::Duration};
 // use core::ops::{Deref, DerefMut};
 /// Common methods that a socket must have
 #[async_trait]
@@ -183,11 +193,43 @@ pub trait Socket: Send + Sync + Debug {
     /// missing documentation
     async fn read(&self, data: &mut [u8]) -> (SysResult, Endpoint);
     /// missing documentation
-    fn write(&self, data: &[u8], sendto_endpoint: Option<Endpoint>) -> SysResult;
+    async fn write(&self, data: &[u8], sendto_endpoint: Option<Endpoint>) -> SysResult;
     /// missing documentation
     fn poll(&self) -> (bool, bool, bool); // (in, out, err)
+    /// Register `waker` to be woken up once, when the status of the socket changes.
+    ///
+    /// Returns `false` if the socket can not notify its status changes.
+    fn register_waker(&self, _waker: &Waker) -> bool {
+        false
+    }
     /// missing documentation
     async fn connect(&self, endpoint: Endpoint) -> SysResult;
+    /// Receive a message with `flags` of `recvmsg`.
+    async fn recvmsg(&self, data: &mut [u8], _flags: MsgFlags) -> LxResult<RecvMsg> {
+        let (result, endpoint) = self.read(data).await;
+        Ok(RecvMsg {
+            len: result?,
+            endpoint: Some(endpoint),
+            control: Vec::new(),
+        })
+    }
+    /// Send a message with control messages and `flags` of `sendmsg`.
+    async fn sendmsg(
+        &self,
+        data: &[u8],
+        sendto_endpoint: Option<Endpoint>,
+        control: Vec<ControlMessage>,
+        _flags: MsgFlags,
+    ) -> SysResult {
+        // only unix sockets can pass files
+        if control
+            .iter()
+            .any(|msg| matches!(msg, ControlMessage::Rights(_)))
+        {
+            return Err(LxError::EINVAL);
+        }
+        self.write(data, sendto_endpoint).await
+    }
     /// missing documentation
     fn bind(&mut self, _endpoint: Endpoint) -> SysResult {
         Err(LxError::EINVAL)
@@ -217,6 +259,11 @@ pub trait Socket: Send + Sync + Debug {
         warn!("setsockopt is unimplemented");
         Ok(0)
     }
+    /// Get the value of a socket option, which is empty if it is unknown.
+    fn getsockopt(&self, _level: usize, _opt: usize) -> LxResult<Vec<u8>> {
+        warn!("getsockopt is unimplemented");
+        Ok(Vec::new())
+    }
     /// missing documentation
     fn ioctl(&self, _request: usize, _arg1: usize, _arg2: usize, _arg3: usize) -> SysResult {
         warn!("ioctl is unimplemented for this socket");
@@ -228,3 +275,19 @@ pub trait Socket: Send + Sync + Debug {
         Ok(0)
     }
 }
+
+/// The interval to poll again a socket which can not notify its status changes.
+const SOCKET_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Register `waker` to be woken up once, when the status of `socket` may change.
+///
+/// A socket which can not notify is polled again after [`SOCKET_POLL_INTERVAL`].
+pub fn register_socket_waker(socket: &dyn Socket, waker: &Waker) {
+    if !socket.register_waker(waker) {
+        let waker = waker.clone();
+        kernel_hal::timer::timer_set(
+            kernel_hal::timer::deadline_after(SOCKET_POLL_INTERVAL),
+            Box::new(move |_| waker.wake()),
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Messages and ancillary data of `sendmsg` and `recvmsg`
+
+use alloc::{sync::Arc, vec::Vec};
+use core::mem::size_of;
+use spin::Mutex;
+
+use crate::cred::Id;
+use crate::error::{LxError, LxResult};
+use crate::fs::FileLike;
+use crate::net::{Endpoint, Socket};
+
+bitflags::bitflags! {
+    /// Flags of `send` and `recv` families
+    pub struct MsgFlags: usize {
+        /// Out-of-band data
+        const OOB = 0x1;
+        /// Receive without removing the data from the queue.
+        const PEEK = 0x2;
+        /// Do not route.
+        const DONTROUTE = 0x4;
+        /// Control data was discarded for lack of space.
+        const CTRUNC = 0x8;
+        /// Return the real length of a datagram, or the datagram was truncated.
+        const TRUNC = 0x20;
+        /// Do not block.
+        const DONTWAIT = 0x40;
+        /// End of record
+        const EOR = 0x80;
+        /// Wait for the full request.
+        const WAITALL = 0x100;
+        /// Do not generate `SIGPIPE`.
+        const NOSIGNAL = 0x4000;
+        /// Set close-on-exec on file descriptors received by `SCM_RIGHTS`.
+        const CMSG_CLOEXEC = 0x4000_0000;
+    }
+}
+
+/// `struct msghdr` of `sendmsg` and `recvmsg`
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct MsgHdr {
+    /// Address of the socket address
+    pub name: usize,
+    /// Length of the socket address
+    pub namelen: u32,
+    /// Address of the `iovec` array
+    pub iov: usize,
+    /// Number of elements in the `iovec` array
+    pub iovlen: usize,
+    /// Address of the ancillary data
+    pub control: usize,
+    /// Length of the ancillary data
+    pub controllen: usize,
+    /// Flags of the received message
+    pub flags: i32,
+}
+
+/// `struct cmsghdr`, the header of a control message
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct CmsgHdr {
+    /// Length of the header and data
+    len: usize,
+    /// Originating protocol
+    level: i32,
+    /// Protocol-specific type
+    type_: i32,
+}
+
+/// Level of the control messages about sockets
+pub const SOL_SOCKET: i32 = 1;
+/// Control message type to pass file descriptors
+pub const SCM_RIGHTS: i32 = 1;
+/// Control message type to pass credentials
+pub const SCM_CREDENTIALS: i32 = 2;
+
+/// Round `len` up to the alignment of control messages.
+const fn cmsg_align(len: usize) -> usize {
+    (len + size_of::<usize>() - 1) & !(size_of::<usize>() - 1)
+}
+
+/// Length of a control message with `len` bytes of data, as `CMSG_LEN`.
+const fn cmsg_len(len: usize) -> usize {
+    size_of::<CmsgHdr>() + len
+}
+
+/// Space taken by a control message with `len` bytes of data, as `CMSG_SPACE`.
+const fn cmsg_space(len: usize) -> usize {
+    size_of::<CmsgHdr>() + cmsg_align(len)
+}
+
+/// `struct ucred`, credentials of a process passed by `SCM_CREDENTIALS`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UCred {
+    /// Process ID
+    pub pid: i32,
+    /// User ID
+    pub uid: Id,
+    /// Group ID
+    pub gid: Id,
+}
+
+impl UCred {
+    /// Returns the bytes of the structure.
+    pub fn as_bytes(&self) -> &[u8] {
+        #[allow(unsafe_code)]
+        unsafe {
+            core::slice::from_raw_parts(self as *const UCred as *const u8, size_of::<UCred>())
+        }
+    }
+
+    /// Read the structure from `data`.
+    pub fn from_bytes(data: &[u8]) -> LxResult<Self> {
+        if data.len() < size_of::<UCred>() {
+            return Err(LxError::EINVAL);
+        }
+        #[allow(unsafe_code)]
+        Ok(unsafe { (data.as_ptr() as *const UCred).read_unaligned() })
+    }
+}
+
+/// An open file passed by `SCM_RIGHTS`
+#[derive(Clone)]
+pub enum PassedFile {
+    /// A file, device, pipe or other [`FileLike`]
+    File(Arc<dyn FileLike>),
+    /// A socket, which is kept in the socket table of the receiver
+    Socket(Arc<Mutex<dyn Socket>>),
+}
+
+/// A control message, also known as ancillary data
+pub enum ControlMessage {
+    /// `SCM_RIGHTS`: files passed to the receiver
+    Rights(Vec<PassedFile>),
+    /// `SCM_CREDENTIALS`: credentials of the sender
+    ///
+    /// The sender always attaches its own credentials if it does not give any,
+    /// which are only delivered by unix sockets with `SO_PASSCRED`.
+    Credentials(UCred),
+}
+
+/// A raw control message in the buffer of `sendmsg`
+pub struct RawControlMessage<'a> {
+    /// Originating protocol
+    pub level: i32,
+    /// Protocol-specific type
+    pub type_: i32,
+    /// Data of the message
+    pub data: &'a [u8],
+}
+
+/// Parse the control messages in `buf`.
+pub fn parse_control(mut buf: &[u8]) -> LxResult<Vec<RawControlMessage>> {
+    let mut messages = Vec::new();
+    while buf.len() >= size_of::<CmsgHdr>() {
+        #[allow(unsafe_code)]
+        let hdr = unsafe { (buf.as_ptr() as *const CmsgHdr).read_unaligned() };
+        // `cmsg_len` is a 32-bit `socklen_t` followed by padding in musl
+        let len = hdr.len as u32 as usize;
+        if len < size_of::<CmsgHdr>() || len > buf.len() {
+            return Err(LxError::EINVAL);
+        }
+        messages.push(RawControlMessage {
+            level: hdr.level,
+            type_: hdr.type_,
+            data: &buf[size_of::<CmsgHdr>()..len],
+        });
+        buf = &buf[cmsg_align(len).min(buf.len())..];
+    }
+    Ok(messages)
+}
+
+/// Buffer of the control messages to be copied to `recvmsg`.
+pub struct ControlBuffer {
+    buf: Vec<u8>,
+    capacity: usize,
+    truncated: bool,
+}
+
+impl ControlBuffer {
+    /// Create a buffer for at most `capacity` bytes.
+    pub fn new(capacity: usize) -> Self {
+        ControlBuffer {
+            buf: Vec::new(),
+            capacity,
+            truncated: false,
+        }
+    }
+
+    /// Returns the number of items of `item_size` bytes that fit in the buffer.
+    pub fn fit(&self, count: usize, item_size: usize) -> usize {
+        let space = self.capacity.saturating_sub(self.buf.len());
+        if space < cmsg_len(0) {
+            return 0;
+        }
+        count.min((space - cmsg_len(0)) / item_size)
+    }
+
+    /// Append a control message, truncating it if there is no enough space.
+    pub fn push(&mut self, level: i32, type_: i32, data: &[u8]) {
+        let space = self.capacity.saturating_sub(self.buf.len());
+        if space < cmsg_len(0) {
+            self.truncated = true;
+            return;
+        }
+        let data = if cmsg_len(data.len()) > space {
+            self.truncated = true;
+            &data[..space - cmsg_len(0)]
+        } else {
+            data
+        };
+        let hdr = CmsgHdr {
+            len: cmsg_len(data.len()),
+            level,
+            type_,
+        };
+        #[allow(unsafe_code)]
+        let hdr = unsafe {
+            core::slice::from_raw_parts(&hdr as *const CmsgHdr as *const u8, size_of::<CmsgHdr>())
+        };
+        self.buf.extend_from_slice(hdr);
+        self.buf.extend_from_slice(data);
+        let end = self.buf.len() - data.len() + cmsg_space(data.len());
+        self.buf.resize(end.min(self.capacity), 0);
+    }
+
+    /// Returns the content and whether any control data was discarded.
+    pub fn into_inner(self) -> (Vec<u8>, bool) {
+        (self.buf, self.truncated)
+    }
+}
+
+/// A message received by `recvmsg`
+pub struct RecvMsg {
+    /// Length of the message, which may be longer than the buffer for a datagram
+    pub len: usize,
+    /// Address of the sender
+    pub endpoint: Option<Endpoint>,
+    /// Control messages
+    pub control: Vec<ControlMessage>,
+}
@@ -1,5 +1,7 @@
 // core
 
+use alloc::string::String;
+use alloc::vec::Vec;
 use core::cmp::min;
 use core::mem::size_of;
 
@@ -103,6 +105,19 @@ pub enum Endpoint {
     LinkLevel(LinkLevelEndpoint),
     /// missing documentation
     Netlink(NetlinkEndpoint),
+    /// Address of a unix domain socket
+    Unix(UnixEndpoint),
+}
+
+/// Address of a unix domain socket
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UnixEndpoint {
+    /// An unbound socket
+    Unnamed,
+    /// A socket bound to a path in the file system
+    Path(String),
+    /// A socket bound to a name in the abstract namespace, without the leading NUL
+    Abstract(Vec<u8>),
 }
 
 /// missing documentation
@@ -184,6 +199,23 @@ impl From<Endpoint> for SockAddr {
                     nl_groups: netlink.multicast_groups_mask,
                 },
             }
+        } else if let Endpoint::Unix(unix) = endpoint {
+            let mut addr_un = SockAddrUn {
+                sun_family: AddressFamily::Unix.into(),
+                sun_path: [0; 108],
+            };
+            match unix {
+                UnixEndpoint::Unnamed => {}
+                UnixEndpoint::Path(path) => {
+                    let len = path.len().min(addr_un.sun_path.len() - 1);
+                    addr_un.sun_path[..len].copy_from_slice(&path.as_bytes()[..len]);
+                }
+                UnixEndpoint::Abstract(name) => {
+                    let len = name.len().min(addr_un.sun_path.len() - 1);
+                    addr_un.sun_path[1..=len].copy_from_slice(&name[..len]);
+                }
+            }
+            SockAddr { addr_un }
         } else {
             unimplemented!("not match");
         }
@@ -196,12 +228,15 @@ pub fn sockaddr_to_endpoint(addr: SockAddr, len: usize) -> Result<Endpoint, LxEr
         return Err(LxError::EINVAL);
     }
     // let addr = unsafe { vm.check_read_ptr(addr)? };
-    if len < addr.len()? {
+    // the length of a unix address depends on its name
+    #[allow(unsafe_code)]
+    let family = AddressFamily::from(unsafe { addr.family });
+    if family != AddressFamily::Unix && len < addr.len()? {
         return Err(LxError::EINVAL);
     }
     #[allow(unsafe_code)]
     unsafe {
-        match AddressFamily::from(addr.family) {
+        match family {
             AddressFamily::Internet => {
                 let port = u16::from_be(addr.addr_in.sin_port);
                 let addr = IpAddress::from(Ipv4Address::from_bytes(
@@ -209,7 +244,19 @@ pub fn sockaddr_to_endpoint(addr: SockAddr, len: usize) -> Result<Endpoint, LxEr
                 ));
                 Ok(Endpoint::Ip((addr, port).into()))
             }
-            AddressFamily::Unix => Err(LxError::EINVAL),
+            AddressFamily::Unix => {
+                let path = &addr.addr_un.sun_path[..min(len, size_of::<SockAddrUn>()) - 2];
+                match path.first() {
+                    None => Ok(Endpoint::Unix(UnixEndpoint::Unnamed)),
+                    Some(0) => Ok(Endpoint::Unix(UnixEndpoint::Abstract(path[1..].to_vec()))),
+                    Some(_) => {
+                        let end = path.iter().position(|&c| c == 0).unwrap_or(path.len());
+                        let path =
+                            core::str::from_utf8(&path[..end]).map_err(|_| LxError::EINVAL)?;
+                        Ok(Endpoint::Unix(UnixEndpoint::Path(path.into())))
+                    }
+                }
+            }
             // AddressFamily::Packet => Ok(Endpoint::LinkLevel(LinkLevelEndpoint::new(
             //     addr.addr_ll.sll_ifindex as usize,
             // ))),
@@ -229,7 +276,18 @@ impl SockAddr {
             AddressFamily::Internet => Ok(size_of::<SockAddrIn>()),
             AddressFamily::Packet => Ok(size_of::<SockAddrLl>()),
             AddressFamily::Netlink => Ok(size_of::<SockAddrNl>()),
-            AddressFamily::Unix => Err(LxError::EINVAL),
+            AddressFamily::Unix => {
+                // the family alone for an unbound socket, and the name
+                // including the leading NUL for an abstract one
+                #[allow(unsafe_code)]
+                let path = unsafe { &self.addr_un.sun_path };
+                let len = if path[0] != 0 {
+                    path.iter().position(|&c| c == 0).unwrap_or(path.len()) + 1
+                } else {
+                    path.iter().rposition(|&c| c != 0).map_or(0, |i| i + 1)
+                };
+                Ok(size_of::<u16>() + len)
+            }
             _ => Err(LxError::EINVAL),
         }
     }
@@ -249,8 +307,19 @@ impl SockAddr {
         }
 
         let max_addr_len = addr_len.read()? as usize;
-        let full_len = self.len()?;
+        let full_len = self.write_to_limited(addr, max_addr_len)?;
+        addr_len.write(full_len as u32)?;
+        Ok(0)
+    }
 
+    /// Write at most `max_addr_len` bytes to user sockaddr,
+    /// and return the full length of the address.
+    pub fn write_to_limited(
+        self,
+        addr: UserOutPtr<SockAddr>,
+        max_addr_len: usize,
+    ) -> Result<usize, LxError> {
+        let full_len = self.len()?;
         let written_len = min(max_addr_len, full_len);
         if written_len > 0 {
             #[allow(unsafe_code)]
@@ -261,8 +330,7 @@ impl SockAddr {
             let mut addr: UserOutPtr<u8> = unsafe { core::mem::transmute(addr) };
             addr.write_array(source)?;
         }
-        addr_len.write(full_len as u32)?;
-        Ok(0)
+        Ok(full_len)
     }
 }
 
@@ -10,6 +10,8 @@ use crate::net::poll_ifaces;
 use crate::net::Endpoint;
 use crate::net::GlobalSocketHandle;
 use crate::net::IpEndpoint;
+use crate::net::MsgFlags;
+use crate::net::RecvMsg;
 use crate::net::Socket;
 use crate::net::SysResult;
 use crate::net::TCP_RECVBUF;
@@ -20,6 +22,7 @@ use spin::Mutex;
 // alloc
 use alloc::boxed::Box;
 use alloc::vec;
+use alloc::vec::Vec;
 
 // smoltcp
 
@@ -96,6 +99,40 @@ impl TcpSocketState {
         }
     }
 
+    /// Receive data from the stream, leaving it in the queue on `MSG_PEEK`.
+    pub async fn recvmsg(&self, data: &mut [u8], flags: MsgFlags) -> LxResult<RecvMsg> {
+        loop {
+            poll_ifaces();
+            let net_sockets = get_sockets();
+            let mut sockets = net_sockets.lock();
+            let mut socket = sockets.get::<TcpSocket>(self.handle.0);
+            if !socket.may_recv() {
+                return Err(LxError::ENOTCONN);
+            }
+            let result = if flags.contains(MsgFlags::PEEK) {
+                socket.peek_slice(data)
+            } else {
+                socket.recv_slice(data)
+            };
+            if let Ok(size) = result {
+                if size > 0 || data.is_empty() {
+                    // avoid deadlock
+                    drop(socket);
+                    drop(sockets);
+                    poll_ifaces();
+                    return Ok(RecvMsg {
+                        len: size,
+                        endpoint: None,
+                        control: Vec::new(),
+                    });
+                }
+            }
+            if flags.contains(MsgFlags::DONTWAIT) {
+                return Err(LxError::EAGAIN);
+            }
+        }
+    }
+
     /// missing documentation
     pub fn write(&self, data: &[u8], _sendto_endpoint: Option<Endpoint>) -> SysResult {
         warn!("tcp write");
@@ -324,9 +361,13 @@ impl Socket for TcpSocketState {
         self.read(data).await
     }
     /// write from buffer
-    fn write(&self, _data: &[u8], _sendto_endpoint: Option<Endpoint>) -> SysResult {
+    async fn write(&self, _data: &[u8], _sendto_endpoint: Option<Endpoint>) -> SysResult {
         self.write(_data, _sendto_endpoint)
     }
+    /// receive from the stream
+    async fn recvmsg(&self, data: &mut [u8], flags: MsgFlags) -> LxResult<RecvMsg> {
+        self.recvmsg(data, flags).await
+    }
     /// connect
     async fn connect(&self, _endpoint: Endpoint) -> SysResult {
         self.connect(_endpoint).await
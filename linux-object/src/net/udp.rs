@@ -16,6 +16,8 @@ use crate::net::GlobalSocketHandle;
 use crate::net::IpAddress;
 use crate::net::IpEndpoint;
 use crate::net::Ipv4Address;
+use crate::net::MsgFlags;
+use crate::net::RecvMsg;
 use crate::net::SockAddr;
 use crate::net::SockAddrPlaceholder;
 use crate::net::Socket;
@@ -29,6 +31,7 @@ use spin::Mutex;
 use alloc::boxed::Box;
 use alloc::sync::Arc;
 use alloc::vec;
+use alloc::vec::Vec;
 
 // smoltcp
 
@@ -122,6 +125,41 @@ impl UdpSocketState {
         }
     }
 
+    /// Receive a datagram, returning its full length even if `data` is shorter.
+    pub fn recvmsg(&self, data: &mut [u8], flags: MsgFlags) -> LxResult<RecvMsg> {
+        poll_ifaces();
+        let net_sockets = get_sockets();
+        let mut sockets = net_sockets.lock();
+        let mut socket = sockets.get::<UdpSocket>(self.handle.0);
+        if !socket.can_recv() {
+            return Err(if flags.contains(MsgFlags::DONTWAIT) {
+                LxError::EAGAIN
+            } else {
+                LxError::ENOTCONN
+            });
+        }
+        let (payload, endpoint) = if flags.contains(MsgFlags::PEEK) {
+            socket
+                .peek()
+                .map(|(payload, endpoint)| (payload, *endpoint))
+                .map_err(|_| LxError::EAGAIN)?
+        } else {
+            socket.recv().map_err(|_| LxError::EAGAIN)?
+        };
+        let len = payload.len().min(data.len());
+        data[..len].copy_from_slice(&payload[..len]);
+        let msg = RecvMsg {
+            len: payload.len(),
+            endpoint: Some(Endpoint::Ip(endpoint)),
+            control: Vec::new(),
+        };
+        // avoid deadlock
+        drop(socket);
+        drop(sockets);
+        poll_ifaces();
+        Ok(msg)
+    }
+
     /// missing documentation
     pub fn write(&self, data: &[u8], sendto_endpoint: Option<Endpoint>) -> SysResult {
         info!("udp write");
@@ -287,9 +325,13 @@ impl Socket for UdpSocketState {
         self.read(data).await
     }
     /// write from buffer
-    fn write(&self, data: &[u8], sendto_endpoint: Option<Endpoint>) -> SysResult {
+    async fn write(&self, data: &[u8], sendto_endpoint: Option<Endpoint>) -> SysResult {
         self.write(data, sendto_endpoint)
     }
+    /// receive a datagram
+    async fn recvmsg(&self, data: &mut [u8], flags: MsgFlags) -> LxResult<RecvMsg> {
+        self.recvmsg(data, flags)
+    }
     /// connect
     async fn connect(&self, endpoint: Endpoint) -> SysResult {
         self.connect(endpoint).await
@@ -0,0 +1,831 @@
+//! Unix domain sockets
+//!
+//! Sockets are named by paths or names in the abstract namespace, all kept in
+//! one global table. The callers resolve a path to an absolute one and create
+//! the socket file before binding it.
+
+use alloc::{
+    boxed::Box,
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use core::convert::TryFrom;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use core::task::Waker;
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+use crate::error::{LxError, LxResult};
+use crate::net::{ControlMessage, Endpoint, MsgFlags, PassedFile, RecvMsg, Socket, SysResult};
+use crate::net::{UCred, UnixEndpoint, SOL_SOCKET};
+use crate::sync::{wait_for_event, Event, EventBus};
+
+/// Capacity of the receive queue of a unix socket
+pub const UNIX_RECVBUF: usize = 256 * 1024;
+
+/// Socket option to get the type of a socket
+const SO_TYPE: usize = 3;
+/// Socket option to receive `SCM_CREDENTIALS` messages
+const SO_PASSCRED: usize = 16;
+/// Socket option to get the credentials of the peer
+const SO_PEERCRED: usize = 17;
+
+/// `O_RDWR` returned by `F_GETFL`
+const O_RDWR: usize = 2;
+/// `O_NONBLOCK` of `F_GETFL` and `F_SETFL`
+const O_NONBLOCK: usize = 0o4000;
+/// Get the file status flags.
+const F_GETFL: usize = 3;
+/// Set the file status flags.
+const F_SETFL: usize = 4;
+
+lazy_static! {
+    /// Names bound by unix sockets
+    static ref NAMESPACE: Mutex<BTreeMap<UnixEndpoint, Weak<Name>>> = Mutex::new(BTreeMap::new());
+}
+
+/// Type of a unix socket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnixSocketType {
+    /// `SOCK_STREAM`
+    Stream = 1,
+    /// `SOCK_DGRAM`
+    Datagram = 2,
+    /// `SOCK_SEQPACKET`
+    SeqPacket = 5,
+}
+
+impl TryFrom<usize> for UnixSocketType {
+    type Error = LxError;
+    fn try_from(socket_type: usize) -> LxResult<Self> {
+        match socket_type {
+            1 => Ok(UnixSocketType::Stream),
+            2 => Ok(UnixSocketType::Datagram),
+            5 => Ok(UnixSocketType::SeqPacket),
+            _ => Err(LxError::ESOCKTNOSUPPORT),
+        }
+    }
+}
+
+/// Data written by one `sendmsg`
+struct Packet {
+    data: Vec<u8>,
+    /// Bytes already read from a stream
+    offset: usize,
+    /// Address of the sender
+    from: UnixEndpoint,
+    /// Files passed by `SCM_RIGHTS`
+    rights: Vec<PassedFile>,
+    /// Credentials of the sender
+    cred: UCred,
+}
+
+#[derive(Default)]
+struct QueueInner {
+    packets: VecDeque<Packet>,
+    /// Unread bytes in all packets
+    bytes: usize,
+    /// Whether the writer or the reader has gone
+    closed: bool,
+}
+
+/// Packets waiting to be received by a socket
+struct RecvQueue {
+    inner: Mutex<QueueInner>,
+    eventbus: Arc<Mutex<EventBus>>,
+}
+
+impl RecvQueue {
+    fn new() -> Arc<Self> {
+        Arc::new(RecvQueue {
+            inner: Mutex::new(QueueInner::default()),
+            eventbus: EventBus::new(),
+        })
+    }
+
+    /// Queue a packet, and returns the number of bytes queued.
+    ///
+    /// A stream takes as much data as fits, while other packets are queued as a whole.
+    /// The packet is given back if there is no room for it.
+    fn push(&self, mut packet: Packet, stream: bool) -> LxResult<Result<usize, Packet>> {
+        let mut inner = self.inner.lock();
+        if inner.closed {
+            return Err(LxError::EPIPE);
+        }
+        let room = UNIX_RECVBUF - inner.bytes;
+        if packet.data.len() > room {
+            if !stream && packet.data.len() > UNIX_RECVBUF {
+                return Err(LxError::EMSGSIZE);
+            } else if !stream || room == 0 {
+                // set again when the reader takes some packets
+                self.eventbus.lock().clear(Event::WRITABLE);
+                return Ok(Err(packet));
+            }
+            packet.data.truncate(room);
+        }
+        let len = packet.data.len();
+        inner.bytes += len;
+        inner.packets.push_back(packet);
+        self.eventbus.lock().set(Event::READABLE);
+        Ok(Ok(len))
+    }
+
+    /// Register `waker` to be woken up once, when the status of the queue changes.
+    fn register_waker(&self, waker: &Waker) {
+        let waker = waker.clone();
+        self.eventbus.lock().subscribe(Box::new(move |_| {
+            waker.wake_by_ref();
+            true
+        }));
+    }
+
+    /// Shut down the queue, discarding unread packets if `discard` is set.
+    fn close(&self, discard: bool) {
+        let mut inner = self.inner.lock();
+        inner.closed = true;
+        if discard {
+            inner.packets.clear();
+            inner.bytes = 0;
+        }
+        self.eventbus.lock().set(Event::CLOSED);
+    }
+
+    fn is_closed(&self) -> bool {
+        self.inner.lock().closed
+    }
+
+    fn readable(&self) -> bool {
+        let inner = self.inner.lock();
+        !inner.packets.is_empty() || inner.closed
+    }
+
+    fn writable(&self) -> bool {
+        let inner = self.inner.lock();
+        !inner.closed && inner.bytes < UNIX_RECVBUF
+    }
+}
+
+/// One end of a connected stream or seqpacket socket
+struct Connection {
+    /// Packets from the peer
+    recv: Arc<RecvQueue>,
+    /// Packets to the peer
+    send: Arc<RecvQueue>,
+    /// Address of the peer
+    peer: UnixEndpoint,
+    /// Credentials of the peer
+    peer_cred: UCred,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // the peer reads to the end of data and fails to write
+        self.recv.close(true);
+        self.send.close(false);
+    }
+}
+
+/// Default destination of a datagram socket
+struct DatagramPeer {
+    endpoint: UnixEndpoint,
+    queue: Weak<RecvQueue>,
+    /// Credentials of the peer created by `socketpair`
+    cred: Option<UCred>,
+}
+
+/// Connections waiting to be accepted by a listening socket
+struct Listener {
+    backlog: Mutex<VecDeque<UnixSocketState>>,
+    eventbus: Arc<Mutex<EventBus>>,
+    /// Credentials reported to the connecting sockets
+    cred: UCred,
+    /// Whether accepted sockets receive credentials
+    passcred: bool,
+}
+
+impl Listener {
+    fn push(&self, socket: UnixSocketState) {
+        let mut backlog = self.backlog.lock();
+        backlog.push_back(socket);
+        self.eventbus.lock().set(Event::READABLE);
+    }
+
+    fn pop(&self) -> Option<UnixSocketState> {
+        let mut backlog = self.backlog.lock();
+        let socket = backlog.pop_front();
+        if backlog.is_empty() {
+            self.eventbus.lock().clear(Event::READABLE);
+        }
+        socket
+    }
+}
+
+/// A name bound by a socket, removed from the namespace when the socket is closed
+struct Name {
+    endpoint: UnixEndpoint,
+    type_: UnixSocketType,
+    /// Receive queue of a datagram socket
+    queue: Option<Arc<RecvQueue>>,
+    /// Pending connections of a listening socket
+    listener: Mutex<Option<Arc<Listener>>>,
+}
+
+impl Drop for Name {
+    fn drop(&mut self) {
+        let mut namespace = NAMESPACE.lock();
+        // the name may have been bound again after the socket file was removed
+        if namespace
+            .get(&self.endpoint)
+            .map_or(false, |name| name.strong_count() == 0)
+        {
+            namespace.remove(&self.endpoint);
+        }
+    }
+}
+
+fn lookup(endpoint: &UnixEndpoint) -> Option<Arc<Name>> {
+    NAMESPACE.lock().get(endpoint).and_then(Weak::upgrade)
+}
+
+/// Generate a name in the abstract namespace for a socket bound without a name.
+fn autobind_name() -> UnixEndpoint {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let id = NEXT.fetch_add(1, Ordering::Relaxed) & 0xfffff;
+    UnixEndpoint::Abstract(format!("{:05x}", id).into_bytes())
+}
+
+enum State {
+    Unconnected,
+    Listening(Arc<Listener>),
+    Connected(Connection),
+    /// A datagram socket with a default destination
+    Peer(DatagramPeer),
+}
+
+/// A unix domain socket
+pub struct UnixSocketState {
+    type_: UnixSocketType,
+    /// Credentials of the creator, reported to the peers
+    cred: UCred,
+    /// Name bound by `bind`
+    name: Option<Arc<Name>>,
+    /// Local address, which an accepted socket shares with its listener
+    local: UnixEndpoint,
+    /// Receive queue of a datagram socket
+    datagrams: Option<Arc<RecvQueue>>,
+    state: Mutex<State>,
+    /// Whether `SCM_CREDENTIALS` messages are received
+    passcred: bool,
+    nonblock: AtomicBool,
+}
+
+impl fmt::Debug for UnixSocketState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UnixSocketState")
+            .field("type", &self.type_)
+            .field("local", &self.local)
+            .finish()
+    }
+}
+
+impl UnixSocketState {
+    /// Create an unbound socket created by a process with `cred`.
+    pub fn new(type_: UnixSocketType, cred: UCred, nonblock: bool) -> Self {
+        let datagrams = match type_ {
+            UnixSocketType::Datagram => Some(RecvQueue::new()),
+            _ => None,
+        };
+        UnixSocketState {
+            type_,
+            cred,
+            name: None,
+            local: UnixEndpoint::Unnamed,
+            datagrams,
+            state: Mutex::new(State::Unconnected),
+            passcred: false,
+            nonblock: AtomicBool::new(nonblock),
+        }
+    }
+
+    /// Create a pair of connected sockets, as `socketpair`.
+    pub fn pair(type_: UnixSocketType, cred: UCred, nonblock: bool) -> (Self, Self) {
+        let a = Self::new(type_, cred, nonblock);
+        let b = Self::new(type_, cred, nonblock);
+        match (&a.datagrams, &b.datagrams) {
+            (Some(queue_a), Some(queue_b)) => {
+                *a.state.lock() = State::Peer(DatagramPeer {
+                    endpoint: UnixEndpoint::Unnamed,
+                    queue: Arc::downgrade(queue_b),
+                    cred: Some(cred),
+                });
+                *b.state.lock() = State::Peer(DatagramPeer {
+                    endpoint: UnixEndpoint::Unnamed,
+                    queue: Arc::downgrade(queue_a),
+                    cred: Some(cred),
+                });
+            }
+            _ => {
+                let (queue_a, queue_b) = (RecvQueue::new(), RecvQueue::new());
+                *a.state.lock() = State::Connected(Connection {
+                    recv: queue_a.clone(),
+                    send: queue_b.clone(),
+                    peer: UnixEndpoint::Unnamed,
+                    peer_cred: cred,
+                });
+                *b.state.lock() = State::Connected(Connection {
+                    recv: queue_b,
+                    send: queue_a,
+                    peer: UnixEndpoint::Unnamed,
+                    peer_cred: cred,
+                });
+            }
+        }
+        (a, b)
+    }
+
+    /// Returns the queue to receive from.
+    fn recv_queue(&self) -> LxResult<Arc<RecvQueue>> {
+        if let Some(queue) = &self.datagrams {
+            return Ok(queue.clone());
+        }
+        match &*self.state.lock() {
+            State::Connected(conn) => Ok(conn.recv.clone()),
+            _ => Err(LxError::EINVAL),
+        }
+    }
+
+    /// Receive from `queue` without blocking, or returns `None` if it is empty.
+    fn try_recv(&self, queue: &RecvQueue, data: &mut [u8], flags: MsgFlags) -> Option<RecvMsg> {
+        let peek = flags.contains(MsgFlags::PEEK);
+        let mut guard = queue.inner.lock();
+        let inner = &mut *guard;
+        let (cred, from) = match inner.packets.front() {
+            Some(packet) => (packet.cred, packet.from.clone()),
+            None if inner.closed => {
+                return Some(RecvMsg {
+                    len: 0,
+                    endpoint: None,
+                    control: Vec::new(),
+                })
+            }
+            None => return None,
+        };
+        let mut len = 0;
+        let mut rights = Vec::new();
+        if self.type_ == UnixSocketType::Stream {
+            let mut index = 0;
+            while len < data.len() {
+                let packet = match inner.packets.get_mut(index) {
+                    Some(packet) => packet,
+                    None => break,
+                };
+                // files and different credentials are not merged into one read
+                if len > 0 && (!packet.rights.is_empty() || (self.passcred && packet.cred != cred))
+                {
+                    break;
+                }
+                let remaining = &packet.data[packet.offset..];
+                let n = remaining.len().min(data.len() - len);
+                data[len..len + n].copy_from_slice(&remaining[..n]);
+                len += n;
+                let has_rights = !packet.rights.is_empty();
+                if peek {
+                    rights.extend(packet.rights.iter().cloned());
+                    index += 1;
+                } else {
+                    rights.append(&mut packet.rights);
+                    packet.offset += n;
+                    if packet.offset == packet.data.len() {
+                        inner.packets.pop_front();
+                    }
+                    inner.bytes -= n;
+                }
+                if has_rights {
+                    break;
+                }
+            }
+        } else {
+            let packet = inner.packets.front().unwrap();
+            let n = packet.data.len().min(data.len());
+            data[..n].copy_from_slice(&packet.data[..n]);
+            // the rest of a datagram is discarded
+            len = packet.data.len();
+            if peek {
+                rights.extend(packet.rights.iter().cloned());
+            } else {
+                let packet = inner.packets.pop_front().unwrap();
+                inner.bytes -= packet.data.len();
+                rights = packet.rights;
+            }
+        }
+        let mut eventbus = queue.eventbus.lock();
+        if !peek {
+            eventbus.set(Event::WRITABLE);
+        }
+        if inner.packets.is_empty() && !inner.closed {
+            eventbus.clear(Event::READABLE);
+        }
+        drop(eventbus);
+        let mut control = Vec::new();
+        if self.passcred {
+            control.push(ControlMessage::Credentials(cred));
+        }
+        if !rights.is_empty() {
+            control.push(ControlMessage::Rights(rights));
+        }
+        Some(RecvMsg {
+            len,
+            endpoint: Some(Endpoint::Unix(from)),
+            control,
+        })
+    }
+
+    /// Receive a message, waiting for one unless it is non-blocking.
+    pub async fn recv(&self, data: &mut [u8], flags: MsgFlags) -> LxResult<RecvMsg> {
+        let queue = self.recv_queue()?;
+        loop {
+            if let Some(msg) = self.try_recv(&queue, data, flags) {
+                return Ok(msg);
+            }
+            if flags.contains(MsgFlags::DONTWAIT) || self.nonblock.load(Ordering::Relaxed) {
+                return Err(LxError::EAGAIN);
+            }
+            wait_for_event(queue.eventbus.clone(), Event::READABLE | Event::CLOSED).await;
+        }
+    }
+
+    /// Send a message to the peer, or to `to` for a datagram socket.
+    ///
+    /// Waits for room in the receive queue of the peer unless it is non-blocking.
+    pub async fn send(
+        &self,
+        data: &[u8],
+        to: Option<Endpoint>,
+        control: Vec<ControlMessage>,
+        flags: MsgFlags,
+    ) -> SysResult {
+        let mut packet = Packet {
+            data: data.to_vec(),
+            offset: 0,
+            from: self.local.clone(),
+            rights: Vec::new(),
+            cred: self.cred,
+        };
+        for msg in control {
+            match msg {
+                ControlMessage::Rights(files) => packet.rights.extend(files),
+                ControlMessage::Credentials(cred) => packet.cred = cred,
+            }
+        }
+        if self.type_ == UnixSocketType::Datagram {
+            let queue = match to {
+                Some(Endpoint::Unix(endpoint)) => {
+                    let name = lookup(&endpoint).ok_or(LxError::ECONNREFUSED)?;
+                    if name.type_ != self.type_ {
+                        return Err(LxError::EPROTOTYPE);
+                    }
+                    name.queue.clone().ok_or(LxError::ECONNREFUSED)?
+                }
+                Some(_) => return Err(LxError::EINVAL),
+                None => match &*self.state.lock() {
+                    State::Peer(peer) => peer.queue.upgrade().ok_or(LxError::ECONNREFUSED)?,
+                    _ => return Err(LxError::ENOTCONN),
+                },
+            };
+            return self.push(&queue, packet, false, flags).await;
+        }
+        let queue = match &*self.state.lock() {
+            State::Connected(conn) => conn.send.clone(),
+            _ => return Err(LxError::ENOTCONN),
+        };
+        if to.is_some() {
+            return Err(LxError::EISCONN);
+        }
+        let stream = self.type_ == UnixSocketType::Stream;
+        if stream && data.is_empty() {
+            return Ok(0);
+        }
+        self.push(&queue, packet, stream, flags).await
+    }
+
+    /// Queue `packet` to `queue`, waiting for room unless it is non-blocking.
+    async fn push(
+        &self,
+        queue: &RecvQueue,
+        mut packet: Packet,
+        stream: bool,
+        flags: MsgFlags,
+    ) -> SysResult {
+        loop {
+            match queue.push(packet, stream)? {
+                Ok(len) => return Ok(len),
+                Err(back) => packet = back,
+            }
+            if flags.contains(MsgFlags::DONTWAIT) || self.nonblock.load(Ordering::Relaxed) {
+                return Err(LxError::EAGAIN);
+            }
+            wait_for_event(queue.eventbus.clone(), Event::WRITABLE | Event::CLOSED).await;
+        }
+    }
+
+    fn connect(&self, endpoint: Endpoint) -> SysResult {
+        let endpoint = match endpoint {
+            Endpoint::Unix(endpoint) => endpoint,
+            _ => return Err(LxError::EINVAL),
+        };
+        let name = lookup(&endpoint).ok_or(LxError::ECONNREFUSED)?;
+        if name.type_ != self.type_ {
+            return Err(LxError::EPROTOTYPE);
+        }
+        let mut state = self.state.lock();
+        if let Some(queue) = &name.queue {
+            *state = State::Peer(DatagramPeer {
+                endpoint,
+                queue: Arc::downgrade(queue),
+                cred: None,
+            });
+            return Ok(0);
+        }
+        if !matches!(*state, State::Unconnected) {
+            return Err(LxError::EISCONN);
+        }
+        let listener = name.listener.lock().clone().ok_or(LxError::ECONNREFUSED)?;
+        let (client_queue, server_queue) = (RecvQueue::new(), RecvQueue::new());
+        let server = UnixSocketState {
+            type_: self.type_,
+            cred: listener.cred,
+            name: None,
+            local: endpoint.clone(),
+            datagrams: None,
+            state: Mutex::new(State::Connected(Connection {
+                recv: server_queue.clone(),
+                send: client_queue.clone(),
+                peer: self.local.clone(),
+                peer_cred: self.cred,
+            })),
+            passcred: listener.passcred,
+            nonblock: AtomicBool::new(false),
+        };
+        listener.push(server);
+        *state = State::Connected(Connection {
+            recv: client_queue,
+            send: server_queue,
+            peer: endpoint,
+            peer_cred: listener.cred,
+        });
+        Ok(0)
+    }
+
+    /// Bind the socket to `endpoint`, whose path must be absolute.
+    fn bind(&mut self, endpoint: Endpoint) -> SysResult {
+        let endpoint = match endpoint {
+            Endpoint::Unix(UnixEndpoint::Unnamed) => autobind_name(),
+            Endpoint::Unix(endpoint) => endpoint,
+            _ => return Err(LxError::EINVAL),
+        };
+        if self.name.is_some() {
+            return Err(LxError::EINVAL);
+        }
+        let mut namespace = NAMESPACE.lock();
+        // a path is in use only if the socket file exists, which the caller checks
+        if let UnixEndpoint::Abstract(_) = endpoint {
+            if namespace
+                .get(&endpoint)
+                .map_or(false, |name| name.strong_count() > 0)
+            {
+                return Err(LxError::EADDRINUSE);
+            }
+        }
+        let name = Arc::new(Name {
+            endpoint: endpoint.clone(),
+            type_: self.type_,
+            queue: self.datagrams.clone(),
+            listener: Mutex::new(None),
+        });
+        namespace.insert(endpoint.clone(), Arc::downgrade(&name));
+        drop(namespace);
+        self.name = Some(name);
+        self.local = endpoint;
+        Ok(0)
+    }
+
+    fn listen(&mut self) -> SysResult {
+        if self.type_ == UnixSocketType::Datagram {
+            return Err(LxError::EOPNOTSUPP);
+        }
+        let name = self.name.as_ref().ok_or(LxError::EINVAL)?;
+        let mut state = self.state.lock();
+        match *state {
+            State::Listening(_) => return Ok(0),
+            State::Unconnected => {}
+            _ => return Err(LxError::EINVAL),
+        }
+        let listener = Arc::new(Listener {
+            backlog: Mutex::new(VecDeque::new()),
+            eventbus: EventBus::new(),
+            cred: self.cred,
+            passcred: self.passcred,
+        });
+        *name.listener.lock() = Some(listener.clone());
+        *state = State::Listening(listener);
+        Ok(0)
+    }
+
+    async fn accept(&mut self) -> LxResult<(Arc<Mutex<dyn Socket>>, Endpoint)> {
+        let listener = match &*self.state.lock() {
+            State::Listening(listener) => listener.clone(),
+            _ => return Err(LxError::EINVAL),
+        };
+        loop {
+            if let Some(socket) = listener.pop() {
+                let peer = socket
+                    .remote_endpoint()
+                    .unwrap_or(Endpoint::Unix(UnixEndpoint::Unnamed));
+                return Ok((Arc::new(Mutex::new(socket)), peer));
+            }
+            if self.nonblock.load(Ordering::Relaxed) {
+                return Err(LxError::EAGAIN);
+            }
+            wait_for_event(listener.eventbus.clone(), Event::READABLE).await;
+        }
+    }
+
+    fn shutdown(&self) -> SysResult {
+        match &*self.state.lock() {
+            State::Connected(conn) => {
+                conn.recv.close(false);
+                conn.send.close(false);
+                Ok(0)
+            }
+            _ => Err(LxError::ENOTCONN),
+        }
+    }
+
+    fn poll(&self) -> (bool, bool, bool) {
+        if let Some(queue) = &self.datagrams {
+            return (queue.readable(), true, false);
+        }
+        match &*self.state.lock() {
+            State::Listening(listener) => (!listener.backlog.lock().is_empty(), false, false),
+            State::Connected(conn) => {
+                let hangup = conn.send.is_closed();
+                (conn.recv.readable(), conn.send.writable(), hangup)
+            }
+            _ => (false, false, false),
+        }
+    }
+
+    fn register_waker(&self, waker: &Waker) -> bool {
+        if let Some(queue) = &self.datagrams {
+            queue.register_waker(waker);
+            return true;
+        }
+        match &*self.state.lock() {
+            State::Listening(listener) => {
+                let waker = waker.clone();
+                listener.eventbus.lock().subscribe(Box::new(move |_| {
+                    waker.wake_by_ref();
+                    true
+                }));
+                true
+            }
+            State::Connected(conn) => {
+                conn.recv.register_waker(waker);
+                conn.send.register_waker(waker);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn remote_endpoint(&self) -> Option<Endpoint> {
+        match &*self.state.lock() {
+            State::Connected(conn) => Some(Endpoint::Unix(conn.peer.clone())),
+            State::Peer(peer) => Some(Endpoint::Unix(peer.endpoint.clone())),
+            _ => None,
+        }
+    }
+
+    fn peer_cred(&self) -> Option<UCred> {
+        match &*self.state.lock() {
+            State::Connected(conn) => Some(conn.peer_cred),
+            State::Peer(peer) => peer.cred,
+            _ => None,
+        }
+    }
+}
+
+#[async_trait]
+impl Socket for UnixSocketState {
+    async fn read(&self, data: &mut [u8]) -> (SysResult, Endpoint) {
+        match self.recv(data, MsgFlags::empty()).await {
+            Ok(msg) => (
+                Ok(msg.len.min(data.len())),
+                msg.endpoint
+                    .unwrap_or(Endpoint::Unix(UnixEndpoint::Unnamed)),
+            ),
+            Err(err) => (Err(err), Endpoint::Unix(UnixEndpoint::Unnamed)),
+        }
+    }
+
+    async fn write(&self, data: &[u8], sendto_endpoint: Option<Endpoint>) -> SysResult {
+        self.send(data, sendto_endpoint, Vec::new(), MsgFlags::empty())
+            .await
+    }
+
+    async fn recvmsg(&self, data: &mut [u8], flags: MsgFlags) -> LxResult<RecvMsg> {
+        self.recv(data, flags).await
+    }
+
+    async fn sendmsg(
+        &self,
+        data: &[u8],
+        sendto_endpoint: Option<Endpoint>,
+        control: Vec<ControlMessage>,
+        flags: MsgFlags,
+    ) -> SysResult {
+        self.send(data, sendto_endpoint, control, flags).await
+    }
+
+    fn poll(&self) -> (bool, bool, bool) {
+        self.poll()
+    }
+
+    fn register_waker(&self, waker: &Waker) -> bool {
+        self.register_waker(waker)
+    }
+
+    async fn connect(&self, endpoint: Endpoint) -> SysResult {
+        self.connect(endpoint)
+    }
+
+    fn bind(&mut self, endpoint: Endpoint) -> SysResult {
+        self.bind(endpoint)
+    }
+
+    fn listen(&mut self) -> SysResult {
+        self.listen()
+    }
+
+    fn shutdown(&self) -> SysResult {
+        self.shutdown()
+    }
+
+    async fn accept(&mut self) -> LxResult<(Arc<Mutex<dyn Socket>>, Endpoint)> {
+        self.accept().await
+    }
+
+    fn endpoint(&self) -> Option<Endpoint> {
+        Some(Endpoint::Unix(self.local.clone()))
+    }
+
+    fn remote_endpoint(&self) -> Option<Endpoint> {
+        self.remote_endpoint()
+    }
+
+    fn setsockopt(&mut self, level: usize, opt: usize, data: &[u8]) -> SysResult {
+        if level == SOL_SOCKET as usize && opt == SO_PASSCRED {
+            self.passcred = data.iter().take(4).any(|&b| b != 0);
+        }
+        Ok(0)
+    }
+
+    fn getsockopt(&self, level: usize, opt: usize) -> LxResult<Vec<u8>> {
+        if level != SOL_SOCKET as usize {
+            return Ok(Vec::new());
+        }
+        let value = match opt {
+            SO_TYPE => (self.type_ as i32).to_ne_bytes().to_vec(),
+            SO_PASSCRED => (self.passcred as i32).to_ne_bytes().to_vec(),
+            // a socket without peer gets the overflow IDs
+            SO_PEERCRED => self
+                .peer_cred()
+                .unwrap_or(UCred {
+                    pid: 0,
+                    uid: u32::MAX,
+                    gid: u32::MAX,
+                })
+                .as_bytes()
+                .to_vec(),
+            _ => Vec::new(),
+        };
+        Ok(value)
+    }
+
+    fn fcntl(&self, cmd: usize, arg: usize) -> SysResult {
+        match cmd {
+            F_GETFL if self.nonblock.load(Ordering::Relaxed) => Ok(O_RDWR | O_NONBLOCK),
+            F_GETFL => Ok(O_RDWR),
+            F_SETFL => {
+                self.nonblock
+                    .store(arg & O_NONBLOCK != 0, Ordering::Relaxed);
+                Ok(0)
+            }
+            _ => Ok(0),
+        }
+    }
+}
@@ -1,11 +1,21 @@
 //! Linux Process
 
 use crate::{
+    cred::{Access, Credentials},
     error::{LxError, LxResult},
-    fs::{File, FileDesc, FileLike, OpenFlags, STDIN, STDOUT},
+    fs::{
+        lock::{self, LockOwner},
+        File, FileDesc, FileLike, MountTable, OpenFlags, CONSOLE_TTY, STDIN, STDOUT,
+    },
     ipc::*,
     net::Socket,
-    signal::{Signal as LinuxSignal, SignalAction},
+    signal::{
+        ChildCode, SigInfo, Signal as LinuxSignal, SignalAction, SignalActionFlags,
+        SignalDefaultAction, SignalQueue, Sigset, SIG_DFL, SIG_IGN,
+    },
+    sync::{Event, EventBus, EventHandler},
+    thread::ThreadExt,
+    timer::{IntervalTimer, TimerClock, TimerNotify},
 };
 use alloc::{
     boxed::Box,
@@ -13,8 +23,8 @@ use alloc::{
     sync::{Arc, Weak},
     vec::Vec,
 };
-use core::sync::atomic::AtomicI32;
-use hashbrown::HashMap;
+use core::{convert::TryFrom, sync::atomic::AtomicI32, time::Duration};
+use hashbrown::{HashMap, HashSet};
 use kernel_hal::VirtAddr;
 use rcore_fs::vfs::{FileSystem, INode};
 use smoltcp::socket::SocketHandle;
@@ -22,8 +32,9 @@ use spin::{Mutex, MutexGuard};
 use zircon_object::{
     object::{KernelObject, KoID, Signal},
     signal::Futex,
-    task::{Job, Process, Status},
-    ZxResult,
+    task::{Job, Process, Status, Task, Thread},
+    vm::{pages, MMUFlags, VmAddressRegion, VmObject, PAGE_SIZE},
+    ZxError, ZxResult,
 };
 
 pub use rcore_fs::vfs::FsInfo;
@@ -34,14 +45,31 @@ pub trait ProcessExt {
     fn create_linux(job: &Arc<Job>, rootfs: Arc<dyn FileSystem>) -> ZxResult<Arc<Self>>;
     /// get linux process
     fn linux(&self) -> &LinuxProcess;
-    /// fork from current linux process
-    fn fork_from(parent: &Arc<Self>, vfork: bool) -> ZxResult<Arc<Self>>;
+    /// fork from current linux process, sharing the resources selected by `flags`
+    fn fork_from(parent: &Arc<Self>, flags: CloneFlags) -> ZxResult<Arc<Self>>;
+    /// Send a process-directed signal.
+    fn send_signal(&self, info: SigInfo);
+    /// Find a process with `pid` in the same job.
+    fn find_process(&self, pid: KoID) -> LxResult<Arc<Self>>;
+    /// Move the process `pid` into the process group `pgid`.
+    fn set_pgid(&self, pid: KoID, pgid: KoID) -> LxResult;
+    /// Create a new session and process group led by the process.
+    fn set_sid(&self) -> LxResult<KoID>;
 }
 
 impl ProcessExt for Process {
     fn create_linux(job: &Arc<Job>, rootfs: Arc<dyn FileSystem>) -> ZxResult<Arc<Self>> {
-        let linux_proc = LinuxProcess::new(rootfs);
-        Process::create_with_ext(job, "root", linux_proc)
+        let linux_proc = LinuxProcess::new(job, rootfs);
+        let proc = Process::create_with_ext(job, "root", linux_proc)?;
+        // the first process leads a new session, which is controlled by the console
+        {
+            let mut inner = proc.linux().inner.lock();
+            inner.pid = proc.id();
+            inner.pgid = proc.id();
+            inner.sid = proc.id();
+        }
+        CONSOLE_TTY.attach(&proc);
+        Ok(proc)
     }
 
     fn linux(&self) -> &LinuxProcess {
@@ -50,87 +78,456 @@ impl ProcessExt for Process {
 
     /// [Fork] the process.
     ///
+    /// The file descriptor table, the working directory and the signal actions
+    /// are shared with `parent` if `FILES`, `FS` and `SIGHAND` are set in `flags`,
+    /// otherwise they are copied. The address space is shared rather than copied if `VM` is set.
+    /// With `PARENT`, the new process becomes a child of the parent of `parent`.
+    ///
     /// [Fork]: http://man7.org/linux/man-pages/man2/fork.2.html
-    fn fork_from(parent: &Arc<Self>, vfork: bool) -> ZxResult<Arc<Self>> {
+    fn fork_from(parent: &Arc<Self>, flags: CloneFlags) -> ZxResult<Arc<Self>> {
         let linux_parent = parent.linux();
-        let mut linux_parent_inner = linux_parent.inner.lock();
+        let files = if flags.contains(CloneFlags::FILES) {
+            linux_parent.files.clone()
+        } else {
+            let files = linux_parent.files.lock().clone();
+            Arc::new(Mutex::new(files))
+        };
+        let current_working_directory = if flags.contains(CloneFlags::FS) {
+            linux_parent.current_working_directory.clone()
+        } else {
+            let cwd = linux_parent.current_working_directory.lock().clone();
+            Arc::new(Mutex::new(cwd))
+        };
+        let signal_actions = if flags.contains(CloneFlags::SIGHAND) {
+            linux_parent.signal_actions.clone()
+        } else {
+            let actions = linux_parent.signal_actions.lock().clone();
+            Arc::new(Mutex::new(actions))
+        };
+        let real_parent = if flags.contains(CloneFlags::PARENT) {
+            linux_parent.parent().ok_or(ZxError::INVALID_ARGS)?
+        } else {
+            parent.clone()
+        };
+        let linux_parent_inner = linux_parent.inner.lock();
         let new_linux_proc = LinuxProcess {
             root_inode: linux_parent.root_inode.clone(),
-            parent: Arc::downgrade(parent),
+            mounts: linux_parent.mounts.clone(),
+            parent: Arc::downgrade(&real_parent),
+            files,
+            current_working_directory,
+            signal_actions,
+            signal_bus: Mutex::default(),
             inner: Mutex::new(LinuxProcessInner {
                 execute_path: linux_parent_inner.execute_path.clone(),
-                current_working_directory: linux_parent_inner.current_working_directory.clone(),
-                files: linux_parent_inner.files.clone(),
-                signal_actions: linux_parent_inner.signal_actions.clone(),
+                args: linux_parent_inner.args.clone(),
+                envs: linux_parent_inner.envs.clone(),
+                file_limit: linux_parent_inner.file_limit,
+                heap: linux_parent_inner.heap,
+                vdso_base: linux_parent_inner.vdso_base,
+                cred: linux_parent_inner.cred.clone(),
+                pgid: linux_parent_inner.pgid,
+                sid: linux_parent_inner.sid,
+                vm_users: if flags.contains(CloneFlags::VM) {
+                    linux_parent_inner.vm_users.clone()
+                } else {
+                    Default::default()
+                },
+                exit_signal: LinuxSignal::try_from((flags & CloneFlags::CSIGNAL).bits() as u8).ok(),
                 ..Default::default()
             }),
         };
+        drop(linux_parent_inner);
         let new_proc = Process::create_with_ext(&parent.job(), "", new_linux_proc)?;
-        linux_parent_inner
+        new_proc.linux().inner.lock().pid = new_proc.id();
+        real_parent
+            .linux()
+            .inner
+            .lock()
             .children
             .insert(new_proc.id(), new_proc.clone());
-        if !vfork {
+        if flags.contains(CloneFlags::VM) {
+            new_proc.set_vmar(parent.vmar());
+        } else {
             new_proc.vmar().fork_from(&parent.vmar())?;
         }
 
         // notify parent on terminated
-        let parent = parent.clone();
+        let child = Arc::downgrade(&new_proc);
         new_proc.add_signal_callback(Box::new(move |signal| {
             if signal.contains(Signal::PROCESS_TERMINATED) {
                 info!("Received signal: {:?}", signal);
-                parent.signal_set(Signal::SIGCHLD);
+                if let Some(child) = child.upgrade() {
+                    child.linux().terminated(&child);
+                }
+                return true;
             }
             false
         }));
         Ok(new_proc)
     }
+
+    /// Send a process-directed signal.
+    ///
+    /// `SIGKILL`, `SIGSTOP` and `SIGCONT` take effect immediately,
+    /// other signals are queued until a thread which does not block it returns to user mode.
+    fn send_signal(&self, info: SigInfo) {
+        let signal = match LinuxSignal::try_from(info.signo as u8) {
+            Ok(signal) => signal,
+            Err(_) => return,
+        };
+        info!("send signal {:?} to process {}", signal, self.id());
+        let linux = self.linux();
+        if linux.prepare_signal(self, signal) {
+            linux.inner.lock().signal_queue.push(info);
+            wake_signal_waiters(self);
+            let mut bus = linux.signal_bus.lock();
+            bus.set(Event::RECEIVE_SIGNAL);
+            bus.clear(Event::RECEIVE_SIGNAL);
+        }
+    }
+
+    fn find_process(&self, pid: KoID) -> LxResult<Arc<Self>> {
+        self.job()
+            .get_child(pid)
+            .ok()
+            .and_then(|obj| obj.downcast_arc::<Process>().ok())
+            .ok_or(LxError::ESRCH)
+    }
+
+    /// Move the process `pid` into the process group `pgid`, as [setpgid] does.
+    ///
+    /// `pid` of 0 means the process itself, otherwise it must be a child of the process.
+    /// `pgid` of 0 means `pid`, otherwise the group must exist in the same session.
+    ///
+    /// [setpgid]: https://man7.org/linux/man-pages/man2/setpgid.2.html
+    fn set_pgid(&self, pid: KoID, pgid: KoID) -> LxResult {
+        let child;
+        let target: &Process = if pid == 0 || pid == self.id() {
+            self
+        } else {
+            let inner = self.linux().inner.lock();
+            child = inner.children.get(&pid).cloned().ok_or(LxError::ESRCH)?;
+            &child
+        };
+        let sid = self.linux().sid();
+        let linux = target.linux();
+        // a session leader can not change its group
+        if linux.sid() != sid || target.id() == sid {
+            return Err(LxError::EPERM);
+        }
+        let pgid = if pgid == 0 { target.id() } else { pgid };
+        if pgid != target.id()
+            && !process_group(&self.job(), pgid)
+                .iter()
+                .any(|p| p.linux().sid() == sid)
+        {
+            return Err(LxError::EPERM);
+        }
+        linux.inner.lock().pgid = pgid;
+        Ok(())
+    }
+
+    /// Create a new session and process group led by the process, as [setsid] does.
+    ///
+    /// Fails if the process is already a process group leader.
+    ///
+    /// [setsid]: https://man7.org/linux/man-pages/man2/setsid.2.html
+    fn set_sid(&self) -> LxResult<KoID> {
+        let id = self.id();
+        if !process_group(&self.job(), id).is_empty() {
+            return Err(LxError::EPERM);
+        }
+        let mut inner = self.linux().inner.lock();
+        inner.pgid = id;
+        inner.sid = id;
+        Ok(id)
+    }
+}
+
+bitflags::bitflags! {
+    /// Flags of `clone`
+    pub struct CloneFlags: usize {
+        /// signal sent to the parent when the child terminates
+        const CSIGNAL =         0xff;
+        /// the calling process and the child process run in the same memory space
+        const VM =              1 << 8;
+        /// the caller and the child process share the same filesystem information
+        const FS =              1 << 9;
+        /// the calling process and the child process share the same file descriptor table
+        const FILES =           1 << 10;
+        /// the calling process and the child process share the same table of signal handlers.
+        const SIGHAND =         1 << 11;
+        /// the calling process is being traced
+        const PTRACE =          1 << 13;
+        /// the execution of the calling process is suspended until the child releases its virtual memory resources
+        const VFORK =           1 << 14;
+        /// the parent of the new child will be the same as that of the call‐ing process.
+        const PARENT =          1 << 15;
+        /// the child is placed in the same thread group as the calling process.
+        const THREAD =          1 << 16;
+        /// cloned child is started in a new mount namespace
+        const NEWNS	=           1 << 17;
+        /// the child and the calling process share a single list of System V semaphore adjustment values.
+        const SYSVSEM =         1 << 18;
+        /// architecture dependent, The TLS (Thread Local Storage) descriptor is set to tls.
+        const SETTLS =          1 << 19;
+        /// Store the child thread ID at the location in the parent's memory.
+        const PARENT_SETTID =   1 << 20;
+        /// Clear (zero) the child thread ID
+        const CHILD_CLEARTID =  1 << 21;
+        /// the parent not to receive a signal when the child terminated
+        const DETACHED =        1 << 22;
+        /// a tracing process cannot force CLONE_PTRACE on this child process.
+        const UNTRACED =        1 << 23;
+        /// Store the child thread ID
+        const CHILD_SETTID =    1 << 24;
+        /// Create the process in a new cgroup namespace.
+        const NEWCGROUP =       1 << 25;
+        /// create the process in a new UTS namespace
+        const NEWUTS =          1 << 26;
+        /// create the process in a new IPC namespace.
+        const NEWIPC =          1 << 27;
+        /// create the process in a new user namespace
+        const NEWUSER =         1 << 28;
+        /// create the process in a new PID namespace
+        const NEWPID =          1 << 29;
+        /// create the process in a new net‐work namespace.
+        const NEWNET =          1 << 30;
+        /// the new process shares an I/O context with the calling process.
+        const IO =              1 << 31;
+    }
+}
+
+/// Returns the processes in `job`.
+pub(crate) fn job_processes(job: &Job) -> impl Iterator<Item = Arc<Process>> + '_ {
+    job.process_ids()
+        .into_iter()
+        .filter_map(move |id| job.get_child(id).ok())
+        .filter_map(|obj| obj.downcast_arc::<Process>().ok())
+}
+
+/// Returns the processes in the process group `pgid` of `job`.
+pub fn process_group(job: &Job, pgid: KoID) -> Vec<Arc<Process>> {
+    job_processes(job)
+        .filter(|p| p.linux().pgid() == pgid)
+        .collect()
+}
+
+/// Returns the processes in the session `sid` of `job`.
+pub fn session(job: &Job, sid: KoID) -> Vec<Arc<Process>> {
+    job_processes(job)
+        .filter(|p| p.linux().sid() == sid)
+        .collect()
+}
+
+bitflags::bitflags! {
+    /// Options of `wait4` and `waitid`
+    pub struct WaitOptions: u32 {
+        /// return immediately if no child has changed state
+        const NOHANG    = 1;
+        /// also report stopped children
+        const STOPPED   = 2;
+        /// report terminated children
+        const EXITED    = 4;
+        /// also report children continued by `SIGCONT`
+        const CONTINUED = 8;
+        /// leave the child in a waitable state
+        const NOWAIT    = 0x100_0000;
+    }
+}
+
+/// The children to wait for.
+#[derive(Debug, Clone, Copy)]
+pub enum WaitTarget {
+    /// Any child
+    Any,
+    /// The child with the process ID
+    Pid(KoID),
+    /// Any child in the process group
+    Pgid(KoID),
+}
+
+/// A state change of a child process, reported by `wait4` and `waitid`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitStatus {
+    /// Exited normally with the exit code
+    Exited(u8),
+    /// Terminated by the signal, and whether a core is dumped
+    Signaled(LinuxSignal, bool),
+    /// Stopped by the signal
+    Stopped(LinuxSignal),
+    /// Continued by `SIGCONT`
+    Continued,
+}
+
+impl WaitStatus {
+    /// Returns the status encoded as `wstatus` of `wait4`.
+    pub fn to_wstatus(self) -> i32 {
+        match self {
+            WaitStatus::Exited(code) => (code as i32) << 8,
+            WaitStatus::Signaled(signal, core_dumped) => {
+                signal as i32 | if core_dumped { 0x80 } else { 0 }
+            }
+            WaitStatus::Stopped(signal) => (signal as i32) << 8 | 0x7f,
+            WaitStatus::Continued => 0xffff,
+        }
+    }
+
+    /// Returns `si_code` and `si_status` of the `siginfo_t` reporting the change.
+    pub fn to_child_info(self) -> (ChildCode, i32) {
+        match self {
+            WaitStatus::Exited(code) => (ChildCode::Exited, code as i32),
+            WaitStatus::Signaled(signal, false) => (ChildCode::Killed, signal as i32),
+            WaitStatus::Signaled(signal, true) => (ChildCode::Dumped, signal as i32),
+            WaitStatus::Stopped(signal) => (ChildCode::Stopped, signal as i32),
+            WaitStatus::Continued => (ChildCode::Continued, LinuxSignal::SIGCONT as i32),
+        }
+    }
+
+    fn is_terminated(self) -> bool {
+        matches!(self, WaitStatus::Exited(_) | WaitStatus::Signaled(..))
+    }
+}
+
+/// Resources used by a process, or by its terminated and waited-for children.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ResourceUsage {
+    /// CPU time consumed in user mode
+    pub user_time: Duration,
+    /// CPU time consumed in kernel mode
+    pub system_time: Duration,
+    /// Maximum resident set size in bytes
+    pub max_rss: usize,
+    /// Page faults handled without I/O
+    pub minor_faults: usize,
+    /// Page faults which required I/O
+    ///
+    /// File contents are read into memory when they are mapped,
+    /// so no page fault requires I/O.
+    pub major_faults: usize,
+}
+
+impl ResourceUsage {
+    /// Add the usage of a terminated child, `max_rss` is the largest of the children.
+    fn add_child(&mut self, child: &ResourceUsage) {
+        self.user_time += child.user_time;
+        self.system_time += child.system_time;
+        self.max_rss = self.max_rss.max(child.max_rss);
+        self.minor_faults += child.minor_faults;
+        self.major_faults += child.major_faults;
+    }
+}
+
+/// Wake the threads of `proc` blocked in interruptible syscalls.
+fn wake_signal_waiters(proc: &Process) {
+    for tid in proc.thread_ids() {
+        let thread = proc.get_child(tid).ok();
+        if let Some(thread) = thread.and_then(|obj| obj.downcast_arc::<Thread>().ok()) {
+            thread.lock_linux().wake_signal_waiter();
+        }
+    }
+}
+
+/// Returns the bytes of memory committed to the mappings of `proc`.
+fn resident_bytes(proc: &Process) -> usize {
+    proc.vmar()
+        .get_mappings()
+        .iter()
+        .map(|m| m.committed_bytes)
+        .sum()
 }
 
 /// Wait for state changes in a child of the calling process, and obtain information about
 /// the child whose state has changed.
 ///
 /// A state change is considered to be:
-/// - the child terminated.
-/// - the child was stopped by a signal. TODO
-/// - the child was resumed by a signal. TODO
-pub async fn wait_child(proc: &Arc<Process>, pid: KoID, nonblock: bool) -> LxResult<ExitCode> {
+/// - the child terminated, reported with `EXITED`.
+/// - the child was stopped by a signal, reported with `STOPPED`.
+/// - the child was resumed by `SIGCONT`, reported with `CONTINUED`.
+///
+/// A terminated child is reaped unless `NOWAIT` is set. Returns `None` if `NOHANG` is set
+/// and no child has changed state, or `ECHILD` if there is no child selected by `target`.
+/// Children terminated while `SIGCHLD` is ignored by the parent are reaped silently.
+pub async fn wait_child(
+    proc: &Arc<Process>,
+    target: WaitTarget,
+    options: WaitOptions,
+) -> LxResult<Option<(Arc<Process>, WaitStatus)>> {
     loop {
+        // clear before checking, so no state change is missed
+        proc.signal_clear(Signal::SIGCHLD);
         let mut inner = proc.linux().inner.lock();
-        let child = inner.children.get(&pid).ok_or(LxError::ECHILD)?;
-        if let Status::Exited(code) = child.status() {
+        let mut found = false;
+        let mut changed = None;
+        let mut reaped = Vec::new();
+        for (&pid, child) in inner.children.iter() {
+            let child_linux = child.linux();
+            let selected = match target {
+                WaitTarget::Any => true,
+                WaitTarget::Pid(id) => pid == id,
+                WaitTarget::Pgid(pgid) => child_linux.pgid() == pgid,
+            };
+            if !selected {
+                continue;
+            }
+            let status = if let Status::Exited(code) = child.status() {
+                if child_linux.inner.lock().auto_reaped {
+                    reaped.push(pid);
+                    continue;
+                }
+                child_linux
+                    .termination()
+                    .unwrap_or(WaitStatus::Exited(code as u8))
+            } else {
+                found = true;
+                let mut child_inner = child_linux.inner.lock();
+                let status = match child_inner.state_change {
+                    Some(status @ WaitStatus::Stopped(_))
+                        if options.contains(WaitOptions::STOPPED) =>
+                    {
+                        status
+                    }
+                    Some(status @ WaitStatus::Continued)
+                        if options.contains(WaitOptions::CONTINUED) =>
+                    {
+                        status
+                    }
+                    _ => continue,
+                };
+                if !options.contains(WaitOptions::NOWAIT) {
+                    child_inner.state_change = None;
+                }
+                status
+            };
+            found = true;
+            if status.is_terminated() && !options.contains(WaitOptions::EXITED) {
+                continue;
+            }
+            changed = Some((child.clone(), status));
+            break;
+        }
+        for pid in reaped {
             inner.children.remove(&pid);
-            return Ok(code as ExitCode);
         }
-        if nonblock {
-            return Err(LxError::EAGAIN);
+        if let Some((child, status)) = changed {
+            if status.is_terminated() && !options.contains(WaitOptions::NOWAIT) {
+                inner.children.remove(&child.id());
+                let child_inner = child.linux().inner.lock();
+                let mut usage = child_inner.usage;
+                usage.add_child(&child_inner.children_usage);
+                inner.children_usage.add_child(&usage);
+            }
+            return Ok(Some((child, status)));
         }
-        let child: Arc<dyn KernelObject> = child.clone();
-        drop(inner);
-        child.signal_clear(Signal::PROCESS_TERMINATED);
-        child.wait_signal(Signal::PROCESS_TERMINATED).await;
-    }
-}
-
-/// Wait for state changes in a child of the calling process.
-pub async fn wait_child_any(proc: &Arc<Process>, nonblock: bool) -> LxResult<(KoID, ExitCode)> {
-    loop {
-        let mut inner = proc.linux().inner.lock();
-        if inner.children.is_empty() {
+        if !found {
             return Err(LxError::ECHILD);
         }
-        for (&pid, child) in inner.children.iter() {
-            if let Status::Exited(code) = child.status() {
-                inner.children.remove(&pid);
-                return Ok((pid, code as ExitCode));
-            }
+        if options.contains(WaitOptions::NOHANG) {
+            return Ok(None);
         }
         drop(inner);
-        if nonblock {
-            return Err(LxError::EAGAIN);
-        }
         let proc: Arc<dyn KernelObject> = proc.clone();
-        proc.signal_clear(Signal::SIGCHLD);
-        //等待进程结束信号
         proc.wait_signal(Signal::SIGCHLD).await;
     }
 }
@@ -139,25 +536,48 @@ pub async fn wait_child_any(proc: &Arc<Process>, nonblock: bool) -> LxResult<(Ko
 pub struct LinuxProcess {
     /// The root INode of file system
     root_inode: Arc<dyn INode>,
+    /// Mounted file systems
+    mounts: Arc<MountTable>,
     /// Parent process
     parent: Weak<Process>,
+    /// Opened files, shared by processes cloned with `CLONE_FILES`
+    files: Arc<Mutex<FileTable>>,
+    /// Current Working Directory, shared by processes cloned with `CLONE_FS`
+    ///
+    /// Omit leading '/'.
+    current_working_directory: Arc<Mutex<String>>,
+    /// Signal actions, shared by processes cloned with `CLONE_SIGHAND`
+    signal_actions: Arc<Mutex<SignalActions>>,
+    /// Notified with `RECEIVE_SIGNAL` every time a process-directed signal is queued
+    signal_bus: Mutex<EventBus>,
     /// Inner
     inner: Mutex<LinuxProcessInner>,
 }
 
+/// The file descriptor table of a process
+#[derive(Default, Clone)]
+struct FileTable {
+    /// Opened files
+    files: HashMap<FileDesc, Arc<dyn FileLike>>,
+    /// Descriptors of files and sockets with `FD_CLOEXEC` set
+    cloexec: HashSet<FileDesc>,
+    /// Sockets
+    sockets: HashMap<SocketHandle, Arc<Mutex<dyn Socket>>>,
+}
+
 /// Linux process mut inner data
 #[derive(Default)]
 struct LinuxProcessInner {
+    /// Process ID, the same as the ID of the zircon process
+    pid: KoID,
     /// Execute path
     execute_path: String,
-    /// Current Working Directory
-    ///
-    /// Omit leading '/'.
-    current_working_directory: String,
+    /// Arguments of the program
+    args: Vec<String>,
+    /// Environment variables of the program
+    envs: Vec<String>,
     /// file open number limit
     file_limit: RLimit,
-    /// Opened files
-    files: HashMap<FileDesc, Arc<dyn FileLike>>,
     /// Semaphore
     semaphores: SemProc,
     /// Share Memory
@@ -166,10 +586,48 @@ struct LinuxProcessInner {
     futexes: HashMap<VirtAddr, Arc<Futex>>,
     /// Child processes
     children: HashMap<KoID, Arc<Process>>,
-    /// Signal actions
-    signal_actions: SignalActions,
-    /// Sockets
-    sockets: HashMap<SocketHandle, Arc<Mutex<dyn Socket>>>,
+    /// Pending process-directed signals
+    signal_queue: SignalQueue,
+    /// Whether the process is stopped by a signal
+    stopped: bool,
+    /// Stop or continue not reported to the parent by `wait` yet
+    state_change: Option<WaitStatus>,
+    /// How the process terminated, unset if it is terminated by zircon
+    termination: Option<WaitStatus>,
+    /// Signal sent to the parent on termination
+    exit_signal: Option<LinuxSignal>,
+    /// Whether the process is reaped on termination as the parent ignores `SIGCHLD`
+    auto_reaped: bool,
+    /// Interval timers set by `setitimer`, indexed by [`TimerClock`]
+    itimers: [Option<Arc<IntervalTimer>>; 3],
+    /// POSIX timers created by `timer_create`
+    posix_timers: HashMap<i32, Arc<IntervalTimer>>,
+    /// Resources used by all threads
+    usage: ResourceUsage,
+    /// Resources used by terminated children which have been waited for
+    children_usage: ResourceUsage,
+    /// Heap managed by `brk`
+    heap: Heap,
+    /// Base of the vDSO page mapped by the loader
+    vdso_base: VirtAddr,
+    /// User and group credentials
+    cred: Credentials,
+    /// Process group ID
+    pgid: KoID,
+    /// Session ID
+    sid: KoID,
+    /// Shared by the processes sharing the address space by `CLONE_VM`
+    vm_users: Arc<()>,
+}
+
+/// The heap of a process, which starts after the highest loaded ELF segment
+/// of the main program, in a region reserved by the loader.
+#[derive(Default, Clone, Copy)]
+struct Heap {
+    /// Start of the heap, page aligned
+    start: VirtAddr,
+    /// Current program break
+    end: VirtAddr,
 }
 
 #[derive(Clone)]
@@ -208,8 +666,8 @@ impl Default for RLimit {
 pub type ExitCode = i32;
 
 impl LinuxProcess {
-    /// Create a new process.
-    pub fn new(rootfs: Arc<dyn FileSystem>) -> Self {
+    /// Create a new process in `job`.
+    pub fn new(job: &Arc<Job>, rootfs: Arc<dyn FileSystem>) -> Self {
         let stdin = File::new(
             STDIN.clone(), // FIXME: stdin
             OpenFlags::RDONLY,
@@ -230,13 +688,19 @@ impl LinuxProcess {
         files.insert(1.into(), stdout);
         files.insert(2.into(), stderr);
 
+        let mounts = MountTable::new(rootfs, Arc::downgrade(job));
         LinuxProcess {
-            root_inode: crate::fs::create_root_fs(rootfs), //Arc::clone(&ROOT_INODE),访问磁盘可能更快？
+            root_inode: mounts.root(),
+            mounts,
             parent: Weak::default(),
-            inner: Mutex::new(LinuxProcessInner {
+            files: Arc::new(Mutex::new(FileTable {
                 files,
                 ..Default::default()
-            }),
+            })),
+            current_working_directory: Default::default(),
+            signal_actions: Default::default(),
+            signal_bus: Mutex::default(),
+            inner: Mutex::new(LinuxProcessInner::default()),
         }
     }
 
@@ -257,42 +721,104 @@ impl LinuxProcess {
 
     /// Get lowest free fd
     pub fn get_free_fd(&self) -> FileDesc {
-        self.inner.lock().get_free_fd()
+        self.files.lock().get_free_fd()
     }
 
     /// get the lowest available fd great than or equal to `start`.
     pub fn get_free_fd_from(&self, start: usize) -> FileDesc {
-        self.inner.lock().get_free_fd_from(start)
+        self.files.lock().get_free_fd_from(start)
     }
 
     /// Add a file to the file descriptor table.
+    ///
+    /// `FD_CLOEXEC` of the new descriptor is set if the file is opened with `O_CLOEXEC`.
     pub fn add_file(&self, file: Arc<dyn FileLike>) -> LxResult<FileDesc> {
-        let inner = self.inner.lock();
-        let fd = inner.get_free_fd();
-        self.insert_file(inner, fd, file)
+        let cloexec = take_cloexec(&*file)?;
+        let files = self.files.lock();
+        let fd = files.get_free_fd();
+        self.insert_file(files, fd, file, cloexec)
     }
 
     /// Add a file to the file descriptor table at given `fd`.
+    ///
+    /// `FD_CLOEXEC` of the new descriptor is set if the file is opened with `O_CLOEXEC`.
     pub fn add_file_at(&self, fd: FileDesc, file: Arc<dyn FileLike>) -> LxResult<FileDesc> {
-        let inner = self.inner.lock();
-        self.insert_file(inner, fd, file)
+        let cloexec = take_cloexec(&*file)?;
+        let files = self.files.lock();
+        self.insert_file(files, fd, file, cloexec)
+    }
+
+    /// Add an open file description to the file descriptor table,
+    /// with `FD_CLOEXEC` set to `cloexec`.
+    ///
+    /// Used by `SCM_RIGHTS`, the new descriptor refers to the same open file description
+    /// as the one of the sender.
+    pub fn install_file(&self, file: Arc<dyn FileLike>, cloexec: bool) -> LxResult<FileDesc> {
+        let files = self.files.lock();
+        let fd = files.get_free_fd();
+        self.insert_file(files, fd, file, cloexec)
+    }
+
+    /// Duplicate `fd` to the lowest free descriptor greater than or equal to `start`,
+    /// with `FD_CLOEXEC` set to `cloexec`.
+    ///
+    /// Both descriptors refer to the same open file description.
+    pub fn dup_file_from(&self, fd: FileDesc, start: usize, cloexec: bool) -> LxResult<FileDesc> {
+        let files = self.files.lock();
+        let file = files.files.get(&fd).ok_or(LxError::EBADF)?.clone();
+        let new_fd = files.get_free_fd_from(start);
+        self.insert_file(files, new_fd, file, cloexec)
+    }
+
+    /// Duplicate `fd` to `new_fd` with `FD_CLOEXEC` set to `cloexec`,
+    /// closing `new_fd` first if it is open.
+    ///
+    /// Both descriptors refer to the same open file description.
+    pub fn dup_file_to(&self, fd: FileDesc, new_fd: FileDesc, cloexec: bool) -> LxResult<FileDesc> {
+        let mut files = self.files.lock();
+        let file = files.files.get(&fd).ok_or(LxError::EBADF)?.clone();
+        if let Some(old) = files.files.remove(&new_fd) {
+            self.release_record_locks(&old);
+        }
+        self.insert_file(files, new_fd, file, cloexec)
     }
 
     /// insert a file and fd into the file descriptor table
     fn insert_file(
         &self,
-        mut inner: MutexGuard<LinuxProcessInner>,
+        mut files: MutexGuard<FileTable>,
         fd: FileDesc,
         file: Arc<dyn FileLike>,
+        cloexec: bool,
     ) -> LxResult<FileDesc> {
-        if inner.files.len() < inner.file_limit.cur as usize {
-            inner.files.insert(fd, file);
+        if files.files.len() < self.inner.lock().file_limit.cur as usize {
+            files.files.insert(fd, file);
+            files.set_cloexec(fd, cloexec);
             Ok(fd)
         } else {
             Err(LxError::EMFILE)
         }
     }
 
+    /// Returns whether `FD_CLOEXEC` is set on the file or socket descriptor `fd`.
+    pub fn cloexec(&self, fd: FileDesc) -> LxResult<bool> {
+        let files = self.files.lock();
+        if !files.contains(fd) {
+            return Err(LxError::EBADF);
+        }
+        Ok(files.cloexec.contains(&fd))
+    }
+
+    /// Set or clear `FD_CLOEXEC` on the file or socket descriptor `fd`.
+    pub fn set_cloexec(&self, fd: FileDesc, cloexec: bool) -> LxResult {
+        let mut files = self.files.lock();
+        if !files.contains(fd) {
+            return Err(LxError::EBADF);
+        }
+        files.set_cloexec(fd, cloexec);
+        Ok(())
+    }
+
     /// get and set file limit number
     pub fn file_limit(&self, new_limit: Option<RLimit>) -> RLimit {
         let mut inner = self.inner.lock();
@@ -314,40 +840,57 @@ impl LinuxProcess {
 
     /// Get the `FileLike` with given `fd`.
     pub fn get_file_like(&self, fd: FileDesc) -> LxResult<Arc<dyn FileLike>> {
-        let inner = self.inner.lock();
-        trace!("get_file_like: {:#x?}", inner.files);
-        inner.files.get(&fd).cloned().ok_or(LxError::EBADF)
+        let files = self.files.lock();
+        trace!("get_file_like: {:#x?}", files.files);
+        files.files.get(&fd).cloned().ok_or(LxError::EBADF)
     }
 
     /// get all files
     pub fn get_files(&self) -> LxResult<HashMap<FileDesc, Arc<dyn FileLike>>> {
-        let inner = self.inner.lock();
-        Ok(inner.files.clone())
+        let files = self.files.lock();
+        Ok(files.files.clone())
     }
 
     /// Close file descriptor `fd`.
     pub fn close_file(&self, fd: FileDesc) -> LxResult {
-        let mut inner = self.inner.lock();
-        inner.files.remove(&fd).map(|_| ()).ok_or(LxError::EBADF)
+        let mut files = self.files.lock();
+        files.cloexec.remove(&fd);
+        let file = files.files.remove(&fd).ok_or(LxError::EBADF)?;
+        self.release_record_locks(&file);
+        Ok(())
     }
 
-    /// Add a socket to the socket set at given `SocketHandle`.
-    pub fn add_socket(&self, socket: Arc<Mutex<dyn Socket>>) -> LxResult<SocketHandle> {
-        let inner = self.inner.lock();
-        let fd = inner.get_free_hd();
-        self.insert_socket(inner, fd, socket)
+    /// Release the record locks of the process on `file`, as closing any descriptor of a file does.
+    fn release_record_locks(&self, file: &Arc<dyn FileLike>) {
+        if let Some(file) = file.downcast_ref::<File>() {
+            lock::release_locks(&file.inode(), LockOwner::Process(self.pid()));
+        }
+    }
+
+    /// Add a socket to the socket set at given `SocketHandle`,
+    /// with `FD_CLOEXEC` set to `cloexec`.
+    pub fn add_socket(
+        &self,
+        socket: Arc<Mutex<dyn Socket>>,
+        cloexec: bool,
+    ) -> LxResult<SocketHandle> {
+        let files = self.files.lock();
+        let fd = files.get_free_hd();
+        self.insert_socket(files, fd, socket, cloexec)
         // unimplemented!()
     }
 
     /// insert a file and fd into the file descriptor table
     fn insert_socket(
         &self,
-        mut inner: MutexGuard<LinuxProcessInner>,
+        mut files: MutexGuard<FileTable>,
         fd: SocketHandle,
         socket: Arc<Mutex<dyn Socket>>,
+        cloexec: bool,
     ) -> LxResult<SocketHandle> {
-        if inner.sockets.len() < inner.file_limit.cur as usize {
-            inner.sockets.insert(fd, socket);
+        if files.sockets.len() < self.inner.lock().file_limit.cur as usize {
+            files.sockets.insert(fd, socket);
+            files.set_cloexec(socket_fd(fd), cloexec);
             Ok(fd)
         } else {
             Err(LxError::EMFILE)
@@ -357,15 +900,16 @@ impl LinuxProcess {
     /// Get the `Socket` with given `fd`.
     pub fn get_socket(&self, fd: SocketHandle) -> LxResult<Arc<Mutex<dyn Socket>>> {
         // unimplemented!()
-        let inner = self.inner.lock();
-        let socket = inner.sockets.get(&fd).cloned().ok_or(LxError::EBADF);
+        let files = self.files.lock();
+        let socket = files.sockets.get(&fd).cloned().ok_or(LxError::EBADF);
         socket
     }
 
     /// Close file descriptor `fd`.
     pub fn close_socket(&self, fd: SocketHandle) -> LxResult {
-        let mut inner = self.inner.lock();
-        inner.sockets.remove(&fd).map(|_| ()).ok_or(LxError::EBADF)
+        let mut files = self.files.lock();
+        files.cloexec.remove(&socket_fd(fd));
+        files.sockets.remove(&fd).map(|_| ()).ok_or(LxError::EBADF)
     }
 
     /// Get root INode of the process.
@@ -373,6 +917,11 @@ impl LinuxProcess {
         &self.root_inode
     }
 
+    /// Get the mount table of the file system tree.
+    pub fn mounts(&self) -> &Arc<MountTable> {
+        &self.mounts
+    }
+
     /// Get parent process.
     pub fn parent(&self) -> Option<Arc<Process>> {
         self.parent.upgrade()
@@ -380,7 +929,7 @@ impl LinuxProcess {
 
     /// Get current working directory.
     pub fn current_working_directory(&self) -> String {
-        String::from("/") + &self.inner.lock().current_working_directory
+        String::from("/") + self.current_working_directory.lock().as_str()
     }
 
     /// Change working directory.
@@ -388,10 +937,16 @@ impl LinuxProcess {
         if path.is_empty() {
             return;
         }
-        let mut inner = self.inner.lock();
-        let cwd = match path.as_bytes()[0] {
-            b'/' => String::new(),
-            _ => inner.current_working_directory.clone(),
+        let cwd = self.absolute_path(path);
+        *self.current_working_directory.lock() = String::from(&cwd[1..]);
+    }
+
+    /// Get the absolute path of `path` relative to the current working directory,
+    /// without resolving symbolic links.
+    pub fn absolute_path(&self, path: &str) -> String {
+        let cwd = match path.as_bytes().first() {
+            Some(b'/') => String::new(),
+            _ => self.current_working_directory.lock().clone(),
         };
         let mut cwd_vec: Vec<_> = cwd.split('/').filter(|x| !x.is_empty()).collect();
         for seg in path.split('/') {
@@ -403,7 +958,7 @@ impl LinuxProcess {
                 _ => cwd_vec.push(seg),
             }
         }
-        inner.current_working_directory = cwd_vec.join("/");
+        String::from("/") + &cwd_vec.join("/")
     }
 
     /// Get execute path.
@@ -416,36 +971,483 @@ impl LinuxProcess {
         self.inner.lock().execute_path = String::from(path);
     }
 
+    /// Get the arguments and environment variables of the program.
+    pub fn args(&self) -> (Vec<String>, Vec<String>) {
+        let inner = self.inner.lock();
+        (inner.args.clone(), inner.envs.clone())
+    }
+
+    /// Set the arguments and environment variables of the program on `execve`.
+    pub fn set_args(&self, args: &[String], envs: &[String]) {
+        let mut inner = self.inner.lock();
+        inner.args = args.to_vec();
+        inner.envs = envs.to_vec();
+    }
+
+    /// Get the process ID.
+    pub fn pid(&self) -> KoID {
+        self.inner.lock().pid
+    }
+
+    /// Get the process group ID.
+    pub fn pgid(&self) -> KoID {
+        self.inner.lock().pgid
+    }
+
+    /// Get the session ID.
+    pub fn sid(&self) -> KoID {
+        self.inner.lock().sid
+    }
+
+    /// Get the credentials of the process.
+    pub fn cred(&self) -> Credentials {
+        self.inner.lock().cred.clone()
+    }
+
+    /// Change the credentials of the process by `f`.
+    ///
+    /// The credentials are left unchanged if `f` returns an error.
+    pub fn modify_cred<T>(&self, f: impl FnOnce(&mut Credentials) -> LxResult<T>) -> LxResult<T> {
+        let mut inner = self.inner.lock();
+        let mut cred = inner.cred.clone();
+        let ret = f(&mut cred)?;
+        inner.cred = cred;
+        Ok(ret)
+    }
+
+    /// Check the permission of the process to `access` the file `inode`.
+    pub fn check_access(&self, inode: &Arc<dyn INode>, access: Access) -> LxResult {
+        let meta = inode.metadata()?;
+        self.inner.lock().cred.check_access(&meta, access)
+    }
+
+    /// Make the newly created `inode` owned by the file system user and group of the process.
+    pub fn set_owner(&self, inode: &Arc<dyn INode>) {
+        let cred = self.cred();
+        if let Ok(mut meta) = inode.metadata() {
+            if meta.uid != cred.uid.fs as usize || meta.gid != cred.gid.fs as usize {
+                meta.uid = cred.uid.fs as usize;
+                meta.gid = cred.gid.fs as usize;
+                // not all file systems support changing the owner
+                inode.set_metadata(&meta).ok();
+            }
+        }
+    }
+
+    /// Set the start of the heap after a new program is loaded.
+    pub fn init_heap(&self, start: VirtAddr) {
+        self.inner.lock().heap = Heap { start, end: start };
+    }
+
+    /// Set the base of the vDSO page after a new program is loaded.
+    pub fn init_vdso(&self, base: VirtAddr) {
+        self.inner.lock().vdso_base = base;
+    }
+
+    /// Get the base of the vDSO page, which starts with the code calling `rt_sigreturn`.
+    pub fn vdso_base(&self) -> VirtAddr {
+        self.inner.lock().vdso_base
+    }
+
+    /// Stop sharing the address space with other processes created by `CLONE_VM`.
+    ///
+    /// Returns false if the address space is not shared.
+    pub fn unshare_vm(&self) -> bool {
+        let mut inner = self.inner.lock();
+        if Arc::strong_count(&inner.vm_users) == 1 {
+            return false;
+        }
+        inner.vm_users = Default::default();
+        true
+    }
+
+    /// Get the current program break.
+    pub fn brk(&self) -> VirtAddr {
+        self.inner.lock().heap.end
+    }
+
+    /// Move the program break to `addr`, mapping or unmapping heap pages in the
+    /// region of `vmar` reserved for the heap by the loader.
+    ///
+    /// Returns the new program break, which is unchanged if `addr` is invalid
+    /// or the memory can not be mapped.
+    pub fn set_brk(&self, vmar: &VmAddressRegion, addr: VirtAddr) -> VirtAddr {
+        let mut inner = self.inner.lock();
+        let heap = &mut inner.heap;
+        if addr < heap.start {
+            return heap.end;
+        }
+        let vmar = match vmar.find_child(heap.start) {
+            Some(vmar) if vmar.addr() == heap.start => vmar,
+            _ => {
+                warn!("brk: no region reserved for the heap at {:#x}", heap.start);
+                return heap.end;
+            }
+        };
+        let old_top = pages(heap.end) * PAGE_SIZE;
+        let new_top = pages(addr) * PAGE_SIZE;
+        if new_top > old_top {
+            let len = new_top - old_top;
+            let vmo = VmObject::new_paged(pages(len));
+            vmo.set_name("[heap]");
+            let flags = MMUFlags::READ | MMUFlags::WRITE | MMUFlags::USER;
+            if let Err(err) = vmar.map_at(old_top - vmar.addr(), vmo, 0, len, flags) {
+                warn!("brk: failed to map heap at {:#x}: {:?}", old_top, err);
+                return heap.end;
+            }
+        } else if new_top < old_top {
+            if let Err(err) = vmar.unmap(new_top, old_top - new_top) {
+                warn!("brk: failed to unmap heap at {:#x}: {:?}", new_top, err);
+                return heap.end;
+            }
+        }
+        heap.end = addr;
+        addr
+    }
+
     /// Get signal action.
     pub fn signal_action(&self, signal: LinuxSignal) -> SignalAction {
-        self.inner.lock().signal_actions.table[signal as u8 as usize]
+        self.signal_actions.lock().table[signal as u8 as usize]
     }
 
     /// Set signal action.
     pub fn set_signal_action(&self, signal: LinuxSignal, action: SignalAction) {
-        self.inner.lock().signal_actions.table[signal as u8 as usize] = action;
+        self.signal_actions.lock().table[signal as u8 as usize] = action;
     }
 
-    /// Close file that FD_CLOEXEC is set
-    pub fn remove_cloexec_files(&self) {
+    /// Reset handled signals to default on `execve`, ignored signals are left unchanged.
+    pub fn reset_signal_actions(&self) {
+        let mut actions = self.signal_actions.lock();
+        for action in actions.table.iter_mut() {
+            if action.handler != SIG_IGN {
+                *action = SignalAction::default();
+            }
+        }
+    }
+
+    /// Get the interval timer of `proc` measuring `clock`, as `setitimer` does.
+    pub fn itimer(&self, proc: &Arc<Process>, clock: TimerClock) -> Arc<IntervalTimer> {
+        let signal = match clock {
+            TimerClock::Real => LinuxSignal::SIGALRM,
+            TimerClock::Virtual => LinuxSignal::SIGVTALRM,
+            TimerClock::Prof => LinuxSignal::SIGPROF,
+        };
+        self.inner.lock().itimers[clock as usize]
+            .get_or_insert_with(|| {
+                IntervalTimer::new(proc, clock, TimerNotify::Process(signal), None, 0)
+            })
+            .clone()
+    }
+
+    /// Create a POSIX timer of `proc`, and returns the timer ID.
+    pub fn create_timer(
+        &self,
+        proc: &Arc<Process>,
+        clock: TimerClock,
+        notify: TimerNotify,
+        value: Option<usize>,
+    ) -> LxResult<i32> {
         let mut inner = self.inner.lock();
-        let close_fds = inner
-            .files
-            .iter()
-            .filter_map(|(fd, file_like)| {
-                if let Ok(file) = file_like.clone().downcast_arc::<File>() {
-                    if file.flags().close_on_exec() {
-                        Some(*fd)
-                    } else {
-                        None
-                    }
-                } else {
-                    None
+        let id = (0..i32::MAX)
+            .find(|id| !inner.posix_timers.contains_key(id))
+            .ok_or(LxError::EAGAIN)?;
+        // the signal value is the timer ID by default
+        let value = value.unwrap_or(id as usize);
+        let timer = IntervalTimer::new(proc, clock, notify, Some(id), value);
+        inner.posix_timers.insert(id, timer);
+        Ok(id)
+    }
+
+    /// Get the POSIX timer `id`.
+    pub fn get_timer(&self, id: i32) -> LxResult<Arc<IntervalTimer>> {
+        let inner = self.inner.lock();
+        inner.posix_timers.get(&id).cloned().ok_or(LxError::EINVAL)
+    }
+
+    /// Disarm and delete the POSIX timer `id`.
+    pub fn delete_timer(&self, id: i32) -> LxResult {
+        let timer = self.inner.lock().posix_timers.remove(&id);
+        let timer = timer.ok_or(LxError::EINVAL)?;
+        timer.set(None, Duration::ZERO);
+        Ok(())
+    }
+
+    /// Delete all POSIX timers on `execve`, interval timers are left unchanged.
+    pub fn delete_posix_timers(&self) {
+        let timers = core::mem::take(&mut self.inner.lock().posix_timers);
+        for timer in timers.values() {
+            timer.set(None, Duration::ZERO);
+        }
+    }
+
+    /// Add `time` to the CPU time consumed in user mode,
+    /// and check the timers measuring the CPU time.
+    pub fn account_user_time(&self, time: Duration) {
+        self.inner.lock().usage.user_time += time;
+        self.check_cpu_timers();
+    }
+
+    /// Add `time` to the CPU time consumed in kernel mode,
+    /// and check the timers measuring the CPU time.
+    pub fn account_system_time(&self, time: Duration) {
+        self.inner.lock().usage.system_time += time;
+        self.check_cpu_timers();
+    }
+
+    /// Count a page fault handled without I/O.
+    pub fn account_minor_fault(&self) {
+        self.inner.lock().usage.minor_faults += 1;
+    }
+
+    /// Check the timers measuring the CPU time.
+    fn check_cpu_timers(&self) {
+        let timers: Vec<_> = {
+            let inner = self.inner.lock();
+            inner
+                .itimers
+                .iter()
+                .flatten()
+                .chain(inner.posix_timers.values())
+                .filter(|timer| timer.clock() != TimerClock::Real)
+                .cloned()
+                .collect()
+        };
+        for timer in timers {
+            timer.check(timer.now());
+        }
+    }
+
+    /// CPU time consumed in user mode by all threads.
+    pub fn user_time(&self) -> Duration {
+        self.inner.lock().usage.user_time
+    }
+
+    /// CPU time consumed in user and kernel mode by all threads.
+    pub fn cpu_time(&self) -> Duration {
+        let inner = self.inner.lock();
+        inner.usage.user_time + inner.usage.system_time
+    }
+
+    /// Record the current resident set size of the process `proc`,
+    /// if it is the maximum so far.
+    ///
+    /// The size is sampled when the usage is queried, before `execve`
+    /// replaces the address space and on termination.
+    pub fn update_max_rss(&self, proc: &Process) {
+        let rss = resident_bytes(proc);
+        let mut inner = self.inner.lock();
+        inner.usage.max_rss = inner.usage.max_rss.max(rss);
+    }
+
+    /// Resources used by all threads.
+    pub fn usage(&self) -> ResourceUsage {
+        self.inner.lock().usage
+    }
+
+    /// Resources used by terminated children which have been waited for,
+    /// including their own children.
+    pub fn children_usage(&self) -> ResourceUsage {
+        self.inner.lock().children_usage
+    }
+
+    /// Dequeue a pending process-directed signal which is not blocked by `mask`.
+    pub fn dequeue_signal(&self, mask: &Sigset) -> Option<SigInfo> {
+        self.inner.lock().signal_queue.pop(mask)
+    }
+
+    /// Returns the set of pending process-directed signals.
+    pub fn pending_signals(&self) -> Sigset {
+        self.inner.lock().signal_queue.pending()
+    }
+
+    /// Subscribe `callback` to be called when a process-directed signal is queued,
+    /// unless a signal in `mask` is pending already.
+    ///
+    /// Returns whether a signal in `mask` is pending.
+    pub fn subscribe_signal(&self, mask: &Sigset, callback: EventHandler) -> bool {
+        let mut bus = self.signal_bus.lock();
+        if !self
+            .pending_signals()
+            .difference(&mask.complement())
+            .is_empty()
+        {
+            return true;
+        }
+        bus.subscribe(callback);
+        false
+    }
+
+    /// Apply the side effects of generating `signal` for the process `proc`.
+    ///
+    /// Returns whether the signal should be queued for delivery.
+    pub(crate) fn prepare_signal(&self, proc: &Process, signal: LinuxSignal) -> bool {
+        let action = self.signal_action(signal);
+        let mut inner = self.inner.lock();
+        match signal {
+            LinuxSignal::SIGKILL => {
+                drop(inner);
+                self.exit_by_signal(proc, signal);
+                return false;
+            }
+            LinuxSignal::SIGSTOP => {
+                inner.signal_queue.remove(LinuxSignal::SIGCONT);
+                drop(inner);
+                self.stop(proc, signal);
+                return false;
+            }
+            LinuxSignal::SIGCONT => {
+                for &stop in [
+                    LinuxSignal::SIGSTOP,
+                    LinuxSignal::SIGTSTP,
+                    LinuxSignal::SIGTTIN,
+                    LinuxSignal::SIGTTOU,
+                ]
+                .iter()
+                {
+                    inner.signal_queue.remove(stop);
                 }
-            })
-            .collect::<Vec<_>>();
+                if inner.stopped {
+                    inner.stopped = false;
+                    inner.state_change = Some(WaitStatus::Continued);
+                    drop(inner);
+                    proc.resume();
+                    self.notify_parent(proc, WaitStatus::Continued);
+                }
+            }
+            LinuxSignal::SIGTSTP | LinuxSignal::SIGTTIN | LinuxSignal::SIGTTOU => {
+                inner.signal_queue.remove(LinuxSignal::SIGCONT);
+            }
+            _ => {}
+        }
+        // ignored signals are discarded when generated
+        !(action.handler == SIG_IGN
+            || (action.handler == SIG_DFL
+                && matches!(
+                    signal.default_action(),
+                    SignalDefaultAction::Ignore | SignalDefaultAction::Continue
+                )))
+    }
+
+    /// Whether the process is stopped by a signal.
+    pub fn is_stopped(&self) -> bool {
+        self.inner.lock().stopped
+    }
+
+    /// Stop the process `proc` on delivery of the stop signal `signal`.
+    pub fn stop(&self, proc: &Process, signal: LinuxSignal) {
+        let mut inner = self.inner.lock();
+        if !inner.stopped {
+            inner.stopped = true;
+            inner.state_change = Some(WaitStatus::Stopped(signal));
+            drop(inner);
+            proc.suspend();
+            self.notify_parent(proc, WaitStatus::Stopped(signal));
+        }
+    }
+
+    /// Terminate the process `proc` normally with exit code `code`.
+    pub fn exit(&self, proc: &Process, code: i32) {
+        self.inner
+            .lock()
+            .termination
+            .get_or_insert(WaitStatus::Exited(code as u8));
+        proc.exit(code as i64);
+        // wake the threads blocked in syscalls, so that they exit
+        wake_signal_waiters(proc);
+    }
+
+    /// Terminate the process `proc` by the signal `signal`.
+    ///
+    /// The process is killed rather than exiting with a code.
+    pub fn exit_by_signal(&self, proc: &Process, signal: LinuxSignal) {
+        let core_dumped = signal.default_action() == SignalDefaultAction::CoreDump;
+        self.inner
+            .lock()
+            .termination
+            .get_or_insert(WaitStatus::Signaled(signal, core_dumped));
+        proc.kill();
+        wake_signal_waiters(proc);
+    }
+
+    /// Returns how the process terminated, if it is terminated by Linux.
+    pub fn termination(&self) -> Option<WaitStatus> {
+        self.inner.lock().termination
+    }
+
+    /// Notify the parent that the process `proc` has terminated.
+    ///
+    /// The process is reaped on termination if the parent ignores `SIGCHLD`
+    /// or sets `SA_NOCLDWAIT`.
+    fn terminated(&self, proc: &Process) {
+        let status = self.termination().unwrap_or(WaitStatus::Exited(0));
+        self.update_max_rss(proc);
+        // timers of a terminated process never expire
+        {
+            let mut inner = self.inner.lock();
+            inner.itimers = Default::default();
+            inner.posix_timers.clear();
+            inner.vm_users = Default::default();
+        }
+        lock::release_process_locks(proc.id());
+        // close the files unless the table is shared with a living process
+        if Arc::strong_count(&self.files) == 1 {
+            let files = core::mem::take(&mut *self.files.lock());
+            drop(files);
+        }
+        if let Some(parent) = self.parent() {
+            let action = parent.linux().signal_action(LinuxSignal::SIGCHLD);
+            let mut inner = self.inner.lock();
+            inner.auto_reaped = inner.exit_signal == Some(LinuxSignal::SIGCHLD)
+                && (action.handler == SIG_IGN
+                    || action.flags.contains(SignalActionFlags::NOCLDWAIT));
+        }
+        self.notify_parent(proc, status);
+    }
+
+    /// Send `SIGCHLD` to the parent for the state change `status` of the process `proc`,
+    /// and wake up the parent waiting for it.
+    ///
+    /// The exit signal set by `clone` is sent instead on termination, and no signal is sent
+    /// on stop or continue if the parent sets `SA_NOCLDSTOP`.
+    fn notify_parent(&self, proc: &Process, status: WaitStatus) {
+        let parent = match self.parent() {
+            Some(parent) => parent,
+            None => return,
+        };
+        let (exit_signal, uid) = {
+            let inner = self.inner.lock();
+            (inner.exit_signal, inner.cred.uid.real)
+        };
+        let signal = if status.is_terminated() {
+            exit_signal
+        } else {
+            let action = parent.linux().signal_action(LinuxSignal::SIGCHLD);
+            Some(LinuxSignal::SIGCHLD)
+                .filter(|_| !action.flags.contains(SignalActionFlags::NOCLDSTOP))
+        };
+        if let Some(signal) = signal {
+            let (code, status) = status.to_child_info();
+            parent.send_signal(SigInfo::from_child(
+                signal,
+                code,
+                proc.id() as i32,
+                uid,
+                status,
+            ));
+        }
+        parent.signal_set(Signal::SIGCHLD);
+    }
+
+    /// Close files and sockets that FD_CLOEXEC is set
+    pub fn remove_cloexec_files(&self) {
+        let mut files = self.files.lock();
+        let close_fds = files.cloexec.drain().collect::<Vec<_>>();
         for fd in close_fds {
-            inner.files.remove(&fd).map(|_| ()).unwrap();
+            if usize::from(fd) >= SOCKET_FD {
+                files.sockets.remove(&SocketHandle::from(usize::from(fd)));
+            } else if let Some(file) = files.files.remove(&fd) {
+                self.release_record_locks(&file);
+            }
         }
     }
 
@@ -495,7 +1497,7 @@ impl LinuxProcess {
     }
 }
 
-impl LinuxProcessInner {
+impl FileTable {
     fn get_free_fd(&self) -> FileDesc {
         self.get_free_fd_from(0)
     }
@@ -507,6 +1509,24 @@ impl LinuxProcessInner {
             .unwrap()
     }
 
+    /// Whether `fd` is an opened file or socket.
+    fn contains(&self, fd: FileDesc) -> bool {
+        if usize::from(fd) >= SOCKET_FD {
+            self.sockets
+                .contains_key(&SocketHandle::from(usize::from(fd)))
+        } else {
+            self.files.contains_key(&fd)
+        }
+    }
+
+    fn set_cloexec(&mut self, fd: FileDesc, cloexec: bool) {
+        if cloexec {
+            self.cloexec.insert(fd);
+        } else {
+            self.cloexec.remove(&fd);
+        }
+    }
+
     fn get_free_hd(&self) -> SocketHandle {
         (SOCKET_FD..)
             .map(|i| i.into())
@@ -517,3 +1537,18 @@ impl LinuxProcessInner {
 
 // Temp , TODO warp a struct impl into/from with FileDesc and SocketHandle
 const SOCKET_FD: usize = 10000;
+
+/// The file descriptor of a socket.
+fn socket_fd(handle: SocketHandle) -> FileDesc {
+    usize::from(handle).into()
+}
+
+/// Take `O_CLOEXEC` out of the open flags of `file`,
+/// as `FD_CLOEXEC` is a flag of the descriptor kept by the file descriptor table.
+fn take_cloexec(file: &dyn FileLike) -> LxResult<bool> {
+    let flags = file.flags();
+    if flags.close_on_exec() {
+        file.set_flags(flags - OpenFlags::CLOEXEC)?;
+    }
+    Ok(flags.close_on_exec())
+}
@@ -9,7 +9,9 @@ pub const SIG_IGN: usize = 1;
 ///
 /// yet there's a bug because of mismatching bits: <https://sourceware.org/bugzilla/show_bug.cgi?id=25657>
 /// just support 64bits size sigset
-#[derive(Default, Clone, Copy, Debug)]
+///
+/// Signal `n` is represented by bit `n - 1`.
+#[derive(Default, Clone, Copy, Debug, Eq, PartialEq)]
 #[repr(C)]
 pub struct Sigset(u64);
 
@@ -17,21 +19,40 @@ impl Sigset {
     pub fn empty() -> Self {
         Sigset(0)
     }
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
     pub fn contains(&self, sig: Signal) -> bool {
-        (self.0 >> sig as u64 & 1) != 0
+        self.contains_signo(sig as i32)
+    }
+    pub fn contains_signo(&self, signo: i32) -> bool {
+        (1..=64).contains(&signo) && (self.0 >> (signo - 1) & 1) != 0
     }
     pub fn insert(&mut self, sig: Signal) {
-        self.0 |= 1 << sig as u64;
+        self.0 |= 1 << (sig as u64 - 1);
     }
     pub fn insert_set(&mut self, sigset: &Sigset) {
         self.0 |= sigset.0;
     }
     pub fn remove(&mut self, sig: Signal) {
-        self.0 ^= self.0 & (1 << sig as u64);
+        self.remove_signo(sig as i32);
+    }
+    pub fn remove_signo(&mut self, signo: i32) {
+        if (1..=64).contains(&signo) {
+            self.0 &= !(1 << (signo - 1));
+        }
     }
     pub fn remove_set(&mut self, sigset: &Sigset) {
         self.0 ^= self.0 & sigset.0;
     }
+    /// Returns signals not in `self`.
+    pub fn complement(&self) -> Sigset {
+        Sigset(!self.0)
+    }
+    /// Returns signals in `self` but not in `other`.
+    pub fn difference(&self, other: &Sigset) -> Sigset {
+        Sigset(self.0 & !other.0)
+    }
 }
 
 /// Linux struct sigaction
@@ -40,6 +61,8 @@ impl Sigset {
 pub struct SignalAction {
     pub handler: usize, // this field may be an union
     pub flags: SignalActionFlags,
+    /// riscv64 does not have `SA_RESTORER`
+    #[cfg(not(target_arch = "riscv64"))]
     pub restorer: usize,
     pub mask: Sigset,
 }
@@ -48,7 +71,12 @@ pub struct SignalAction {
 #[derive(Copy, Clone)]
 pub union SiginfoFields {
     pad: [u8; Self::PAD_SIZE],
-    // TODO: fill this union
+    /// Sent by `kill`, `tkill` or `sigqueue`
+    pub kill: SiginfoKill,
+    /// `SIGCHLD`, or reported by `waitid`
+    pub child: SiginfoChild,
+    /// Sent by a POSIX timer
+    pub timer: SiginfoTimer,
 }
 
 impl SiginfoFields {
@@ -63,15 +91,120 @@ impl Default for SiginfoFields {
     }
 }
 
+/// Fields of `siginfo_t` for signals sent by a process.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SiginfoKill {
+    /// sending process ID
+    pub pid: i32,
+    /// real user ID of sending process
+    pub uid: u32,
+    /// signal value, used by `sigqueue`
+    pub value: usize,
+}
+
+/// Fields of `siginfo_t` for signals sent by a POSIX timer.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SiginfoTimer {
+    /// timer ID
+    pub tid: i32,
+    /// overrun count
+    pub overrun: i32,
+    /// signal value set by `timer_create`
+    pub value: usize,
+}
+
+/// Fields of `siginfo_t` for a state change of a child process.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SiginfoChild {
+    /// child process ID
+    pub pid: i32,
+    /// real user ID of the child
+    pub uid: u32,
+    /// exit code or signal, see [`ChildCode`]
+    pub status: i32,
+    /// user CPU time consumed
+    pub utime: isize,
+    /// system CPU time consumed
+    pub stime: isize,
+}
+
+/// Linux struct siginfo_t
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub struct SigInfo {
     pub signo: i32,
     pub errno: i32,
-    pub code: SignalCode,
+    /// See [`SignalCode`]
+    pub code: i32,
     pub field: SiginfoFields,
 }
 
+impl SigInfo {
+    /// Create a `SigInfo` with given signal and code, other fields are zeroed.
+    pub fn new(signal: Signal, code: SignalCode) -> Self {
+        SigInfo {
+            signo: signal as i32,
+            errno: 0,
+            code: code as i32,
+            field: SiginfoFields::default(),
+        }
+    }
+
+    /// Create a `SigInfo` of a signal sent by process `pid` of user `uid`.
+    pub fn from_kill(signal: Signal, code: SignalCode, pid: i32, uid: u32) -> Self {
+        SigInfo {
+            field: SiginfoFields {
+                kill: SiginfoKill { pid, uid, value: 0 },
+            },
+            ..Self::new(signal, code)
+        }
+    }
+
+    /// Create a `SigInfo` of an expiration of the POSIX timer `id`.
+    pub fn from_timer(signal: Signal, id: i32, overrun: i32, value: usize) -> Self {
+        SigInfo {
+            field: SiginfoFields {
+                timer: SiginfoTimer {
+                    tid: id,
+                    overrun,
+                    value,
+                },
+            },
+            ..Self::new(signal, SignalCode::TIMER)
+        }
+    }
+
+    /// Create a `SigInfo` reporting the state change of the child process `pid` of user `uid`.
+    pub fn from_child(signal: Signal, code: ChildCode, pid: i32, uid: u32, status: i32) -> Self {
+        SigInfo {
+            signo: signal as i32,
+            errno: 0,
+            code: code as i32,
+            field: SiginfoFields {
+                child: SiginfoChild {
+                    pid,
+                    uid,
+                    status,
+                    ..Default::default()
+                },
+            },
+        }
+    }
+}
+
+impl core::fmt::Debug for SigInfo {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("SigInfo")
+            .field("signo", &self.signo)
+            .field("errno", &self.errno)
+            .field("code", &self.code)
+            .finish()
+    }
+}
+
 /// A code identifying the cause of the signal.
 #[repr(i32)]
 #[derive(Debug, Copy, Clone)]
@@ -89,6 +222,24 @@ pub enum SignalCode {
     KERNEL = 128,
 }
 
+/// The code of `SIGCHLD` identifying the state change of the child.
+#[repr(i32)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ChildCode {
+    /// child has exited
+    Exited = 1,
+    /// child was killed
+    Killed = 2,
+    /// child terminated abnormally
+    Dumped = 3,
+    /// traced child has trapped
+    Trapped = 4,
+    /// child has stopped
+    Stopped = 5,
+    /// stopped child has continued
+    Continued = 6,
+}
+
 bitflags! {
     #[derive(Default)]
     pub struct SignalActionFlags : usize {
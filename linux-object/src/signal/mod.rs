@@ -1,6 +1,11 @@
 //! Linux signals
 #![allow(missing_docs)]
+use alloc::collections::VecDeque;
 use bitflags::*;
+use core::convert::TryFrom;
+use kernel_hal::context::UserContext;
+#[cfg(target_arch = "riscv64")]
+use kernel_hal::context::UserContextField;
 use numeric_enum_macro::numeric_enum;
 
 mod action;
@@ -8,8 +13,9 @@ mod action;
 pub use self::action::*;
 
 /// struct mcontext
+#[cfg(target_arch = "x86_64")]
 #[repr(C)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct MachineContext {
     // gregs
     pub r8: usize,
@@ -45,6 +51,131 @@ pub struct MachineContext {
     pub _reserved1: [usize; 8],
 }
 
+#[cfg(target_arch = "x86_64")]
+impl MachineContext {
+    /// Flags in `eflags` that user mode is allowed to change.
+    const USER_EFLAGS: usize = 0xdd5;
+
+    /// Save the general registers of `ctx`.
+    pub fn from_user_context(ctx: &mut UserContext) -> Self {
+        let regs = ctx.general();
+        MachineContext {
+            r8: regs.r8,
+            r9: regs.r9,
+            r10: regs.r10,
+            r11: regs.r11,
+            r12: regs.r12,
+            r13: regs.r13,
+            r14: regs.r14,
+            r15: regs.r15,
+            rdi: regs.rdi,
+            rsi: regs.rsi,
+            rbp: regs.rbp,
+            rbx: regs.rbx,
+            rdx: regs.rdx,
+            rax: regs.rax,
+            rcx: regs.rcx,
+            rsp: regs.rsp,
+            rip: regs.rip,
+            eflags: regs.rflags,
+            trapno: ctx.raw_trap_reason(),
+            err: ctx.error_code(),
+            ..Default::default()
+        }
+    }
+
+    /// Restore the general registers to `ctx`.
+    pub fn restore_to(&self, ctx: &mut UserContext) {
+        let regs = ctx.general_mut();
+        regs.r8 = self.r8;
+        regs.r9 = self.r9;
+        regs.r10 = self.r10;
+        regs.r11 = self.r11;
+        regs.r12 = self.r12;
+        regs.r13 = self.r13;
+        regs.r14 = self.r14;
+        regs.r15 = self.r15;
+        regs.rdi = self.rdi;
+        regs.rsi = self.rsi;
+        regs.rbp = self.rbp;
+        regs.rbx = self.rbx;
+        regs.rdx = self.rdx;
+        regs.rax = self.rax;
+        regs.rcx = self.rcx;
+        regs.rsp = self.rsp;
+        regs.rip = self.rip;
+        regs.rflags = (regs.rflags & !Self::USER_EFLAGS) | (self.eflags & Self::USER_EFLAGS);
+    }
+}
+
+/// struct mcontext
+#[cfg(target_arch = "riscv64")]
+#[repr(C)]
+#[derive(Clone, Debug)]
+pub struct MachineContext {
+    /// pc, then x1 ~ x31
+    pub gregs: [usize; 32],
+    // fpregs
+    // TODO
+    pub fpregs: [u64; 66],
+}
+
+#[cfg(target_arch = "riscv64")]
+impl MachineContext {
+    /// Save the general registers of `ctx`.
+    pub fn from_user_context(ctx: &mut UserContext) -> Self {
+        let pc = ctx.get_field(UserContextField::InstrPointer);
+        let regs = ctx.general();
+        MachineContext {
+            gregs: [
+                pc, regs.ra, regs.sp, regs.gp, regs.tp, regs.t0, regs.t1, regs.t2, regs.s0,
+                regs.s1, regs.a0, regs.a1, regs.a2, regs.a3, regs.a4, regs.a5, regs.a6, regs.a7,
+                regs.s2, regs.s3, regs.s4, regs.s5, regs.s6, regs.s7, regs.s8, regs.s9, regs.s10,
+                regs.s11, regs.t3, regs.t4, regs.t5, regs.t6,
+            ],
+            fpregs: [0; 66],
+        }
+    }
+
+    /// Restore the general registers to `ctx`.
+    pub fn restore_to(&self, ctx: &mut UserContext) {
+        let g = &self.gregs;
+        ctx.set_field(UserContextField::InstrPointer, g[0]);
+        let regs = ctx.general_mut();
+        regs.ra = g[1];
+        regs.sp = g[2];
+        regs.gp = g[3];
+        regs.tp = g[4];
+        regs.t0 = g[5];
+        regs.t1 = g[6];
+        regs.t2 = g[7];
+        regs.s0 = g[8];
+        regs.s1 = g[9];
+        regs.a0 = g[10];
+        regs.a1 = g[11];
+        regs.a2 = g[12];
+        regs.a3 = g[13];
+        regs.a4 = g[14];
+        regs.a5 = g[15];
+        regs.a6 = g[16];
+        regs.a7 = g[17];
+        regs.s2 = g[18];
+        regs.s3 = g[19];
+        regs.s4 = g[20];
+        regs.s5 = g[21];
+        regs.s6 = g[22];
+        regs.s7 = g[23];
+        regs.s8 = g[24];
+        regs.s9 = g[25];
+        regs.s10 = g[26];
+        regs.s11 = g[27];
+        regs.t3 = g[28];
+        regs.t4 = g[29];
+        regs.t5 = g[30];
+        regs.t6 = g[31];
+    }
+}
+
 numeric_enum! {
 #[repr(u8)]
 #[derive(Eq, PartialEq, Debug, Copy, Clone)]
@@ -124,11 +255,41 @@ impl Signal {
     pub fn is_standard(self) -> bool {
         (self as usize) < Self::RTMIN
     }
+
+    /// The action taken when the signal is delivered with `SIG_DFL` handler.
+    pub fn default_action(self) -> SignalDefaultAction {
+        use self::Signal::*;
+        use self::SignalDefaultAction::*;
+        match self {
+            SIGCHLD | SIGURG | SIGWINCH => Ignore,
+            SIGCONT => Continue,
+            SIGSTOP | SIGTSTP | SIGTTIN | SIGTTOU => Stop,
+            SIGQUIT | SIGILL | SIGTRAP | SIGABRT | SIGBUS | SIGFPE | SIGSEGV | SIGXCPU
+            | SIGXFSZ | SIGSYS => CoreDump,
+            _ => Terminate,
+        }
+    }
+}
+
+/// Default action of a signal. See `man 7 signal`.
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum SignalDefaultAction {
+    /// Terminate the process.
+    Terminate,
+    /// Terminate the process and dump core.
+    CoreDump,
+    /// Ignore the signal.
+    Ignore,
+    /// Stop the process.
+    Stop,
+    /// Continue the process if it is currently stopped.
+    Continue,
 }
 
 /// See musl struct __ucontext
 ///
 /// Not exactly the same for now
+#[cfg(target_arch = "x86_64")]
 #[repr(C)]
 #[derive(Clone)]
 pub struct SignalUserContext {
@@ -139,17 +300,138 @@ pub struct SignalUserContext {
     pub sig_mask: Sigset,
 }
 
+/// See musl struct __ucontext
+///
+/// Not exactly the same for now
+#[cfg(target_arch = "riscv64")]
+#[repr(C)]
+#[derive(Clone)]
+pub struct SignalUserContext {
+    pub flags: usize,
+    pub link: usize,
+    pub stack: SignalStack,
+    pub sig_mask: Sigset,
+    pub _unused: [u8; 1024 / 8 - core::mem::size_of::<Sigset>()],
+    pub context: MachineContext,
+}
+
+impl SignalUserContext {
+    /// Create a user context to be saved on the signal frame.
+    pub fn new(ctx: &mut UserContext, stack: SignalStack, sig_mask: Sigset) -> Self {
+        SignalUserContext {
+            flags: 0,
+            link: 0,
+            stack,
+            context: MachineContext::from_user_context(ctx),
+            sig_mask,
+            #[cfg(target_arch = "riscv64")]
+            _unused: [0; 1024 / 8 - core::mem::size_of::<Sigset>()],
+        }
+    }
+}
+
+/// The code calling `rt_sigreturn`, mapped executable in every process as the
+/// return address of the signal handlers without a restorer.
+#[cfg(target_arch = "x86_64")]
+pub const SIGRETURN_CODE: [u8; 8] = [
+    0xb8, 0x0f, 0x00, 0x00, 0x00, // mov eax, 15 (__NR_rt_sigreturn)
+    0x0f, 0x05, // syscall
+    0x90, // nop
+];
+/// The code calling `rt_sigreturn`, mapped executable in every process as the
+/// return address of the signal handlers without a restorer.
+#[cfg(target_arch = "riscv64")]
+pub const SIGRETURN_CODE: [u8; 8] = [
+    0x93, 0x08, 0xb0, 0x08, // li a7, 139 (__NR_rt_sigreturn)
+    0x73, 0x00, 0x00, 0x00, // ecall
+];
+
+/// The frame pushed on the user stack when a signal handler is invoked.
 #[repr(C)]
 #[derive(Clone)]
 pub struct SignalFrame {
-    /// point to ret_code
+    /// The return address of the handler
     pub ret_code_addr: usize,
     /// Signal Frame info
     pub info: SigInfo,
     /// adapt interface, a little bit waste
     pub ucontext: SignalUserContext,
-    /// call sys_sigreturn
-    pub ret_code: [u8; 7],
+}
+
+impl SignalFrame {
+    /// Offset of `info` in the frame.
+    pub const INFO_OFFSET: usize = core::mem::size_of::<usize>();
+    /// Offset of `ucontext` in the frame.
+    pub const UCONTEXT_OFFSET: usize = Self::INFO_OFFSET + core::mem::size_of::<SigInfo>();
+
+    /// Offset from the frame start to the user stack pointer when `rt_sigreturn` is called.
+    ///
+    /// On x86_64 the handler pops `ret_code_addr` when returning.
+    #[cfg(target_arch = "x86_64")]
+    pub const SIGRETURN_SP_OFFSET: usize = core::mem::size_of::<usize>();
+    /// Offset from the frame start to the user stack pointer when `rt_sigreturn` is called.
+    #[cfg(target_arch = "riscv64")]
+    pub const SIGRETURN_SP_OFFSET: usize = 0;
+}
+
+/// Signals queued for delivery to a thread or a whole process.
+///
+/// A standard signal is pending at most once, while real-time signals are
+/// queued in the order of their arrival.
+#[derive(Default)]
+pub struct SignalQueue {
+    /// set of pending signals
+    pending: Sigset,
+    /// information of pending signals
+    infos: VecDeque<SigInfo>,
+}
+
+impl SignalQueue {
+    /// Enqueue a signal. Returns `false` if it is a standard signal which is already pending.
+    pub fn push(&mut self, info: SigInfo) -> bool {
+        let signal = match Signal::try_from(info.signo as u8) {
+            Ok(signal) => signal,
+            Err(_) => return false,
+        };
+        if signal.is_standard() && self.pending.contains(signal) {
+            return false;
+        }
+        self.pending.insert(signal);
+        self.infos.push_back(info);
+        true
+    }
+
+    /// Dequeue the first pending signal which is not blocked by `mask`.
+    ///
+    /// Standard signals are delivered before real-time signals.
+    pub fn pop(&mut self, mask: &Sigset) -> Option<SigInfo> {
+        let deliverable = self.pending.difference(mask);
+        let idx = self
+            .infos
+            .iter()
+            .position(|info| deliverable.contains_signo(info.signo) && info.signo < 32)
+            .or_else(|| {
+                self.infos
+                    .iter()
+                    .position(|info| deliverable.contains_signo(info.signo))
+            })?;
+        let info = self.infos.remove(idx).unwrap();
+        if !self.infos.iter().any(|i| i.signo == info.signo) {
+            self.pending.remove_signo(info.signo);
+        }
+        Some(info)
+    }
+
+    /// Discard all pending instances of `signal`.
+    pub fn remove(&mut self, signal: Signal) {
+        self.pending.remove(signal);
+        self.infos.retain(|info| info.signo != signal as i32);
+    }
+
+    /// Returns the set of pending signals.
+    pub fn pending(&self) -> Sigset {
+        self.pending
+    }
 }
 
 bitflags! {
@@ -1,11 +1,22 @@
 //! Linux Thread
 
+use crate::error::LxResult;
+use crate::futex::{exit_robust_list, RobustListHead};
 use crate::process::ProcessExt;
-use crate::signal::{SignalStack, Sigset};
+use crate::signal::{
+    SigInfo, Signal, SignalAction, SignalActionFlags, SignalDefaultAction, SignalFrame,
+    SignalQueue, SignalStack, SignalStackFlags, SignalUserContext, Sigset, SIG_DFL, SIG_IGN,
+};
 use alloc::sync::Arc;
-use kernel_hal::user::{Out, UserOutPtr, UserPtr};
+use core::{
+    convert::TryFrom,
+    task::{Context, Waker},
+};
+use kernel_hal::context::{UserContext, UserContextField};
+use kernel_hal::user::{Out, UserInPtr, UserOutPtr, UserPtr};
 use spin::{Mutex, MutexGuard};
-use zircon_object::task::{CurrentThread, Process, Thread};
+use zircon_object::object::KernelObject;
+use zircon_object::task::{CurrentThread, Process, Thread, ThreadState};
 use zircon_object::ZxResult;
 
 /// Thread extension for linux
@@ -16,20 +27,43 @@ pub trait ThreadExt {
     fn lock_linux(&self) -> MutexGuard<'_, LinuxThread>;
     /// Set pointer to thread ID.
     fn set_tid_address(&self, tidptr: UserOutPtr<i32>);
+    /// Send a thread-directed signal.
+    fn send_signal(&self, info: SigInfo);
+    /// Send a signal caused by the thread itself, such as `SIGSEGV` on a bad memory access.
+    ///
+    /// The signal is unblocked, and its action is reset to the default if it is blocked
+    /// or ignored, so the thread does not return to the faulting code.
+    fn force_signal(&self, info: SigInfo);
 }
 
 /// CurrentThread extension for linux
 pub trait CurrentThreadExt {
-    /// exit linux thread
+    /// Exit the thread, releasing its robust futexes and waking the `clear_child_tid` futex.
+    ///
+    /// Also called when the thread terminates with its process.
     fn exit_linux(&self, exit_code: i32);
+    /// Deliver a pending signal before returning to user mode.
+    ///
+    /// Returns `false` if the thread should not return to user mode now,
+    /// because the process is stopped or terminated by the signal.
+    fn handle_signal(&self, ctx: &mut UserContext) -> bool;
+    /// Whether a blocking syscall should be interrupted, because the thread is dying
+    /// or a signal which is not blocked is pending.
+    ///
+    /// The waker of `cx` is woken when a signal is sent to the thread or the process.
+    fn signal_interrupted(&self, cx: &mut Context) -> bool;
 }
 
 impl ThreadExt for Thread {
     fn create_linux(proc: &Arc<Process>) -> ZxResult<Arc<Self>> {
         let linux_thread = Mutex::new(LinuxThread {
             clear_child_tid: 0.into(),
+            robust_list: 0.into(),
             signal_mask: Sigset::default(),
             signal_alternate_stack: SignalStack::default(),
+            signal_queue: SignalQueue::default(),
+            signal_waker: None,
+            minor_faults: 0,
         });
         Thread::create_with_ext(proc, "", linux_thread)
     }
@@ -45,24 +79,223 @@ impl ThreadExt for Thread {
     fn set_tid_address(&self, tidptr: UserPtr<i32, Out>) {
         self.lock_linux().clear_child_tid = tidptr;
     }
+
+    /// Send a thread-directed signal.
+    fn send_signal(&self, info: SigInfo) {
+        let signal = match Signal::try_from(info.signo as u8) {
+            Ok(signal) => signal,
+            Err(_) => return,
+        };
+        info!("send signal {:?} to thread {}", signal, self.id());
+        let proc = self.proc();
+        if proc.linux().prepare_signal(proc, signal) {
+            let mut thread = self.lock_linux();
+            thread.signal_queue.push(info);
+            thread.wake_signal_waiter();
+        }
+    }
+
+    fn force_signal(&self, info: SigInfo) {
+        let signal = match Signal::try_from(info.signo as u8) {
+            Ok(signal) => signal,
+            Err(_) => return,
+        };
+        let linux = self.proc().linux();
+        {
+            let mut thread = self.lock_linux();
+            if thread.signal_mask.contains(signal) || linux.signal_action(signal).handler == SIG_IGN
+            {
+                linux.set_signal_action(signal, SignalAction::default());
+                thread.signal_mask.remove(signal);
+            }
+        }
+        self.send_signal(info);
+    }
 }
 
 impl CurrentThreadExt for CurrentThread {
     /// Exit current thread for Linux.
     fn exit_linux(&self, _exit_code: i32) {
-        let mut linux_thread = self.lock_linux();
-        let clear_child_tid = &mut linux_thread.clear_child_tid;
+        // both are released only once
+        let (robust_list, mut clear_child_tid) = {
+            let mut linux_thread = self.lock_linux();
+            (
+                core::mem::replace(&mut linux_thread.robust_list, 0.into()),
+                core::mem::replace(&mut linux_thread.clear_child_tid, 0.into()),
+            )
+        };
+        if !robust_list.is_null() {
+            exit_robust_list(self.proc(), robust_list, self.id());
+        }
         // perform futex wake 1
         // ref: http://man7.org/linux/man-pages/man2/set_tid_address.2.html
         if !clear_child_tid.is_null() {
             info!("exit: do futex {:?} wake 1", clear_child_tid);
-            clear_child_tid.write(0).unwrap();
-            let uaddr = clear_child_tid.as_addr();
-            let futex = self.proc().linux().get_futex(uaddr);
-            futex.wake(1);
+            if clear_child_tid.write(0).is_ok() {
+                let uaddr = clear_child_tid.as_addr();
+                let futex = self.proc().linux().get_futex(uaddr);
+                futex.wake(1);
+            }
         }
         self.exit();
     }
+
+    /// Deliver a pending signal before returning to user mode.
+    ///
+    /// Thread-directed signals are delivered before process-directed ones.
+    fn handle_signal(&self, ctx: &mut UserContext) -> bool {
+        let proc = self.proc();
+        let linux_proc = proc.linux();
+        loop {
+            let (mask, info) = {
+                let mut thread = self.lock_linux();
+                let mask = thread.signal_mask;
+                (mask, thread.signal_queue.pop(&mask))
+            };
+            let info = match info.or_else(|| linux_proc.dequeue_signal(&mask)) {
+                Some(info) => info,
+                None => return true,
+            };
+            let signal = Signal::try_from(info.signo as u8).unwrap();
+            let action = linux_proc.signal_action(signal);
+            info!("handle signal {:?}, action: {:x?}", signal, action);
+            match action.handler {
+                SIG_IGN => continue,
+                SIG_DFL => match signal.default_action() {
+                    SignalDefaultAction::Ignore | SignalDefaultAction::Continue => continue,
+                    SignalDefaultAction::Stop => {
+                        linux_proc.stop(proc, signal);
+                        return false;
+                    }
+                    SignalDefaultAction::Terminate | SignalDefaultAction::CoreDump => {
+                        linux_proc.exit_by_signal(proc, signal);
+                        return false;
+                    }
+                },
+                handler => {
+                    if self.setup_signal_frame(ctx, handler, info).is_err() {
+                        // cannot write the signal frame, force SIGSEGV
+                        linux_proc.exit_by_signal(proc, Signal::SIGSEGV);
+                        return false;
+                    }
+                    let mut thread = self.lock_linux();
+                    thread.signal_mask.insert_set(&action.mask);
+                    if !action.flags.contains(SignalActionFlags::NODEFER) {
+                        thread.signal_mask.insert(signal);
+                    }
+                    drop(thread);
+                    if action.flags.contains(SignalActionFlags::RESETHAND) {
+                        linux_proc.set_signal_action(signal, Default::default());
+                    }
+                    return true;
+                }
+            }
+        }
+    }
+
+    fn signal_interrupted(&self, cx: &mut Context) -> bool {
+        if self.state() == ThreadState::Dying {
+            return true;
+        }
+        // senders queue the signal before taking the waker, so no signal is missed
+        let (mask, mut pending) = {
+            let mut thread = self.lock_linux();
+            thread.signal_waker = Some(cx.waker().clone());
+            (thread.signal_mask, thread.signal_queue.pending())
+        };
+        pending.insert_set(&self.proc().linux().pending_signals());
+        !pending.difference(&mask).is_empty()
+    }
+}
+
+trait SignalFrameExt {
+    fn setup_signal_frame(
+        &self,
+        ctx: &mut UserContext,
+        handler: usize,
+        info: SigInfo,
+    ) -> LxResult<()>;
+}
+
+impl SignalFrameExt for CurrentThread {
+    /// Push a [`SignalFrame`] to the user stack and redirect `ctx` to the handler.
+    fn setup_signal_frame(
+        &self,
+        ctx: &mut UserContext,
+        handler: usize,
+        info: SigInfo,
+    ) -> LxResult<()> {
+        let signal = Signal::try_from(info.signo as u8).unwrap();
+        let action = self.proc().linux().signal_action(signal);
+        // the stack is not executable, so return to the code in the vDSO page
+        let ret_code_addr = self.proc().linux().vdso_base();
+        let mut thread = self.lock_linux();
+        let sp = ctx.get_field(UserContextField::StackPointer);
+        // skip the red zone
+        #[cfg(target_arch = "x86_64")]
+        let sp = sp - 128;
+        let saved_stack = thread.signal_alternate_stack;
+        let alt_stack = &mut thread.signal_alternate_stack;
+        let sp = if action.flags.contains(SignalActionFlags::ONSTACK)
+            && !alt_stack.flags.contains(SignalStackFlags::DISABLE)
+            && !alt_stack.flags.contains(SignalStackFlags::ONSTACK)
+        {
+            let top = alt_stack.sp + alt_stack.size;
+            if alt_stack.flags.contains(SignalStackFlags::AUTODISARM) {
+                *alt_stack = SignalStack::default();
+            } else {
+                alt_stack.flags.insert(SignalStackFlags::ONSTACK);
+            }
+            top
+        } else {
+            sp
+        };
+        let frame_size = core::mem::size_of::<SignalFrame>();
+        // the stack looks like just after a `call` instruction
+        #[cfg(target_arch = "x86_64")]
+        let frame_addr = ((sp - frame_size) & !0xf) - core::mem::size_of::<usize>();
+        #[cfg(target_arch = "riscv64")]
+        let frame_addr = (sp - frame_size) & !0xf;
+
+        #[cfg(not(target_arch = "riscv64"))]
+        let ret_code_addr = if action.flags.contains(SignalActionFlags::RESTORER) {
+            action.restorer
+        } else {
+            ret_code_addr
+        };
+        let frame = SignalFrame {
+            ret_code_addr,
+            info,
+            ucontext: SignalUserContext::new(ctx, saved_stack, thread.signal_mask),
+        };
+        drop(thread);
+        let mut frame_ptr: UserOutPtr<SignalFrame> = frame_addr.into();
+        if let Err(err) = frame_ptr.write(frame) {
+            self.lock_linux().signal_alternate_stack = saved_stack;
+            return Err(err.into());
+        }
+
+        let info_addr = frame_addr + SignalFrame::INFO_OFFSET;
+        let ucontext_addr = frame_addr + SignalFrame::UCONTEXT_OFFSET;
+        ctx.set_field(UserContextField::InstrPointer, handler);
+        ctx.set_field(UserContextField::StackPointer, frame_addr);
+        #[cfg(target_arch = "x86_64")]
+        {
+            let regs = ctx.general_mut();
+            regs.rdi = signal as usize;
+            regs.rsi = info_addr;
+            regs.rdx = ucontext_addr;
+        }
+        #[cfg(target_arch = "riscv64")]
+        {
+            let regs = ctx.general_mut();
+            regs.a0 = signal as usize;
+            regs.a1 = info_addr;
+            regs.a2 = ucontext_addr;
+            regs.ra = ret_code_addr;
+        }
+        Ok(())
+    }
 }
 
 /// Linux specific thread information.
@@ -70,8 +303,25 @@ pub struct LinuxThread {
     /// Kernel performs futex wake when thread exits.
     /// Ref: <http://man7.org/linux/man-pages/man2/set_tid_address.2.html>
     clear_child_tid: UserOutPtr<i32>,
+    /// Robust futexes released when the thread exits, registered by `set_robust_list`
+    pub robust_list: UserInPtr<RobustListHead>,
     /// Signal mask
     pub signal_mask: Sigset,
     /// signal alternate stack
     pub signal_alternate_stack: SignalStack,
+    /// Pending thread-directed signals
+    pub signal_queue: SignalQueue,
+    /// Waker of the interruptible syscall the thread is blocked in
+    signal_waker: Option<Waker>,
+    /// Page faults handled without I/O
+    pub minor_faults: usize,
+}
+
+impl LinuxThread {
+    /// Wake the thread if it is blocked in an interruptible syscall.
+    pub(crate) fn wake_signal_waiter(&mut self) {
+        if let Some(waker) = self.signal_waker.take() {
+            waker.wake();
+        }
+    }
 }
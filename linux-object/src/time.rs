@@ -97,14 +97,86 @@ impl From<TimeSpec> for TimeVal {
     }
 }
 
+impl From<Duration> for TimeSpec {
+    fn from(d: Duration) -> Self {
+        Self {
+            sec: d.as_secs() as _,
+            nsec: d.subsec_nanos() as _,
+        }
+    }
+}
+
+impl From<TimeVal> for Duration {
+    fn from(t: TimeVal) -> Self {
+        Self::new(t.sec as _, t.usec as u32 * 1_000)
+    }
+}
+
+impl From<Duration> for TimeVal {
+    fn from(d: Duration) -> Self {
+        Self {
+            sec: d.as_secs() as _,
+            usec: d.subsec_micros() as _,
+        }
+    }
+}
+
+/// ITimerVal struct for getitimer and setitimer
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct ITimerVal {
+    /// interval of a periodic timer
+    pub interval: TimeVal,
+    /// time until the next expiration
+    pub value: TimeVal,
+}
+
+/// ITimerSpec struct for timer_gettime and timer_settime
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct ITimerSpec {
+    /// interval of a periodic timer
+    pub interval: TimeSpec,
+    /// time until the next expiration, or the time of it with `TIMER_ABSTIME`
+    pub value: TimeSpec,
+}
+
 /// RUsage for sys_getrusage()
-/// ignore other fields for now
 #[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
 pub struct RUsage {
     /// user CPU time used
     pub utime: TimeVal,
     /// system CPU time used
     pub stime: TimeVal,
+    /// maximum resident set size in kilobytes
+    pub maxrss: usize,
+    /// integral shared memory size, unused
+    pub ixrss: usize,
+    /// integral unshared data size, unused
+    pub idrss: usize,
+    /// integral unshared stack size, unused
+    pub isrss: usize,
+    /// page faults without I/O
+    pub minflt: usize,
+    /// page faults with I/O
+    pub majflt: usize,
+    /// swaps, unused
+    pub nswap: usize,
+    /// block input operations
+    pub inblock: usize,
+    /// block output operations
+    pub oublock: usize,
+    /// IPC messages sent, unused
+    pub msgsnd: usize,
+    /// IPC messages received, unused
+    pub msgrcv: usize,
+    /// signals received, unused
+    pub nsignals: usize,
+    /// voluntary context switches
+    pub nvcsw: usize,
+    /// involuntary context switches
+    pub nivcsw: usize,
 }
 
 /// Tms for times()
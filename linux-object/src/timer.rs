@@ -0,0 +1,253 @@
+//! Interval timers and POSIX per-process timers
+//!
+//! Timers measuring the wall clock are driven by `kernel_hal::timer` and expire in kernel tasks,
+//! while timers measuring the CPU time of a process are checked when the time is
+//! accounted by [`LinuxProcess::account_user_time`] and [`LinuxProcess::account_system_time`].
+//!
+//! [`LinuxProcess::account_user_time`]: crate::process::LinuxProcess::account_user_time
+//! [`LinuxProcess::account_system_time`]: crate::process::LinuxProcess::account_system_time
+
+use alloc::{
+    boxed::Box,
+    sync::{Arc, Weak},
+};
+use core::time::Duration;
+
+use spin::{Mutex, MutexGuard};
+use zircon_object::{
+    object::{KernelObject, KoID},
+    task::{Process, Thread},
+};
+
+use crate::process::ProcessExt;
+use crate::signal::{SigInfo, Signal, SignalCode};
+use crate::thread::ThreadExt;
+
+/// `SIGEV_SIGNAL`: send a signal to the process
+pub const SIGEV_SIGNAL: i32 = 0;
+/// `SIGEV_NONE`: no notification
+pub const SIGEV_NONE: i32 = 1;
+/// `SIGEV_THREAD_ID`: send a signal to the thread
+pub const SIGEV_THREAD_ID: i32 = 4;
+
+/// The clock measured by a timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerClock {
+    /// The wall clock, for `ITIMER_REAL` and `CLOCK_REALTIME`
+    Real,
+    /// The user CPU time of the process, for `ITIMER_VIRTUAL`
+    Virtual,
+    /// The CPU time of the process, for `ITIMER_PROF` and `CLOCK_PROCESS_CPUTIME_ID`
+    Prof,
+}
+
+/// How the expiration of a timer is notified.
+#[derive(Debug, Clone, Copy)]
+pub enum TimerNotify {
+    /// Nothing is notified
+    None,
+    /// Send the signal to the process
+    Process(Signal),
+    /// Send the signal to the thread
+    Thread(Signal, KoID),
+}
+
+/// Linux `struct sigevent`
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SigEvent {
+    /// the value passed to the signal handler
+    pub value: usize,
+    /// the signal to send
+    pub signo: i32,
+    /// notification method, `SIGEV_*`
+    pub notify: i32,
+    /// the thread to send the signal to, for `SIGEV_THREAD_ID`
+    pub tid: i32,
+    _pad: [i32; 11],
+}
+
+/// An interval timer of a process, set by `setitimer` or `timer_settime`.
+pub struct IntervalTimer {
+    proc: Weak<Process>,
+    clock: TimerClock,
+    notify: TimerNotify,
+    /// ID of a POSIX timer, unset for `setitimer`
+    id: Option<i32>,
+    /// The value passed to the signal handler of a POSIX timer
+    value: usize,
+    inner: Mutex<TimerInner>,
+}
+
+#[derive(Default)]
+struct TimerInner {
+    /// Time of the next expiration in the clock, unset if disarmed
+    deadline: Option<Duration>,
+    /// Period of a periodic timer, zero for a one-shot timer
+    interval: Duration,
+    /// Number of expirations missed by the last notification
+    overrun: usize,
+    /// Increased every time the timer is set, to ignore HAL timers set before
+    generation: usize,
+}
+
+impl IntervalTimer {
+    /// Create a disarmed timer of `proc`.
+    pub fn new(
+        proc: &Arc<Process>,
+        clock: TimerClock,
+        notify: TimerNotify,
+        id: Option<i32>,
+        value: usize,
+    ) -> Arc<Self> {
+        Arc::new(IntervalTimer {
+            proc: Arc::downgrade(proc),
+            clock,
+            notify,
+            id,
+            value,
+            inner: Mutex::default(),
+        })
+    }
+
+    /// The clock measured by the timer.
+    pub fn clock(&self) -> TimerClock {
+        self.clock
+    }
+
+    /// Current time of the clock measured by the timer.
+    pub fn now(&self) -> Duration {
+        match self.clock {
+            TimerClock::Real => kernel_hal::timer::timer_now(),
+            TimerClock::Virtual => self
+                .proc
+                .upgrade()
+                .map(|proc| proc.linux().user_time())
+                .unwrap_or_default(),
+            TimerClock::Prof => self
+                .proc
+                .upgrade()
+                .map(|proc| proc.linux().cpu_time())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns the time until the next expiration and the interval,
+    /// the former is zero if the timer is disarmed.
+    pub fn get(&self) -> (Duration, Duration) {
+        let now = self.now();
+        let inner = self.inner.lock();
+        let remaining = inner
+            .deadline
+            .map(|deadline| deadline.saturating_sub(now))
+            .unwrap_or_default();
+        (remaining, inner.interval)
+    }
+
+    /// Arm the timer to expire at `deadline` in its clock, then every `interval`,
+    /// or disarm it if `deadline` is `None`.
+    ///
+    /// Returns the time until the next expiration and the interval before.
+    pub fn set(
+        self: &Arc<Self>,
+        deadline: Option<Duration>,
+        interval: Duration,
+    ) -> (Duration, Duration) {
+        let old = self.get();
+        let mut inner = self.inner.lock();
+        inner.deadline = deadline;
+        inner.interval = interval;
+        inner.overrun = 0;
+        inner.generation += 1;
+        if let (Some(deadline), TimerClock::Real) = (deadline, self.clock) {
+            self.arm(deadline, inner.generation);
+        }
+        old
+    }
+
+    /// Number of expirations missed by the last notification.
+    pub fn overrun(&self) -> usize {
+        self.inner.lock().overrun
+    }
+
+    /// Check the timer at the time `now` of its clock.
+    pub fn check(self: &Arc<Self>, now: Duration) {
+        let inner = self.inner.lock();
+        self.expire(inner, now);
+    }
+
+    /// Set a HAL timer for the wall clock timer.
+    ///
+    /// The HAL timer fires in interrupt context, which may have interrupted a holder
+    /// of the locks of the process, so the expiration is handled in a kernel task.
+    fn arm(self: &Arc<Self>, deadline: Duration, generation: usize) {
+        let me = Arc::downgrade(self);
+        kernel_hal::timer::timer_set(
+            deadline,
+            Box::new(move |now| {
+                kernel_hal::thread::spawn(async move {
+                    if let Some(timer) = me.upgrade() {
+                        let inner = timer.inner.lock();
+                        if inner.generation == generation {
+                            timer.expire(inner, now);
+                        }
+                    }
+                })
+            }),
+        );
+    }
+
+    /// Notify the expiration if the deadline has passed at `now`,
+    /// and rearm the timer if it is periodic.
+    fn expire(self: &Arc<Self>, mut inner: MutexGuard<TimerInner>, now: Duration) {
+        let deadline = match inner.deadline {
+            Some(deadline) if deadline <= now => deadline,
+            _ => return,
+        };
+        if inner.interval == Duration::ZERO {
+            inner.deadline = None;
+            inner.overrun = 0;
+        } else {
+            let interval = inner.interval.as_nanos();
+            let missed = (now - deadline).as_nanos() / interval;
+            let next = deadline.as_nanos() + (missed + 1) * interval;
+            let next = Duration::from_nanos(next as u64);
+            inner.deadline = Some(next);
+            inner.overrun = missed.min(i32::MAX as u128) as usize;
+            if self.clock == TimerClock::Real {
+                self.arm(next, inner.generation);
+            }
+        }
+        let overrun = inner.overrun;
+        drop(inner);
+        self.notify(overrun);
+    }
+
+    /// Send the signal of the timer.
+    fn notify(&self, overrun: usize) {
+        let proc = match self.proc.upgrade() {
+            Some(proc) => proc,
+            None => return,
+        };
+        let signal = match self.notify {
+            TimerNotify::None => return,
+            TimerNotify::Process(signal) | TimerNotify::Thread(signal, _) => signal,
+        };
+        let info = match self.id {
+            Some(id) => SigInfo::from_timer(signal, id, overrun as i32, self.value),
+            None => SigInfo::new(signal, SignalCode::KERNEL),
+        };
+        match self.notify {
+            TimerNotify::Thread(_, tid) => {
+                let thread = proc
+                    .get_child(tid)
+                    .ok()
+                    .and_then(|obj| obj.downcast_arc::<Thread>().ok());
+                if let Some(thread) = thread {
+                    thread.send_signal(info);
+                }
+            }
+            _ => proc.send_signal(info),
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Syscalls of user and group identity
+//!
+//! - getuid, geteuid, getgid, getegid
+//! - setuid, setgid
+//! - setreuid, setregid
+//! - setresuid, setresgid
+//! - getresuid, getresgid
+//! - setfsuid, setfsgid
+//! - getgroups, setgroups
+
+use super::*;
+use alloc::vec::Vec;
+use linux_object::cred::{Id, NGROUPS_MAX};
+
+impl Syscall<'_> {
+    /// Returns the real user ID of the calling process.
+    pub fn sys_getuid(&self) -> SysResult {
+        Ok(self.linux_process().cred().uid.real as usize)
+    }
+
+    /// Returns the effective user ID of the calling process.
+    pub fn sys_geteuid(&self) -> SysResult {
+        Ok(self.linux_process().cred().uid.effective as usize)
+    }
+
+    /// Returns the real group ID of the calling process.
+    pub fn sys_getgid(&self) -> SysResult {
+        Ok(self.linux_process().cred().gid.real as usize)
+    }
+
+    /// Returns the effective group ID of the calling process.
+    pub fn sys_getegid(&self) -> SysResult {
+        Ok(self.linux_process().cred().gid.effective as usize)
+    }
+
+    /// Sets the effective user ID of the calling process.
+    /// If the process is privileged, the real and saved set user ID are also set.
+    pub fn sys_setuid(&self, uid: Id) -> SysResult {
+        info!("setuid: uid={}", uid);
+        self.linux_process().modify_cred(|cred| cred.set_uid(uid))?;
+        Ok(0)
+    }
+
+    /// Sets the effective group ID of the calling process.
+    /// If the process is privileged, the real and saved set group ID are also set.
+    pub fn sys_setgid(&self, gid: Id) -> SysResult {
+        info!("setgid: gid={}", gid);
+        self.linux_process().modify_cred(|cred| cred.set_gid(gid))?;
+        Ok(0)
+    }
+
+    /// Sets the real and effective user IDs, `-1` leaves the ID unchanged.
+    pub fn sys_setreuid(&self, ruid: Id, euid: Id) -> SysResult {
+        info!("setreuid: ruid={}, euid={}", ruid as i32, euid as i32);
+        self.linux_process()
+            .modify_cred(|cred| cred.set_reuid(ruid, euid))?;
+        Ok(0)
+    }
+
+    /// Sets the real and effective group IDs, `-1` leaves the ID unchanged.
+    pub fn sys_setregid(&self, rgid: Id, egid: Id) -> SysResult {
+        info!("setregid: rgid={}, egid={}", rgid as i32, egid as i32);
+        self.linux_process()
+            .modify_cred(|cred| cred.set_regid(rgid, egid))?;
+        Ok(0)
+    }
+
+    /// Sets the real, effective and saved set user IDs, `-1` leaves the ID unchanged.
+    pub fn sys_setresuid(&self, ruid: Id, euid: Id, suid: Id) -> SysResult {
+        info!(
+            "setresuid: ruid={}, euid={}, suid={}",
+            ruid as i32, euid as i32, suid as i32
+        );
+        self.linux_process()
+            .modify_cred(|cred| cred.set_resuid(ruid, euid, suid))?;
+        Ok(0)
+    }
+
+    /// Sets the real, effective and saved set group IDs, `-1` leaves the ID unchanged.
+    pub fn sys_setresgid(&self, rgid: Id, egid: Id, sgid: Id) -> SysResult {
+        info!(
+            "setresgid: rgid={}, egid={}, sgid={}",
+            rgid as i32, egid as i32, sgid as i32
+        );
+        self.linux_process()
+            .modify_cred(|cred| cred.set_resgid(rgid, egid, sgid))?;
+        Ok(0)
+    }
+
+    /// Gets the real, effective and saved set user IDs.
+    pub fn sys_getresuid(
+        &self,
+        mut ruid: UserOutPtr<Id>,
+        mut euid: UserOutPtr<Id>,
+        mut suid: UserOutPtr<Id>,
+    ) -> SysResult {
+        let uid = self.linux_process().cred().uid;
+        ruid.write(uid.real)?;
+        euid.write(uid.effective)?;
+        suid.write(uid.saved)?;
+        Ok(0)
+    }
+
+    /// Gets the real, effective and saved set group IDs.
+    pub fn sys_getresgid(
+        &self,
+        mut rgid: UserOutPtr<Id>,
+        mut egid: UserOutPtr<Id>,
+        mut sgid: UserOutPtr<Id>,
+    ) -> SysResult {
+        let gid = self.linux_process().cred().gid;
+        rgid.write(gid.real)?;
+        egid.write(gid.effective)?;
+        sgid.write(gid.saved)?;
+        Ok(0)
+    }
+
+    /// Sets the user ID used for file system checks, returns the previous one.
+    pub fn sys_setfsuid(&self, fsuid: Id) -> SysResult {
+        info!("setfsuid: fsuid={}", fsuid as i32);
+        let old = self
+            .linux_process()
+            .modify_cred(|cred| Ok(cred.set_fsuid(fsuid)))?;
+        Ok(old as usize)
+    }
+
+    /// Sets the group ID used for file system checks, returns the previous one.
+    pub fn sys_setfsgid(&self, fsgid: Id) -> SysResult {
+        info!("setfsgid: fsgid={}", fsgid as i32);
+        let old = self
+            .linux_process()
+            .modify_cred(|cred| Ok(cred.set_fsgid(fsgid)))?;
+        Ok(old as usize)
+    }
+
+    /// Gets the supplementary group IDs.
+    ///
+    /// If `size` is 0, only returns the number of groups.
+    pub fn sys_getgroups(&self, size: usize, mut list: UserOutPtr<Id>) -> SysResult {
+        let groups = self.linux_process().cred().groups;
+        if size == 0 {
+            return Ok(groups.len());
+        }
+        if size < groups.len() {
+            return Err(LxError::EINVAL);
+        }
+        list.write_array(&groups)?;
+        Ok(groups.len())
+    }
+
+    /// Sets the supplementary group IDs, the process must be privileged.
+    pub fn sys_setgroups(&self, size: usize, list: UserInPtr<Id>) -> SysResult {
+        info!("setgroups: size={}, list={:?}", size, list);
+        if size > NGROUPS_MAX {
+            return Err(LxError::EINVAL);
+        }
+        let groups = if size == 0 {
+            Vec::new()
+        } else {
+            list.read_array(size)?
+        };
+        self.linux_process()
+            .modify_cred(|cred| cred.set_groups(groups))?;
+        Ok(0)
+    }
+}
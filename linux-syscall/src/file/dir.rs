@@ -6,8 +6,10 @@
 //! - rmdir(at)
 //! - getdents64
 //! - link(at)
+//! - symlink(at)
 //! - unlink(at)
 //! - rename(at)
+//! - renameat2
 //! - readlink(at)
 
 use super::*;
@@ -69,7 +71,10 @@ impl Syscall<'_> {
         if inode.find(file_name).is_ok() {
             return Err(LxError::EEXIST);
         }
-        inode.create(file_name, FileType::Dir, mode as u32)?;
+        proc.check_access(&inode, Access::WRITE | Access::EXEC)?;
+        proc.mounts().check_writable(&inode)?;
+        let new_inode = inode.create(file_name, FileType::Dir, mode as u32)?;
+        proc.set_owner(&new_inode);
         Ok(0)
     }
     /// Remove a directory.
@@ -85,6 +90,7 @@ impl Syscall<'_> {
         if file_inode.metadata()?.type_ != FileType::Dir {
             return Err(LxError::ENOTDIR);
         }
+        proc.mounts().check_writable(&dir_inode)?;
         dir_inode.unlink(file_name)?;
         Ok(0)
     }
@@ -151,12 +157,53 @@ impl Syscall<'_> {
 
         let proc = self.linux_process();
         let (new_dir_path, new_file_name) = split_path(newpath);
-        let inode = proc.lookup_inode_at(olddirfd, oldpath, true)?;
+        let follow = flags.contains(AtFlags::SYMLINK_FOLLOW);
+        let inode = proc.lookup_inode_at(olddirfd, oldpath, follow)?;
         let new_dir_inode = proc.lookup_inode_at(newdirfd, new_dir_path, true)?;
+        proc.mounts().check_writable(&new_dir_inode)?;
         new_dir_inode.link(new_file_name, &inode)?;
         Ok(0)
     }
 
+    /// make a new name for a file, as a symbolic link containing `target`
+    pub fn sys_symlink(&self, target: UserInPtr<u8>, linkpath: UserInPtr<u8>) -> SysResult {
+        self.sys_symlinkat(target, FileDesc::CWD, linkpath)
+    }
+
+    /// create a symbolic link relative to a directory file descriptor
+    ///
+    /// The link contains `target`, which is not checked to exist and is resolved
+    /// only when the link is followed.
+    pub fn sys_symlinkat(
+        &self,
+        target: UserInPtr<u8>,
+        newdirfd: FileDesc,
+        linkpath: UserInPtr<u8>,
+    ) -> SysResult {
+        let target = target.as_c_str()?;
+        let linkpath = linkpath.as_c_str()?;
+        info!(
+            "symlinkat: target={:?}, newdirfd={:?}, linkpath={:?}",
+            target, newdirfd, linkpath
+        );
+        if target.is_empty() {
+            return Err(LxError::ENOENT);
+        }
+
+        let proc = self.linux_process();
+        let (dir_path, file_name) = split_path(linkpath);
+        let dir_inode = proc.lookup_inode_at(newdirfd, dir_path, true)?;
+        if dir_inode.find(file_name).is_ok() {
+            return Err(LxError::EEXIST);
+        }
+        proc.check_access(&dir_inode, Access::WRITE | Access::EXEC)?;
+        proc.mounts().check_writable(&dir_inode)?;
+        let inode = dir_inode.create(file_name, FileType::SymLink, 0o777)?;
+        inode.write_at(0, target.as_bytes())?;
+        proc.set_owner(&inode);
+        Ok(0)
+    }
+
     /// delete name/possibly file it refers to
     /// If that name was the last link to a file and no processes have the file open, the file is deleted.
     /// If the name was the last link to a file but any processes still have the file open,
@@ -182,6 +229,7 @@ impl Syscall<'_> {
         if file_inode.metadata()?.type_ == FileType::Dir {
             return Err(LxError::EISDIR);
         }
+        proc.mounts().check_writable(&dir_inode)?;
         dir_inode.unlink(file_name)?;
         Ok(0)
     }
@@ -198,19 +246,42 @@ impl Syscall<'_> {
         oldpath: UserInPtr<u8>,
         newdirfd: FileDesc,
         newpath: UserInPtr<u8>,
+    ) -> SysResult {
+        self.sys_renameat2(olddirfd, oldpath, newdirfd, newpath, 0)
+    }
+
+    /// rename file relative to directory file descriptors, with additional `flags`
+    ///
+    /// `RENAME_EXCHANGE` and `RENAME_WHITEOUT` are not supported by the file systems.
+    pub fn sys_renameat2(
+        &self,
+        olddirfd: FileDesc,
+        oldpath: UserInPtr<u8>,
+        newdirfd: FileDesc,
+        newpath: UserInPtr<u8>,
+        flags: usize,
     ) -> SysResult {
         let oldpath = oldpath.as_c_str()?;
         let newpath = newpath.as_c_str()?;
+        let flags = RenameFlags::from_bits(flags).ok_or(LxError::EINVAL)?;
         info!(
-            "renameat: olddirfd={:?}, oldpath={:?}, newdirfd={:?}, newpath={:?}",
-            olddirfd, oldpath, newdirfd, newpath
+            "renameat2: olddirfd={:?}, oldpath={:?}, newdirfd={:?}, newpath={:?}, flags={:?}",
+            olddirfd, oldpath, newdirfd, newpath, flags
         );
+        if flags.intersects(RenameFlags::EXCHANGE | RenameFlags::WHITEOUT) {
+            return Err(LxError::EINVAL);
+        }
 
         let proc = self.linux_process();
         let (old_dir_path, old_file_name) = split_path(oldpath);
         let (new_dir_path, new_file_name) = split_path(newpath);
-        let old_dir_inode = proc.lookup_inode_at(olddirfd, old_dir_path, false)?;
-        let new_dir_inode = proc.lookup_inode_at(newdirfd, new_dir_path, false)?;
+        let old_dir_inode = proc.lookup_inode_at(olddirfd, old_dir_path, true)?;
+        let new_dir_inode = proc.lookup_inode_at(newdirfd, new_dir_path, true)?;
+        if flags.contains(RenameFlags::NOREPLACE) && new_dir_inode.find(new_file_name).is_ok() {
+            return Err(LxError::EEXIST);
+        }
+        proc.mounts().check_writable(&old_dir_inode)?;
+        proc.mounts().check_writable(&new_dir_inode)?;
         old_dir_inode.move_(old_file_name, &new_dir_inode, new_file_name)?;
         Ok(0)
     }
@@ -222,7 +293,6 @@ impl Syscall<'_> {
 
     /// read value of symbolic link relative to directory file descriptor
     /// readlink() places the contents of the symbolic link path in the buffer base, which has size len
-    /// TODO: recursive link resolution and loop detection
     pub fn sys_readlinkat(
         &self,
         dirfd: FileDesc,
@@ -241,7 +311,6 @@ impl Syscall<'_> {
         if inode.metadata()?.type_ != FileType::SymLink {
             return Err(LxError::EINVAL);
         }
-        // TODO: recursive link resolution and loop detection
         let mut buf = vec![0; len];
         let len = inode.read_at(0, &mut buf)?;
         base.write_array(&buf[..len])?;
@@ -350,9 +419,22 @@ impl From<FileType> for DirentType {
     }
 }
 
+bitflags! {
+    pub struct RenameFlags: usize {
+        /// Don't overwrite `newpath` of the rename.
+        const NOREPLACE = 1;
+        /// Atomically exchange `oldpath` and `newpath`.
+        const EXCHANGE = 2;
+        /// Whiteout `oldpath`, used by overlay file systems.
+        const WHITEOUT = 4;
+    }
+}
+
 bitflags! {
     pub struct AtFlags: usize {
         const EMPTY_PATH = 0x1000;
         const SYMLINK_NOFOLLOW = 0x100;
+        const EACCESS = 0x200;
+        const SYMLINK_FOLLOW = 0x400;
     }
 }
@@ -0,0 +1,154 @@
+//! Files for event notification
+//!
+//! - eventfd, eventfd2
+//! - timerfd_create, timerfd_settime, timerfd_gettime
+//! - signalfd, signalfd4
+
+use super::*;
+use core::time::Duration;
+use linux_object::error::LxResult;
+use linux_object::signal::Sigset;
+use linux_object::time::ITimerSpec;
+
+use crate::time::timespec_to_duration;
+
+impl Syscall<'_> {
+    /// create a file for event notification
+    #[cfg(target_arch = "x86_64")]
+    pub fn sys_eventfd(&self, initval: u32) -> SysResult {
+        self.sys_eventfd2(initval, 0)
+    }
+
+    /// create a file for event notification, with `EFD_SEMAPHORE`,
+    /// `EFD_NONBLOCK` and `EFD_CLOEXEC` allowed in `flags`
+    pub fn sys_eventfd2(&self, initval: u32, flags: usize) -> SysResult {
+        const EFD_SEMAPHORE: usize = 1;
+        info!("eventfd2: initval={}, flags={:#x}", initval, flags);
+        let semaphore = flags & EFD_SEMAPHORE != 0;
+        let flags = file_flags(flags & !EFD_SEMAPHORE)?;
+        let eventfd = EventFd::new(initval, semaphore, flags);
+        let fd = self.linux_process().add_file(eventfd)?;
+        Ok(fd.into())
+    }
+
+    /// create a timer notifying expirations by a file,
+    /// with `TFD_NONBLOCK` and `TFD_CLOEXEC` allowed in `flags`
+    pub fn sys_timerfd_create(&self, clockid: usize, flags: usize) -> SysResult {
+        const CLOCK_REALTIME: usize = 0;
+        const CLOCK_MONOTONIC: usize = 1;
+        const CLOCK_BOOTTIME: usize = 7;
+        const CLOCK_REALTIME_ALARM: usize = 8;
+        const CLOCK_BOOTTIME_ALARM: usize = 9;
+        info!("timerfd_create: clockid={}, flags={:#x}", clockid, flags);
+        match clockid {
+            CLOCK_REALTIME | CLOCK_MONOTONIC | CLOCK_BOOTTIME | CLOCK_REALTIME_ALARM
+            | CLOCK_BOOTTIME_ALARM => {}
+            _ => return Err(LxError::EINVAL),
+        }
+        let timerfd = TimerFd::new(file_flags(flags)?);
+        let fd = self.linux_process().add_file(timerfd)?;
+        Ok(fd.into())
+    }
+
+    /// arm or disarm the timer of the timerfd `fd`
+    pub fn sys_timerfd_settime(
+        &self,
+        fd: FileDesc,
+        flags: usize,
+        new: UserInPtr<ITimerSpec>,
+        mut old: UserOutPtr<ITimerSpec>,
+    ) -> SysResult {
+        const TFD_TIMER_ABSTIME: usize = 1;
+        // the timer is never canceled as the clock can not be set
+        const TFD_TIMER_CANCEL_ON_SET: usize = 2;
+        info!(
+            "timerfd_settime: fd={:?}, flags={:#x}, new={:?}, old={:?}",
+            fd, flags, new, old
+        );
+        if flags & !(TFD_TIMER_ABSTIME | TFD_TIMER_CANCEL_ON_SET) != 0 {
+            return Err(LxError::EINVAL);
+        }
+        let timerfd = self.get_timerfd(fd)?;
+        let new = new.read()?;
+        let value = timespec_to_duration(new.value)?;
+        let interval = timespec_to_duration(new.interval)?;
+        let deadline = if value == Duration::ZERO {
+            None
+        } else if flags & TFD_TIMER_ABSTIME != 0 {
+            Some(value)
+        } else {
+            let now = kernel_hal::timer::timer_now();
+            Some(now.checked_add(value).ok_or(LxError::EINVAL)?)
+        };
+        let (old_value, old_interval) = timerfd.set(deadline, interval);
+        old.write_if_not_null(ITimerSpec {
+            interval: old_interval.into(),
+            value: old_value.into(),
+        })?;
+        Ok(0)
+    }
+
+    /// get the time until the next expiration of the timerfd `fd`, and the interval
+    pub fn sys_timerfd_gettime(&self, fd: FileDesc, mut curr: UserOutPtr<ITimerSpec>) -> SysResult {
+        info!("timerfd_gettime: fd={:?}, curr={:?}", fd, curr);
+        let (value, interval) = self.get_timerfd(fd)?.get();
+        curr.write(ITimerSpec {
+            interval: interval.into(),
+            value: value.into(),
+        })?;
+        Ok(0)
+    }
+
+    /// create a file accepting signals in `mask`
+    #[cfg(target_arch = "x86_64")]
+    pub fn sys_signalfd(&self, fd: i32, mask: UserInPtr<Sigset>, sizemask: usize) -> SysResult {
+        self.sys_signalfd4(fd, mask, sizemask, 0)
+    }
+
+    /// create a file accepting signals in `mask` if `fd` is -1,
+    /// or replace the mask of the signalfd `fd`
+    pub fn sys_signalfd4(
+        &self,
+        fd: i32,
+        mask: UserInPtr<Sigset>,
+        sizemask: usize,
+        flags: usize,
+    ) -> SysResult {
+        info!(
+            "signalfd4: fd={}, mask={:?}, sizemask={}, flags={:#x}",
+            fd, mask, sizemask, flags
+        );
+        if sizemask != core::mem::size_of::<Sigset>() {
+            return Err(LxError::EINVAL);
+        }
+        let flags = file_flags(flags)?;
+        let mask = mask.read()?;
+        let proc = self.linux_process();
+        if fd == -1 {
+            let signalfd = SignalFd::new(self.zircon_process(), mask, flags);
+            let fd = proc.add_file(signalfd)?;
+            return Ok(fd.into());
+        }
+        let fd = FileDesc::from(fd);
+        proc.get_file_like(fd)?
+            .downcast_arc::<SignalFd>()
+            .map_err(|_| LxError::EINVAL)?
+            .set_mask(mask);
+        Ok(fd.into())
+    }
+
+    /// Get the timerfd `fd`.
+    fn get_timerfd(&self, fd: FileDesc) -> LxResult<Arc<TimerFd>> {
+        self.linux_process()
+            .get_file_like(fd)?
+            .downcast_arc::<TimerFd>()
+            .map_err(|_| LxError::EINVAL)
+    }
+}
+
+/// Open flags of an event file, only `O_NONBLOCK` and `O_CLOEXEC` are allowed.
+fn file_flags(flags: usize) -> LxResult<OpenFlags> {
+    OpenFlags::from_bits(flags)
+        .filter(|flags| (*flags - OpenFlags::NON_BLOCK - OpenFlags::CLOEXEC).is_empty())
+        .ok_or(LxError::EINVAL)
+}
@@ -1,12 +1,16 @@
 //! File descriptor operations
 //!
 //! - open(at)
+//! - creat
 //! - close
-//! - dup2
-//! - pipe
+//! - dup(2/3)
+//! - pipe(2)
+//! - flock
 
 use super::*;
 use alloc::string::String;
+use linux_object::fs::lock::{self, FileLock, LockKind, LockOwner, LockType};
+use linux_object::thread::CurrentThreadExt;
 
 impl Syscall<'_> {
     /// Opens or creates a file, depending on the flags passed to the call. Returns an integer with the file descriptor.
@@ -14,6 +18,12 @@ impl Syscall<'_> {
         self.sys_openat(FileDesc::CWD, path, flags, mode)
     }
 
+    /// Creates a file, equivalent to `open` with flags `O_CREAT|O_WRONLY|O_TRUNC`.
+    pub fn sys_creat(&self, path: UserInPtr<u8>, mode: usize) -> SysResult {
+        let flags = OpenFlags::CREATE | OpenFlags::WRONLY | OpenFlags::TRUNCATE;
+        self.sys_open(path, flags.bits(), mode)
+    }
+
     /// open file relative to directory file descriptor
     pub fn sys_openat(
         &self,
@@ -30,25 +40,47 @@ impl Syscall<'_> {
             dir_fd, path, flags, mode
         );
 
-        let inode = if flags.contains(OpenFlags::CREATE) {
-            let (dir_path, file_name) = split_path(path);
-            // relative to cwd
-            let dir_inode = proc.lookup_inode_at(dir_fd, dir_path, true)?;
-            match dir_inode.find(file_name) {
-                Ok(file_inode) => {
-                    if flags.contains(OpenFlags::EXCLUSIVE) {
-                        return Err(LxError::EEXIST);
-                    }
-                    file_inode
-                }
+        let follow = !flags.contains(OpenFlags::NOFOLLOW);
+        let (inode, created) = if flags.contains(OpenFlags::CREATE) {
+            // a dangling symbolic link creates its target, unless with `O_EXCL`
+            let exclusive = flags.contains(OpenFlags::EXCLUSIVE);
+            let (dir_inode, file_name) =
+                proc.lookup_parent_at(dir_fd, path, follow && !exclusive)?;
+            match dir_inode.find(&file_name) {
+                Ok(_) if exclusive => return Err(LxError::EEXIST),
+                Ok(file_inode) => (file_inode, false),
                 Err(FsError::EntryNotFound) => {
-                    dir_inode.create(file_name, FileType::File, mode as u32)?
+                    proc.check_access(&dir_inode, Access::WRITE | Access::EXEC)?;
+                    proc.mounts().check_writable(&dir_inode)?;
+                    let file_inode = dir_inode.create(&file_name, FileType::File, mode as u32)?;
+                    proc.set_owner(&file_inode);
+                    (file_inode, true)
                 }
                 Err(e) => return Err(LxError::from(e)),
             }
         } else {
-            proc.lookup_inode_at(dir_fd, path, true)?
+            (proc.lookup_inode_at(dir_fd, path, follow)?, false)
         };
+        let type_ = inode.metadata()?.type_;
+        // only reached with `O_NOFOLLOW`
+        if type_ == FileType::SymLink {
+            return Err(LxError::ELOOP);
+        }
+        // the creator can always access a new file, whatever its mode is
+        if !created {
+            let mut access = Access::empty();
+            if flags.readable() {
+                access |= Access::READ;
+            }
+            if flags.writable() || flags.contains(OpenFlags::TRUNCATE) {
+                access |= Access::WRITE;
+            }
+            proc.check_access(&inode, access)?;
+            // devices, pipes and sockets can still be written on a read-only file system
+            if access.contains(Access::WRITE) && matches!(type_, FileType::File | FileType::Dir) {
+                proc.mounts().check_writable(&inode)?;
+            }
+        }
 
         let file = File::new(inode, flags, path.into());
         let fd = proc.add_file(file)?;
@@ -59,7 +91,11 @@ impl Syscall<'_> {
     pub fn sys_close(&self, fd: FileDesc) -> SysResult {
         info!("close: fd={:?}", fd);
         let proc = self.linux_process();
-        proc.close_file(fd)?;
+        if usize::from(fd) >= SOCKET_FD {
+            proc.close_socket(usize::from(fd).into())?;
+        } else {
+            proc.close_file(fd)?;
+        }
         Ok(0)
     }
 
@@ -67,10 +103,25 @@ impl Syscall<'_> {
     pub fn sys_dup2(&self, fd1: FileDesc, fd2: FileDesc) -> SysResult {
         info!("dup2: from {:?} to {:?}", fd1, fd2);
         let proc = self.linux_process();
-        // close fd2 first if it is opened
-        let _ = proc.close_file(fd2);
-        let file_like = proc.get_file_like(fd1)?.dup();
-        let fd2 = proc.add_file_at(fd2, file_like)?;
+        if fd1 == fd2 {
+            proc.get_file_like(fd1)?;
+            return Ok(fd2.into());
+        }
+        let fd2 = proc.dup_file_to(fd1, fd2, false)?;
+        Ok(fd2.into())
+    }
+
+    /// create a copy of the file descriptor oldfd, with `O_CLOEXEC` allowed in `flags`.
+    pub fn sys_dup3(&self, fd1: FileDesc, fd2: FileDesc, flags: usize) -> SysResult {
+        info!("dup3: from {:?} to {:?}, flags={:#x}", fd1, fd2, flags);
+        let flags = OpenFlags::from_bits(flags)
+            .filter(|flags| (*flags - OpenFlags::CLOEXEC).is_empty())
+            .ok_or(LxError::EINVAL)?;
+        if fd1 == fd2 {
+            return Err(LxError::EINVAL);
+        }
+        let proc = self.linux_process();
+        let fd2 = proc.dup_file_to(fd1, fd2, flags.close_on_exec())?;
         Ok(fd2.into())
     }
 
@@ -78,8 +129,7 @@ impl Syscall<'_> {
     pub fn sys_dup(&self, fd1: FileDesc) -> SysResult {
         info!("dup: from {:?}", fd1);
         let proc = self.linux_process();
-        let file_like = proc.get_file_like(fd1)?.dup();
-        let fd2 = proc.add_file(file_like)?;
+        let fd2 = proc.dup_file_from(fd1, 0, false)?;
         Ok(fd2.into())
     }
 
@@ -88,26 +138,42 @@ impl Syscall<'_> {
         self.sys_pipe2(fds, 0)
     }
 
-    /// Creates a pipe, a unidirectional data channel that can be used for interprocess communication.
+    /// Creates a pipe, with `O_CLOEXEC`, `O_NONBLOCK` and `O_DIRECT` allowed in `flags`.
+    ///
+    /// With `O_DIRECT`, the pipe is in packet mode: each write is a separate packet,
+    /// and each read returns at most one packet.
     pub fn sys_pipe2(&self, mut fds: UserOutPtr<[i32; 2]>, flags: usize) -> SysResult {
         info!("pipe2: fds={:?}, flags: {:#x}", fds, flags);
+        let allowed = OpenFlags::NON_BLOCK | OpenFlags::CLOEXEC | OpenFlags::DIRECT;
+        let flags = OpenFlags::from_bits(flags)
+            .filter(|flags| (*flags - allowed).is_empty())
+            .ok_or(LxError::EINVAL)?;
 
         let proc = self.linux_process();
-        let (read, write) = Pipe::create_pair();
+        let (read, write) = if flags.contains(OpenFlags::DIRECT) {
+            Pipe::create_packet_pair()
+        } else {
+            Pipe::create_pair()
+        };
 
-        let base_flags =
-            OpenFlags::from_bits_truncate(flags) & (OpenFlags::NON_BLOCK | OpenFlags::CLOEXEC);
+        let base_flags = flags - OpenFlags::DIRECT;
         let read_fd = proc.add_file(File::new(
             Arc::new(read),
             base_flags | OpenFlags::RDONLY,
             String::from("pipe_r:[]"),
         ))?;
 
-        let write_fd = proc.add_file(File::new(
+        let write_fd = match proc.add_file(File::new(
             Arc::new(write),
             base_flags | OpenFlags::WRONLY,
             String::from("pipe_w:[]"),
-        ))?;
+        )) {
+            Ok(fd) => fd,
+            Err(err) => {
+                proc.close_file(read_fd)?;
+                return Err(err);
+            }
+        };
         fds.write([read_fd.into(), write_fd.into()])?;
 
         info!(
@@ -119,21 +185,45 @@ impl Syscall<'_> {
     }
 
     /// apply or remove an advisory lock on an open file
-    /// TODO: handle operation
-    pub fn sys_flock(&mut self, fd: FileDesc, operation: usize) -> SysResult {
+    ///
+    /// The lock is owned by the open file description, so it is shared by duplicated
+    /// descriptors and children, and released when the last descriptor is closed.
+    pub async fn sys_flock(&mut self, fd: FileDesc, operation: usize) -> SysResult {
         bitflags! {
-            struct Operation: u8 {
+            struct Operation: usize {
                 const LOCK_SH = 1;
                 const LOCK_EX = 2;
                 const LOCK_NB = 4;
                 const LOCK_UN = 8;
             }
         }
-        let operation = Operation::from_bits(operation as u8).unwrap();
+        let operation = Operation::from_bits(operation).ok_or(LxError::EINVAL)?;
         info!("flock: fd: {:?}, operation: {:?}", fd, operation);
         let proc = self.linux_process();
-
-        proc.get_file(fd)?;
+        let file = proc.get_file(fd)?;
+        let inode = file.inode();
+        let owner = LockOwner::file(&file);
+        let type_ = match operation - Operation::LOCK_NB {
+            Operation::LOCK_SH => LockType::Read,
+            Operation::LOCK_EX => LockType::Write,
+            Operation::LOCK_UN => {
+                lock::unlock(&inode, LockKind::Flock, owner, 0, u64::MAX);
+                return Ok(0);
+            }
+            _ => return Err(LxError::EINVAL),
+        };
+        let lock = FileLock {
+            owner,
+            type_,
+            start: 0,
+            end: u64::MAX,
+        };
+        let wait = !operation.contains(Operation::LOCK_NB);
+        let thread = self.thread;
+        lock::lock(&inode, LockKind::Flock, lock, wait, |cx| {
+            thread.signal_interrupted(cx)
+        })
+        .await?;
         Ok(0)
     }
 }
@@ -6,10 +6,16 @@
 //! - truncate, ftruncate
 //! - sendfile, copy_file_range
 //! - sync, fsync, fdatasync
-//! - ioctl, fcntl
+//! - ioctl, fcntl (including record locks)
 //! - access, faccessat
+//! - chmod, fchmod(at)
+//! - chown, lchown, fchown(at)
 
 use super::*;
+use linux_object::error::LxResult;
+use linux_object::fs::lock::{self, FileLock, LockKind, LockOwner, LockType};
+use linux_object::fs::vfs::INode;
+use linux_object::thread::CurrentThreadExt;
 use linux_object::{process::FsInfo, time::TimeSpec};
 
 impl Syscall<'_> {
@@ -27,14 +33,17 @@ impl Syscall<'_> {
             let x = usize::from(fd);
             let socket = proc.get_socket(x.into())?;
             let mut buf = vec![0u8; len];
-            let (len, _) = socket.lock().read(&mut buf).await;
+            let (len, _) = self
+                .interruptible(async { Ok(socket.lock().read(&mut buf).await) })
+                .await?;
             let len = len.unwrap_or(0);
             base.write_array(&buf[..len])?;
             Ok(len)
         } else {
             let file_like = proc.get_file_like(fd)?;
+            self.check_tty_read(&file_like)?;
             let mut buf = vec![0u8; len];
-            let len = file_like.read(&mut buf).await?;
+            let len = self.interruptible(file_like.read(&mut buf)).await?;
             base.write_array(&buf[..len])?;
             Ok(len)
         }
@@ -45,11 +54,21 @@ impl Syscall<'_> {
     /// - fd – file descriptor
     /// - base – pointer to the buffer write
     /// - len – number of bytes to write
-    pub fn sys_write(&self, fd: FileDesc, base: UserInPtr<u8>, len: usize) -> SysResult {
+    pub async fn sys_write(&self, fd: FileDesc, base: UserInPtr<u8>, len: usize) -> SysResult {
         info!("write: fd={:?}, base={:?}, len={:#x}", fd, base, len);
-        self.linux_process()
-            .get_file_like(fd)?
-            .write(base.as_slice(len)?)
+        let proc = self.linux_process();
+
+        // TODO wait a new struct to refactor
+        if usize::from(fd) >= SOCKET_FD {
+            let x = usize::from(fd);
+            let socket = proc.get_socket(x.into())?;
+            let buf = base.as_slice(len)?;
+            self.interruptible(async { socket.lock().write(buf, None).await })
+                .await
+        } else {
+            let buf = base.as_slice(len)?;
+            self.interruptible(proc.get_file_like(fd)?.write(buf)).await
+        }
     }
 
     /// read from or write to a file descriptor at a given offset
@@ -110,14 +129,17 @@ impl Syscall<'_> {
             let x = usize::from(fd);
             let socket = proc.get_socket(x.into())?;
             let mut buf = vec![0u8; iovs.total_len()];
-            let (len, _) = socket.lock().read(&mut buf).await;
+            let (len, _) = self
+                .interruptible(async { Ok(socket.lock().read(&mut buf).await) })
+                .await?;
             let len = len.unwrap();
             iovs.write_from_buf(&buf)?;
             Ok(len)
         } else {
             let file_like = proc.get_file_like(fd)?;
+            self.check_tty_read(&file_like)?;
             let mut buf = vec![0u8; iovs.total_len()];
-            let len = file_like.read(&mut buf).await?;
+            let len = self.interruptible(file_like.read(&mut buf)).await?;
             iovs.write_from_buf(&buf)?;
             Ok(len)
         }
@@ -126,7 +148,7 @@ impl Syscall<'_> {
     /// works just like write except that multiple buffers are written out.
     /// writes iov_count buffers of data described
     /// by iov to the file associated with the file descriptor fd ("gather output").
-    pub fn sys_writev(
+    pub async fn sys_writev(
         &self,
         fd: FileDesc,
         iov_ptr: UserInPtr<IoVecIn>,
@@ -144,12 +166,11 @@ impl Syscall<'_> {
         if usize::from(fd) >= SOCKET_FD {
             let x = usize::from(fd);
             let socket = proc.get_socket(x.into())?;
-            let len = socket.lock().write(&buf, None)?;
-            Ok(len)
+            self.interruptible(async { socket.lock().write(&buf, None).await })
+                .await
         } else {
             let file_like = proc.get_file_like(fd)?;
-            let len = file_like.write(&buf)?;
-            Ok(len)
+            self.interruptible(file_like.write(&buf)).await
         }
     }
 
@@ -178,7 +199,10 @@ impl Syscall<'_> {
     pub fn sys_truncate(&self, path: UserInPtr<u8>, len: usize) -> SysResult {
         let path = path.as_c_str()?;
         info!("truncate: path={:?}, len={}", path, len);
-        self.linux_process().lookup_inode(path)?.resize(len)?;
+        let proc = self.linux_process();
+        let inode = proc.lookup_inode(path)?;
+        proc.mounts().check_writable(&inode)?;
+        inode.resize(len)?;
         Ok(0)
     }
 
@@ -254,7 +278,9 @@ impl Syscall<'_> {
             let mut bytes_written = 0;
             let mut rlen = read_len;
             while bytes_written < read_len {
-                let write_len = out_file.write(&buffer[bytes_written..(bytes_written + rlen)])?;
+                let write_len = out_file
+                    .write(&buffer[bytes_written..(bytes_written + rlen)])
+                    .await?;
                 if write_len == 0 {
                     info!(
                         "copy_file_range:END_ERR in={:?}, out={:?}, in_offset={:?}, out_offset={:?}, count={} = bytes_read {}, bytes_written {}, write_len {}",
@@ -328,17 +354,42 @@ impl Syscall<'_> {
             x.ioctl(request, arg1, arg2, arg3)
         } else {
             let file_like = proc.get_file_like(fd)?;
+            if let Some(tty) = file_tty(&file_like) {
+                // job control needs the calling process
+                if let Some(ret) = tty.ioctl(self.zircon_process(), request, arg1) {
+                    return ret;
+                }
+            }
             file_like.ioctl(request, arg1, arg2, arg3)
         }
     }
 
+    /// Check that a read from `file_like` is not from a background process group,
+    /// if it is a terminal.
+    fn check_tty_read(&self, file_like: &Arc<dyn FileLike>) -> LxResult {
+        match file_tty(file_like) {
+            Some(tty) => tty.check_read(self.zircon_process()),
+            None => Ok(()),
+        }
+    }
+
     /// Manipulate a file descriptor.
     /// - cmd – cmd flag
     /// - arg – additional parameters based on cmd
-    pub fn sys_fcntl(&self, fd: FileDesc, cmd: usize, arg: usize) -> SysResult {
+    pub async fn sys_fcntl(&self, fd: FileDesc, cmd: usize, arg: usize) -> SysResult {
         info!("fcntl: fd={:?}, cmd={:x}, arg={}", fd, cmd, arg);
         let proc = self.linux_process();
 
+        // descriptor flags are kept by the file descriptor table for files and sockets
+        match FcntlCmd::try_from(cmd) {
+            Ok(FcntlCmd::GETFD) => return Ok(proc.cloexec(fd)? as usize),
+            Ok(FcntlCmd::SETFD) => {
+                proc.set_cloexec(fd, arg & FD_CLOEXEC != 0)?;
+                return Ok(0);
+            }
+            _ => {}
+        }
+
         // TODO wait a new struct to refactor
         if usize::from(fd) >= SOCKET_FD {
             let f = usize::from(fd);
@@ -350,35 +401,29 @@ impl Syscall<'_> {
 
             if let Ok(cmd) = FcntlCmd::try_from(cmd) {
                 match cmd {
-                    FcntlCmd::GETFD => Ok(file_like.flags().close_on_exec() as usize),
-                    FcntlCmd::SETFD => {
-                        let mut flags = file_like.flags();
-                        if (arg & 1) != 0 {
-                            flags |= OpenFlags::CLOEXEC;
-                        } else {
-                            flags -= OpenFlags::CLOEXEC;
-                        }
-                        file_like.set_flags(flags)?;
-                        Ok(0)
-                    }
                     FcntlCmd::GETFL => Ok(file_like.flags().bits()),
                     FcntlCmd::SETFL => {
-                        file_like.set_flags(OpenFlags::from_bits_truncate(arg))?;
+                        // `O_CLOEXEC` is not a file status flag
+                        let flags = OpenFlags::from_bits_truncate(arg) - OpenFlags::CLOEXEC;
+                        file_like.set_flags(flags)?;
                         Ok(0)
                     }
                     FcntlCmd::DUPFD | FcntlCmd::DUPFD_CLOEXEC => {
-                        let new_fd = proc.get_free_fd_from(arg);
-                        self.sys_dup2(fd, new_fd)?;
-                        let dup = proc.get_file_like(new_fd)?;
-                        let mut flags = dup.flags();
-                        if cmd == FcntlCmd::DUPFD_CLOEXEC {
-                            flags |= OpenFlags::CLOEXEC;
-                        } else {
-                            flags -= OpenFlags::CLOEXEC;
-                        }
-                        dup.set_flags(flags)?;
+                        let cloexec = cmd == FcntlCmd::DUPFD_CLOEXEC;
+                        let new_fd = proc.dup_file_from(fd, arg, cloexec)?;
                         Ok(new_fd.into())
                     }
+                    FcntlCmd::GETLK
+                    | FcntlCmd::SETLK
+                    | FcntlCmd::SETLKW
+                    | FcntlCmd::OFD_GETLK
+                    | FcntlCmd::OFD_SETLK
+                    | FcntlCmd::OFD_SETLKW => {
+                        let file = file_like
+                            .downcast_arc::<File>()
+                            .map_err(|_| LxError::EBADF)?;
+                        self.fcntl_lock(&file, cmd, arg.into()).await
+                    }
                     _ => Err(LxError::EINVAL),
                 }
             } else {
@@ -387,13 +432,93 @@ impl Syscall<'_> {
         }
     }
 
+    /// Test, acquire or release a record lock of `file` for `fcntl`.
+    ///
+    /// POSIX record locks are owned by the process, and released when it closes any
+    /// descriptor of the file. Open file description locks (`F_OFD_*`) are owned by
+    /// the open file description, like `flock` locks.
+    async fn fcntl_lock(
+        &self,
+        file: &Arc<File>,
+        cmd: FcntlCmd,
+        mut ptr: UserInOutPtr<Flock>,
+    ) -> SysResult {
+        let mut flock = ptr.read()?;
+        info!("fcntl_lock: cmd={:?}, flock={:?}", cmd, flock);
+        let ofd = matches!(
+            cmd,
+            FcntlCmd::OFD_GETLK | FcntlCmd::OFD_SETLK | FcntlCmd::OFD_SETLKW
+        );
+        let owner = if ofd {
+            if flock.pid != 0 {
+                return Err(LxError::EINVAL);
+            }
+            LockOwner::file(file)
+        } else {
+            LockOwner::Process(self.linux_process().pid())
+        };
+        let (start, end) = flock.range(file)?;
+        let type_ = match flock.type_ {
+            F_RDLCK => Some(LockType::Read),
+            F_WRLCK => Some(LockType::Write),
+            F_UNLCK => None,
+            _ => return Err(LxError::EINVAL),
+        };
+        let inode = file.inode();
+        match cmd {
+            FcntlCmd::GETLK | FcntlCmd::OFD_GETLK => {
+                let lock = FileLock {
+                    owner,
+                    type_: type_.ok_or(LxError::EINVAL)?,
+                    start,
+                    end,
+                };
+                flock = match lock::test_lock(&inode, LockKind::Record, &lock) {
+                    Some(conflict) => Flock::from(conflict),
+                    None => Flock {
+                        type_: F_UNLCK,
+                        ..flock
+                    },
+                };
+                ptr.write(flock)?;
+            }
+            _ => match type_ {
+                None => lock::unlock(&inode, LockKind::Record, owner, start, end),
+                Some(type_) => {
+                    let flags = file.flags();
+                    let permitted = match type_ {
+                        LockType::Read => flags.readable(),
+                        LockType::Write => flags.writable(),
+                    };
+                    if !permitted {
+                        return Err(LxError::EBADF);
+                    }
+                    let lock = FileLock {
+                        owner,
+                        type_,
+                        start,
+                        end,
+                    };
+                    let wait = matches!(cmd, FcntlCmd::SETLKW | FcntlCmd::OFD_SETLKW);
+                    let thread = self.thread;
+                    lock::lock(&inode, LockKind::Record, lock, wait, |cx| {
+                        thread.signal_interrupted(cx)
+                    })
+                    .await?;
+                }
+            },
+        }
+        Ok(0)
+    }
+
     /// Checks whether the calling process can access the file pathname
     pub fn sys_access(&self, path: UserInPtr<u8>, mode: usize) -> SysResult {
         self.sys_faccessat(FileDesc::CWD, path, mode, 0)
     }
 
     /// Check user's permissions of a file relative to a directory file descriptor
-    /// TODO: check permissions based on uid/gid
+    ///
+    /// The real user and group IDs are used, unless `AT_EACCESS` is given in `flags`.
     pub fn sys_faccessat(
         &self,
         dirfd: FileDesc,
@@ -401,16 +526,122 @@ impl Syscall<'_> {
         mode: usize,
         flags: usize,
     ) -> SysResult {
-        // TODO: check permissions based on uid/gid
         let path = path.as_c_str()?;
-        let flags = AtFlags::from_bits_truncate(flags);
+        let flags = AtFlags::from_bits(flags).ok_or(LxError::EINVAL)?;
         info!(
             "faccessat: dirfd={:?}, path={:?}, mode={:#o}, flags={:?}",
             dirfd, path, mode, flags
         );
+        if mode > 0o7 {
+            return Err(LxError::EINVAL);
+        }
+        let access = Access::from_bits_truncate(mode as _);
         let proc = self.linux_process();
         let follow = !flags.contains(AtFlags::SYMLINK_NOFOLLOW);
-        let _inode = proc.lookup_inode_at(dirfd, path, follow)?;
+        let inode = proc.lookup_inode_at(dirfd, path, follow)?;
+        let cred = if flags.contains(AtFlags::EACCESS) {
+            proc.cred()
+        } else {
+            proc.cred().real()
+        };
+        cred.check_access(&inode.metadata()?, access)?;
+        Ok(0)
+    }
+
+    /// Change permissions of a file
+    pub fn sys_chmod(&self, path: UserInPtr<u8>, mode: usize) -> SysResult {
+        self.sys_fchmodat(FileDesc::CWD, path, mode)
+    }
+
+    /// Change permissions of the file referred to by `fd`
+    pub fn sys_fchmod(&self, fd: FileDesc, mode: usize) -> SysResult {
+        info!("fchmod: fd={:?}, mode={:#o}", fd, mode);
+        let inode = self.linux_process().get_file(fd)?.inode();
+        self.chmod_inode(&inode, mode)
+    }
+
+    /// Change permissions of a file relative to a directory file descriptor
+    pub fn sys_fchmodat(&self, dirfd: FileDesc, path: UserInPtr<u8>, mode: usize) -> SysResult {
+        let path = path.as_c_str()?;
+        info!(
+            "fchmodat: dirfd={:?}, path={:?}, mode={:#o}",
+            dirfd, path, mode
+        );
+        let inode = self.linux_process().lookup_inode_at(dirfd, path, true)?;
+        self.chmod_inode(&inode, mode)
+    }
+
+    /// Change ownership of a file
+    pub fn sys_chown(&self, path: UserInPtr<u8>, uid: usize, gid: usize) -> SysResult {
+        self.sys_fchownat(FileDesc::CWD, path, uid, gid, 0)
+    }
+
+    /// Change ownership of a file, without following symbolic links
+    pub fn sys_lchown(&self, path: UserInPtr<u8>, uid: usize, gid: usize) -> SysResult {
+        self.sys_fchownat(
+            FileDesc::CWD,
+            path,
+            uid,
+            gid,
+            AtFlags::SYMLINK_NOFOLLOW.bits(),
+        )
+    }
+
+    /// Change ownership of the file referred to by `fd`
+    pub fn sys_fchown(&self, fd: FileDesc, uid: usize, gid: usize) -> SysResult {
+        info!(
+            "fchown: fd={:?}, uid={}, gid={}",
+            fd, uid as i32, gid as i32
+        );
+        let inode = self.linux_process().get_file(fd)?.inode();
+        self.chown_inode(&inode, uid, gid)
+    }
+
+    /// Change ownership of a file relative to a directory file descriptor
+    ///
+    /// An ID of `-1` leaves the corresponding ID unchanged.
+    pub fn sys_fchownat(
+        &self,
+        dirfd: FileDesc,
+        path: UserInPtr<u8>,
+        uid: usize,
+        gid: usize,
+        flags: usize,
+    ) -> SysResult {
+        let path = path.as_c_str()?;
+        let flags = AtFlags::from_bits(flags).ok_or(LxError::EINVAL)?;
+        info!(
+            "fchownat: dirfd={:?}, path={:?}, uid={}, gid={}, flags={:?}",
+            dirfd, path, uid as i32, gid as i32, flags
+        );
+        let proc = self.linux_process();
+        let inode = if path.is_empty() && flags.contains(AtFlags::EMPTY_PATH) {
+            proc.get_file(dirfd)?.inode()
+        } else {
+            let follow = !flags.contains(AtFlags::SYMLINK_NOFOLLOW);
+            proc.lookup_inode_at(dirfd, path, follow)?
+        };
+        self.chown_inode(&inode, uid, gid)
+    }
+
+    /// Change the permission bits of `inode` to `mode`.
+    fn chmod_inode(&self, inode: &Arc<dyn INode>, mode: usize) -> SysResult {
+        let proc = self.linux_process();
+        proc.mounts().check_writable(inode)?;
+        let mut meta = inode.metadata()?;
+        proc.cred().chmod(&mut meta, mode as _)?;
+        inode.set_metadata(&meta)?;
+        Ok(0)
+    }
+
+    /// Change the owner and group of `inode`.
+    fn chown_inode(&self, inode: &Arc<dyn INode>, uid: usize, gid: usize) -> SysResult {
+        let proc = self.linux_process();
+        proc.mounts().check_writable(inode)?;
+        let mut meta = inode.metadata()?;
+        let cred = proc.cred();
+        cred.chown(&mut meta, uid as u32, gid as u32)?;
+        inode.set_metadata(&meta)?;
         Ok(0)
     }
 
@@ -455,6 +686,7 @@ impl Syscall<'_> {
             };
             proc.lookup_inode_at(dirfd, pathname, follow)?
         };
+        proc.mounts().check_writable(&inode)?;
         let mut metadata = inode.metadata()?;
         if times[0].nsec != UTIME_OMIT {
             if times[0].nsec == UTIME_NOW {
@@ -488,13 +720,8 @@ impl Syscall<'_> {
         let path = path.as_c_str()?;
         info!("statfs: path={:?}, buf={:?}", path, buf);
 
-        // TODO
-        // 现在 `path` 没用到，因为没实现真正的挂载，不可能搞一个非主要文件系统的路径。
-        // 实现挂载之后，要用 `path` 分辨路径在哪个文件系统里，根据对应文件系统的特性返回统计信息。
-        // （以及根据挂载选项填写 `StatFs::f_flags`！）
-
-        let info = self.linux_process().root_inode().fs().info();
-        buf.write(info.into())?;
+        let inode = self.linux_process().lookup_inode(path)?;
+        buf.write(self.statfs(&inode))?;
         Ok(0)
     }
 
@@ -507,10 +734,20 @@ impl Syscall<'_> {
     pub fn sys_fstatfs(&self, fd: FileDesc, mut buf: UserOutPtr<StatFs>) -> SysResult {
         info!("statfs: fd={:?}, buf={:?}", fd, buf);
 
-        let info = self.linux_process().get_file(fd)?.inode().fs().info();
-        buf.write(info.into())?;
+        let inode = self.linux_process().get_file(fd)?.inode();
+        buf.write(self.statfs(&inode))?;
         Ok(0)
     }
+
+    /// Returns statistics of the mounted file system containing `inode`.
+    fn statfs(&self, inode: &Arc<dyn INode>) -> StatFs {
+        let mut stat = StatFs::from(inode.fs().info());
+        if let Some(mount) = self.linux_process().mounts().find(inode) {
+            stat.f_type = mount.fs_type.magic;
+            stat.f_flags = mount.flags.statfs_flags() as _;
+        }
+        stat
+    }
 }
 
 const F_LINUX_SPECIFIC_BASE: usize = 1024;
@@ -539,8 +776,7 @@ static_assertions::const_assert_eq!(120, core::mem::size_of::<StatFs>());
 impl From<FsInfo> for StatFs {
     fn from(info: FsInfo) -> Self {
         StatFs {
-            // TODO 文件系统的魔数，需要 rcore-fs 提供一个渠道获取
-            // 但是这个似乎并没有什么用处，新的 vfs 相关函数都去掉了，也许永远填个常数就好了
+            // filled by the mount table
             f_type: 0,
             f_bsize: info.bsize as _,
             f_blocks: info.blocks as _,
@@ -552,13 +788,16 @@ impl From<FsInfo> for StatFs {
             f_fsid: (0, 0),
             f_namelen: info.namemax as _,
             f_frsize: info.frsize as _,
-            // TODO 需要先实现挂载
+            // filled by the mount table
             f_flags: 0,
             f_spare: [0; 4],
         }
     }
 }
 
+/// The only file descriptor flag, close the descriptor on `execve`
+const FD_CLOEXEC: usize = 1;
+
 numeric_enum_macro::numeric_enum! {
     #[repr(usize)]
     #[allow(non_camel_case_types)]
@@ -581,10 +820,93 @@ numeric_enum_macro::numeric_enum! {
         SETLK = 6,
         /// Set record locking info (blocking).
         SETLKW = 7,
+        /// Get open file description record locking info.
+        OFD_GETLK = 36,
+        /// Set open file description record locking info (non-blocking).
+        OFD_SETLK = 37,
+        /// Set open file description record locking info (blocking).
+        OFD_SETLKW = 38,
         /// like F_DUPFD, but additionally set the close-on-exec flag
         DUPFD_CLOEXEC = F_LINUX_SPECIFIC_BASE + 6,
     }
 }
 
-// Temp , TODO warp a struct impl into/from with FileDesc and SocketHandle
-const SOCKET_FD: usize = 10000;
+/// Shared record lock
+const F_RDLCK: i16 = 0;
+/// Exclusive record lock
+const F_WRLCK: i16 = 1;
+/// Remove a record lock
+const F_UNLCK: i16 = 2;
+
+/// `struct flock` describing a record lock for `fcntl`
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Flock {
+    /// type of the lock: `F_RDLCK`, `F_WRLCK` or `F_UNLCK`
+    type_: i16,
+    /// how `start` is interpreted: `SEEK_SET`, `SEEK_CUR` or `SEEK_END`
+    whence: i16,
+    /// starting offset of the lock
+    start: i64,
+    /// number of bytes to lock, 0 for up to the end of file, negative for the bytes before `start`
+    len: i64,
+    /// process holding a conflicting lock, -1 for an open file description lock
+    pid: i32,
+}
+
+impl Flock {
+    /// Returns the first and the last byte of the range to lock in `file`.
+    fn range(&self, file: &File) -> LxResult<(u64, u64)> {
+        let base = match self.whence {
+            0 => 0,
+            1 => file.seek(SeekFrom::Current(0))? as i64,
+            2 => file.metadata()?.size as i64,
+            _ => return Err(LxError::EINVAL),
+        };
+        let start = base.checked_add(self.start).ok_or(LxError::EOVERFLOW)?;
+        let (start, end) = match self.len {
+            0 => (start, i64::MAX),
+            len if len > 0 => {
+                let end = start.checked_add(len - 1).ok_or(LxError::EOVERFLOW)?;
+                (start, end)
+            }
+            len => (start.checked_add(len).ok_or(LxError::EOVERFLOW)?, start - 1),
+        };
+        if start < 0 {
+            return Err(LxError::EINVAL);
+        }
+        let end = if end == i64::MAX {
+            u64::MAX
+        } else {
+            end as u64
+        };
+        Ok((start as u64, end))
+    }
+}
+
+impl From<FileLock> for Flock {
+    fn from(lock: FileLock) -> Self {
+        Flock {
+            type_: match lock.type_ {
+                LockType::Read => F_RDLCK,
+                LockType::Write => F_WRLCK,
+            },
+            whence: 0,
+            start: lock.start as i64,
+            len: if lock.end == u64::MAX {
+                0
+            } else {
+                (lock.end - lock.start + 1) as i64
+            },
+            pid: match lock.owner {
+                LockOwner::Process(pid) => pid as i32,
+                LockOwner::File(_) => -1,
+            },
+        }
+    }
+}
+
+/// Returns the terminal behind `file_like`, if any.
+fn file_tty(file_like: &Arc<dyn FileLike>) -> Option<Arc<Tty>> {
+    file_like.clone().downcast_arc::<File>().ok()?.tty()
+}
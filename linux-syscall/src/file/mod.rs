@@ -2,14 +2,20 @@
 #![deny(missing_docs)]
 use super::*;
 use bitflags::bitflags;
+use linux_object::cred::Access;
 use linux_object::fs::vfs::{FileType, FsError};
 use linux_object::fs::*;
 
 mod dir;
+mod event;
 mod fd;
 #[allow(clippy::module_inception)]
 mod file;
+mod mount;
 mod poll;
 mod stat;
 
 use self::dir::AtFlags;
+
+// Temp , TODO warp a struct impl into/from with FileDesc and SocketHandle
+const SOCKET_FD: usize = 10000;
@@ -0,0 +1,55 @@
+//! Mount operations
+//!
+//! - mount
+//! - umount2
+
+use super::*;
+
+impl Syscall<'_> {
+    /// Attach the file system of type `fs_type` from `source` to the directory `target`
+    /// (see [linux man mount(2)](https://man7.org/linux/man-pages/man2/mount.2.html)).
+    ///
+    /// `data` is ignored, since no file system takes options yet.
+    pub fn sys_mount(
+        &self,
+        source: UserInPtr<u8>,
+        target: UserInPtr<u8>,
+        fs_type: UserInPtr<u8>,
+        flags: usize,
+        data: usize,
+    ) -> SysResult {
+        let source = if source.is_null() {
+            "none"
+        } else {
+            source.as_c_str()?
+        };
+        let target = target.as_c_str()?;
+        let fs_type = if fs_type.is_null() {
+            ""
+        } else {
+            fs_type.as_c_str()?
+        };
+        let flags = MountFlags::from_bits_truncate(flags);
+        info!(
+            "mount: source={:?}, target={:?}, fs_type={:?}, flags={:?}, data={:#x}",
+            source, target, fs_type, flags, data
+        );
+        self.linux_process().mount(source, target, fs_type, flags)?;
+        Ok(0)
+    }
+
+    /// Detach the file system mounted on `target`
+    /// (see [linux man umount(2)](https://man7.org/linux/man-pages/man2/umount.2.html)).
+    pub fn sys_umount2(&self, target: UserInPtr<u8>, flags: usize) -> SysResult {
+        let target = target.as_c_str()?;
+        let flags = UmountFlags::from_bits(flags).ok_or(LxError::EINVAL)?;
+        info!("umount2: target={:?}, flags={:?}", target, flags);
+        if flags.contains(UmountFlags::EXPIRE)
+            && flags.intersects(UmountFlags::FORCE | UmountFlags::DETACH)
+        {
+            return Err(LxError::EINVAL);
+        }
+        self.linux_process().umount(target, flags)?;
+        Ok(0)
+    }
+}
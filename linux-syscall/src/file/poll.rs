@@ -2,6 +2,7 @@
 //!
 //! - select, pselect
 //! - poll, ppoll
+//! - epoll_create, epoll_create1, epoll_ctl, epoll_wait, epoll_pwait
 
 use super::*;
 use alloc::boxed::Box;
@@ -12,8 +13,13 @@ use core::pin::Pin;
 use core::task::{Context, Poll};
 use core::time::Duration;
 use kernel_hal::timer;
+use linux_object::error::LxResult;
 use linux_object::fs::FileDesc;
+use linux_object::net::{register_socket_waker, Socket};
+use linux_object::signal::Sigset;
+use linux_object::thread::ThreadExt;
 use linux_object::time::*;
+use spin::Mutex;
 
 impl Syscall<'_> {
     /// Wait for some event on a file descriptor
@@ -68,6 +74,25 @@ impl Syscall<'_> {
                             poll.revents |= PE::OUT;
                             events += 1;
                         }
+                    } else if let Ok(socket) = proc.get_socket(usize::from(poll.fd).into()) {
+                        let (read, write, error) =
+                            poll_socket(&socket, cx, |(read, write, error)| {
+                                error
+                                    || read && poll.events.contains(PE::IN)
+                                    || write && poll.events.contains(PE::OUT)
+                            });
+                        if error {
+                            poll.revents |= PE::HUP;
+                            events += 1;
+                        }
+                        if read && poll.events.contains(PE::IN) {
+                            poll.revents |= PE::IN;
+                            events += 1;
+                        }
+                        if write && poll.events.contains(PE::OUT) {
+                            poll.revents |= PE::OUT;
+                            events += 1;
+                        }
                     } else {
                         poll.revents |= PE::ERR;
                         events += 1;
@@ -177,6 +202,7 @@ impl Syscall<'_> {
 
         #[must_use = "future does nothing unless polled/`await`-ed"]
         struct SelectFuture<'a> {
+            nfds: usize,
             read_fds: &'a mut FdSet,
             write_fds: &'a mut FdSet,
             err_fds: &'a mut FdSet,
@@ -189,7 +215,8 @@ impl Syscall<'_> {
             type Output = SysResult;
 
             fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-                let files = self.syscall.linux_process().get_files()?;
+                let proc = self.syscall.linux_process();
+                let files = proc.get_files()?;
 
                 let mut events = 0;
                 for (&fd, file_like) in files.iter() {
@@ -218,6 +245,36 @@ impl Syscall<'_> {
                         events += 1;
                     }
                 }
+                for fd in (0..self.nfds).map(FileDesc::from) {
+                    if files.contains_key(&fd)
+                        || !self.err_fds.contains(fd)
+                            && !self.read_fds.contains(fd)
+                            && !self.write_fds.contains(fd)
+                    {
+                        continue;
+                    }
+                    let socket = match proc.get_socket(usize::from(fd).into()) {
+                        Ok(socket) => socket,
+                        Err(_) => continue,
+                    };
+                    let (read, write, error) = poll_socket(&socket, cx, |(read, write, error)| {
+                        error && self.err_fds.contains(fd)
+                            || read && self.read_fds.contains(fd)
+                            || write && self.write_fds.contains(fd)
+                    });
+                    if error && self.err_fds.contains(fd) {
+                        self.err_fds.set(fd);
+                        events += 1;
+                    }
+                    if read && self.read_fds.contains(fd) {
+                        self.read_fds.set(fd);
+                        events += 1;
+                    }
+                    if write && self.write_fds.contains(fd) {
+                        self.write_fds.set(fd);
+                        events += 1;
+                    }
+                }
 
                 // some event happens, so evoke the process
                 if events > 0 {
@@ -246,6 +303,7 @@ impl Syscall<'_> {
             }
         }
         let future = SelectFuture {
+            nfds,
             read_fds: &mut read_fds,
             write_fds: &mut write_fds,
             err_fds: &mut err_fds,
@@ -255,6 +313,168 @@ impl Syscall<'_> {
         };
         future.await
     }
+
+    /// Open an epoll file descriptor, `size` is ignored but must be greater than zero.
+    pub fn sys_epoll_create(&self, size: i32) -> SysResult {
+        info!("epoll_create: size: {}", size);
+        if size <= 0 {
+            return Err(LxError::EINVAL);
+        }
+        self.sys_epoll_create1(0)
+    }
+
+    /// Open an epoll file descriptor, only `EPOLL_CLOEXEC` is allowed in `flags`.
+    pub fn sys_epoll_create1(&self, flags: usize) -> SysResult {
+        info!("epoll_create1: flags: {:#x}", flags);
+        let flags = OpenFlags::from_bits(flags)
+            .filter(|flags| (*flags - OpenFlags::CLOEXEC).is_empty())
+            .ok_or(LxError::EINVAL)?;
+        let epoll = EpollInstance::new(flags);
+        let fd = self.linux_process().add_file(epoll)?;
+        Ok(fd.into())
+    }
+
+    /// Add, modify, or remove entries in the interest list of the epoll instance `epfd`.
+    pub fn sys_epoll_ctl(
+        &self,
+        epfd: FileDesc,
+        op: usize,
+        fd: FileDesc,
+        event: UserInPtr<EpollEvent>,
+    ) -> SysResult {
+        let op = match op {
+            1 => EpollCtlOp::Add,
+            2 => EpollCtlOp::Del,
+            3 => EpollCtlOp::Mod,
+            _ => return Err(LxError::EINVAL),
+        };
+        info!(
+            "epoll_ctl: epfd: {:?}, op: {:?}, fd: {:?}, event: {:?}",
+            epfd, op, fd, event
+        );
+        let proc = self.linux_process();
+        let epoll = proc
+            .get_file_like(epfd)?
+            .downcast_arc::<EpollInstance>()
+            .map_err(|_| LxError::EINVAL)?;
+        let target = match proc.get_file_like(fd) {
+            Ok(file) => EpollTarget::File(Arc::downgrade(&file)),
+            Err(_) => {
+                EpollTarget::Socket(Arc::downgrade(&proc.get_socket(usize::from(fd).into())?))
+            }
+        };
+        if epfd == fd {
+            return Err(LxError::EINVAL);
+        }
+        let event = match op {
+            EpollCtlOp::Del => EpollEvent {
+                events: EpollEvents::empty(),
+                data: 0,
+            },
+            _ => event.read()?,
+        };
+        epoll.control(op, fd, target, event)?;
+        Ok(0)
+    }
+
+    /// Wait for events on the epoll instance `epfd`.
+    pub async fn sys_epoll_wait(
+        &self,
+        epfd: FileDesc,
+        events: UserOutPtr<EpollEvent>,
+        maxevents: i32,
+        timeout_msecs: isize,
+    ) -> SysResult {
+        self.sys_epoll_pwait(epfd, events, maxevents, timeout_msecs, 0usize.into(), 0)
+            .await
+    }
+
+    /// Wait for events on the epoll instance `epfd`,
+    /// with the signal mask temporarily replaced by `sigmask`.
+    pub async fn sys_epoll_pwait(
+        &self,
+        epfd: FileDesc,
+        mut events: UserOutPtr<EpollEvent>,
+        maxevents: i32,
+        timeout_msecs: isize,
+        sigmask: UserInPtr<Sigset>,
+        sigsetsize: usize,
+    ) -> SysResult {
+        info!(
+            "epoll_pwait: epfd: {:?}, events: {:?}, maxevents: {}, timeout_msecs: {}, sigmask: {:?}",
+            epfd, events, maxevents, timeout_msecs, sigmask
+        );
+        if maxevents <= 0 {
+            return Err(LxError::EINVAL);
+        }
+        let epoll = self
+            .linux_process()
+            .get_file_like(epfd)?
+            .downcast_arc::<EpollInstance>()
+            .map_err(|_| LxError::EINVAL)?;
+        let old_mask = if let Some(mask) = sigmask.read_if_not_null()? {
+            if sigsetsize != core::mem::size_of::<Sigset>() {
+                return Err(LxError::EINVAL);
+            }
+            let mut thread = self.thread.lock_linux();
+            Some(core::mem::replace(&mut thread.signal_mask, mask))
+        } else {
+            None
+        };
+
+        #[must_use = "future does nothing unless polled/`await`-ed"]
+        struct EpollFuture<'a> {
+            epoll: &'a EpollInstance,
+            maxevents: usize,
+            timeout_msecs: isize,
+            begin_time_ms: usize,
+        }
+
+        impl<'a> Future for EpollFuture<'a> {
+            type Output = LxResult<Vec<EpollEvent>>;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+                let events = self.epoll.poll_events(cx, self.maxevents)?;
+                if !events.is_empty() {
+                    return Poll::Ready(Ok(events));
+                }
+                match self.timeout_msecs {
+                    // no timeout, return now;
+                    0 => return Poll::Ready(Ok(events)),
+                    1.. => {
+                        let current_time_ms = TimeVal::now().to_msec();
+                        let deadline = self.begin_time_ms + self.timeout_msecs as usize;
+                        if current_time_ms >= deadline {
+                            return Poll::Ready(Ok(events));
+                        } else {
+                            let waker = cx.waker().clone();
+                            timer::timer_set(
+                                Duration::from_millis(deadline as u64),
+                                Box::new(move |_| waker.wake_by_ref()),
+                            );
+                        }
+                    }
+                    _ => {}
+                }
+                Poll::Pending
+            }
+        }
+
+        let result = self
+            .interruptible(EpollFuture {
+                epoll: &epoll,
+                maxevents: maxevents as usize,
+                timeout_msecs,
+                begin_time_ms: TimeVal::now().to_msec(),
+            })
+            .await;
+        if let Some(mask) = old_mask {
+            self.thread.lock_linux().signal_mask = mask;
+        }
+        let ready = result?;
+        events.write_array(&ready)?;
+        Ok(ready.len())
+    }
 }
 
 #[repr(C)]
@@ -285,6 +505,24 @@ const FD_PER_ITEM: usize = u32::BITS as usize;
 /// max Fdset size
 const MAX_FDSET_SIZE: usize = 1024 / FD_PER_ITEM;
 
+/// Returns the status `(in, out, err)` of `socket`.
+///
+/// If it is not `ready`, the waker of `cx` is registered to be woken up when it changes.
+fn poll_socket(
+    socket: &Mutex<dyn Socket>,
+    cx: &mut Context,
+    ready: impl Fn((bool, bool, bool)) -> bool,
+) -> (bool, bool, bool) {
+    let socket = socket.lock();
+    let status = socket.poll();
+    if ready(status) {
+        return status;
+    }
+    // register the waker and check again, in case the socket changed meanwhile
+    register_socket_waker(&*socket, cx.waker());
+    socket.poll()
+}
+
 /// FdSet data struct for select
 struct FdSet {
     /// input addr, for update Fdset use
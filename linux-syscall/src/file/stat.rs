@@ -3,9 +3,10 @@
 //! - stat
 //! - lstat
 //! - fstat(at)
+//! - statx
 
 use super::*;
-use linux_object::fs::vfs::{FileType, Metadata};
+use linux_object::fs::vfs::{FileType, Metadata, Timespec};
 
 impl Syscall<'_> {
     /// Works exactly like the stat syscall, but if the file in question is a symbolic link,
@@ -60,6 +61,42 @@ impl Syscall<'_> {
     pub fn sys_stat(&self, path: UserInPtr<u8>, stat_ptr: UserOutPtr<Stat>) -> SysResult {
         self.sys_fstatat(FileDesc::CWD, path, stat_ptr, 0)
     }
+
+    /// get file status (extended)
+    ///
+    /// All fields in `STATX_BASIC_STATS` are returned regardless of `mask`.
+    pub fn sys_statx(
+        &self,
+        dirfd: FileDesc,
+        path: UserInPtr<u8>,
+        flags: usize,
+        mask: u32,
+        mut buf: UserOutPtr<Statx>,
+    ) -> SysResult {
+        let path = path.as_c_str()?;
+        let flags = AtFlags::from_bits_truncate(flags);
+        info!(
+            "statx: dirfd={:?}, path={:?}, flags={:?}, mask={:#x}, buf={:?}",
+            dirfd, path, flags, mask, buf
+        );
+
+        let proc = self.linux_process();
+        let meta = if path.is_empty() {
+            if !flags.contains(AtFlags::EMPTY_PATH) {
+                return Err(LxError::ENOENT);
+            }
+            if dirfd == FileDesc::CWD {
+                proc.lookup_inode_at(dirfd, ".", true)?.metadata()?
+            } else {
+                proc.get_file(dirfd)?.metadata()?
+            }
+        } else {
+            let follow = !flags.contains(AtFlags::SYMLINK_NOFOLLOW);
+            proc.lookup_inode_at(dirfd, path, follow)?.metadata()?
+        };
+        buf.write(meta.into())?;
+        Ok(0)
+    }
 }
 
 #[cfg(not(target_arch = "mips"))]
@@ -222,6 +259,120 @@ impl From<Metadata> for Stat {
     }
 }
 
+/// Timestamp in `struct statx`
+#[repr(C)]
+#[derive(Debug)]
+pub struct StatxTimestamp {
+    /// seconds
+    sec: i64,
+    /// nanoseconds
+    nsec: u32,
+    /// reserved
+    _reserved: i32,
+}
+
+impl From<Timespec> for StatxTimestamp {
+    fn from(t: Timespec) -> Self {
+        StatxTimestamp {
+            sec: t.sec as _,
+            nsec: t.nsec as _,
+            _reserved: 0,
+        }
+    }
+}
+
+/// Extended file status, the same layout on all architectures
+#[repr(C)]
+#[derive(Debug)]
+pub struct Statx {
+    /// mask of fields filled in
+    mask: u32,
+    /// blocksize for filesystem I/O
+    blksize: u32,
+    /// extra file attribute indicators
+    attributes: u64,
+    /// number of hard links
+    nlink: u32,
+    /// user ID of owner
+    uid: u32,
+    /// group ID of owner
+    gid: u32,
+    /// file type and mode
+    mode: u16,
+    /// padding
+    _spare0: u16,
+    /// inode number
+    ino: u64,
+    /// total size, in bytes
+    size: u64,
+    /// number of 512B blocks allocated
+    blocks: u64,
+    /// mask of supported attributes
+    attributes_mask: u64,
+    /// last access time
+    atime: StatxTimestamp,
+    /// creation time, not supported
+    btime: StatxTimestamp,
+    /// last status change time
+    ctime: StatxTimestamp,
+    /// last modification time
+    mtime: StatxTimestamp,
+    /// major ID of the device (if special file)
+    rdev_major: u32,
+    /// minor ID of the device (if special file)
+    rdev_minor: u32,
+    /// major ID of device containing file
+    dev_major: u32,
+    /// minor ID of device containing file
+    dev_minor: u32,
+    /// padding
+    _spare2: [u64; 14],
+}
+
+/// All the fields of `struct stat`, without `STATX_BTIME`.
+const STATX_BASIC_STATS: u32 = 0x7ff;
+
+/// Split a device number into `(major, minor)` as glibc `gnu_dev_major/minor` does.
+fn split_dev(dev: u64) -> (u32, u32) {
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    (major as u32, minor as u32)
+}
+
+impl From<Metadata> for Statx {
+    fn from(info: Metadata) -> Self {
+        let (rdev_major, rdev_minor) = split_dev(info.rdev as u64);
+        let (dev_major, dev_minor) = split_dev(info.dev as u64);
+        Statx {
+            mask: STATX_BASIC_STATS,
+            blksize: info.blk_size as _,
+            attributes: 0,
+            nlink: info.nlinks as _,
+            uid: info.uid as _,
+            gid: info.gid as _,
+            mode: StatMode::from_type_mode(info.type_, info.mode as _).bits() as _,
+            _spare0: 0,
+            ino: info.inode as _,
+            size: info.size as _,
+            blocks: info.blocks as _,
+            attributes_mask: 0,
+            atime: info.atime.into(),
+            btime: StatxTimestamp {
+                sec: 0,
+                nsec: 0,
+                _reserved: 0,
+            },
+            ctime: info.ctime.into(),
+            mtime: info.mtime.into(),
+            rdev_major,
+            rdev_minor,
+            dev_major,
+            dev_minor,
+            _spare2: [0; 14],
+        }
+    }
+}
+
 bitflags! {
     pub struct StatMode: u32 {
         /// Type
@@ -240,7 +240,7 @@ impl Syscall<'_> {
             vmo.len(),
             shmflg
         );
-        let addr = vmar.map(
+        let addr = vmar.map_shared(
             None,
             vmo.clone(),
             0,
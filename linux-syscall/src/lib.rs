@@ -24,17 +24,21 @@ extern crate alloc;
 #[macro_use]
 extern crate log;
 
-use alloc::sync::Arc;
+use alloc::{boxed::Box, sync::Arc};
 use core::convert::TryFrom;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
 
 use kernel_hal::user::{IoVecIn, IoVecOut, UserInOutPtr, UserInPtr, UserOutPtr};
 #[cfg(target_os = "none")]
 use kernel_hal::vm::PagingError;
 use kernel_hal::vm::PagingResult;
 use kernel_hal::MMUFlags;
-use linux_object::error::{LxError, SysResult};
+use linux_object::error::{LxError, LxResult, SysResult};
 use linux_object::fs::FileDesc;
-use linux_object::process::{wait_child, wait_child_any, LinuxProcess, ProcessExt, RLimit};
+use linux_object::process::{LinuxProcess, ProcessExt, RLimit};
+use linux_object::thread::CurrentThreadExt;
 use zircon_object::object::{KernelObject, KoID, Signal};
 use zircon_object::task::{CurrentThread, Process, Thread, ThreadFn};
 use zircon_object::{vm::VirtAddr, ZxError};
@@ -45,6 +49,7 @@ mod consts {
     // generated from syscall.h.in
     include!(concat!(env!("OUT_DIR"), "/consts.rs"));
 }
+mod cred;
 mod file;
 mod ipc;
 mod misc;
@@ -164,7 +169,10 @@ impl Syscall<'_> {
                 self.sys_read(a0.into(), self.into_out_userptr(a1).unwrap(), a2)
                     .await
             }
-            Sys::WRITE => self.sys_write(a0.into(), self.into_in_userptr(a1).unwrap(), a2),
+            Sys::WRITE => {
+                self.sys_write(a0.into(), self.into_in_userptr(a1).unwrap(), a2)
+                    .await
+            }
             Sys::OPENAT => self.sys_openat(a0.into(), self.into_in_userptr(a1).unwrap(), a2, a3),
             Sys::CLOSE => self.sys_close(a0.into()),
             Sys::FSTAT => self.sys_fstat(a0.into(), self.into_out_userptr(a1).unwrap()),
@@ -174,6 +182,13 @@ impl Syscall<'_> {
                 self.into_out_userptr(a2).unwrap(),
                 a3,
             ),
+            Sys::STATX => self.sys_statx(
+                a0.into(),
+                self.into_in_userptr(a1).unwrap(),
+                a2,
+                a3 as _,
+                self.into_out_userptr(a4).unwrap(),
+            ),
             Sys::LSEEK => self.sys_lseek(a0.into(), a1 as i64, a2 as u8),
             Sys::IOCTL => self.sys_ioctl(a0.into(), a1, a2, a3, a4),
             Sys::PREAD64 => {
@@ -187,7 +202,10 @@ impl Syscall<'_> {
                 self.sys_readv(a0.into(), self.into_in_userptr(a1).unwrap(), a2)
                     .await
             }
-            Sys::WRITEV => self.sys_writev(a0.into(), self.into_in_userptr(a1).unwrap(), a2),
+            Sys::WRITEV => {
+                self.sys_writev(a0.into(), self.into_in_userptr(a1).unwrap(), a2)
+                    .await
+            }
             Sys::SENDFILE => {
                 self.sys_sendfile(
                     a0.into(),
@@ -197,10 +215,13 @@ impl Syscall<'_> {
                 )
                 .await
             }
-            Sys::FCNTL => self.sys_fcntl(a0.into(), a1, a2),
-            Sys::FLOCK => self.sys_flock(a0.into(), a1),
+            Sys::FCNTL => self.sys_fcntl(a0.into(), a1, a2).await,
+            Sys::FLOCK => self.sys_flock(a0.into(), a1).await,
             Sys::FSYNC => self.sys_fsync(a0.into()),
             Sys::FDATASYNC => self.sys_fdatasync(a0.into()),
+            Sys::SYNC_FILE_RANGE => self.unimplemented("sync_file_range", Ok(0)),
+            Sys::FADVISE64 => self.unimplemented("fadvise64", Ok(0)),
+            Sys::READAHEAD => self.unimplemented("readahead", Ok(0)),
             Sys::TRUNCATE => self.sys_truncate(self.into_in_userptr(a0).unwrap(), a1),
             Sys::FTRUNCATE => self.sys_ftruncate(a0.into(), a1),
             Sys::GETDENTS64 => {
@@ -214,6 +235,13 @@ impl Syscall<'_> {
                 a2.into(),
                 self.into_in_userptr(a3).unwrap(),
             ),
+            Sys::RENAMEAT2 => self.sys_renameat2(
+                a0.into(),
+                self.into_in_userptr(a1).unwrap(),
+                a2.into(),
+                self.into_in_userptr(a3).unwrap(),
+                a4,
+            ),
             Sys::MKDIRAT => self.sys_mkdirat(a0.into(), self.into_in_userptr(a1).unwrap(), a2),
             Sys::LINKAT => self.sys_linkat(
                 a0.into(),
@@ -223,23 +251,29 @@ impl Syscall<'_> {
                 a4,
             ),
             Sys::UNLINKAT => self.sys_unlinkat(a0.into(), self.into_in_userptr(a1).unwrap(), a2),
-            Sys::SYMLINKAT => self.unimplemented("symlinkat", Err(LxError::EACCES)),
+            Sys::SYMLINKAT => self.sys_symlinkat(
+                self.into_in_userptr(a0).unwrap(),
+                a1.into(),
+                self.into_in_userptr(a2).unwrap(),
+            ),
             Sys::READLINKAT => self.sys_readlinkat(
                 a0.into(),
                 self.into_in_userptr(a1).unwrap(),
                 self.into_out_userptr(a2).unwrap(),
                 a3,
             ),
-            Sys::FCHMOD => self.unimplemented("fchmod", Ok(0)),
-            Sys::FCHMODAT => self.unimplemented("fchmodat", Ok(0)),
-            Sys::FCHOWN => self.unimplemented("fchown", Ok(0)),
-            Sys::FCHOWNAT => self.unimplemented("fchownat", Ok(0)),
+            Sys::FCHMOD => self.sys_fchmod(a0.into(), a1),
+            Sys::FCHMODAT => self.sys_fchmodat(a0.into(), self.into_in_userptr(a1).unwrap(), a2),
+            Sys::FCHOWN => self.sys_fchown(a0.into(), a1, a2),
+            Sys::FCHOWNAT => {
+                self.sys_fchownat(a0.into(), self.into_in_userptr(a1).unwrap(), a2, a3, a4)
+            }
             Sys::FACCESSAT => {
-                self.sys_faccessat(a0.into(), self.into_in_userptr(a1).unwrap(), a2, a3)
+                self.sys_faccessat(a0.into(), self.into_in_userptr(a1).unwrap(), a2, 0)
             }
             Sys::DUP => self.sys_dup(a0.into()),
-            Sys::DUP3 => self.sys_dup2(a0.into(), a1.into()), // TODO: handle `flags`
-            Sys::PIPE2 => self.sys_pipe2(a0.into(), a1),      // TODO: handle `flags`
+            Sys::DUP3 => self.sys_dup3(a0.into(), a1.into(), a2),
+            Sys::PIPE2 => self.sys_pipe2(a0.into(), a1),
             Sys::UTIMENSAT => {
                 self.sys_utimensat(a0.into(), self.into_in_userptr(a1).unwrap(), a2.into(), a3)
             }
@@ -275,12 +309,36 @@ impl Syscall<'_> {
                 )
                 .await
             } // ignore sigmask
-            //            Sys::EPOLL_CREATE1 => self.sys_epoll_create1(a0),
-            //            Sys::EPOLL_CTL => self.sys_epoll_ctl(a0, a1, a2, a3.into()),
-            //            Sys::EPOLL_PWAIT => self.sys_epoll_pwait(a0, a1.into(), a2, a3, a4),
-            //            Sys::EVENTFD2 => self.unimplemented("eventfd2", Err(LxError::EACCES)),
-
-            //            Sys::SOCKETPAIR => self.unimplemented("socketpair", Err(LxError::EACCES)),
+            Sys::EPOLL_CREATE1 => self.sys_epoll_create1(a0),
+            Sys::EPOLL_CTL => {
+                self.sys_epoll_ctl(a0.into(), a1, a2.into(), self.into_in_userptr(a3).unwrap())
+            }
+            Sys::EPOLL_PWAIT => {
+                self.sys_epoll_pwait(
+                    a0.into(),
+                    self.into_out_userptr(a1).unwrap(),
+                    a2 as _,
+                    a3 as _,
+                    self.into_in_userptr(a4).unwrap(),
+                    a5,
+                )
+                .await
+            }
+            Sys::EVENTFD2 => self.sys_eventfd2(a0 as _, a1),
+            Sys::TIMERFD_CREATE => self.sys_timerfd_create(a0, a1),
+            Sys::TIMERFD_SETTIME => self.sys_timerfd_settime(
+                a0.into(),
+                a1,
+                self.into_in_userptr(a2).unwrap(),
+                self.into_out_userptr(a3).unwrap(),
+            ),
+            Sys::TIMERFD_GETTIME => {
+                self.sys_timerfd_gettime(a0.into(), self.into_out_userptr(a1).unwrap())
+            }
+            Sys::SIGNALFD4 => {
+                self.sys_signalfd4(a0 as _, self.into_in_userptr(a1).unwrap(), a2, a3)
+            }
+            Sys::SOCKETPAIR => self.sys_socketpair(a0, a1, a2, a3.into()),
             // file system
             Sys::STATFS => self.sys_statfs(
                 self.into_in_userptr(a0).unwrap(),
@@ -288,15 +346,28 @@ impl Syscall<'_> {
             ),
             Sys::FSTATFS => self.sys_fstatfs(a0.into(), self.into_out_userptr(a1).unwrap()),
             Sys::SYNC => self.sys_sync(),
-            Sys::MOUNT => self.unimplemented("mount", Err(LxError::EACCES)),
-            Sys::UMOUNT2 => self.unimplemented("umount2", Err(LxError::EACCES)),
+            Sys::SYNCFS => self.sys_sync(),
+            Sys::MOUNT => self.sys_mount(
+                self.into_in_userptr(a0).unwrap(),
+                self.into_in_userptr(a1).unwrap(),
+                self.into_in_userptr(a2).unwrap(),
+                a3,
+                a4,
+            ),
+            Sys::UMOUNT2 => self.sys_umount2(self.into_in_userptr(a0).unwrap(), a1),
 
             // memory
-            Sys::BRK => self.unimplemented("brk", Err(LxError::ENOMEM)),
+            Sys::BRK => self.sys_brk(a0),
             Sys::MMAP => self.sys_mmap(a0, a1, a2, a3, a4.into(), a5 as _).await,
             Sys::MPROTECT => self.sys_mprotect(a0, a1, a2),
             Sys::MUNMAP => self.sys_munmap(a0, a1),
             Sys::MADVISE => self.unimplemented("madvise", Ok(0)),
+            Sys::MSYNC => self.sys_msync(a0, a1, a2),
+            Sys::MLOCK => self.unimplemented("mlock", Ok(0)),
+            Sys::MLOCK2 => self.unimplemented("mlock2", Ok(0)),
+            Sys::MUNLOCK => self.unimplemented("munlock", Ok(0)),
+            Sys::MLOCKALL => self.unimplemented("mlockall", Ok(0)),
+            Sys::MUNLOCKALL => self.unimplemented("munlockall", Ok(0)),
 
             // signal
             Sys::RT_SIGACTION => self.sys_rt_sigaction(
@@ -311,12 +382,18 @@ impl Syscall<'_> {
                 self.into_out_userptr(a2).unwrap(),
                 a3,
             ),
-            // Sys::RT_SIGRETURN => self.sys_rt_sigreturn(),
+            Sys::RT_SIGPENDING => self.sys_rt_sigpending(self.into_out_userptr(a0).unwrap(), a1),
+            Sys::RT_SIGRETURN => self.sys_rt_sigreturn(),
             Sys::SIGALTSTACK => self.sys_sigaltstack(
                 self.into_in_userptr(a0).unwrap(),
                 self.into_out_userptr(a1).unwrap(),
             ),
-            //            Sys::KILL => self.sys_kill(a0, a1),
+            Sys::RT_SIGQUEUEINFO => {
+                self.sys_rt_sigqueueinfo(a0, a1, self.into_in_userptr(a2).unwrap())
+            }
+            Sys::KILL => self.sys_kill(a0 as _, a1),
+            Sys::TKILL => self.sys_tkill(a0, a1),
+            Sys::TGKILL => self.sys_tgkill(a0, a1, a2),
 
             // schedule
             Sys::SCHED_YIELD => self.unimplemented("yield", Ok(0)),
@@ -334,21 +411,32 @@ impl Syscall<'_> {
                 )
                 .await
             }
-            //            Sys::ACCEPT4 => self.sys_accept(a0, a1.into(), a2.into()), // use accept for accept4
-            Sys::SENDTO => self.sys_sendto(
-                a0,
-                self.into_in_userptr(a1).unwrap(),
-                a2,
-                a3,
-                self.into_in_userptr(a4).unwrap(),
-                a5,
-            ),
+            Sys::ACCEPT4 => {
+                self.sys_accept4(
+                    a0,
+                    self.into_out_userptr(a1).unwrap(),
+                    self.into_inout_userptr(a2).unwrap(),
+                    a3,
+                )
+                .await
+            }
+            Sys::SENDTO => {
+                self.sys_sendto(
+                    a0,
+                    self.into_in_userptr(a1).unwrap(),
+                    a2,
+                    a3,
+                    self.into_in_userptr(a4).unwrap(),
+                    a5,
+                )
+                .await
+            }
             Sys::RECVFROM => {
                 self.sys_recvfrom(a0, a1.into(), a2, a3, a4.into(), a5.into())
                     .await
             }
-            Sys::SENDMSG => self.unimplemented("sys_sendmsg(),", Ok(0)),
-            Sys::RECVMSG => self.unimplemented("sys_recvmsg(a0, a1.into(), a2),", Ok(0)),
+            Sys::SENDMSG => self.sys_sendmsg(a0, a1.into(), a2).await,
+            Sys::RECVMSG => self.sys_recvmsg(a0, a1.into(), a2).await,
             Sys::SHUTDOWN => self.sys_shutdown(a0, a1),
             Sys::BIND => self.sys_bind(a0, self.into_in_userptr(a1).unwrap(), a2),
             Sys::LISTEN => self.sys_listen(a0, a1),
@@ -366,9 +454,13 @@ impl Syscall<'_> {
             Sys::SETSOCKOPT => {
                 self.sys_setsockopt(a0, a1, a2, self.into_in_userptr(a3).unwrap(), a4)
             }
-            Sys::GETSOCKOPT => {
-                self.sys_getsockopt(a0, a1, a2, self.into_out_userptr(a3).unwrap(), a4)
-            }
+            Sys::GETSOCKOPT => self.sys_getsockopt(
+                a0,
+                a1,
+                a2,
+                self.into_out_userptr(a3).unwrap(),
+                self.into_inout_userptr(a4).unwrap(),
+            ),
 
             // process
             Sys::CLONE => {
@@ -380,8 +472,9 @@ impl Syscall<'_> {
                 //     self.into_out_userptr(a3).unwrap(),
                 //     a4,
                 // )
-                self.sys_clone(a0, a1, a2.into(), a3.into(), a4)
+                self.sys_clone(a0, a1, a2.into(), a3.into(), a4).await
             }
+            Sys::CLONE3 => self.sys_clone3(self.into_in_userptr(a0).unwrap(), a1).await,
             Sys::EXECVE => self.sys_execve(
                 self.into_in_userptr(a0).unwrap(),
                 self.into_in_userptr(a1).unwrap(),
@@ -393,12 +486,15 @@ impl Syscall<'_> {
                 self.sys_wait4(a0 as _, self.into_out_userptr(a1).unwrap(), a2 as _)
                     .await
             }
-            Sys::SET_TID_ADDRESS => self.sys_set_tid_address(self.into_out_userptr(a0).unwrap()),
-            Sys::FUTEX => {
-                // ignore timeout argument when op is wake
-                self.sys_futex(a0, a1 as _, a2 as _, a3).await
+            Sys::WAITID => {
+                self.sys_waitid(a0 as _, a1, self.into_out_userptr(a2).unwrap(), a3 as _)
+                    .await
             }
-            Sys::TKILL => self.unimplemented("tkill", Ok(0)),
+            Sys::SET_TID_ADDRESS => self.sys_set_tid_address(self.into_out_userptr(a0).unwrap()),
+            Sys::SET_ROBUST_LIST => self.sys_set_robust_list(a0.into(), a1),
+            Sys::GET_ROBUST_LIST => self.sys_get_robust_list(a0, a1.into(), a2.into()),
+            Sys::RESTART_SYSCALL => self.unimplemented("restart_syscall", Err(LxError::EINTR)),
+            Sys::FUTEX => self.sys_futex(a0, a1 as _, a2 as _, a3, a4, a5 as _).await,
 
             // time
             Sys::NANOSLEEP => self.sys_nanosleep(self.into_in_userptr(a0).unwrap()).await,
@@ -411,7 +507,28 @@ impl Syscall<'_> {
                 )
                 .await
             }
-            Sys::SETITIMER => self.unimplemented("setitimer", Ok(0)),
+            Sys::GETITIMER => self.sys_getitimer(a0, self.into_out_userptr(a1).unwrap()),
+            Sys::SETITIMER => self.sys_setitimer(
+                a0,
+                self.into_in_userptr(a1).unwrap(),
+                self.into_out_userptr(a2).unwrap(),
+            ),
+            Sys::TIMER_CREATE => self.sys_timer_create(
+                a0,
+                self.into_in_userptr(a1).unwrap(),
+                self.into_out_userptr(a2).unwrap(),
+            ),
+            Sys::TIMER_SETTIME => self.sys_timer_settime(
+                a0 as _,
+                a1,
+                self.into_in_userptr(a2).unwrap(),
+                self.into_out_userptr(a3).unwrap(),
+            ),
+            Sys::TIMER_GETTIME => {
+                self.sys_timer_gettime(a0 as _, self.into_out_userptr(a1).unwrap())
+            }
+            Sys::TIMER_GETOVERRUN => self.sys_timer_getoverrun(a0 as _),
+            Sys::TIMER_DELETE => self.sys_timer_delete(a0 as _),
             Sys::GETTIMEOFDAY => self.sys_gettimeofday(
                 self.into_out_userptr(a0).unwrap(),
                 self.into_in_userptr(a1).unwrap(),
@@ -445,24 +562,47 @@ impl Syscall<'_> {
             Sys::GETTID => self.sys_gettid(),
             Sys::UNAME => self.sys_uname(self.into_out_userptr(a0).unwrap()),
             Sys::UMASK => self.unimplemented("umask", Ok(0o777)),
-            //            Sys::GETRLIMIT => self.sys_getrlimit(),
-            //            Sys::SETRLIMIT => self.sys_setrlimit(),
+            Sys::GETRLIMIT => {
+                self.sys_prlimit64(0, a0, 0usize.into(), self.into_out_userptr(a1).unwrap())
+            }
+            Sys::SETRLIMIT => {
+                self.sys_prlimit64(0, a0, self.into_in_userptr(a1).unwrap(), 0usize.into())
+            }
             Sys::GETRUSAGE => self.sys_getrusage(a0, self.into_out_userptr(a1).unwrap()),
             Sys::SYSINFO => self.sys_sysinfo(self.into_out_userptr(a0).unwrap()),
             Sys::TIMES => self.sys_times(self.into_out_userptr(a0).unwrap()),
-            Sys::GETUID => self.unimplemented("getuid", Ok(0)),
-            Sys::GETGID => self.unimplemented("getgid", Ok(0)),
-            Sys::SETUID => self.unimplemented("setuid", Ok(0)),
-            Sys::GETEUID => self.unimplemented("geteuid", Ok(0)),
-            Sys::GETEGID => self.unimplemented("getegid", Ok(0)),
-            Sys::SETPGID => self.unimplemented("setpgid", Ok(0)),
+            Sys::GETUID => self.sys_getuid(),
+            Sys::GETGID => self.sys_getgid(),
+            Sys::SETUID => self.sys_setuid(a0 as _),
+            Sys::SETGID => self.sys_setgid(a0 as _),
+            Sys::SETREUID => self.sys_setreuid(a0 as _, a1 as _),
+            Sys::SETREGID => self.sys_setregid(a0 as _, a1 as _),
+            Sys::SETRESUID => self.sys_setresuid(a0 as _, a1 as _, a2 as _),
+            Sys::SETRESGID => self.sys_setresgid(a0 as _, a1 as _, a2 as _),
+            Sys::GETRESUID => self.sys_getresuid(
+                self.into_out_userptr(a0).unwrap(),
+                self.into_out_userptr(a1).unwrap(),
+                self.into_out_userptr(a2).unwrap(),
+            ),
+            Sys::GETRESGID => self.sys_getresgid(
+                self.into_out_userptr(a0).unwrap(),
+                self.into_out_userptr(a1).unwrap(),
+                self.into_out_userptr(a2).unwrap(),
+            ),
+            Sys::SETFSUID => self.sys_setfsuid(a0 as _),
+            Sys::SETFSGID => self.sys_setfsgid(a0 as _),
+            Sys::GETEUID => self.sys_geteuid(),
+            Sys::GETEGID => self.sys_getegid(),
+            Sys::SETPGID => self.sys_setpgid(a0 as _, a1 as _),
             Sys::GETPPID => self.sys_getppid(),
-            Sys::SETSID => self.unimplemented("setsid", Ok(0)),
-            Sys::GETPGID => self.unimplemented("getpgid", Ok(0)),
-            Sys::GETGROUPS => self.unimplemented("getgroups", Ok(0)),
-            Sys::SETGROUPS => self.unimplemented("setgroups", Ok(0)),
+            Sys::SETSID => self.sys_setsid(),
+            Sys::GETPGID => self.sys_getpgid(a0 as _),
+            Sys::GETSID => self.sys_getsid(a0 as _),
+            Sys::GETGROUPS => self.sys_getgroups(a0, self.into_out_userptr(a1).unwrap()),
+            Sys::SETGROUPS => self.sys_setgroups(a0, self.into_in_userptr(a1).unwrap()),
             //            Sys::SETPRIORITY => self.sys_set_priority(a0),
             Sys::PRCTL => self.unimplemented("prctl", Ok(0)),
+            Sys::PERSONALITY => self.unimplemented("personality", Ok(0)),
             Sys::MEMBARRIER => self.unimplemented("membarrier", Ok(0)),
             Sys::PRLIMIT64 => self.sys_prlimit64(
                 a0,
@@ -474,12 +614,139 @@ impl Syscall<'_> {
             Sys::GETRANDOM => {
                 self.sys_getrandom(self.into_out_userptr(a0).unwrap(), a1 as usize, a2 as u32)
             }
-            Sys::RT_SIGQUEUEINFO => self.unimplemented("rt_sigqueueinfo", Ok(0)),
 
             // kernel module
             //            Sys::INIT_MODULE => self.sys_init_module(a0.into(), a1 as usize, a2.into()),
             Sys::FINIT_MODULE => self.unimplemented("finit_module", Err(LxError::ENOSYS)),
             //            Sys::DELETE_MODULE => self.sys_delete_module(a0.into(), a1 as u32),
+
+            // not supported yet, fail without killing the process
+            Sys::IO_SETUP
+            | Sys::IO_DESTROY
+            | Sys::IO_SUBMIT
+            | Sys::IO_CANCEL
+            | Sys::IO_GETEVENTS
+            | Sys::SETXATTR
+            | Sys::LSETXATTR
+            | Sys::FSETXATTR
+            | Sys::GETXATTR
+            | Sys::LGETXATTR
+            | Sys::FGETXATTR
+            | Sys::LISTXATTR
+            | Sys::LLISTXATTR
+            | Sys::FLISTXATTR
+            | Sys::REMOVEXATTR
+            | Sys::LREMOVEXATTR
+            | Sys::FREMOVEXATTR
+            | Sys::LOOKUP_DCOOKIE
+            | Sys::INOTIFY_INIT1
+            | Sys::INOTIFY_ADD_WATCH
+            | Sys::INOTIFY_RM_WATCH
+            | Sys::IOPRIO_SET
+            | Sys::IOPRIO_GET
+            | Sys::MKNODAT
+            | Sys::PIVOT_ROOT
+            | Sys::NFSSERVCTL
+            | Sys::FALLOCATE
+            | Sys::FCHDIR
+            | Sys::CHROOT
+            | Sys::VHANGUP
+            | Sys::QUOTACTL
+            | Sys::PREADV
+            | Sys::PWRITEV
+            | Sys::VMSPLICE
+            | Sys::SPLICE
+            | Sys::TEE
+            | Sys::ACCT
+            | Sys::CAPGET
+            | Sys::CAPSET
+            | Sys::UNSHARE
+            | Sys::KEXEC_LOAD
+            | Sys::INIT_MODULE
+            | Sys::DELETE_MODULE
+            | Sys::CLOCK_SETTIME
+            | Sys::SYSLOG
+            | Sys::PTRACE
+            | Sys::SCHED_SETPARAM
+            | Sys::SCHED_SETSCHEDULER
+            | Sys::SCHED_GETSCHEDULER
+            | Sys::SCHED_GETPARAM
+            | Sys::SCHED_GET_PRIORITY_MAX
+            | Sys::SCHED_GET_PRIORITY_MIN
+            | Sys::SCHED_RR_GET_INTERVAL
+            | Sys::RT_SIGSUSPEND
+            | Sys::RT_SIGTIMEDWAIT
+            | Sys::SETPRIORITY
+            | Sys::GETPRIORITY
+            | Sys::REBOOT
+            | Sys::SETHOSTNAME
+            | Sys::SETDOMAINNAME
+            | Sys::GETCPU
+            | Sys::SETTIMEOFDAY
+            | Sys::ADJTIMEX
+            | Sys::MQ_OPEN
+            | Sys::MQ_UNLINK
+            | Sys::MQ_TIMEDSEND
+            | Sys::MQ_TIMEDRECEIVE
+            | Sys::MQ_NOTIFY
+            | Sys::MQ_GETSETATTR
+            | Sys::MSGGET
+            | Sys::MSGCTL
+            | Sys::MSGRCV
+            | Sys::MSGSND
+            | Sys::SEMTIMEDOP
+            | Sys::MREMAP
+            | Sys::ADD_KEY
+            | Sys::REQUEST_KEY
+            | Sys::KEYCTL
+            | Sys::SWAPON
+            | Sys::SWAPOFF
+            | Sys::MINCORE
+            | Sys::REMAP_FILE_PAGES
+            | Sys::MBIND
+            | Sys::GET_MEMPOLICY
+            | Sys::SET_MEMPOLICY
+            | Sys::MIGRATE_PAGES
+            | Sys::MOVE_PAGES
+            | Sys::RT_TGSIGQUEUEINFO
+            | Sys::PERF_EVENT_OPEN
+            | Sys::RECVMMSG
+            | Sys::FANOTIFY_INIT
+            | Sys::FANOTIFY_MARK
+            | Sys::NAME_TO_HANDLE_AT
+            | Sys::OPEN_BY_HANDLE_AT
+            | Sys::CLOCK_ADJTIME
+            | Sys::SETNS
+            | Sys::SENDMMSG
+            | Sys::PROCESS_VM_READV
+            | Sys::PROCESS_VM_WRITEV
+            | Sys::KCMP
+            | Sys::SCHED_SETATTR
+            | Sys::SCHED_GETATTR
+            | Sys::SECCOMP
+            | Sys::MEMFD_CREATE
+            | Sys::BPF
+            | Sys::EXECVEAT
+            | Sys::USERFAULTFD
+            | Sys::PREADV2
+            | Sys::PWRITEV2
+            | Sys::PKEY_MPROTECT
+            | Sys::PKEY_ALLOC
+            | Sys::PKEY_FREE
+            | Sys::IO_PGETEVENTS
+            | Sys::RSEQ
+            | Sys::KEXEC_FILE_LOAD
+            | Sys::PIDFD_SEND_SIGNAL
+            | Sys::IO_URING_SETUP
+            | Sys::IO_URING_ENTER
+            | Sys::IO_URING_REGISTER
+            | Sys::OPEN_TREE
+            | Sys::MOVE_MOUNT
+            | Sys::FSOPEN
+            | Sys::FSCONFIG
+            | Sys::FSMOUNT
+            | Sys::FSPICK
+            | Sys::PIDFD_OPEN => self.unsupported_syscall(sys_type),
             #[cfg(target_arch = "x86_64")]
             _ => self.x86_64_syscall(sys_type, args).await,
             #[cfg(target_arch = "riscv64")]
@@ -498,6 +765,7 @@ impl Syscall<'_> {
         let [a0, a1, a2, a3, a4, _a5] = args;
         match sys_type {
             Sys::OPEN => self.sys_open(self.into_in_userptr(a0).unwrap(), a1, a2),
+            Sys::CREAT => self.sys_creat(self.into_in_userptr(a0).unwrap(), a1),
             Sys::STAT => self.sys_stat(
                 self.into_in_userptr(a0).unwrap(),
                 self.into_out_userptr(a1).unwrap(),
@@ -523,9 +791,9 @@ impl Syscall<'_> {
                 .await
             }
             Sys::DUP2 => self.sys_dup2(a0.into(), a1.into()),
-            //            Sys::ALARM => self.unimplemented("alarm", Ok(0)),
             Sys::FORK => self.sys_fork(),
             Sys::VFORK => self.sys_vfork().await,
+            Sys::ALARM => self.sys_alarm(a0 as _),
             Sys::RENAME => self.sys_rename(
                 self.into_in_userptr(a0).unwrap(),
                 self.into_in_userptr(a1).unwrap(),
@@ -542,12 +810,56 @@ impl Syscall<'_> {
                 self.into_out_userptr(a1).unwrap(),
                 a2,
             ),
-            Sys::CHMOD => self.unimplemented("chmod", Ok(0)),
-            Sys::CHOWN => self.unimplemented("chown", Ok(0)),
+            Sys::CHMOD => self.sys_chmod(self.into_in_userptr(a0).unwrap(), a1),
+            Sys::CHOWN => self.sys_chown(self.into_in_userptr(a0).unwrap(), a1, a2),
+            Sys::LCHOWN => self.sys_lchown(self.into_in_userptr(a0).unwrap(), a1, a2),
+            Sys::SYMLINK => self.sys_symlink(
+                self.into_in_userptr(a0).unwrap(),
+                self.into_in_userptr(a1).unwrap(),
+            ),
+            Sys::GETPGRP => self.sys_getpgrp(),
             Sys::ARCH_PRCTL => self.sys_arch_prctl(a0 as _, a1),
             Sys::TIME => self.sys_time(self.into_out_userptr(a0).unwrap()),
-            //            Sys::EPOLL_CREATE => self.sys_epoll_create(a0),
-            //            Sys::EPOLL_WAIT => self.sys_epoll_wait(a0, a1.into(), a2, a3),
+            Sys::EPOLL_CREATE => self.sys_epoll_create(a0 as _),
+            Sys::EVENTFD => self.sys_eventfd(a0 as _),
+            Sys::SIGNALFD => self.sys_signalfd(a0 as _, self.into_in_userptr(a1).unwrap(), a2),
+            Sys::EPOLL_WAIT => {
+                self.sys_epoll_wait(
+                    a0.into(),
+                    self.into_out_userptr(a1).unwrap(),
+                    a2 as _,
+                    a3 as _,
+                )
+                .await
+            }
+            // not supported yet, fail without killing the process
+            Sys::PAUSE
+            | Sys::GETDENTS
+            | Sys::UTIME
+            | Sys::MKNOD
+            | Sys::USELIB
+            | Sys::USTAT
+            | Sys::SYSFS
+            | Sys::MODIFY_LDT
+            | Sys::_SYSCTL
+            | Sys::IOPL
+            | Sys::IOPERM
+            | Sys::CREATE_MODULE
+            | Sys::GET_KERNEL_SYMS
+            | Sys::QUERY_MODULE
+            | Sys::GETPMSG
+            | Sys::PUTPMSG
+            | Sys::AFS_SYSCALL
+            | Sys::TUXCALL
+            | Sys::SECURITY
+            | Sys::SET_THREAD_AREA
+            | Sys::GET_THREAD_AREA
+            | Sys::EPOLL_CTL_OLD
+            | Sys::EPOLL_WAIT_OLD
+            | Sys::UTIMES
+            | Sys::VSERVER
+            | Sys::INOTIFY_INIT
+            | Sys::FUTIMESAT => self.unsupported_syscall(sys_type),
             _ => self.unknown_syscall(sys_type),
         }
     }
@@ -555,9 +867,18 @@ impl Syscall<'_> {
     #[cfg(target_arch = "riscv64")]
     async fn riscv64_syscall(&mut self, sys_type: Sys, args: [usize; 6]) -> SysResult {
         debug!("riscv64_syscall: {:?}, {:?}", sys_type, args);
-        //let [a0, a1, a2, a3, a4, _a5] = args;
+        let [a0, a1, a2, a3, _a4, _a5] = args;
         match sys_type {
-            //Sys::OPEN => self.sys_open(a0.into(), a1, a2),
+            Sys::FACCESSAT2 => {
+                self.sys_faccessat(a0.into(), self.into_in_userptr(a1).unwrap(), a2, a3)
+            }
+            Sys::RISCV_FLUSH_ICACHE => self.sys_riscv_flush_icache(a0, a1, a2),
+            // not supported yet, fail without killing the process
+            Sys::ARCH_SPECIFIC_SYSCALL
+            | Sys::CLOSE_RANGE
+            | Sys::OPENAT2
+            | Sys::PIDFD_GETFD
+            | Sys::PROCESS_MADVISE => self.unsupported_syscall(sys_type),
             _ => self.unknown_syscall(sys_type),
         }
     }
@@ -576,6 +897,12 @@ impl Syscall<'_> {
         ret
     }
 
+    /// known but unsupported syscalls, fail with `ENOSYS` so that the caller can fall back
+    fn unsupported_syscall(&self, sys_type: Sys) -> SysResult {
+        warn!("{:?}: unsupported", sys_type);
+        Err(LxError::ENOSYS)
+    }
+
     /// get zircon process
     fn zircon_process(&self) -> &Arc<Process> {
         self.thread.proc()
@@ -585,4 +912,34 @@ impl Syscall<'_> {
     fn linux_process(&self) -> &LinuxProcess {
         self.zircon_process().linux()
     }
+
+    /// Wait for `future` of a blocking syscall, which fails with `EINTR`
+    /// if a signal which is not blocked arrives first.
+    async fn interruptible<T>(&self, future: impl Future<Output = LxResult<T>>) -> LxResult<T> {
+        Interruptible {
+            thread: self.thread,
+            future: Box::pin(future),
+        }
+        .await
+    }
+}
+
+/// The future returned by [`Syscall::interruptible`].
+struct Interruptible<'a, F> {
+    thread: &'a CurrentThread,
+    future: Pin<Box<F>>,
+}
+
+impl<T, F: Future<Output = LxResult<T>>> Future for Interruptible<'_, F> {
+    type Output = LxResult<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if let Poll::Ready(ret) = self.future.as_mut().poll(cx) {
+            return Poll::Ready(ret);
+        }
+        if self.thread.signal_interrupted(cx) {
+            return Poll::Ready(Err(LxError::EINTR));
+        }
+        Poll::Pending
+    }
 }
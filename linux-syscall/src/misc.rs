@@ -1,6 +1,17 @@
 use super::*;
 use bitflags::bitflags;
+use core::sync::atomic::Ordering;
+use core::time::Duration;
+use linux_object::error::LxResult;
+use linux_object::futex::{
+    futex_wake_op, futex_word, get_shared_futex, RobustListHead, FUTEX_BITSET_MATCH_ANY,
+};
+use linux_object::thread::ThreadExt;
 use linux_object::time::*;
+use zircon_object::signal::Futex;
+use zircon_object::task::ThreadState;
+use zircon_object::vm::PAGE_SIZE;
+use zircon_object::ZxResult;
 
 impl Syscall<'_> {
     #[cfg(target_arch = "x86_64")]
@@ -20,6 +31,24 @@ impl Syscall<'_> {
         }
     }
 
+    #[cfg(target_arch = "riscv64")]
+    /// synchronize the instruction cache with the stores to instruction memory
+    ///
+    /// The range `[start, end)` is ignored and the caches of all harts are flushed,
+    /// which also satisfies `SYS_RISCV_FLUSH_ICACHE_LOCAL`.
+    pub fn sys_riscv_flush_icache(&self, start: usize, end: usize, flags: usize) -> SysResult {
+        const SYS_RISCV_FLUSH_ICACHE_LOCAL: usize = 1;
+        info!(
+            "riscv_flush_icache: start={:#x}, end={:#x}, flags={:#x}",
+            start, end, flags
+        );
+        if flags & !SYS_RISCV_FLUSH_ICACHE_LOCAL != 0 {
+            return Err(LxError::EINVAL);
+        }
+        kernel_hal::mem::icache_flush_all();
+        Ok(0)
+    }
+
     /// get name and information about current kernel
     pub fn sys_uname(&self, buf: UserOutPtr<u8>) -> SysResult {
         info!("uname: buf={:?}", buf);
@@ -57,8 +86,20 @@ impl Syscall<'_> {
     }
 
     /// provides a simple way of getting overall system statistics
+    ///
+    /// Memory sizes are counted from the physical frame allocator,
+    /// load averages are not tracked and there is no swap.
     pub fn sys_sysinfo(&mut self, mut sys_info: UserOutPtr<SysInfo>) -> SysResult {
-        let sysinfo = SysInfo::default();
+        info!("sysinfo: sys_info: {:?}", sys_info);
+        let (total_frames, free_frames) = kernel_hal::mem::frame_stats();
+        let sysinfo = SysInfo {
+            uptime: kernel_hal::timer::timer_now().as_secs(),
+            totalram: (total_frames * PAGE_SIZE) as u64,
+            freeram: (free_frames * PAGE_SIZE) as u64,
+            procs: self.zircon_process().job().process_ids().len() as u16,
+            mem_unit: 1,
+            ..Default::default()
+        };
         sys_info.write(sysinfo)?;
         Ok(0)
     }
@@ -67,55 +108,151 @@ impl Syscall<'_> {
     /// - `uaddr` - points to the futex word.
     /// - `op` -  the operation to perform on the futex
     /// - `val` -  a value whose meaning and purpose depends on op
-    /// - `timeout` - not support now
-    /// TODO: support timeout
+    /// - `val2` - the timeout of wait operations, or the number of waiters to requeue or wake
+    /// - `uaddr2` - points to the second futex word of requeue and wake_op operations
+    /// - `val3` - the expected value, the bitset or the encoded operation depending on op
     pub async fn sys_futex(
         &self,
         uaddr: usize,
         op: u32,
         val: i32,
-        timeout_addr: usize,
+        val2: usize,
+        uaddr2: usize,
+        val3: u32,
     ) -> SysResult {
-        let op = FutexFlags::from_bits_truncate(op);
-        let timeout = if op.contains(FutexFlags::WAKE) {
-            self.into_inout_userptr::<TimeSpec>(0).unwrap()
-        } else {
-            let timeout_result = self.into_inout_userptr::<TimeSpec>(timeout_addr);
-            match timeout_result {
-                Ok(t) => t,
-                Err(_e) => return Err(LxError::EACCES),
-            }
-        };
+        let flags = FutexFlags::from_bits_truncate(op & !FUTEX_CMD_MASK);
+        let cmd = op & FUTEX_CMD_MASK;
         info!(
-            "futex: uaddr: {:#x}, op: {:?}, val: {}, timeout_ptr: {:?}",
-            uaddr, op, val, timeout
+            "futex: uaddr: {:#x}, op: {:#x}, val: {}, val2: {:#x}, uaddr2: {:#x}, val3: {:#x}",
+            uaddr, op, val, val2, uaddr2, val3
         );
-
-        if op.contains(FutexFlags::PRIVATE) {
-            warn!("process-shared futex is unimplemented");
-        }
-        let futex = self.linux_process().get_futex(uaddr);
-        match op.bits & 0xf {
-            0 => {
-                // FIXME: support timeout
-                let _timeout = timeout.read_if_not_null()?;
-                match futex.wait(val).await {
-                    Ok(_) => Ok(0),
-                    Err(ZxError::BAD_STATE) => Err(LxError::EAGAIN),
+        let get_futex = |uaddr: usize| -> LxResult<Arc<Futex>> {
+            if flags.contains(FutexFlags::PRIVATE) {
+                futex_word(uaddr)?;
+                Ok(self.linux_process().get_futex(uaddr))
+            } else {
+                get_shared_futex(&self.zircon_process().vmar(), uaddr)
+            }
+        };
+        match cmd {
+            FUTEX_WAIT | FUTEX_WAIT_BITSET => {
+                let bitset = if cmd == FUTEX_WAIT {
+                    FUTEX_BITSET_MATCH_ANY
+                } else {
+                    val3
+                };
+                if bitset == 0 {
+                    return Err(LxError::EINVAL);
+                }
+                let timeout = self
+                    .into_in_userptr::<TimeSpec>(val2)
+                    .map_err(|_| LxError::EFAULT)?;
+                let deadline = match timeout.read_if_not_null()? {
+                    // the timeout of `FUTEX_WAIT` is relative
+                    Some(timeout) if cmd == FUTEX_WAIT => {
+                        kernel_hal::timer::deadline_after(timeout.into())
+                    }
+                    // and the one of `FUTEX_WAIT_BITSET` is absolute,
+                    // all clocks are the same here
+                    Some(timeout) => timeout.into(),
+                    None => Duration::MAX,
+                };
+                let futex = get_futex(uaddr)?;
+                let future = futex.wait_bitset(futex_word(uaddr)?, val, bitset);
+                let future = Box::pin(self.interruptible(async {
+                    match future.await {
+                        Ok(_) => Ok(0),
+                        Err(ZxError::BAD_STATE) => Err(LxError::EAGAIN),
+                        Err(e) => Err(e.into()),
+                    }
+                }));
+                let ret: ZxResult<SysResult> = self
+                    .thread
+                    .blocking_run(future, ThreadState::BlockedFutex, deadline, None)
+                    .await;
+                match ret {
+                    Ok(ret) => ret,
+                    Err(ZxError::TIMED_OUT) => Err(LxError::ETIMEDOUT),
                     Err(e) => Err(e.into()),
                 }
             }
-            1 => {
-                let woken_up_count = futex.wake(val as usize);
-                Ok(woken_up_count)
+            FUTEX_WAKE | FUTEX_WAKE_BITSET => {
+                let bitset = if cmd == FUTEX_WAKE {
+                    FUTEX_BITSET_MATCH_ANY
+                } else {
+                    val3
+                };
+                if bitset == 0 {
+                    return Err(LxError::EINVAL);
+                }
+                let futex = get_futex(uaddr)?;
+                Ok(futex.wake_bitset(val as usize, bitset))
+            }
+            FUTEX_REQUEUE | FUTEX_CMP_REQUEUE => {
+                if val < 0 || (val2 as i32) < 0 {
+                    return Err(LxError::EINVAL);
+                }
+                let futex = get_futex(uaddr)?;
+                let requeue_futex = get_futex(uaddr2)?;
+                if cmd == FUTEX_CMP_REQUEUE
+                    && futex_word(uaddr)?.load(Ordering::SeqCst) != val3 as i32
+                {
+                    return Err(LxError::EAGAIN);
+                }
+                let (woken, requeued) =
+                    futex.wake_requeue(val as usize, val2 as u32 as usize, &requeue_futex);
+                Ok(woken + requeued)
+            }
+            FUTEX_WAKE_OP => {
+                let futex = get_futex(uaddr)?;
+                let futex2 = get_futex(uaddr2)?;
+                let cmp = futex_wake_op(uaddr2, val3)?;
+                let mut woken = futex.wake(val as usize);
+                if cmp {
+                    woken += futex2.wake(val2 as u32 as usize);
+                }
+                Ok(woken)
             }
             _ => {
-                warn!("unsupported futex operation: {:?}", op);
+                warn!("unsupported futex operation: {:#x}", op);
                 Err(LxError::ENOSYS)
             }
         }
     }
 
+    /// Register the robust futex list of the calling thread, which is released
+    /// when the thread exits.
+    pub fn sys_set_robust_list(&self, head: UserInPtr<RobustListHead>, len: usize) -> SysResult {
+        info!("set_robust_list: head: {:?}, len: {}", head, len);
+        if len != core::mem::size_of::<RobustListHead>() {
+            return Err(LxError::EINVAL);
+        }
+        self.thread.lock_linux().robust_list = head;
+        Ok(0)
+    }
+
+    /// Get the robust futex list of the thread `pid`, or the calling thread if `pid` is 0.
+    pub fn sys_get_robust_list(
+        &self,
+        pid: usize,
+        mut head_ptr: UserOutPtr<usize>,
+        mut len_ptr: UserOutPtr<usize>,
+    ) -> SysResult {
+        info!(
+            "get_robust_list: pid: {}, head_ptr: {:?}, len_ptr: {:?}",
+            pid, head_ptr, len_ptr
+        );
+        let thread = if pid == 0 {
+            self.thread.inner()
+        } else {
+            Self::find_thread(self.zircon_process(), pid as KoID)?
+        };
+        let head = thread.lock_linux().robust_list.as_addr();
+        head_ptr.write(head)?;
+        len_ptr.write(core::mem::size_of::<RobustListHead>())?;
+        Ok(0)
+    }
+
     /// Combines and extends the functionality of setrlimit() and getrlimit()
     pub fn sys_prlimit64(
         &mut self,
@@ -173,17 +310,32 @@ impl Syscall<'_> {
 bitflags! {
     /// for op argument in futex()
     struct FutexFlags: u32 {
-        /// tests that the value at the futex word pointed
-        /// to by the address uaddr still contains the expected value val,
-        /// and if so, then sleeps waiting for a FUTEX_WAKE operation on the futex word.
-        const WAIT      = 0;
-        /// wakes at most val of the waiters that are waiting on the futex word at the address uaddr.
-        const WAKE      = 1;
         /// can be employed with all futex operations, tells the kernel that the futex is process-private and not shared with another process
         const PRIVATE   = 0x80;
+        /// the timeout of `FUTEX_WAIT_BITSET` is measured against `CLOCK_REALTIME`
+        const CLOCK_REALTIME = 0x100;
     }
 }
 
+/// mask of the futex operation in the op argument
+const FUTEX_CMD_MASK: u32 = 0x7f;
+/// tests that the value at the futex word pointed
+/// to by the address uaddr still contains the expected value val,
+/// and if so, then sleeps waiting for a FUTEX_WAKE operation on the futex word.
+const FUTEX_WAIT: u32 = 0;
+/// wakes at most val of the waiters that are waiting on the futex word at the address uaddr.
+const FUTEX_WAKE: u32 = 1;
+/// wakes at most val waiters, and requeues the others to the futex at uaddr2.
+const FUTEX_REQUEUE: u32 = 3;
+/// same as `FUTEX_REQUEUE`, but checks the value at uaddr first.
+const FUTEX_CMP_REQUEUE: u32 = 4;
+/// modifies the value at uaddr2, and wakes waiters on both futexes.
+const FUTEX_WAKE_OP: u32 = 5;
+/// same as `FUTEX_WAIT`, but only woken by wakes with a bitset intersecting val3.
+const FUTEX_WAIT_BITSET: u32 = 9;
+/// same as `FUTEX_WAKE`, but only wakes waiters with a bitset intersecting val3.
+const FUTEX_WAKE_BITSET: u32 = 10;
+
 const USER_STACK_SIZE: usize = 8 * 1024 * 1024; // 8 MB, the default config of Linux
 
 const RLIMIT_STACK: usize = 3;
@@ -1,13 +1,29 @@
 use super::*;
 
+use alloc::{string::String, vec::Vec};
+use core::mem::size_of;
+
+use linux_object::cred::Access;
+use linux_object::error::LxResult;
+use linux_object::fs::{split_path, vfs::FileType};
 use linux_object::net::sockaddr_to_endpoint;
-use linux_object::net::SockAddr;
-use linux_object::net::Socket;
 use linux_object::net::TcpSocketState;
 use linux_object::net::UdpSocketState;
+use linux_object::net::{
+    parse_control, ControlBuffer, ControlMessage, MsgFlags, MsgHdr, PassedFile,
+};
+use linux_object::net::{Endpoint, UCred, UnixEndpoint, UnixSocketState, UnixSocketType};
+use linux_object::net::{SockAddr, Socket, SCM_CREDENTIALS, SCM_RIGHTS, SOL_SOCKET};
 
 use spin::Mutex;
 
+/// Mask of the socket type, without the flags
+const SOCK_TYPE_MASK: usize = 0xf;
+/// Flag of the socket type to create a non-blocking socket
+const SOCK_NONBLOCK: usize = 0o4000;
+/// Flag of the socket type to set `FD_CLOEXEC` on the new descriptor
+const SOCK_CLOEXEC: usize = 0o2000000;
+
 impl Syscall<'_> {
     /// net socket
     pub fn sys_socket(&mut self, domain: usize, socket_type: usize, protocol: usize) -> SysResult {
@@ -16,12 +32,26 @@ impl Syscall<'_> {
             domain, socket_type, protocol
         );
         let proc = self.linux_process();
+        let nonblock = socket_type & SOCK_NONBLOCK != 0;
+        let cloexec = socket_type & SOCK_CLOEXEC != 0;
+        let socket_type = socket_type & SOCK_TYPE_MASK;
         let socket: Arc<Mutex<dyn Socket>> = match domain {
             //     musl
             //     domain local 1
             //     domain inet  2
             //     domain inet6 10
-            2 | 1 => match socket_type {
+            1 => {
+                if protocol != 0 {
+                    return Err(LxError::EPROTONOSUPPORT);
+                }
+                let type_ = UnixSocketType::try_from(socket_type)?;
+                Arc::new(Mutex::new(UnixSocketState::new(
+                    type_,
+                    self.ucred(),
+                    nonblock,
+                )))
+            }
+            2 => match socket_type {
                 //         musl socket type
                 //              1 STREAM
                 //              2 DGRAM
@@ -42,10 +72,96 @@ impl Syscall<'_> {
             _ => return Err(LxError::EAFNOSUPPORT),
         };
         // socket
-        let fd = proc.add_socket(socket)?;
+        let fd = proc.add_socket(socket, cloexec)?;
         Ok(fd.into())
     }
 
+    /// Create a pair of connected unix sockets.
+    pub fn sys_socketpair(
+        &mut self,
+        domain: usize,
+        socket_type: usize,
+        protocol: usize,
+        mut sv: UserOutPtr<[i32; 2]>,
+    ) -> SysResult {
+        info!(
+            "sys_socketpair: domain={}, socket_type={:#x}, protocol={}, sv={:?}",
+            domain, socket_type, protocol, sv
+        );
+        if domain != 1 {
+            return Err(LxError::EOPNOTSUPP);
+        }
+        if protocol != 0 {
+            return Err(LxError::EPROTONOSUPPORT);
+        }
+        let nonblock = socket_type & SOCK_NONBLOCK != 0;
+        let cloexec = socket_type & SOCK_CLOEXEC != 0;
+        let type_ = UnixSocketType::try_from(socket_type & SOCK_TYPE_MASK)?;
+        let (socket0, socket1) = UnixSocketState::pair(type_, self.ucred(), nonblock);
+        let proc = self.linux_process();
+        let fd0: usize = proc
+            .add_socket(Arc::new(Mutex::new(socket0)), cloexec)?
+            .into();
+        let fd1: usize = match proc.add_socket(Arc::new(Mutex::new(socket1)), cloexec) {
+            Ok(fd) => fd.into(),
+            Err(err) => {
+                proc.close_socket(fd0.into())?;
+                return Err(err);
+            }
+        };
+        sv.write([fd0 as i32, fd1 as i32])?;
+        Ok(0)
+    }
+
+    /// Credentials of the calling process, attached to messages of unix sockets.
+    fn ucred(&self) -> UCred {
+        let proc = self.linux_process();
+        let cred = proc.cred();
+        UCred {
+            pid: proc.pid() as i32,
+            uid: cred.uid.effective,
+            gid: cred.gid.effective,
+        }
+    }
+
+    /// Check the credentials given by `SCM_CREDENTIALS`,
+    /// which must be the caller's own unless it is privileged.
+    fn check_ucred(&self, ucred: &UCred) -> LxResult {
+        let proc = self.linux_process();
+        let cred = proc.cred();
+        let uid = &cred.uid;
+        let gid = &cred.gid;
+        if cred.privileged()
+            || (ucred.pid == proc.pid() as i32
+                && [uid.real, uid.effective, uid.saved].contains(&ucred.uid)
+                && [gid.real, gid.effective, gid.saved].contains(&ucred.gid))
+        {
+            Ok(())
+        } else {
+            Err(LxError::EPERM)
+        }
+    }
+
+    /// Resolve a unix socket path to connect or send to.
+    ///
+    /// The path must be a socket file, and becomes absolute to name the socket.
+    fn resolve_unix_endpoint(&self, endpoint: Endpoint) -> LxResult<Endpoint> {
+        match endpoint {
+            Endpoint::Unix(UnixEndpoint::Path(path)) => {
+                let proc = self.linux_process();
+                let inode = proc.lookup_inode(&path)?;
+                if inode.metadata()?.type_ != FileType::Socket {
+                    return Err(LxError::ECONNREFUSED);
+                }
+                proc.check_access(&inode, Access::WRITE)?;
+                Ok(Endpoint::Unix(UnixEndpoint::Path(
+                    proc.absolute_path(&path),
+                )))
+            }
+            endpoint => Ok(endpoint),
+        }
+    }
+
     /// net sys_connect
     pub async fn sys_connect(
         &mut self,
@@ -61,10 +177,10 @@ impl Syscall<'_> {
         let mut _proc = self.linux_process();
         let sa: SockAddr = addr.read()?;
 
-        let endpoint = sockaddr_to_endpoint(sa, addr_len)?;
+        let endpoint = self.resolve_unix_endpoint(sockaddr_to_endpoint(sa, addr_len)?)?;
         let socket = _proc.get_socket(fd.into())?;
-        let x = socket.lock();
-        x.connect(endpoint).await?;
+        self.interruptible(async { socket.lock().connect(endpoint).await })
+            .await?;
         Ok(0)
     }
 
@@ -93,18 +209,29 @@ impl Syscall<'_> {
         sockfd: usize,
         level: usize,
         optname: usize,
-        optval: UserOutPtr<u8>,
-        optlen: usize,
+        mut optval: UserOutPtr<u8>,
+        mut optlen: UserInOutPtr<u32>,
     ) -> SysResult {
         warn!(
             "sys_getsockopt : sockfd : {:?}, level : {:?}, optname : {:?}, optval : {:?} , optlen : {:?}",
             sockfd, level, optname,optval,optlen
         );
+        let value = self
+            .linux_process()
+            .get_socket(sockfd.into())?
+            .lock()
+            .getsockopt(level, optname)?;
+        // unknown options are left untouched
+        if !value.is_empty() {
+            let len = value.len().min(optlen.read()? as usize);
+            optval.write_array(&value[..len])?;
+            optlen.write(len as u32)?;
+        }
         Ok(0)
     }
 
     /// net setsockopt
-    pub fn sys_sendto(
+    pub async fn sys_sendto(
         &mut self,
         sockfd: usize,
         buffer: UserInPtr<u8>,
@@ -120,14 +247,16 @@ impl Syscall<'_> {
         let endpoint = if dest_addr.is_null() {
             None
         } else {
-            let _sa: SockAddr = dest_addr.read()?;
             let endpoint = sockaddr_to_endpoint(dest_addr.read()?, addrlen)?;
-            Some(endpoint)
+            Some(self.resolve_unix_endpoint(endpoint)?)
         };
+        let flags = MsgFlags::from_bits_truncate(flags);
+        let control = vec![ControlMessage::Credentials(self.ucred())];
         let proc = self.linux_process();
         let socket = proc.get_socket(sockfd.into())?;
-        let len = socket.lock().write(buffer.as_slice(length)?, endpoint)?;
-        Ok(len)
+        let buf = buffer.as_slice(length)?;
+        self.interruptible(async { socket.lock().sendmsg(buf, endpoint, control, flags).await })
+            .await
     }
 
     /// net setsockopt
@@ -144,17 +273,192 @@ impl Syscall<'_> {
             "sys_recvfrom : sockfd : {:?}, buffer : {:?}, length : {:?}, flags : {:?} , optlen : {:?}, addr_len : {:?}",
             sockfd, buffer, length,flags,addr,addr_len
         );
+        let flags = MsgFlags::from_bits_truncate(flags);
         let proc = self.linux_process();
         let mut data = vec![0u8; length];
         let socket = proc.get_socket(sockfd.into())?;
-        let x = socket.lock();
-        let (result, endpoint) = x.read(&mut data).await;
-        if result.is_ok() && !addr.is_null() {
-            let sockaddr_in = SockAddr::from(endpoint);
-            sockaddr_in.write_to(addr, addr_len)?;
+        let msg = self
+            .interruptible(async { socket.lock().recvmsg(&mut data, flags).await })
+            .await?;
+        if let Some(endpoint) = msg.endpoint {
+            if !addr.is_null() {
+                SockAddr::from(endpoint).write_to(addr, addr_len)?;
+            }
         }
-        buffer.write_array(&data[..length])?;
-        result
+        let copied = msg.len.min(length);
+        buffer.write_array(&data[..copied])?;
+        Ok(if flags.contains(MsgFlags::TRUNC) {
+            msg.len
+        } else {
+            copied
+        })
+    }
+
+    /// Send a message with scatter iovecs and control messages on a socket.
+    pub async fn sys_sendmsg(
+        &mut self,
+        sockfd: usize,
+        msg: UserInPtr<MsgHdr>,
+        flags: usize,
+    ) -> SysResult {
+        info!(
+            "sys_sendmsg: sockfd={:?} msg={:?} flags={:#x}",
+            sockfd, msg, flags
+        );
+        let hdr = msg.read()?;
+        let flags = MsgFlags::from_bits_truncate(flags);
+        let proc = self.linux_process();
+        let endpoint = if hdr.name == 0 {
+            None
+        } else {
+            let addr: UserInPtr<SockAddr> = hdr.name.into();
+            let endpoint = sockaddr_to_endpoint(addr.read()?, hdr.namelen as usize)?;
+            Some(self.resolve_unix_endpoint(endpoint)?)
+        };
+        let data = if hdr.iovlen == 0 {
+            Vec::new()
+        } else {
+            let iov: UserInPtr<IoVecIn> = hdr.iov.into();
+            iov.read_iovecs(hdr.iovlen)?.read_to_vec()?
+        };
+        let mut control = Vec::new();
+        let mut credentials = None;
+        if hdr.controllen != 0 {
+            let buf: UserInPtr<u8> = hdr.control.into();
+            for cmsg in parse_control(buf.as_slice(hdr.controllen)?)? {
+                match (cmsg.level, cmsg.type_) {
+                    (SOL_SOCKET, SCM_RIGHTS) => {
+                        let mut files = Vec::new();
+                        for fd in cmsg.data.chunks_exact(size_of::<i32>()) {
+                            let fd = i32::from_ne_bytes([fd[0], fd[1], fd[2], fd[3]]);
+                            let file = match proc.get_file_like(fd.into()) {
+                                Ok(file) => PassedFile::File(file),
+                                Err(_) => {
+                                    PassedFile::Socket(proc.get_socket((fd as usize).into())?)
+                                }
+                            };
+                            files.push(file);
+                        }
+                        control.push(ControlMessage::Rights(files));
+                    }
+                    (SOL_SOCKET, SCM_CREDENTIALS) => {
+                        let ucred = UCred::from_bytes(cmsg.data)?;
+                        self.check_ucred(&ucred)?;
+                        credentials = Some(ucred);
+                    }
+                    _ => return Err(LxError::EINVAL),
+                }
+            }
+        }
+        control.push(ControlMessage::Credentials(
+            credentials.unwrap_or_else(|| self.ucred()),
+        ));
+        let socket = proc.get_socket(sockfd.into())?;
+        self.interruptible(async { socket.lock().sendmsg(&data, endpoint, control, flags).await })
+            .await
+    }
+
+    /// Receive a message into gather iovecs, with its address and control messages.
+    pub async fn sys_recvmsg(
+        &mut self,
+        sockfd: usize,
+        mut msg: UserInOutPtr<MsgHdr>,
+        flags: usize,
+    ) -> SysResult {
+        info!(
+            "sys_recvmsg: sockfd={:?} msg={:?} flags={:#x}",
+            sockfd, msg, flags
+        );
+        let mut hdr = msg.read()?;
+        let flags = MsgFlags::from_bits_truncate(flags);
+        let proc = self.linux_process();
+        let mut iovs = if hdr.iovlen == 0 {
+            None
+        } else {
+            let iov: UserInPtr<IoVecOut> = hdr.iov.into();
+            Some(iov.read_iovecs(hdr.iovlen)?)
+        };
+        let mut data = vec![0u8; iovs.as_ref().map_or(0, |iovs| iovs.total_len())];
+        let socket = proc.get_socket(sockfd.into())?;
+        let received = self
+            .interruptible(async { socket.lock().recvmsg(&mut data, flags).await })
+            .await?;
+        let copied = received.len.min(data.len());
+        if let Some(iovs) = iovs.as_mut() {
+            iovs.write_from_buf(&data[..copied])?;
+        }
+
+        let mut msg_flags = MsgFlags::empty();
+        if received.len > copied {
+            msg_flags.insert(MsgFlags::TRUNC);
+        }
+        match received.endpoint {
+            Some(endpoint) if hdr.name != 0 => {
+                let len = SockAddr::from(endpoint)
+                    .write_to_limited(hdr.name.into(), hdr.namelen as usize)?;
+                hdr.namelen = len as u32;
+            }
+            _ => hdr.namelen = 0,
+        }
+
+        let mut control = ControlBuffer::new(if hdr.control == 0 { 0 } else { hdr.controllen });
+        for cmsg in received.control {
+            match cmsg {
+                ControlMessage::Rights(files) => {
+                    // files which do not fit in the buffer are closed
+                    let count = control.fit(files.len(), size_of::<i32>());
+                    if count < files.len() {
+                        msg_flags.insert(MsgFlags::CTRUNC);
+                    }
+                    let cloexec = flags.contains(MsgFlags::CMSG_CLOEXEC);
+                    let mut installed = Vec::new();
+                    for file in files.into_iter().take(count) {
+                        let fd = match file {
+                            PassedFile::File(file) => proc.install_file(file, cloexec),
+                            PassedFile::Socket(socket) => proc
+                                .add_socket(socket, cloexec)
+                                .map(|handle| usize::from(handle).into()),
+                        };
+                        match fd {
+                            Ok(fd) => installed.push(fd),
+                            Err(err) => {
+                                for fd in installed {
+                                    self.sys_close(fd)?;
+                                }
+                                return Err(err);
+                            }
+                        }
+                    }
+                    let mut fds = Vec::new();
+                    for fd in installed {
+                        let fd: i32 = fd.into();
+                        fds.extend_from_slice(&fd.to_ne_bytes());
+                    }
+                    if count > 0 {
+                        control.push(SOL_SOCKET, SCM_RIGHTS, &fds);
+                    }
+                }
+                ControlMessage::Credentials(ucred) => {
+                    control.push(SOL_SOCKET, SCM_CREDENTIALS, ucred.as_bytes());
+                }
+            }
+        }
+        let (buf, truncated) = control.into_inner();
+        if truncated {
+            msg_flags.insert(MsgFlags::CTRUNC);
+        }
+        if !buf.is_empty() {
+            let mut ptr: UserOutPtr<u8> = hdr.control.into();
+            ptr.write_array(&buf)?;
+        }
+        hdr.controllen = buf.len();
+        hdr.flags = msg_flags.bits() as i32;
+        msg.write(hdr)?;
+        Ok(if flags.contains(MsgFlags::TRUNC) {
+            received.len
+        } else {
+            copied
+        })
     }
 
     /// net bind
@@ -162,12 +466,31 @@ impl Syscall<'_> {
         info!("sys_bind: fd={:?} addr={:?} len={}", fd, addr, addr_len);
         let proc = self.linux_process();
         let sa: SockAddr = addr.read()?;
-        let endpoint = sockaddr_to_endpoint(sa, addr_len)?;
+        let mut endpoint = sockaddr_to_endpoint(sa, addr_len)?;
         info!("sys_bind: fd={:?} bind to {:?}", fd, endpoint);
 
         let socket = proc.get_socket(fd.into())?;
+        // a unix socket bound to a path creates the socket file
+        let mut socket_file = None;
+        if let Endpoint::Unix(UnixEndpoint::Path(path)) = endpoint.clone() {
+            let (dir_path, file_name) = split_path(&path);
+            let dir = proc.lookup_inode_at(FileDesc::CWD, dir_path, true)?;
+            if dir.find(file_name).is_ok() {
+                return Err(LxError::EADDRINUSE);
+            }
+            proc.check_access(&dir, Access::WRITE | Access::EXEC)?;
+            proc.mounts().check_writable(&dir)?;
+            let inode = dir.create(file_name, FileType::Socket, 0o777)?;
+            proc.set_owner(&inode);
+            endpoint = Endpoint::Unix(UnixEndpoint::Path(proc.absolute_path(&path)));
+            socket_file = Some((dir, String::from(file_name)));
+        }
         let mut x = socket.lock();
-        x.bind(endpoint)
+        let result = x.bind(endpoint);
+        if let (Err(_), Some((dir, file_name))) = (&result, socket_file) {
+            dir.unlink(&file_name)?;
+        }
+        result
     }
 
     /// net listen
@@ -198,18 +521,36 @@ impl Syscall<'_> {
         fd: usize,
         addr: UserOutPtr<SockAddr>,
         addr_len: UserInOutPtr<u32>,
+    ) -> SysResult {
+        self.sys_accept4(fd, addr, addr_len, 0).await
+    }
+
+    /// net accept, with `SOCK_CLOEXEC` allowed in `flags`
+    ///
+    /// `SOCK_NONBLOCK` is accepted but ignored.
+    pub async fn sys_accept4(
+        &mut self,
+        fd: usize,
+        addr: UserOutPtr<SockAddr>,
+        addr_len: UserInOutPtr<u32>,
+        flags: usize,
     ) -> SysResult {
         warn!(
-            "sys_accept: fd={:?} addr={:?} addr_len={:?}",
-            fd, addr, addr_len
+            "sys_accept4: fd={:?} addr={:?} addr_len={:?} flags={:#x}",
+            fd, addr, addr_len, flags
         );
+        if flags & !(SOCK_NONBLOCK | SOCK_CLOEXEC) != 0 {
+            return Err(LxError::EINVAL);
+        }
         // smoltcp tcp sockets do not support backlog
         // open multiple sockets for each connection
         let proc = self.linux_process();
 
         let socket = proc.get_socket(fd.into())?;
-        let (new_socket, remote_endpoint) = socket.lock().accept().await?;
-        let new_fd = proc.add_socket(new_socket)?;
+        let (new_socket, remote_endpoint) = self
+            .interruptible(async { socket.lock().accept().await })
+            .await?;
+        let new_fd = proc.add_socket(new_socket, flags & SOCK_CLOEXEC != 0)?;
 
         if !addr.is_null() {
             let sockaddr_in = SockAddr::from(remote_endpoint);
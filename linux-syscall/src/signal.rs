@@ -3,12 +3,22 @@
 //! - rt_sigaction
 //! - rt_sigreturn
 //! - rt_sigprocmask
+//! - rt_sigpending
+//! - rt_sigqueueinfo
 //! - kill
 //! - tkill
+//! - tgkill
 //! - sigaltstack
 
 use super::*;
-use linux_object::signal::{Signal, SignalAction, SignalStack, SignalStackFlags, Sigset};
+use alloc::vec::Vec;
+use kernel_hal::context::UserContextField;
+use linux_object::error::LxResult;
+use linux_object::process::process_group;
+use linux_object::signal::{
+    SigInfo, Signal, SignalAction, SignalCode, SignalFrame, SignalStack, SignalStackFlags,
+    SignalUserContext, Sigset,
+};
 use linux_object::thread::ThreadExt;
 use numeric_enum_macro::numeric_enum;
 
@@ -70,7 +80,10 @@ impl Syscall<'_> {
         if set.is_null() {
             return Ok(0);
         }
-        let set = set.read()?;
+        let mut set = set.read()?;
+        // SIGKILL and SIGSTOP cannot be blocked
+        set.remove(Signal::SIGKILL);
+        set.remove(Signal::SIGSTOP);
         let mut thread = self.thread.lock_linux();
         match how {
             How::Block => thread.signal_mask.insert_set(&set),
@@ -80,6 +93,18 @@ impl Syscall<'_> {
         Ok(0)
     }
 
+    /// Returns the set of signals that are pending for delivery to the calling thread
+    pub fn sys_rt_sigpending(&self, mut set: UserOutPtr<Sigset>, sigsetsize: usize) -> SysResult {
+        info!("rt_sigpending: set={:?}, sigsetsize={}", set, sigsetsize);
+        if sigsetsize != core::mem::size_of::<Sigset>() {
+            return Err(LxError::EINVAL);
+        }
+        let mut pending = self.linux_process().pending_signals();
+        pending.insert_set(&self.thread.lock_linux().signal_queue.pending());
+        set.write(pending)?;
+        Ok(0)
+    }
+
     /// Allows a process to define a new alternate signal stack
     /// and/or retrieve the state of an existing alternate signal stack
     pub fn sys_sigaltstack(
@@ -96,7 +121,7 @@ impl Syscall<'_> {
         let ss = ss.read()?;
         // check stack size when not disable
         const MIN_SIGSTACK_SIZE: usize = 2048;
-        if ss.flags.contains(SignalStackFlags::DISABLE) && ss.size < MIN_SIGSTACK_SIZE {
+        if !ss.flags.contains(SignalStackFlags::DISABLE) && ss.size < MIN_SIGSTACK_SIZE {
             return Err(LxError::ENOMEM);
         }
         // only allow SS_AUTODISARM and SS_DISABLE
@@ -112,4 +137,165 @@ impl Syscall<'_> {
         *old_ss = ss;
         Ok(0)
     }
+
+    /// Return from signal handler and restore the context saved on the signal frame.
+    pub fn sys_rt_sigreturn(&self) -> SysResult {
+        let sp = self
+            .thread
+            .with_context(|ctx| ctx.get_field(UserContextField::StackPointer))?;
+        let frame_addr = sp - SignalFrame::SIGRETURN_SP_OFFSET;
+        info!("rt_sigreturn: frame={:#x}", frame_addr);
+        let ucontext_ptr: UserInPtr<SignalUserContext> =
+            (frame_addr + SignalFrame::UCONTEXT_OFFSET).into();
+        let ucontext = match ucontext_ptr.read() {
+            Ok(ucontext) => ucontext,
+            Err(_) => {
+                // bad frame, force SIGSEGV
+                self.thread
+                    .force_signal(SigInfo::new(Signal::SIGSEGV, SignalCode::KERNEL));
+                return Err(LxError::EFAULT);
+            }
+        };
+        let mut thread = self.thread.lock_linux();
+        let mut mask = ucontext.sig_mask;
+        mask.remove(Signal::SIGKILL);
+        mask.remove(Signal::SIGSTOP);
+        thread.signal_mask = mask;
+        thread.signal_alternate_stack = ucontext.stack;
+        drop(thread);
+        // the return value register is set by the caller with our result
+        let ret = self.thread.with_context(|ctx| {
+            ucontext.context.restore_to(ctx);
+            ctx.get_field(UserContextField::ReturnValue)
+        })?;
+        Ok(ret)
+    }
+
+    /// Send a signal to a process or a group of processes.
+    ///
+    /// - `pid > 0`: send to the process `pid`
+    /// - `pid == 0`: send to the process group of the caller
+    /// - `pid == -1`: send to every process except the caller
+    /// - `pid < -1`: send to the process group `-pid`
+    ///
+    /// If `signum` is 0, only checks the existence of the target.
+    pub fn sys_kill(&self, pid: isize, signum: usize) -> SysResult {
+        info!("kill: pid={}, signum={}", pid, signum);
+        let signal = Self::parse_signal(signum)?;
+        let proc = self.zircon_process();
+        let targets: Vec<Arc<Process>> = match pid {
+            p if p > 0 => vec![proc.find_process(p as KoID)?],
+            0 => process_group(&proc.job(), proc.linux().pgid()),
+            -1 => proc
+                .job()
+                .process_ids()
+                .into_iter()
+                .filter(|&id| id != proc.id())
+                .filter_map(|id| proc.find_process(id).ok())
+                .collect(),
+            p => process_group(&proc.job(), -p as KoID),
+        };
+        if targets.is_empty() {
+            return Err(LxError::ESRCH);
+        }
+        if let Some(signal) = signal {
+            for target in targets {
+                target.send_signal(SigInfo::from_kill(
+                    signal,
+                    SignalCode::USER,
+                    proc.id() as i32,
+                    0,
+                ));
+            }
+        }
+        Ok(0)
+    }
+
+    /// Send a signal to a thread.
+    pub fn sys_tkill(&self, tid: usize, signum: usize) -> SysResult {
+        info!("tkill: tid={}, signum={}", tid, signum);
+        let signal = Self::parse_signal(signum)?;
+        let proc = self.zircon_process();
+        let thread = proc
+            .job()
+            .process_ids()
+            .into_iter()
+            .filter_map(|id| proc.find_process(id).ok())
+            .find_map(|p| Self::find_thread(&p, tid as KoID).ok())
+            .ok_or(LxError::ESRCH)?;
+        if let Some(signal) = signal {
+            thread.send_signal(SigInfo::from_kill(
+                signal,
+                SignalCode::TKILL,
+                proc.id() as i32,
+                0,
+            ));
+        }
+        Ok(0)
+    }
+
+    /// Send a signal to the thread `tid` in the thread group `tgid`.
+    pub fn sys_tgkill(&self, tgid: usize, tid: usize, signum: usize) -> SysResult {
+        info!("tgkill: tgid={}, tid={}, signum={}", tgid, tid, signum);
+        let signal = Self::parse_signal(signum)?;
+        let proc = self.zircon_process();
+        let target = proc.find_process(tgid as KoID)?;
+        let thread = Self::find_thread(&target, tid as KoID)?;
+        if let Some(signal) = signal {
+            thread.send_signal(SigInfo::from_kill(
+                signal,
+                SignalCode::TKILL,
+                proc.id() as i32,
+                0,
+            ));
+        }
+        Ok(0)
+    }
+
+    /// Queue a signal and data to the process `tgid`.
+    pub fn sys_rt_sigqueueinfo(
+        &self,
+        tgid: usize,
+        signum: usize,
+        info: UserInPtr<SigInfo>,
+    ) -> SysResult {
+        info!(
+            "rt_sigqueueinfo: tgid={}, signum={}, info={:?}",
+            tgid, signum, info
+        );
+        let signal = Self::parse_signal(signum)?;
+        let mut info = info.read()?;
+        let proc = self.zircon_process();
+        let target = proc.find_process(tgid as KoID)?;
+        // a process can not pretend to be the kernel or `kill` when sending to others
+        if (info.code >= 0 || info.code == SignalCode::TKILL as i32) && !Arc::ptr_eq(proc, &target)
+        {
+            return Err(LxError::EPERM);
+        }
+        if let Some(signal) = signal {
+            info.signo = signal as i32;
+            target.send_signal(info);
+        }
+        Ok(0)
+    }
+
+    /// Parse `signum` of `kill`-like syscalls, `0` means no signal is sent.
+    fn parse_signal(signum: usize) -> LxResult<Option<Signal>> {
+        if signum == 0 {
+            return Ok(None);
+        }
+        let signal = u8::try_from(signum)
+            .ok()
+            .and_then(|signum| Signal::try_from(signum).ok())
+            .ok_or(LxError::EINVAL)?;
+        Ok(Some(signal))
+    }
+
+    /// Find the thread `tid` in process `proc`.
+    pub(crate) fn find_thread(proc: &Process, tid: KoID) -> LxResult<Arc<Thread>> {
+        proc.get_child(tid)
+            .ok()
+            .and_then(|obj| obj.downcast_arc::<Thread>().ok())
+            .ok_or(LxError::ESRCH)
+    }
 }
@@ -2,12 +2,18 @@ use super::*;
 use core::fmt::Debug;
 
 use alloc::string::ToString;
-use bitflags::bitflags;
 
 use kernel_hal::context::UserContextField;
+use kernel_hal::PAGE_SIZE;
+use linux_object::cred::Access;
+use linux_object::error::LxResult;
+use linux_object::fs::vfs::FileType;
+use linux_object::process::{wait_child, CloneFlags, WaitOptions, WaitTarget};
+use linux_object::signal::{SigInfo, SiginfoFields, Signal as LinuxSignal};
 use linux_object::thread::{CurrentThreadExt, ThreadExt};
 // use linux_object::time::TimeSpec;
 use linux_object::{fs::INodeExt, loader::LinuxElfLoader};
+use zircon_object::vm::VmAddressRegion;
 
 /// Syscalls for process.
 ///
@@ -16,11 +22,18 @@ use linux_object::{fs::INodeExt, loader::LinuxElfLoader};
 /// - [`fork`](Self::sys_fork)
 /// - [`vfork`](Self::sys_vfork)
 /// - [`clone`](Self::sys_clone)
+/// - [`clone3`](Self::sys_clone3)
 /// - [`wait4`](Self::sys_wait4)
+/// - [`waitid`](Self::sys_waitid)
 /// - [`execve`](Self::sys_execve)
 /// - [`gettid`](Self::sys_gettid)
 /// - [`getpid`](Self::sys_getpid)
 /// - [`getppid`](Self::sys_getppid)
+/// - [`setpgid`](Self::sys_setpgid)
+/// - [`getpgid`](Self::sys_getpgid)
+/// - [`getpgrp`](Self::sys_getpgrp)
+/// - [`setsid`](Self::sys_setsid)
+/// - [`getsid`](Self::sys_getsid)
 /// - [`exit`](Self::sys_exit)
 /// - [`exit_group`](Self::sys_exit_group)
 /// - [`nanosleep`](Self::sys_nanosleep)
@@ -59,8 +72,10 @@ impl Syscall<'_> {
     ///   This means that the two file descriptors share open file status flags and file offset.
     pub fn sys_fork(&self) -> SysResult {
         info!("fork:");
-        let new_proc = Process::fork_from(self.zircon_process(), false)?; // old pt NULL here
+        let flags = CloneFlags::from_bits_truncate(LinuxSignal::SIGCHLD as usize);
+        let new_proc = Process::fork_from(self.zircon_process(), flags)?; // old pt NULL here
         let new_thread = Thread::create_linux(&new_proc)?;
+        new_thread.lock_linux().signal_mask = self.thread.lock_linux().signal_mask;
         let mut new_ctx = self.thread.context_cloned()?;
         new_ctx.set_field(UserContextField::ReturnValue, 0);
         new_thread.with_context(|ctx| *ctx = new_ctx)?;
@@ -79,32 +94,29 @@ impl Syscall<'_> {
     /// or it makes a call to [`Self::sys_execve`].
     pub async fn sys_vfork(&self) -> SysResult {
         info!("vfork:");
-        let new_proc = Process::fork_from(self.zircon_process(), true)?;
-        let new_thread = Thread::create_linux(&new_proc)?;
-        let mut new_ctx = self.thread.context_cloned()?;
-        new_ctx.set_field(UserContextField::ReturnValue, 0);
-        new_thread.with_context(|ctx| *ctx = new_ctx)?;
-        new_thread.start(self.thread_fn)?;
-
-        let new_proc: Arc<dyn KernelObject> = new_proc;
-        info!(
-            "vfork: {} -> {}. Waiting for execve SIGNALED",
-            self.zircon_process().id(),
-            new_proc.id()
-        );
-        new_proc.wait_signal(Signal::SIGNALED).await; // wait for execve
-        Ok(new_proc.id() as usize)
+        let flags = CloneFlags::VM | CloneFlags::VFORK;
+        self.sys_clone(
+            flags.bits() | LinuxSignal::SIGCHLD as usize,
+            0,
+            0usize.into(),
+            0usize.into(),
+            0,
+        )
+        .await
     }
 
-    /// `sys_clone` create a new thread in the current process.
-    /// The new thread's stack pointer will be set to `newsp`,
-    /// and thread pointer will be set to `newtls`.
-    /// The child TID will be stored at both `parent_tid` and `child_tid`.
+    /// `sys_clone` creates a new thread or process, sharing the resources selected by `flags`
+    /// (see [linux man clone(2)](https://www.man7.org/linux/man-pages/man2/clone.2.html)).
     ///
-    /// > **NOTE!** This system call is not exactly the same as `clone` in Linux.
+    /// With `CLONE_THREAD`, a new thread is created in the current process,
+    /// otherwise a new process is created as [`Self::sys_fork`] does, sharing
+    /// the file descriptor table, the working directory and the signal actions
+    /// if `CLONE_FILES`, `CLONE_FS` and `CLONE_SIGHAND` are set.
+    /// The stack pointer of the new thread is set to `newsp` unless it is 0,
+    /// and the thread pointer is set to `newtls` with `CLONE_SETTLS`.
     ///
-    /// > **NOTE!** This is partially implemented for `musl` only.
-    pub fn sys_clone(
+    /// Returns `EINVAL` for unsupported flags, including all namespace flags.
+    pub async fn sys_clone(
         &self,
         flags: usize,
         newsp: usize,
@@ -112,36 +124,129 @@ impl Syscall<'_> {
         mut child_tid: UserOutPtr<i32>,
         newtls: usize,
     ) -> SysResult {
-        let _flags = CloneFlags::from_bits_truncate(flags);
         info!(
             "clone: flags={:#x}, newsp={:#x}, parent_tid={:?}, child_tid={:?}, newtls={:#x}",
             flags, newsp, parent_tid, child_tid, newtls
         );
-        if flags == 0x4111 || flags == 0x11 {
-            warn!("sys_clone is calling sys_fork instead, ignoring other args");
-            //unimplemented!()
-            return self.sys_fork();
+        let flags = CloneFlags::from_bits(flags).ok_or(LxError::EINVAL)?;
+        // `DETACHED` is ignored as Linux does, `SYSVSEM` is implied as
+        // semaphore adjustments are not undone on exit
+        let supported = CloneFlags::CSIGNAL
+            | CloneFlags::VM
+            | CloneFlags::FS
+            | CloneFlags::FILES
+            | CloneFlags::SIGHAND
+            | CloneFlags::VFORK
+            | CloneFlags::PARENT
+            | CloneFlags::THREAD
+            | CloneFlags::SYSVSEM
+            | CloneFlags::SETTLS
+            | CloneFlags::PARENT_SETTID
+            | CloneFlags::CHILD_CLEARTID
+            | CloneFlags::DETACHED
+            | CloneFlags::CHILD_SETTID;
+        if !supported.contains(flags) {
+            warn!("clone: unsupported flags {:?}", flags - supported);
+            return Err(LxError::EINVAL);
         }
-        if flags != 0x7d_0f00 && flags != 0x5d_0f00 {
-            // 0x5d0f00: gcc of alpine linux
-            // 0x7d0f00: pthread_create of alpine linux
-            // warn!("sys_clone only support musl pthread_create");
-            panic!("unsupported sys_clone flags: {:#x}", flags);
+        // threads share signal actions, which can only be shared in one address space
+        if (flags.contains(CloneFlags::THREAD) && !flags.contains(CloneFlags::SIGHAND))
+            || (flags.contains(CloneFlags::SIGHAND) && !flags.contains(CloneFlags::VM))
+        {
+            return Err(LxError::EINVAL);
         }
-        let new_thread = Thread::create_linux(self.zircon_process())?;
+        if flags.contains(CloneFlags::PARENT) && self.linux_process().parent().is_none() {
+            return Err(LxError::EINVAL);
+        }
+
+        let new_proc = if flags.contains(CloneFlags::THREAD) {
+            None
+        } else {
+            Some(Process::fork_from(self.zircon_process(), flags)?)
+        };
+        let new_thread = Thread::create_linux(new_proc.as_ref().unwrap_or(self.zircon_process()))?;
+        new_thread.lock_linux().signal_mask = self.thread.lock_linux().signal_mask;
         let mut new_ctx = self.thread.context_cloned()?;
-        new_ctx.set_field(UserContextField::StackPointer, newsp);
-        new_ctx.set_field(UserContextField::ThreadPointer, newtls);
+        if newsp != 0 {
+            new_ctx.set_field(UserContextField::StackPointer, newsp);
+        }
+        if flags.contains(CloneFlags::SETTLS) {
+            new_ctx.set_field(UserContextField::ThreadPointer, newtls);
+        }
         new_ctx.set_field(UserContextField::ReturnValue, 0);
         new_thread.with_context(|ctx| *ctx = new_ctx)?;
-        new_thread.start(self.thread_fn)?;
 
         let tid = new_thread.id();
-        info!("clone: {} -> {}", self.thread.id(), tid);
-        parent_tid.write(tid as i32)?;
-        child_tid.write(tid as i32)?;
-        new_thread.set_tid_address(child_tid);
-        Ok(tid as usize)
+        if flags.contains(CloneFlags::PARENT_SETTID) {
+            parent_tid.write(tid as i32)?;
+        }
+        if flags.contains(CloneFlags::CHILD_SETTID) {
+            match &new_proc {
+                // the child has its own copy of the memory
+                Some(proc) if !flags.contains(CloneFlags::VM) => {
+                    proc.vmar()
+                        .write_memory(child_tid.as_addr(), &(tid as i32).to_ne_bytes())?;
+                }
+                _ => child_tid.write(tid as i32)?,
+            }
+        }
+        if flags.contains(CloneFlags::CHILD_CLEARTID) {
+            new_thread.set_tid_address(child_tid);
+        }
+        new_thread.start(self.thread_fn)?;
+
+        let new_proc = match new_proc {
+            Some(proc) => proc,
+            None => {
+                info!("clone: {} -> {}", self.thread.id(), tid);
+                return Ok(tid as usize);
+            }
+        };
+        info!("clone: {} -> {}", self.zircon_process().id(), new_proc.id());
+        if flags.contains(CloneFlags::VFORK) {
+            // wait until the child stops using the address space
+            let new_proc: Arc<dyn KernelObject> = new_proc.clone();
+            new_proc
+                .wait_signal(Signal::PROCESS_TERMINATED | Signal::PROCESS_EXECVE)
+                .await;
+        }
+        Ok(new_proc.id() as usize)
+    }
+
+    /// `sys_clone3` is the extensible version of [`Self::sys_clone`],
+    /// which reads its arguments from a `struct clone_args` of `size` bytes.
+    ///
+    /// Fields after `tls` are not supported and must be zero.
+    pub async fn sys_clone3(&self, args: UserInPtr<CloneArgs>, size: usize) -> SysResult {
+        let base = core::mem::size_of::<CloneArgs>();
+        if size < base {
+            return Err(LxError::EINVAL);
+        }
+        if size > PAGE_SIZE {
+            return Err(LxError::E2BIG);
+        }
+        let extensions = UserInPtr::<u64>::from(args.as_addr() + base)
+            .read_array((size - base) / core::mem::size_of::<u64>())?;
+        if extensions.iter().any(|&x| x != 0) {
+            return Err(LxError::EINVAL);
+        }
+        let args = args.read()?;
+        info!("clone3: {:x?}", args);
+        if args.flags as usize & CloneFlags::CSIGNAL.bits() != 0 || args.exit_signal > 64 {
+            return Err(LxError::EINVAL);
+        }
+        let newsp = match args.stack {
+            0 => 0,
+            stack => stack.checked_add(args.stack_size).ok_or(LxError::EINVAL)? as usize,
+        };
+        self.sys_clone(
+            args.flags as usize | args.exit_signal as usize,
+            newsp,
+            (args.parent_tid as usize).into(),
+            (args.child_tid as usize).into(),
+            args.tls as usize,
+        )
+        .await
     }
 
     /// `sys_wait4` suspends execution of the calling thread
@@ -152,6 +257,8 @@ impl Syscall<'_> {
     ///
     /// The value of `pid` can be:
     ///
+    /// - **<-1**: meaning wait for any child process whose process group ID is equal to
+    ///            the absolute value of `pid`.
     /// - **-1**: meaning wait for any child process.
     /// - **0**: meaning wait for any child process whose process group ID is equal to
     ///          that of the calling process at the time of the call to `sys_wait4`.
@@ -161,23 +268,18 @@ impl Syscall<'_> {
     ///
     /// - **NOHANG**    = 0x000_0001;
     ///
-    ///   TODO
-    ///
-    /// - **STOPPED**   = 0x000_0002;
-    ///
-    ///   TODO
+    ///   return immediately if no child has changed state.
     ///
-    /// - **EXITED**    = 0x000_0004;
+    /// - **UNTRACED**  = 0x000_0002;
     ///
-    ///   TODO
+    ///   also return if a child has stopped.
     ///
     /// - **CONTINUED** = 0x000_0008;
     ///
-    ///   TODO
+    ///   also return if a stopped child has been resumed by delivery of `SIGCONT`.
     ///
-    /// - **NOWAIT**    = 0x100_0000;
-    ///
-    ///   TODO
+    /// The status of the child is stored in `wstatus`, which can be inspected with
+    /// the macros `WIFEXITED`, `WIFSIGNALED`, `WIFSTOPPED`, `WIFCONTINUED` and so on.
     ///
     /// On success, returns the process ID of the child whose state has changed;
     /// if `NOHANG` flag was specified and one or more child(ren) specified by pid exist,
@@ -189,41 +291,96 @@ impl Syscall<'_> {
         mut wstatus: UserOutPtr<i32>,
         options: u32,
     ) -> SysResult {
-        #[derive(Debug)]
-        enum WaitTarget {
-            AnyChild,
-            AnyChildInGroup,
-            Pid(KoID),
-        }
-        bitflags! {
-            struct WaitFlags: u32 {
-                const NOHANG    = 1;
-                const STOPPED   = 2;
-                const EXITED    = 4;
-                const CONTINUED = 8;
-                const NOWAIT    = 0x100_0000;
-            }
-        }
+        // `__WNOTHREAD`, `__WALL` and `__WCLONE` are accepted and ignored
+        const IGNORED_OPTIONS: u32 = 0xe000_0000;
         let target = match pid {
-            -1 => WaitTarget::AnyChild,
-            0 => WaitTarget::AnyChildInGroup,
+            -1 => WaitTarget::Any,
+            0 => WaitTarget::Pgid(self.linux_process().pgid()),
             p if p > 0 => WaitTarget::Pid(p as KoID),
-            _ => unimplemented!(),
+            p => WaitTarget::Pgid(-(p as i64) as KoID),
         };
-        let flags = WaitFlags::from_bits_truncate(options);
-        let nohang = flags.contains(WaitFlags::NOHANG);
+        let options = WaitOptions::from_bits(options & !IGNORED_OPTIONS)
+            .filter(|options| {
+                (WaitOptions::NOHANG | WaitOptions::STOPPED | WaitOptions::CONTINUED)
+                    .contains(*options)
+            })
+            .ok_or(LxError::EINVAL)?;
         info!(
             "wait4: target={:?}, wstatus={:?}, options={:?}",
-            target, wstatus, flags,
+            target, wstatus, options,
         );
-        let (pid, code) = match target {
-            WaitTarget::AnyChild | WaitTarget::AnyChildInGroup => {
-                wait_child_any(self.zircon_process(), nohang).await?
+        let options = options | WaitOptions::EXITED;
+        let waiting = wait_child(self.zircon_process(), target, options);
+        match self.interruptible(waiting).await? {
+            Some((child, status)) => {
+                wstatus.write_if_not_null(status.to_wstatus())?;
+                Ok(child.id() as usize)
             }
-            WaitTarget::Pid(pid) => (pid, wait_child(self.zircon_process(), pid, nohang).await?),
+            None => Ok(0),
+        }
+    }
+
+    /// `sys_waitid` waits for a state change of a child, like [`Self::sys_wait4`],
+    /// but provides more precise control over which state changes to wait for
+    /// (see [linux man waitid(2)](https://www.man7.org/linux/man-pages/man2/waitid.2.html)).
+    ///
+    /// The children to wait for are selected by `idtype` and `id`:
+    ///
+    /// - **P_ALL** = 0: any child, `id` is ignored.
+    /// - **P_PID** = 1: the child whose process ID is `id`.
+    /// - **P_PGID** = 2: any child whose process group ID is `id`,
+    ///   or that of the calling process if `id` is 0.
+    ///
+    /// `options` must contain at least one of `EXITED`, `STOPPED` and `CONTINUED`,
+    /// which select the state changes to wait for, and may contain `NOHANG` and `NOWAIT`.
+    ///
+    /// The child is reported in `infop`. Returns 0 on success,
+    /// and if `NOHANG` is specified and no child has changed state,
+    /// `si_pid` and `si_signo` of `infop` are set to 0.
+    pub async fn sys_waitid(
+        &self,
+        idtype: u32,
+        id: usize,
+        mut infop: UserOutPtr<SigInfo>,
+        options: u32,
+    ) -> SysResult {
+        const P_ALL: u32 = 0;
+        const P_PID: u32 = 1;
+        const P_PGID: u32 = 2;
+        info!(
+            "waitid: idtype={}, id={}, infop={:?}, options={:#x}",
+            idtype, id, infop, options,
+        );
+        let options = WaitOptions::from_bits(options)
+            .filter(|options| {
+                options
+                    .intersects(WaitOptions::EXITED | WaitOptions::STOPPED | WaitOptions::CONTINUED)
+            })
+            .ok_or(LxError::EINVAL)?;
+        let target = match idtype {
+            P_ALL => WaitTarget::Any,
+            P_PID if id as i32 > 0 => WaitTarget::Pid(id as KoID),
+            P_PGID if id == 0 => WaitTarget::Pgid(self.linux_process().pgid()),
+            P_PGID if id as i32 > 0 => WaitTarget::Pgid(id as KoID),
+            _ => return Err(LxError::EINVAL),
         };
-        wstatus.write_if_not_null(code)?;
-        Ok(pid as usize)
+        let waiting = wait_child(self.zircon_process(), target, options);
+        let info = match self.interruptible(waiting).await? {
+            Some((child, status)) => {
+                let (code, status) = status.to_child_info();
+                let uid = child.linux().cred().uid.real;
+                SigInfo::from_child(LinuxSignal::SIGCHLD, code, child.id() as i32, uid, status)
+            }
+            // no child has changed state with `NOHANG`
+            None => SigInfo {
+                signo: 0,
+                errno: 0,
+                code: 0,
+                field: SiginfoFields::default(),
+            },
+        };
+        infop.write_if_not_null(info)?;
+        Ok(0)
     }
 
     /// `sys_execve` executes the program referred to by `path`
@@ -270,29 +427,51 @@ impl Syscall<'_> {
         // Read program file
         let proc = self.linux_process();
         let inode = proc.lookup_inode(path)?;
+        let meta = inode.metadata()?;
+        if meta.type_ != FileType::File {
+            return Err(LxError::EACCES);
+        }
+        proc.cred().check_access(&meta, Access::EXEC)?;
         let data = inode.read_as_vec()?;
 
         proc.remove_cloexec_files();
+        proc.reset_signal_actions();
+        proc.delete_posix_timers();
+        self.thread.lock_linux().signal_alternate_stack = Default::default();
 
         // 注意！即将销毁旧应用程序的用户空间，现在将必要的信息拷贝到内核！
         // Notice! About to destroy the user space of the old application, now copy the necessary information into kernel!
         let path = path.to_string();
-        let vmar = self.zircon_process().vmar();
-        vmar.clear()?;
+        let vmar = if proc.unshare_vm() {
+            // the old address space is still used by other processes
+            let vmar = VmAddressRegion::new_root();
+            self.zircon_process().set_vmar(vmar.clone());
+            vmar
+        } else {
+            let vmar = self.zircon_process().vmar();
+            vmar.clear()?;
+            vmar
+        };
 
         // Modify exec path
         proc.set_execute_path(&path);
+        proc.set_args(&args, &envs);
+        proc.modify_cred(|cred| {
+            cred.exec(&meta);
+            Ok(())
+        })?;
 
-        let (entry, sp) = LinuxElfLoader {
+        let (entry, sp, heap_start, vdso_base) = LinuxElfLoader {
             syscall_entry: self.syscall_entry,
             stack_pages: 8,
             root_inode: proc.root_inode().clone(),
         }
         .load(&vmar, &data, args, envs, path)?;
+        proc.init_heap(heap_start);
+        proc.init_vdso(vdso_base);
 
-        // TODO: use right signal
-        // self.zircon_process().signal_set(Signal::SIGNALED);
-        // Workaround, the child process could NOT exit correctly
+        // resume the parent waiting in `vfork`
+        self.zircon_process().signal_set(Signal::PROCESS_EXECVE);
 
         self.thread
             .with_context(|ctx| ctx.setup_uspace(entry, sp, 0, 0))?;
@@ -357,6 +536,62 @@ impl Syscall<'_> {
         Ok(ppid as usize)
     }
 
+    /// `sys_setpgid` sets the process group ID of the process `pid` to `pgid`
+    /// (see [linux man setpgid(2)](https://www.man7.org/linux/man-pages/man2/setpgid.2.html)).
+    /// If `pid` is zero, the calling process is used. If `pgid` is zero, the process ID is used.
+    pub fn sys_setpgid(&self, pid: isize, pgid: isize) -> SysResult {
+        info!("setpgid: pid={}, pgid={}", pid, pgid);
+        if pid < 0 || pgid < 0 {
+            return Err(LxError::EINVAL);
+        }
+        let proc = self.zircon_process();
+        proc.set_pgid(pid as KoID, pgid as KoID)?;
+        Ok(0)
+    }
+
+    /// `sys_getpgid` returns the process group ID of the process `pid`
+    /// (see [linux man getpgid(2)](https://www.man7.org/linux/man-pages/man2/setpgid.2.html)).
+    /// If `pid` is zero, the calling process is used.
+    pub fn sys_getpgid(&self, pid: isize) -> SysResult {
+        info!("getpgid: pid={}", pid);
+        Ok(self.target_process(pid)?.linux().pgid() as usize)
+    }
+
+    /// `sys_getpgrp` returns the process group ID of the calling process
+    /// (see [linux man getpgrp(2)](https://www.man7.org/linux/man-pages/man2/setpgid.2.html)).
+    pub fn sys_getpgrp(&self) -> SysResult {
+        info!("getpgrp:");
+        Ok(self.linux_process().pgid() as usize)
+    }
+
+    /// `sys_setsid` creates a new session if the calling process is not a process group leader
+    /// (see [linux man setsid(2)](https://www.man7.org/linux/man-pages/man2/setsid.2.html)).
+    /// The calling process becomes the leader of the new session and a new process group,
+    /// and has no controlling terminal.
+    pub fn sys_setsid(&self) -> SysResult {
+        info!("setsid:");
+        let sid = self.zircon_process().set_sid()?;
+        Ok(sid as usize)
+    }
+
+    /// `sys_getsid` returns the session ID of the process `pid`
+    /// (see [linux man getsid(2)](https://www.man7.org/linux/man-pages/man2/getsid.2.html)).
+    /// If `pid` is zero, the calling process is used.
+    pub fn sys_getsid(&self, pid: isize) -> SysResult {
+        info!("getsid: pid={}", pid);
+        Ok(self.target_process(pid)?.linux().sid() as usize)
+    }
+
+    /// Returns the process `pid`, or the calling process if `pid` is zero.
+    fn target_process(&self, pid: isize) -> LxResult<Arc<Process>> {
+        let proc = self.zircon_process();
+        match pid {
+            0 => Ok(proc.clone()),
+            p if p > 0 => proc.find_process(p as KoID),
+            _ => Err(LxError::ESRCH),
+        }
+    }
+
     /// `sys_exit` system call terminates only the calling thread
     /// (see [linux man _exit(2)](https://www.man7.org/linux/man-pages/man2/exit.2.html),
     /// this syscall is same as a raw `_exit` in glibc),
@@ -377,7 +612,8 @@ impl Syscall<'_> {
     pub fn sys_exit_group(&mut self, exit_code: i32) -> SysResult {
         info!("exit_group: code={}", exit_code);
         let proc = self.zircon_process();
-        proc.exit(exit_code as i64);
+        // each thread releases its robust futexes when it terminates
+        proc.linux().exit(proc, exit_code);
         Err(LxError::ENOSYS)
     }
 
@@ -418,55 +654,24 @@ impl Syscall<'_> {
     }
 }
 
-bitflags! {
-    pub struct CloneFlags: usize {
-        ///
-        const CSIGNAL =         0xff;
-        /// the calling process and the child process run in the same memory space
-        const VM =              1 << 8;
-        /// the caller and the child process share the same filesystem information
-        const FS =              1 << 9;
-        /// the calling process and the child process share the same file descriptor table
-        const FILES =           1 << 10;
-        /// the calling process and the child process share the same table of signal handlers.
-        const SIGHAND =         1 << 11;
-        /// the calling process is being traced
-        const PTRACE =          1 << 13;
-        /// the execution of the calling process is suspended until the child releases its virtual memory resources
-        const VFORK =           1 << 14;
-        /// the parent of the new child will be the same as that of the call‐ing process.
-        const PARENT =          1 << 15;
-        /// the child is placed in the same thread group as the calling process.
-        const THREAD =          1 << 16;
-        /// cloned child is started in a new mount namespace
-        const NEWNS	=           1 << 17;
-        /// the child and the calling process share a single list of System V semaphore adjustment values.
-        const SYSVSEM =         1 << 18;
-        /// architecture dependent, The TLS (Thread Local Storage) descriptor is set to tls.
-        const SETTLS =          1 << 19;
-        /// Store the child thread ID at the location in the parent's memory.
-        const PARENT_SETTID =   1 << 20;
-        /// Clear (zero) the child thread ID
-        const CHILD_CLEARTID =  1 << 21;
-        /// the parent not to receive a signal when the child terminated
-        const DETACHED =        1 << 22;
-        /// a tracing process cannot force CLONE_PTRACE on this child process.
-        const UNTRACED =        1 << 23;
-        /// Store the child thread ID
-        const CHILD_SETTID =    1 << 24;
-        /// Create the process in a new cgroup namespace.
-        const NEWCGROUP =       1 << 25;
-        /// create the process in a new UTS namespace
-        const NEWUTS =          1 << 26;
-        /// create the process in a new IPC namespace.
-        const NEWIPC =          1 << 27;
-        /// create the process in a new user namespace
-        const NEWUSER =         1 << 28;
-        /// create the process in a new PID namespace
-        const NEWPID =          1 << 29;
-        /// create the process in a new net‐work namespace.
-        const NEWNET =          1 << 30;
-        /// the new process shares an I/O context with the calling process.
-        const IO =              1 << 31;
-    }
+/// `struct clone_args` of `clone3`, only the fields of the first version
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CloneArgs {
+    /// flags bit mask
+    flags: u64,
+    /// where to store PID file descriptor
+    pidfd: u64,
+    /// where to store child TID, in child's memory
+    child_tid: u64,
+    /// where to store child TID, in parent's memory
+    parent_tid: u64,
+    /// signal to deliver to parent on child termination
+    exit_signal: u64,
+    /// pointer to lowest byte of stack
+    stack: u64,
+    /// size of stack
+    stack_size: u64,
+    /// location of new TLS
+    tls: u64,
 }
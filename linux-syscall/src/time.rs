@@ -1,11 +1,22 @@
 //! Syscalls for time
 //! - clock_gettime
+//! - getitimer, setitimer, alarm
+//! - timer_create, timer_settime, timer_gettime, timer_getoverrun, timer_delete
 //!
 use crate::Syscall;
+use alloc::sync::Arc;
+use core::convert::TryFrom;
+use core::time::Duration;
 use kernel_hal::{user::UserInPtr, user::UserOutPtr};
 use linux_object::error::LxError;
-use linux_object::error::SysResult;
+use linux_object::error::{LxResult, SysResult};
+use linux_object::process::ResourceUsage;
+use linux_object::signal::Signal;
+use linux_object::thread::ThreadExt;
 use linux_object::time::*;
+use linux_object::timer::*;
+use zircon_object::object::KernelObject;
+use zircon_object::task::Thread;
 
 const USEC_PER_TICK: usize = 10000;
 
@@ -52,38 +63,57 @@ impl Syscall<'_> {
         Ok(sec)
     }
 
-    /// get resource usage
-    /// currently only support ru_utime and ru_stime:
-    /// - `ru_utime`: user CPU time used
-    /// - `ru_stime`: system CPU time used
+    /// get resource usage of the calling process, its terminated and waited-for children,
+    /// or the calling thread
     pub fn sys_getrusage(&mut self, who: usize, mut rusage: UserOutPtr<RUsage>) -> SysResult {
-        info!("getrusage: who: {}, rusage: {:?}", who, rusage);
-
-        let new_rusage = RUsage {
-            utime: TimeVal::now(),
-            stime: TimeVal::now(),
+        const RUSAGE_SELF: isize = 0;
+        const RUSAGE_CHILDREN: isize = -1;
+        const RUSAGE_THREAD: isize = 1;
+        info!("getrusage: who: {}, rusage: {:?}", who as isize, rusage);
+        let proc = self.linux_process();
+        let usage = match who as isize {
+            RUSAGE_SELF => {
+                proc.update_max_rss(self.zircon_process());
+                proc.usage()
+            }
+            RUSAGE_CHILDREN => proc.children_usage(),
+            RUSAGE_THREAD => {
+                proc.update_max_rss(self.zircon_process());
+                ResourceUsage {
+                    user_time: Duration::from_nanos(self.thread.get_user_time()),
+                    system_time: Duration::from_nanos(self.thread.get_system_time()),
+                    minor_faults: self.thread.lock_linux().minor_faults,
+                    major_faults: 0,
+                    ..proc.usage()
+                }
+            }
+            _ => return Err(LxError::EINVAL),
         };
-        rusage.write(new_rusage)?;
+        rusage.write(RUsage {
+            utime: usage.user_time.into(),
+            stime: usage.system_time.into(),
+            maxrss: usage.max_rss / 1024,
+            minflt: usage.minor_faults,
+            majflt: usage.major_faults,
+            ..Default::default()
+        })?;
         Ok(0)
     }
 
-    /// stores the current process times in the struct tms that buf points to
+    /// stores the current process times in the struct tms that buf points to,
+    /// and returns the clock ticks since boot
     pub fn sys_times(&mut self, mut buf: UserOutPtr<Tms>) -> SysResult {
         info!("times: buf: {:?}", buf);
-
-        let tv = TimeVal::now();
-
-        let tick = (tv.sec * 1_000_000 + tv.usec) / USEC_PER_TICK;
-
-        let new_buf = Tms {
-            tms_utime: 0,
-            tms_stime: 0,
-            tms_cutime: 0,
-            tms_cstime: 0,
-        };
-
-        buf.write(new_buf)?;
-
+        let proc = self.linux_process();
+        let usage = proc.usage();
+        let children = proc.children_usage();
+        buf.write_if_not_null(Tms {
+            tms_utime: ticks(usage.user_time),
+            tms_stime: ticks(usage.system_time),
+            tms_cutime: ticks(children.user_time),
+            tms_cstime: ticks(children.system_time),
+        })?;
+        let tick = ticks(kernel_hal::timer::timer_now());
         info!("tick: {:?}", tick);
         Ok(tick as usize)
     }
@@ -93,7 +123,7 @@ impl Syscall<'_> {
     pub async fn sys_nanosleep(&self, req: UserInPtr<TimeSpec>) -> SysResult {
         info!("nanosleep: deadline={:?}", req);
         let duration = req.read()?.into();
-        nanosleep(duration).await;
+        self.sleep(duration).await?;
         Ok(0)
     }
 
@@ -121,22 +151,22 @@ impl Syscall<'_> {
             ClockId::ClockRealTime => {
                 match flags {
                     ClockFlags::ZeroFlag => {
-                        nanosleep(duration).await;
+                        self.sleep(duration).await?;
                     }
                     ClockFlags::TimerAbsTime => {
                         // 目前统一由nanosleep代替了、之后再修改
-                        nanosleep(duration).await;
+                        self.sleep(duration).await?;
                     }
                 }
             }
             ClockId::ClockMonotonic => {
                 match flags {
                     ClockFlags::ZeroFlag => {
-                        nanosleep(duration).await;
+                        self.sleep(duration).await?;
                     }
                     ClockFlags::TimerAbsTime => {
                         // 目前统一由nanosleep代替了、之后再修改
-                        nanosleep(duration).await;
+                        self.sleep(duration).await?;
                     }
                 }
             }
@@ -151,4 +181,221 @@ impl Syscall<'_> {
         }
         Ok(0)
     }
+
+    /// get the value of the interval timer `which`
+    pub fn sys_getitimer(&self, which: usize, mut curr: UserOutPtr<ITimerVal>) -> SysResult {
+        info!("getitimer: which={}, curr={:?}", which, curr);
+        let (value, interval) = self.itimer(which)?.get();
+        curr.write(ITimerVal {
+            interval: interval.into(),
+            value: value.into(),
+        })?;
+        Ok(0)
+    }
+
+    /// arm or disarm the interval timer `which`
+    ///
+    /// `ITIMER_REAL` measures the wall clock and sends `SIGALRM`,
+    /// `ITIMER_VIRTUAL` measures the user CPU time of the process and sends `SIGVTALRM`,
+    /// `ITIMER_PROF` measures the CPU time of the process and sends `SIGPROF`.
+    pub fn sys_setitimer(
+        &self,
+        which: usize,
+        new: UserInPtr<ITimerVal>,
+        mut old: UserOutPtr<ITimerVal>,
+    ) -> SysResult {
+        info!("setitimer: which={}, new={:?}, old={:?}", which, new, old);
+        let timer = self.itimer(which)?;
+        let new = new.read()?;
+        let value = timeval_to_duration(new.value)?;
+        let interval = timeval_to_duration(new.interval)?;
+        let deadline = relative_deadline(&timer, value)?;
+        let (old_value, old_interval) = timer.set(deadline, interval);
+        old.write_if_not_null(ITimerVal {
+            interval: old_interval.into(),
+            value: old_value.into(),
+        })?;
+        Ok(0)
+    }
+
+    /// arrange for `SIGALRM` to be delivered in `seconds` seconds,
+    /// and returns the seconds remaining until the previous alarm
+    #[cfg(target_arch = "x86_64")]
+    pub fn sys_alarm(&self, seconds: u32) -> SysResult {
+        info!("alarm: seconds={}", seconds);
+        let timer = self
+            .linux_process()
+            .itimer(self.zircon_process(), TimerClock::Real);
+        let deadline = relative_deadline(&timer, Duration::from_secs(seconds as u64))?;
+        let (remaining, _) = timer.set(deadline, Duration::ZERO);
+        // round to the nearest second, but a pending alarm never returns 0
+        let mut secs = remaining.as_secs() + (remaining.subsec_micros() >= 500_000) as u64;
+        if secs == 0 && remaining != Duration::ZERO {
+            secs = 1;
+        }
+        Ok(secs as usize)
+    }
+
+    /// create a POSIX per-process timer measuring `clock`
+    ///
+    /// The expiration is notified as `sevp` specifies,
+    /// or by `SIGALRM` to the process if `sevp` is null.
+    /// Only `SIGEV_NONE`, `SIGEV_SIGNAL` and `SIGEV_THREAD_ID` are supported.
+    pub fn sys_timer_create(
+        &self,
+        clock: usize,
+        sevp: UserInPtr<SigEvent>,
+        mut timerid: UserOutPtr<i32>,
+    ) -> SysResult {
+        info!(
+            "timer_create: clock={}, sevp={:?}, timerid={:?}",
+            clock, sevp, timerid
+        );
+        let clock = match clock {
+            // CLOCK_REALTIME, CLOCK_MONOTONIC, CLOCK_BOOTTIME and the alarm clocks
+            0 | 1 | 7 | 8 | 9 => TimerClock::Real,
+            // CLOCK_PROCESS_CPUTIME_ID
+            2 => TimerClock::Prof,
+            _ => return Err(LxError::EINVAL),
+        };
+        let (notify, value) = if sevp.is_null() {
+            (TimerNotify::Process(Signal::SIGALRM), None)
+        } else {
+            let event = sevp.read()?;
+            let signal = || {
+                u8::try_from(event.signo)
+                    .ok()
+                    .and_then(|signo| Signal::try_from(signo).ok())
+                    .ok_or(LxError::EINVAL)
+            };
+            let notify = match event.notify {
+                SIGEV_NONE => TimerNotify::None,
+                SIGEV_SIGNAL => TimerNotify::Process(signal()?),
+                SIGEV_THREAD_ID => {
+                    let tid = event.tid as _;
+                    self.zircon_process()
+                        .get_child(tid)
+                        .ok()
+                        .and_then(|obj| obj.downcast_arc::<Thread>().ok())
+                        .ok_or(LxError::EINVAL)?;
+                    TimerNotify::Thread(signal()?, tid)
+                }
+                _ => return Err(LxError::EINVAL),
+            };
+            (notify, Some(event.value))
+        };
+        let proc = self.linux_process();
+        let id = proc.create_timer(self.zircon_process(), clock, notify, value)?;
+        if let Err(err) = timerid.write(id) {
+            proc.delete_timer(id)?;
+            return Err(err.into());
+        }
+        Ok(0)
+    }
+
+    /// arm or disarm the POSIX timer `timerid`
+    ///
+    /// The time of the first expiration is absolute if `TIMER_ABSTIME` is in `flags`.
+    pub fn sys_timer_settime(
+        &self,
+        timerid: i32,
+        flags: usize,
+        new: UserInPtr<ITimerSpec>,
+        mut old: UserOutPtr<ITimerSpec>,
+    ) -> SysResult {
+        const TIMER_ABSTIME: usize = 1;
+        info!(
+            "timer_settime: timerid={}, flags={:#x}, new={:?}, old={:?}",
+            timerid, flags, new, old
+        );
+        let timer = self.linux_process().get_timer(timerid)?;
+        let new = new.read()?;
+        let value = timespec_to_duration(new.value)?;
+        let interval = timespec_to_duration(new.interval)?;
+        let deadline = if flags & TIMER_ABSTIME != 0 {
+            Some(value).filter(|&value| value != Duration::ZERO)
+        } else {
+            relative_deadline(&timer, value)?
+        };
+        let (old_value, old_interval) = timer.set(deadline, interval);
+        old.write_if_not_null(ITimerSpec {
+            interval: old_interval.into(),
+            value: old_value.into(),
+        })?;
+        Ok(0)
+    }
+
+    /// get the time until the next expiration of the POSIX timer `timerid`, and the interval
+    pub fn sys_timer_gettime(&self, timerid: i32, mut curr: UserOutPtr<ITimerSpec>) -> SysResult {
+        info!("timer_gettime: timerid={}, curr={:?}", timerid, curr);
+        let (value, interval) = self.linux_process().get_timer(timerid)?.get();
+        curr.write(ITimerSpec {
+            interval: interval.into(),
+            value: value.into(),
+        })?;
+        Ok(0)
+    }
+
+    /// get the number of expirations missed by the last signal of the POSIX timer `timerid`
+    pub fn sys_timer_getoverrun(&self, timerid: i32) -> SysResult {
+        info!("timer_getoverrun: timerid={}", timerid);
+        Ok(self.linux_process().get_timer(timerid)?.overrun())
+    }
+
+    /// disarm and delete the POSIX timer `timerid`
+    pub fn sys_timer_delete(&self, timerid: i32) -> SysResult {
+        info!("timer_delete: timerid={}", timerid);
+        self.linux_process().delete_timer(timerid)?;
+        Ok(0)
+    }
+
+    /// Get the interval timer `which` of `setitimer`.
+    fn itimer(&self, which: usize) -> LxResult<Arc<IntervalTimer>> {
+        let clock = match which {
+            0 => TimerClock::Real,
+            1 => TimerClock::Virtual,
+            2 => TimerClock::Prof,
+            _ => return Err(LxError::EINVAL),
+        };
+        Ok(self.linux_process().itimer(self.zircon_process(), clock))
+    }
+
+    /// Sleep for `duration`, which fails with `EINTR` if a signal arrives first.
+    async fn sleep(&self, duration: Duration) -> LxResult {
+        self.interruptible(async {
+            nanosleep(duration).await;
+            Ok(())
+        })
+        .await
+    }
+}
+
+/// Convert `time` to clock ticks.
+fn ticks(time: Duration) -> u64 {
+    (time.as_micros() / USEC_PER_TICK as u128) as u64
+}
+
+/// Check the range of a `TimeVal` and convert it.
+fn timeval_to_duration(tv: TimeVal) -> LxResult<Duration> {
+    if (tv.sec as isize) < 0 || tv.usec >= 1_000_000 {
+        return Err(LxError::EINVAL);
+    }
+    Ok(tv.into())
+}
+
+/// Check the range of a `TimeSpec` and convert it.
+pub(crate) fn timespec_to_duration(ts: TimeSpec) -> LxResult<Duration> {
+    if (ts.sec as isize) < 0 || ts.nsec >= 1_000_000_000 {
+        return Err(LxError::EINVAL);
+    }
+    Ok(ts.into())
+}
+
+/// The deadline `value` after now in the clock of `timer`, a zero `value` disarms the timer.
+fn relative_deadline(timer: &IntervalTimer, value: Duration) -> LxResult<Option<Duration>> {
+    if value == Duration::ZERO {
+        return Ok(None);
+    }
+    let deadline = timer.now().checked_add(value).ok_or(LxError::EINVAL)?;
+    Ok(Some(deadline))
 }
@@ -1,6 +1,8 @@
 use super::*;
 use bitflags::bitflags;
-use zircon_object::vm::{pages, MMUFlags, VmObject};
+use linux_object::error::LxResult;
+use linux_object::fs::{sync_page_cache, File};
+use zircon_object::vm::{pages, MMUFlags, VmObject, PAGE_SIZE};
 
 /// Syscalls for virtual memory.
 ///
@@ -9,6 +11,8 @@ use zircon_object::vm::{pages, MMUFlags, VmObject};
 /// - [`mmap`](Self::sys_mmap)
 /// - [`mprotect`](Self::sys_mprotect)
 /// - [`munmap`](Self::sys_munmap)
+/// - [`msync`](Self::sys_msync)
+/// - [`brk`](Self::sys_brk)
 impl Syscall<'_> {
     /// Map files or devices into memory
     /// (see [linux man mmap(2)](https://www.man7.org/linux/man-pages/man2/mmap.2.html)).
@@ -44,15 +48,16 @@ impl Syscall<'_> {
     /// - **`MmapFlags::SHARED`**
     ///
     ///   Share this mapping. Updates to the mapping are visible to other processes mapping the same region,
-    ///   and (in the case of file-backed mappings) are carried through to the underlying file.
-    ///   (To precisely control when updates are carried through to the underlying file requires the use of `msync`,
-    ///   which has not been implemented in zcore).
+    ///   and (in the case of file-backed mappings) are carried through to the underlying file
+    ///   by [`msync`](Self::sys_msync) or [`munmap`](Self::sys_munmap).
+    ///   A shared mapping is also shared with the child after `fork`.
     ///
     /// - **`MmapFlags::PRIVATE`**
     ///
     ///   Create a private copy-on-write mapping.
     ///   Updates to the mapping are not visible to other processes mapping the same file,
     ///   and are not carried through to the underlying file.
+    ///   The pages are shared with the page cache of the file until written.
     ///   It is unspecified whether changes made to the file after the `sys_mmap` call are visible in the mapped region.
     ///
     /// - **`MmapFlags::FIXED`**
@@ -69,8 +74,6 @@ impl Syscall<'_> {
     ///
     ///   The mapping is not backed by any file; its contents are initialized to zero.
     ///   Both `fd` and `offset` arguments are ignored.
-    ///   The use of `MmapFlags::ANONYMOUS` in conjunction with `MmapFlags::SHARED`
-    ///   causes an [`EINVAL`](LxError::EINVAL) to be returned.
     pub async fn sys_mmap(
         &self,
         addr: usize,
@@ -90,24 +93,49 @@ impl Syscall<'_> {
         let proc = self.zircon_process();
         let vmar = proc.vmar();
 
-        if flags.contains(MmapFlags::FIXED) {
-            // unmap first
-            vmar.unmap(addr, len)?;
-        }
         let vmar_offset = flags.contains(MmapFlags::FIXED).then(|| addr - vmar.addr());
-        if flags.contains(MmapFlags::ANONYMOUS) {
-            if flags.contains(MmapFlags::SHARED) {
-                return Err(LxError::EINVAL);
-            }
+        let (vmo, vmo_offset, len) = if flags.contains(MmapFlags::ANONYMOUS) {
             let vmo = VmObject::new_paged(pages(len));
-            let addr = vmar.map(vmar_offset, vmo.clone(), 0, vmo.len(), prot.to_flags())?;
-            Ok(addr)
+            let len = vmo.len();
+            (vmo, 0, len)
         } else {
             let file_like = self.linux_process().get_file_like(fd)?;
-            let vmo = file_like.get_vmo(offset as usize, len)?;
-            let addr = vmar.map(vmar_offset, vmo.clone(), 0, vmo.len(), prot.to_flags())?;
-            Ok(addr)
+            // the mapping is read from the file, and written back to it if shared
+            let file_flags = file_like.flags();
+            if !file_flags.readable()
+                || (flags.contains(MmapFlags::SHARED)
+                    && prot.contains(MmapProt::WRITE)
+                    && !file_flags.writable())
+            {
+                return Err(LxError::EACCES);
+            }
+            let len = pages(len) * PAGE_SIZE;
+            // shared mappings of a regular file map its page cache directly
+            let page_cache = match file_like.clone().downcast_arc::<File>() {
+                Ok(file) if flags.contains(MmapFlags::SHARED) => {
+                    file.page_cache(offset as usize + len)?
+                }
+                _ => None,
+            };
+            match page_cache {
+                Some(vmo) => (vmo, offset as usize, len),
+                None => {
+                    let vmo = file_like.get_vmo(offset as usize, len)?;
+                    let len = vmo.len();
+                    (vmo, 0, len)
+                }
+            }
+        };
+        if flags.contains(MmapFlags::FIXED) {
+            // unmap first
+            vmar.unmap(addr, len)?;
         }
+        let addr = if flags.contains(MmapFlags::SHARED) {
+            vmar.map_shared(vmar_offset, vmo, vmo_offset, len, prot.to_flags())?
+        } else {
+            vmar.map(vmar_offset, vmo, vmo_offset, len, prot.to_flags())?
+        };
+        Ok(addr)
     }
 
     /// Set protection on a region of memory
@@ -161,13 +189,89 @@ impl Syscall<'_> {
     ///
     /// Both `addr` and `len` must be aligned to the page size, additionally, `len` must greater than 0.
     /// Otherwise, an [`EINVAL`](LxError::EINVAL) is returned.
+    ///
+    /// Updates to shared file mappings in the range are written back to the files.
     pub fn sys_munmap(&self, addr: usize, len: usize) -> SysResult {
         info!("munmap: addr={:#x}, size={:#x}", addr, len);
         let proc = self.thread.proc();
         let vmar = proc.vmar();
+        // errors of the write back are not reported by munmap
+        if addr % PAGE_SIZE == 0 {
+            let _ = self.sync_mappings(addr, len);
+        }
         vmar.unmap(addr, len)?;
         Ok(0)
     }
+
+    /// Synchronize a file with a memory map
+    /// (see [linux man msync(2)](https://www.man7.org/linux/man-pages/man2/msync.2.html)).
+    ///
+    /// `sys_msync` writes the changes to the shared file mappings in `[addr, addr + len)`
+    /// back to the files. The write back is always synchronous.
+    ///
+    /// `addr` must be aligned to the page size, and `flags` must contain exactly one of
+    /// `MS_SYNC` and `MS_ASYNC`. Otherwise, an [`EINVAL`](LxError::EINVAL) is returned.
+    /// If some pages in the range are not mapped, an [`ENOMEM`](LxError::ENOMEM) is returned.
+    pub fn sys_msync(&self, addr: usize, len: usize, flags: usize) -> SysResult {
+        const MS_ASYNC: usize = 1;
+        const MS_INVALIDATE: usize = 2;
+        const MS_SYNC: usize = 4;
+        info!(
+            "msync: addr={:#x}, size={:#x}, flags={:#x}",
+            addr, len, flags
+        );
+        if addr % PAGE_SIZE != 0
+            || flags & !(MS_ASYNC | MS_INVALIDATE | MS_SYNC) != 0
+            || flags & (MS_ASYNC | MS_SYNC) == MS_ASYNC | MS_SYNC
+        {
+            return Err(LxError::EINVAL);
+        }
+        if self.sync_mappings(addr, len)? {
+            Ok(0)
+        } else {
+            Err(LxError::ENOMEM)
+        }
+    }
+
+    /// Write the shared file mappings in `[addr, addr + len)` back to the files.
+    ///
+    /// Returns `false` if some pages in the range are not mapped.
+    fn sync_mappings(&self, addr: usize, len: usize) -> LxResult<bool> {
+        let end = addr.checked_add(len).ok_or(LxError::ENOMEM)?;
+        let mut synced_end = addr;
+        let mut mapped = true;
+        // the mappings are sorted by address
+        for map in self.zircon_process().vmar().get_mappings() {
+            let begin = map.addr.max(addr);
+            let map_end = (map.addr + map.size).min(end);
+            if begin >= map_end {
+                continue;
+            }
+            mapped &= begin <= synced_end;
+            sync_page_cache(
+                &map.vmo,
+                map.vmo_offset + (begin - map.addr),
+                map_end - begin,
+            )?;
+            synced_end = map_end;
+        }
+        Ok(mapped && synced_end >= end)
+    }
+
+    /// Change the location of the program break
+    /// (see [linux man brk(2)](https://www.man7.org/linux/man-pages/man2/brk.2.html)).
+    ///
+    /// The program break is the end of the process's heap, which starts right after
+    /// the highest segment of the loaded program.
+    ///
+    /// Returns the new program break. If `addr` is 0, below the start of the heap,
+    /// or the memory can not be mapped, the current program break is returned.
+    pub fn sys_brk(&self, addr: usize) -> SysResult {
+        info!("brk: addr={:#x}", addr);
+        let proc = self.zircon_process();
+        let brk = proc.linux().set_brk(&proc.vmar(), addr);
+        Ok(brk)
+    }
 }
 
 bitflags! {
@@ -0,0 +1,48 @@
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include <assert.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <sys/syscall.h>
+
+int main(int argc, char **argv)
+{
+    // musl's sbrk refuses to move the break, call brk directly
+    char *start = (char *)syscall(SYS_brk, 0);
+    assert(start != NULL);
+    printf("initial brk: %p\n", start);
+
+    // grow
+    char *end = (char *)syscall(SYS_brk, start + 0x5000);
+    assert(end == start + 0x5000);
+    memset(start, 0xaa, 0x5000);
+
+    // the heap is inherited by a forked child
+    pid_t pid = fork();
+    if (pid == 0) {
+        assert((char *)syscall(SYS_brk, 0) == end);
+        assert(start[0x4fff] == (char)0xaa);
+        _exit(0);
+    }
+    int status;
+    assert(waitpid(pid, &status, 0) == pid);
+
+    // new mappings do not take the room of the heap
+    void *p = mmap(NULL, 0x1000, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    assert(p != MAP_FAILED);
+    end = (char *)syscall(SYS_brk, start + 0x8000);
+    assert(end == start + 0x8000);
+    assert(start[0x4fff] == (char)0xaa);
+
+    // shrink
+    end = (char *)syscall(SYS_brk, start + 0x1000);
+    assert(end == start + 0x1000);
+    assert(start[0xfff] == (char)0xaa);
+
+    // invalid break is ignored
+    assert((char *)syscall(SYS_brk, start - 0x1000) == end);
+
+    printf("brk ok\n");
+    return 0;
+}
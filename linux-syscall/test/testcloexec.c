@@ -0,0 +1,145 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <assert.h>
+#include <sys/eventfd.h>
+#include <sys/socket.h>
+#include <sys/wait.h>
+
+void test_dup()
+{
+    int fd = open("/tmp/testcloexec", O_CREAT | O_RDWR | O_CLOEXEC, 0644);
+    assert(fd >= 0);
+    assert(fcntl(fd, F_GETFD) == FD_CLOEXEC);
+    // not a file status flag
+    assert((fcntl(fd, F_GETFL) & O_CLOEXEC) == 0);
+
+    // duplicated descriptors do not inherit FD_CLOEXEC
+    int fd2 = dup(fd);
+    assert(fd2 >= 0 && fd2 != fd);
+    assert(fcntl(fd2, F_GETFD) == 0);
+    assert(fcntl(fd, F_GETFD) == FD_CLOEXEC);
+    assert(dup2(fd, fd2) == fd2);
+    assert(fcntl(fd2, F_GETFD) == 0);
+    assert(dup2(fd, fd) == fd);
+    assert(fcntl(fd, F_GETFD) == FD_CLOEXEC);
+
+    // dup3
+    assert(dup3(fd2, fd2, 0) == -1 && errno == EINVAL);
+    assert(dup3(fd, fd2, O_NONBLOCK) == -1 && errno == EINVAL);
+    assert(dup3(fd, fd2, O_CLOEXEC) == fd2);
+    assert(fcntl(fd2, F_GETFD) == FD_CLOEXEC);
+    assert(dup3(fd, fd2, 0) == fd2);
+    assert(fcntl(fd2, F_GETFD) == 0);
+
+    // a bad old descriptor does not close the new one
+    assert(dup2(1000, fd2) == -1 && errno == EBADF);
+    assert(fcntl(fd2, F_GETFD) == 0);
+
+    // fcntl
+    assert(fcntl(fd2, F_SETFD, FD_CLOEXEC) == 0);
+    assert(fcntl(fd2, F_GETFD) == FD_CLOEXEC);
+    int fd3 = fcntl(fd2, F_DUPFD, 100);
+    assert(fd3 >= 100);
+    assert(fcntl(fd3, F_GETFD) == 0);
+    int fd4 = fcntl(fd3, F_DUPFD_CLOEXEC, 100);
+    assert(fd4 > fd3);
+    assert(fcntl(fd4, F_GETFD) == FD_CLOEXEC);
+    assert(fcntl(1000, F_GETFD) == -1 && errno == EBADF);
+
+    close(fd);
+    close(fd2);
+    close(fd3);
+    close(fd4);
+    assert(fcntl(fd4, F_GETFD) == -1 && errno == EBADF);
+}
+
+void test_pipe2()
+{
+    int fds[2];
+    char buf[16];
+    assert(pipe2(fds, O_RDWR) == -1 && errno == EINVAL);
+
+    assert(pipe2(fds, O_NONBLOCK | O_CLOEXEC) == 0);
+    assert(fcntl(fds[0], F_GETFD) == FD_CLOEXEC);
+    assert(fcntl(fds[1], F_GETFD) == FD_CLOEXEC);
+    assert(fcntl(fds[0], F_GETFL) & O_NONBLOCK);
+    assert(read(fds[0], buf, sizeof(buf)) == -1 && errno == EAGAIN);
+    assert(write(fds[1], "abc", 3) == 3);
+    assert(read(fds[0], buf, sizeof(buf)) == 3);
+    close(fds[0]);
+    close(fds[1]);
+
+    // packet mode
+    assert(pipe2(fds, O_DIRECT | O_NONBLOCK) == 0);
+    assert(write(fds[1], "abc", 3) == 3);
+    assert(write(fds[1], "defg", 4) == 4);
+    assert(write(fds[1], "h", 1) == 1);
+    assert(read(fds[0], buf, sizeof(buf)) == 3);
+    assert(memcmp(buf, "abc", 3) == 0);
+    // the rest of a packet is discarded
+    assert(read(fds[0], buf, 2) == 2);
+    assert(memcmp(buf, "de", 2) == 0);
+    assert(read(fds[0], buf, sizeof(buf)) == 1);
+    assert(buf[0] == 'h');
+    assert(read(fds[0], buf, sizeof(buf)) == -1 && errno == EAGAIN);
+    close(fds[0]);
+    close(fds[1]);
+}
+
+void test_exec(const char *path)
+{
+    int fds[2];
+    assert(pipe2(fds, O_CLOEXEC) == 0);
+    int efd = eventfd(0, EFD_CLOEXEC);
+    assert(efd >= 0);
+    int sfd = socket(AF_UNIX, SOCK_STREAM | SOCK_CLOEXEC, 0);
+    assert(sfd >= 0);
+    assert(fcntl(sfd, F_GETFD) == FD_CLOEXEC);
+    int sfd2 = socket(AF_UNIX, SOCK_STREAM, 0);
+    assert(sfd2 >= 0);
+    assert(fcntl(sfd2, F_SETFD, FD_CLOEXEC) == 0);
+    // kept across execve
+    int kept = dup(fds[1]);
+    assert(kept >= 0);
+
+    int pid = fork();
+    assert(pid >= 0);
+    if (pid == 0) {
+        char args[6][16];
+        int list[6] = {fds[0], fds[1], efd, sfd, sfd2, kept};
+        for (int i = 0; i < 6; i++)
+            snprintf(args[i], sizeof(args[i]), "%d", list[i]);
+        execl(path, path, args[0], args[1], args[2], args[3], args[4], args[5], NULL);
+        _exit(1);
+    }
+    int status;
+    assert(waitpid(pid, &status, 0) == pid);
+    assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+    close(fds[0]);
+    close(fds[1]);
+    close(efd);
+    close(sfd);
+    close(sfd2);
+    close(kept);
+}
+
+int main(int argc, char **argv)
+{
+    if (argc == 7) {
+        // after execve: only the last one is open
+        for (int i = 1; i < 6; i++)
+            assert(fcntl(atoi(argv[i]), F_GETFD) == -1 && errno == EBADF);
+        assert(fcntl(atoi(argv[6]), F_GETFD) == 0);
+        return 0;
+    }
+    test_dup();
+    test_pipe2();
+    test_exec(argv[0]);
+    printf("cloexec test passed\n");
+    return 0;
+}
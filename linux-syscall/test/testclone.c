@@ -0,0 +1,107 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <errno.h>
+#include <assert.h>
+#include <fcntl.h>
+#include <sched.h>
+#include <signal.h>
+#include <sys/wait.h>
+
+#define STACK_SIZE 0x4000
+
+static char stack[STACK_SIZE] __attribute__((aligned(16)));
+
+static int open_file(void *arg)
+{
+    int fd = open("/dev/null", O_RDONLY);
+    _exit(fd == *(int *)arg ? 0 : 1);
+}
+
+static int change_directory(void *arg)
+{
+    _exit(chdir((const char *)arg) == 0 ? 0 : 1);
+}
+
+static int do_nothing(void *arg)
+{
+    _exit(0);
+}
+
+static int wait_exit(pid_t pid)
+{
+    int status;
+    assert(waitpid(pid, &status, 0) == pid);
+    assert(WIFEXITED(status));
+    return WEXITSTATUS(status);
+}
+
+int main(int argc, char **argv)
+{
+    // executed by the child of vfork
+    if (argc > 1)
+        return 7;
+
+    void *top = stack + STACK_SIZE;
+
+    // unsupported or inconsistent flags are rejected
+    assert(clone(do_nothing, top, CLONE_NEWPID | SIGCHLD, NULL) == -1 && errno == EINVAL);
+    assert(clone(do_nothing, top, CLONE_THREAD | CLONE_VM | SIGCHLD, NULL) == -1 && errno == EINVAL);
+    assert(clone(do_nothing, top, CLONE_SIGHAND | SIGCHLD, NULL) == -1 && errno == EINVAL);
+
+    // a file opened by the child appears in the shared table
+    int fd = open("/dev/null", O_RDONLY);
+    assert(fd >= 0);
+    close(fd);
+    pid_t pid = clone(open_file, top, CLONE_FILES | SIGCHLD, &fd);
+    assert(pid > 0);
+    assert(wait_exit(pid) == 0);
+    assert(close(fd) == 0);
+
+    // without CLONE_FILES the table is copied
+    pid = clone(open_file, top, SIGCHLD, &fd);
+    assert(pid > 0);
+    assert(wait_exit(pid) == 0);
+    assert(close(fd) == -1 && errno == EBADF);
+
+    // the working directory is shared with CLONE_FS
+    char cwd[64];
+    assert(getcwd(cwd, sizeof(cwd)));
+    pid = clone(change_directory, top, CLONE_FS | SIGCHLD, "/dev");
+    assert(wait_exit(pid) == 0);
+    char buf[64];
+    assert(getcwd(buf, sizeof(buf)) && strcmp(buf, "/dev") == 0);
+    pid = clone(change_directory, top, SIGCHLD, "/");
+    assert(wait_exit(pid) == 0);
+    assert(getcwd(buf, sizeof(buf)) && strcmp(buf, "/dev") == 0);
+    assert(chdir(cwd) == 0);
+
+    // the parent gets the child TID
+    pid_t tid = 0;
+    pid = clone(do_nothing, top, CLONE_PARENT_SETTID | SIGCHLD, NULL, &tid);
+    assert(pid > 0 && tid > 0);
+    assert(wait_exit(pid) == 0);
+
+    // the parent is suspended until the child exits
+    pid = clone(do_nothing, top, CLONE_VM | CLONE_VFORK | SIGCHLD, NULL);
+    assert(pid > 0);
+    int status;
+    assert(waitpid(pid, &status, WNOHANG) == pid);
+    assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+    // the parent is resumed when the child calls execve, and its memory is intact
+    int before = 42;
+    pid = vfork();
+    if (pid == 0)
+    {
+        execl("/bin/testclone", "/bin/testclone", "exec", NULL);
+        _exit(1);
+    }
+    assert(pid > 0 && before == 42);
+    assert(wait_exit(pid) == 7);
+
+    printf("clone test passed\n");
+    return 0;
+}
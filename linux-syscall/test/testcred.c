@@ -0,0 +1,69 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdlib.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <unistd.h>
+#include <assert.h>
+#include <grp.h>
+#include <sys/stat.h>
+#include <sys/types.h>
+#include <sys/wait.h>
+
+#define NOBODY 65534
+
+int main(int argc, char **argv)
+{
+    // processes start as root
+    assert(getuid() == 0 && geteuid() == 0);
+    assert(getgid() == 0 && getegid() == 0);
+
+    // supplementary groups
+    gid_t groups[2] = {100, 200};
+    gid_t out[4];
+    assert(setgroups(2, groups) == 0);
+    assert(getgroups(0, NULL) == 2);
+    assert(getgroups(4, out) == 2);
+    assert(out[0] == 100 && out[1] == 200);
+    assert(getgroups(1, out) == -1 && errno == EINVAL);
+    printf("groups ok\n");
+
+    pid_t pid = fork();
+    if (pid == 0) {
+        // credentials are inherited
+        assert(getgroups(0, NULL) == 2);
+
+        // drop privileges, but keep root as the saved set-user-ID
+        assert(setgroups(0, NULL) == 0);
+        assert(setresgid(NOBODY, NOBODY, NOBODY) == 0);
+        assert(setresuid(NOBODY, NOBODY, 0) == 0);
+        uid_t ruid, euid, suid;
+        assert(getresuid(&ruid, &euid, &suid) == 0);
+        assert(ruid == NOBODY && euid == NOBODY && suid == 0);
+        assert(setgroups(0, NULL) == -1 && errno == EPERM);
+
+        // permissions are checked against the file mode
+        assert(access("/bin/busybox", R_OK | X_OK) == 0);
+        assert(access("/bin/busybox", W_OK) == -1 && errno == EACCES);
+        assert(open("/bin/busybox", O_WRONLY) == -1 && errno == EACCES);
+        assert(chmod("/bin/busybox", 0777) == -1 && errno == EPERM);
+        assert(chown("/bin/busybox", NOBODY, NOBODY) == -1 && errno == EPERM);
+
+        // switch back with the saved set-user-ID
+        assert(seteuid(0) == 0);
+        assert(geteuid() == 0 && getuid() == NOBODY);
+
+        // a privileged setuid sets all user IDs
+        assert(setuid(NOBODY) == 0);
+        assert(setuid(0) == -1 && errno == EPERM);
+        exit(0);
+    }
+    int status;
+    assert(waitpid(pid, &status, 0) == pid);
+    assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+    printf("setuid ok\n");
+
+    // the parent is not affected
+    assert(getuid() == 0 && geteuid() == 0);
+    return 0;
+}
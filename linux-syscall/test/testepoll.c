@@ -0,0 +1,102 @@
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <unistd.h>
+#include <assert.h>
+#include <sys/epoll.h>
+#include <sys/time.h>
+
+static volatile int handled = 0;
+
+void handler(int signum)
+{
+    handled = signum;
+}
+
+int main(int argc, char **argv)
+{
+    int fds[2];
+    char buf[16];
+    struct epoll_event ev, events[4];
+
+    assert(pipe(fds) == 0);
+    int epfd = epoll_create1(EPOLL_CLOEXEC);
+    assert(epfd >= 0);
+
+    // level-triggered
+    ev.events = EPOLLIN;
+    ev.data.u64 = 42;
+    assert(epoll_ctl(epfd, EPOLL_CTL_ADD, fds[0], &ev) == 0);
+    assert(epoll_ctl(epfd, EPOLL_CTL_ADD, fds[0], &ev) == -1);
+    assert(epoll_wait(epfd, events, 4, 0) == 0);
+    assert(write(fds[1], "hello", 5) == 5);
+    assert(epoll_wait(epfd, events, 4, 100) == 1);
+    assert(events[0].events & EPOLLIN);
+    assert(events[0].data.u64 == 42);
+    // still readable
+    assert(epoll_wait(epfd, events, 4, 0) == 1);
+    printf("level-triggered ok\n");
+
+    // edge-triggered: reported once until it changes
+    ev.events = EPOLLIN | EPOLLET;
+    ev.data.u64 = 42;
+    assert(epoll_ctl(epfd, EPOLL_CTL_MOD, fds[0], &ev) == 0);
+    assert(epoll_wait(epfd, events, 4, 0) == 1);
+    assert(epoll_wait(epfd, events, 4, 100) == 0);
+    assert(read(fds[0], buf, sizeof(buf)) == 5);
+    assert(epoll_wait(epfd, events, 4, 0) == 0);
+    assert(write(fds[1], "world", 5) == 5);
+    assert(epoll_wait(epfd, events, 4, 100) == 1);
+    assert(events[0].data.u64 == 42);
+    assert(epoll_wait(epfd, events, 4, 0) == 0);
+    assert(read(fds[0], buf, sizeof(buf)) == 5);
+    printf("edge-triggered ok\n");
+
+    // one-shot
+    ev.events = EPOLLIN | EPOLLONESHOT;
+    assert(epoll_ctl(epfd, EPOLL_CTL_MOD, fds[0], &ev) == 0);
+    assert(write(fds[1], "!", 1) == 1);
+    assert(epoll_wait(epfd, events, 4, 0) == 1);
+    assert(epoll_wait(epfd, events, 4, 0) == 0);
+    assert(epoll_ctl(epfd, EPOLL_CTL_MOD, fds[0], &ev) == 0);
+    assert(epoll_wait(epfd, events, 4, 0) == 1);
+    printf("one-shot ok\n");
+
+    assert(epoll_ctl(epfd, EPOLL_CTL_DEL, fds[0], NULL) == 0);
+    assert(epoll_ctl(epfd, EPOLL_CTL_DEL, fds[0], NULL) == -1);
+    assert(epoll_wait(epfd, events, 4, 0) == 0);
+
+    // closed files are removed from the interest list
+    int fds2[2];
+    assert(pipe(fds2) == 0);
+    ev.events = EPOLLOUT;
+    assert(epoll_ctl(epfd, EPOLL_CTL_ADD, fds2[1], &ev) == 0);
+    assert(epoll_wait(epfd, events, 4, 0) == 1);
+    close(fds2[1]);
+    assert(epoll_wait(epfd, events, 4, 0) == 0);
+    assert(epoll_ctl(epfd, EPOLL_CTL_DEL, fds2[1], NULL) == -1);
+    close(fds2[0]);
+    printf("close ok\n");
+
+    // a signal unblocked by the mask of epoll_pwait interrupts the wait
+    struct sigaction sa = {.sa_handler = handler};
+    assert(sigaction(SIGALRM, &sa, NULL) == 0);
+    sigset_t set, empty;
+    sigemptyset(&set);
+    sigemptyset(&empty);
+    sigaddset(&set, SIGALRM);
+    assert(sigprocmask(SIG_BLOCK, &set, NULL) == 0);
+    assert(read(fds[0], buf, sizeof(buf)) == 1);
+    ev.events = EPOLLIN;
+    assert(epoll_ctl(epfd, EPOLL_CTL_ADD, fds[0], &ev) == 0);
+    struct itimerval it = {.it_value = {.tv_usec = 20000}};
+    assert(setitimer(ITIMER_REAL, &it, NULL) == 0);
+    assert(epoll_pwait(epfd, events, 4, -1, &empty) == -1 && errno == EINTR);
+    assert(handled == SIGALRM);
+    printf("signal ok\n");
+
+    close(epfd);
+    close(fds[0]);
+    close(fds[1]);
+    return 0;
+}
@@ -0,0 +1,204 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdint.h>
+#include <string.h>
+#include <unistd.h>
+#include <errno.h>
+#include <assert.h>
+#include <poll.h>
+#include <signal.h>
+#include <time.h>
+#include <sys/epoll.h>
+#include <sys/eventfd.h>
+#include <sys/signalfd.h>
+#include <sys/timerfd.h>
+
+void test_eventfd()
+{
+    uint64_t value;
+    int fd = eventfd(3, EFD_NONBLOCK);
+    assert(fd >= 0);
+    value = 4;
+    assert(write(fd, &value, sizeof(value)) == sizeof(value));
+    assert(read(fd, &value, sizeof(value)) == sizeof(value));
+    assert(value == 7);
+    // the counter is reset by a read
+    assert(read(fd, &value, sizeof(value)) == -1 && errno == EAGAIN);
+
+    // invalid writes
+    value = UINT64_MAX;
+    assert(write(fd, &value, sizeof(value)) == -1 && errno == EINVAL);
+    assert(write(fd, &value, 4) == -1 && errno == EINVAL);
+    assert(read(fd, &value, 4) == -1 && errno == EINVAL);
+
+    // the counter can not exceed UINT64_MAX - 1
+    value = UINT64_MAX - 1;
+    assert(write(fd, &value, sizeof(value)) == sizeof(value));
+    value = 1;
+    assert(write(fd, &value, sizeof(value)) == -1 && errno == EAGAIN);
+    struct pollfd pfd = {.fd = fd, .events = POLLIN | POLLOUT};
+    assert(poll(&pfd, 1, 0) == 1);
+    assert(pfd.revents == POLLIN);
+    assert(read(fd, &value, sizeof(value)) == sizeof(value));
+    assert(poll(&pfd, 1, 0) == 1);
+    assert(pfd.revents == POLLOUT);
+    close(fd);
+
+    // a read decreases the counter by 1 with EFD_SEMAPHORE
+    fd = eventfd(2, EFD_SEMAPHORE | EFD_CLOEXEC);
+    assert(fd >= 0);
+    assert(read(fd, &value, sizeof(value)) == sizeof(value) && value == 1);
+    assert(read(fd, &value, sizeof(value)) == sizeof(value) && value == 1);
+    close(fd);
+
+    assert(eventfd(0, 0x1000) == -1 && errno == EINVAL);
+}
+
+void test_eventfd_epoll()
+{
+    uint64_t value = 1;
+    int fd = eventfd(0, 0);
+    int epfd = epoll_create1(0);
+    struct epoll_event ev = {.events = EPOLLIN, .data.fd = fd};
+    assert(epoll_ctl(epfd, EPOLL_CTL_ADD, fd, &ev) == 0);
+    assert(epoll_wait(epfd, &ev, 1, 0) == 0);
+    if (fork() == 0)
+    {
+        usleep(10000);
+        assert(write(fd, &value, sizeof(value)) == sizeof(value));
+        _exit(0);
+    }
+    assert(epoll_wait(epfd, &ev, 1, 1000) == 1);
+    assert(ev.data.fd == fd && (ev.events & EPOLLIN));
+    // a blocking read returns once the counter is not zero
+    assert(read(fd, &value, sizeof(value)) == sizeof(value) && value == 1);
+
+    // a blocking write waits until a read makes room for the value
+    value = UINT64_MAX - 1;
+    assert(write(fd, &value, sizeof(value)) == sizeof(value));
+    if (fork() == 0)
+    {
+        usleep(10000);
+        assert(read(fd, &value, sizeof(value)) == sizeof(value));
+        _exit(value == UINT64_MAX - 1 ? 0 : 1);
+    }
+    value = 2;
+    assert(write(fd, &value, sizeof(value)) == sizeof(value));
+    assert(read(fd, &value, sizeof(value)) == sizeof(value) && value == 2);
+    close(epfd);
+    close(fd);
+}
+
+void test_timerfd()
+{
+    uint64_t count;
+    int fd = timerfd_create(CLOCK_MONOTONIC, 0);
+    assert(fd >= 0);
+    struct itimerspec its = {.it_value = {.tv_nsec = 20000000}};
+    struct itimerspec old;
+    assert(timerfd_settime(fd, 0, &its, NULL) == 0);
+    assert(timerfd_gettime(fd, &old) == 0);
+    assert(old.it_value.tv_sec == 0 && old.it_value.tv_nsec > 0);
+    // a blocking read waits for the expiration
+    assert(read(fd, &count, sizeof(count)) == sizeof(count) && count == 1);
+    assert(timerfd_gettime(fd, &old) == 0);
+    assert(old.it_value.tv_sec == 0 && old.it_value.tv_nsec == 0);
+
+    // expirations of a periodic timer are accumulated
+    its.it_interval.tv_nsec = 10000000;
+    assert(timerfd_settime(fd, 0, &its, NULL) == 0);
+    usleep(50000);
+    assert(read(fd, &count, sizeof(count)) == sizeof(count) && count >= 2);
+
+    // disarm the timer
+    memset(&its, 0, sizeof(its));
+    assert(timerfd_settime(fd, 0, &its, &old) == 0);
+    assert(old.it_interval.tv_nsec == 10000000);
+    close(fd);
+
+    // the first expiration at an absolute time
+    fd = timerfd_create(CLOCK_MONOTONIC, TFD_NONBLOCK);
+    assert(read(fd, &count, sizeof(count)) == -1 && errno == EAGAIN);
+    struct timespec now;
+    assert(clock_gettime(CLOCK_MONOTONIC, &now) == 0);
+    its.it_value = now;
+    its.it_value.tv_nsec += 10000000;
+    if (its.it_value.tv_nsec >= 1000000000)
+    {
+        its.it_value.tv_sec++;
+        its.it_value.tv_nsec -= 1000000000;
+    }
+    assert(timerfd_settime(fd, TFD_TIMER_ABSTIME, &its, NULL) == 0);
+    struct pollfd pfd = {.fd = fd, .events = POLLIN};
+    assert(poll(&pfd, 1, 1000) == 1 && pfd.revents == POLLIN);
+    assert(read(fd, &count, sizeof(count)) == sizeof(count) && count == 1);
+
+    // invalid arguments
+    its.it_value.tv_nsec = 1000000000;
+    assert(timerfd_settime(fd, 0, &its, NULL) == -1 && errno == EINVAL);
+    assert(write(fd, &count, sizeof(count)) == -1 && errno == EINVAL);
+    close(fd);
+    assert(timerfd_create(CLOCK_PROCESS_CPUTIME_ID, 0) == -1 && errno == EINVAL);
+}
+
+void test_signalfd()
+{
+    sigset_t mask;
+    sigemptyset(&mask);
+    sigaddset(&mask, SIGUSR1);
+    assert(sigprocmask(SIG_BLOCK, &mask, NULL) == 0);
+    int fd = signalfd(-1, &mask, SFD_NONBLOCK);
+    assert(fd >= 0);
+
+    struct signalfd_siginfo info;
+    assert(read(fd, &info, sizeof(info)) == -1 && errno == EAGAIN);
+    assert(read(fd, &info, 4) == -1 && errno == EINVAL);
+
+    assert(kill(getpid(), SIGUSR1) == 0);
+    struct pollfd pfd = {.fd = fd, .events = POLLIN};
+    assert(poll(&pfd, 1, 0) == 1 && pfd.revents == POLLIN);
+    assert(read(fd, &info, sizeof(info)) == sizeof(info));
+    assert(info.ssi_signo == SIGUSR1);
+    assert(info.ssi_pid == getpid());
+    assert(info.ssi_code == SI_USER);
+    // the signal has been accepted
+    sigset_t pending;
+    assert(sigpending(&pending) == 0);
+    assert(!sigismember(&pending, SIGUSR1));
+
+    // signals not in the mask are not accepted
+    sigaddset(&mask, SIGUSR2);
+    assert(sigprocmask(SIG_BLOCK, &mask, NULL) == 0);
+    assert(kill(getpid(), SIGUSR2) == 0);
+    assert(read(fd, &info, sizeof(info)) == -1 && errno == EAGAIN);
+    // update the mask
+    assert(signalfd(fd, &mask, 0) == fd);
+    assert(read(fd, &info, sizeof(info)) == sizeof(info));
+    assert(info.ssi_signo == SIGUSR2);
+    close(fd);
+
+    // a blocking read waits for a signal
+    fd = signalfd(-1, &mask, 0);
+    if (fork() == 0)
+    {
+        usleep(10000);
+        kill(getppid(), SIGUSR1);
+        _exit(0);
+    }
+    assert(read(fd, &info, sizeof(info)) == sizeof(info));
+    assert(info.ssi_signo == SIGUSR1);
+    close(fd);
+
+    // only a signalfd can be updated
+    assert(signalfd(0, &mask, 0) == -1 && errno == EINVAL);
+}
+
+int main(int argc, char **argv)
+{
+    test_eventfd();
+    test_eventfd_epoll();
+    test_timerfd();
+    test_signalfd();
+    printf("eventfd test passed\n");
+    return 0;
+}
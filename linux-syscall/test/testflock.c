@@ -0,0 +1,290 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <assert.h>
+#include <pthread.h>
+#include <sys/file.h>
+#include <sys/time.h>
+#include <sys/wait.h>
+
+#define PATH "/tmp/testflock"
+
+int fd;
+pid_t parent;
+
+// run `f` in a child process, and check that it succeeds
+void in_child(void (*f)(void))
+{
+    pid_t pid = fork();
+    assert(pid >= 0);
+    if (pid == 0) {
+        f();
+        _exit(0);
+    }
+    int status;
+    assert(waitpid(pid, &status, 0) == pid);
+    assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+
+int set_lock(int fd, int cmd, short type, off_t start, off_t len)
+{
+    struct flock fl = {.l_type = type, .l_whence = SEEK_SET, .l_start = start, .l_len = len};
+    return fcntl(fd, cmd, &fl);
+}
+
+struct flock get_lock(int fd, short type, off_t start, off_t len)
+{
+    struct flock fl = {.l_type = type, .l_whence = SEEK_SET, .l_start = start, .l_len = len};
+    assert(fcntl(fd, F_GETLK, &fl) == 0);
+    return fl;
+}
+
+void test_flock()
+{
+    int fd1 = open(PATH, O_RDWR);
+    int fd2 = open(PATH, O_RDWR);
+    assert(fd1 >= 0 && fd2 >= 0);
+
+    assert(flock(fd1, LOCK_SH) == 0);
+    assert(flock(fd2, LOCK_SH | LOCK_NB) == 0);
+    assert(flock(fd2, LOCK_EX | LOCK_NB) == -1 && errno == EWOULDBLOCK);
+    assert(flock(fd1, LOCK_UN) == 0);
+    // upgrade
+    assert(flock(fd2, LOCK_EX | LOCK_NB) == 0);
+    assert(flock(fd1, LOCK_SH | LOCK_NB) == -1 && errno == EWOULDBLOCK);
+    assert(flock(fd2, LOCK_SH | LOCK_UN) == -1 && errno == EINVAL);
+    assert(flock(1000, LOCK_SH) == -1 && errno == EBADF);
+
+    // a duplicated descriptor shares the lock
+    int fd3 = dup(fd2);
+    assert(fd3 >= 0);
+    close(fd2);
+    assert(flock(fd1, LOCK_SH | LOCK_NB) == -1 && errno == EWOULDBLOCK);
+    // released when the last descriptor is closed
+    close(fd3);
+    assert(flock(fd1, LOCK_EX | LOCK_NB) == 0);
+
+    // flock locks do not conflict with record locks
+    assert(set_lock(fd1, F_SETLK, F_WRLCK, 0, 0) == 0);
+    assert(set_lock(fd1, F_SETLK, F_UNLCK, 0, 0) == 0);
+
+    // a forked child shares the lock, and can release it
+    fd = fd1;
+    int pid = fork();
+    assert(pid >= 0);
+    if (pid == 0) {
+        assert(flock(fd, LOCK_UN) == 0);
+        _exit(0);
+    }
+    int status;
+    assert(waitpid(pid, &status, 0) == pid);
+    fd2 = open(PATH, O_RDWR);
+    assert(flock(fd2, LOCK_EX | LOCK_NB) == 0);
+    close(fd1);
+    close(fd2);
+}
+
+void check_split()
+{
+    struct flock fl;
+    // [0, 1] and [4, 9] are write locked, [2, 3] read locked
+    fl = get_lock(fd, F_RDLCK, 2, 2);
+    assert(fl.l_type == F_UNLCK);
+    fl = get_lock(fd, F_WRLCK, 2, 1);
+    assert(fl.l_type == F_RDLCK && fl.l_start == 2 && fl.l_len == 2 && fl.l_pid == parent);
+    fl = get_lock(fd, F_RDLCK, 5, 100);
+    assert(fl.l_type == F_WRLCK && fl.l_start == 4 && fl.l_len == 6 && fl.l_pid == parent);
+    fl = get_lock(fd, F_WRLCK, 10, 0);
+    assert(fl.l_type == F_UNLCK);
+    assert(set_lock(fd, F_SETLK, F_WRLCK, 10, 0) == 0);
+    assert(set_lock(fd, F_SETLK, F_RDLCK, 3, 1) == 0);
+    assert(set_lock(fd, F_SETLK, F_RDLCK, 1, 1) == -1 && (errno == EAGAIN || errno == EACCES));
+}
+
+void check_merged()
+{
+    struct flock fl = get_lock(fd, F_RDLCK, 0, 0);
+    assert(fl.l_type == F_WRLCK && fl.l_start == 0 && fl.l_len == 10 && fl.l_pid == parent);
+}
+
+void check_unlocked()
+{
+    struct flock fl = get_lock(fd, F_WRLCK, 0, 0);
+    assert(fl.l_type == F_UNLCK);
+}
+
+void test_record()
+{
+    fd = open(PATH, O_RDWR);
+    assert(fd >= 0);
+    parent = getpid();
+
+    assert(set_lock(fd, F_SETLK, F_WRLCK, 0, 10) == 0);
+    // split by a read lock
+    assert(set_lock(fd, F_SETLK, F_RDLCK, 2, 2) == 0);
+    in_child(check_split);
+    // merged with adjacent locks
+    assert(set_lock(fd, F_SETLK, F_WRLCK, 2, 2) == 0);
+    in_child(check_merged);
+    // relative to the end of file
+    assert(set_lock(fd, F_SETLK, F_UNLCK, 0, 0) == 0);
+    in_child(check_unlocked);
+    assert(ftruncate(fd, 10) == 0);
+    struct flock fl = {.l_type = F_WRLCK, .l_whence = SEEK_END, .l_start = -10, .l_len = 10};
+    assert(fcntl(fd, F_SETLK, &fl) == 0);
+    in_child(check_merged);
+
+    // a process never conflicts with itself
+    fl = get_lock(fd, F_WRLCK, 0, 0);
+    assert(fl.l_type == F_UNLCK);
+    // closing any descriptor of the file releases the locks of the process
+    int fd2 = open(PATH, O_RDONLY);
+    assert(fd2 >= 0);
+    close(fd2);
+    in_child(check_unlocked);
+
+    // invalid arguments
+    assert(set_lock(fd, F_SETLK, 100, 0, 0) == -1 && errno == EINVAL);
+    assert(set_lock(fd, F_SETLK, F_WRLCK, -1, 1) == -1 && errno == EINVAL);
+    assert(set_lock(fd, F_SETLK, F_WRLCK, 1, -2) == -1 && errno == EINVAL);
+    assert(fcntl(fd, F_GETLK, &(struct flock){.l_type = F_UNLCK}) == -1 && errno == EINVAL);
+    fd2 = open(PATH, O_RDONLY);
+    assert(set_lock(fd2, F_SETLK, F_WRLCK, 0, 0) == -1 && errno == EBADF);
+    close(fd2);
+}
+
+void test_ofd()
+{
+    int fd1 = open(PATH, O_RDWR);
+    int fd2 = open(PATH, O_RDWR);
+    assert(fd1 >= 0 && fd2 >= 0);
+    // open file description locks conflict within a process
+    assert(set_lock(fd1, F_OFD_SETLK, F_WRLCK, 0, 10) == 0);
+    assert(set_lock(fd2, F_OFD_SETLK, F_RDLCK, 5, 1) == -1 && errno == EAGAIN);
+    struct flock fl = {.l_type = F_WRLCK, .l_whence = SEEK_SET, .l_start = 0, .l_len = 0};
+    assert(fcntl(fd2, F_OFD_GETLK, &fl) == 0);
+    assert(fl.l_type == F_WRLCK && fl.l_start == 0 && fl.l_len == 10 && fl.l_pid == -1);
+    // and with the record locks of the process
+    assert(set_lock(fd2, F_SETLK, F_RDLCK, 5, 1) == -1 && errno == EAGAIN);
+    fl.l_pid = 1;
+    assert(fcntl(fd2, F_OFD_SETLK, &fl) == -1 && errno == EINVAL);
+    // released when the open file description is closed
+    close(fd1);
+    assert(set_lock(fd2, F_OFD_SETLK, F_WRLCK, 0, 0) == 0);
+    close(fd2);
+}
+
+void test_deadlock()
+{
+    int fds[2];
+    char c;
+    fd = open(PATH, O_RDWR);
+    assert(fd >= 0 && pipe(fds) == 0);
+    assert(set_lock(fd, F_SETLK, F_WRLCK, 0, 1) == 0);
+
+    int pid = fork();
+    assert(pid >= 0);
+    if (pid == 0) {
+        assert(set_lock(fd, F_SETLK, F_WRLCK, 1, 1) == 0);
+        assert(write(fds[1], "x", 1) == 1);
+        // blocks until the parent unlocks
+        assert(set_lock(fd, F_SETLKW, F_WRLCK, 0, 1) == 0);
+        _exit(0);
+    }
+    assert(read(fds[0], &c, 1) == 1);
+    usleep(100000);
+    assert(set_lock(fd, F_SETLKW, F_WRLCK, 1, 1) == -1 && errno == EDEADLK);
+    assert(set_lock(fd, F_SETLK, F_UNLCK, 0, 1) == 0);
+    int status;
+    assert(waitpid(pid, &status, 0) == pid);
+    assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+    // released when the child exits
+    assert(set_lock(fd, F_SETLK, F_WRLCK, 0, 0) == 0);
+    close(fds[0]);
+    close(fds[1]);
+}
+
+void *wait_byte_2(void *arg)
+{
+    assert(set_lock(fd, F_SETLKW, F_WRLCK, 2, 1) == 0);
+    return NULL;
+}
+
+void test_deadlock_threads()
+{
+    int fds[2];
+    char c;
+    fd = open(PATH, O_RDWR);
+    assert(fd >= 0 && pipe(fds) == 0);
+    assert(set_lock(fd, F_SETLK, F_WRLCK, 0, 1) == 0);
+    assert(set_lock(fd, F_SETLK, F_WRLCK, 2, 1) == 0);
+
+    int pid = fork();
+    assert(pid >= 0);
+    if (pid == 0) {
+        assert(set_lock(fd, F_SETLK, F_WRLCK, 1, 1) == 0);
+        // two threads of the child wait for the parent
+        pthread_t thread;
+        assert(pthread_create(&thread, NULL, wait_byte_2, NULL) == 0);
+        assert(write(fds[1], "x", 1) == 1);
+        assert(set_lock(fd, F_SETLKW, F_WRLCK, 0, 1) == 0);
+        assert(pthread_join(thread, NULL) == 0);
+        _exit(0);
+    }
+    assert(read(fds[0], &c, 1) == 1);
+    usleep(100000);
+    // one thread of the child takes its lock, the other one is still waiting
+    assert(set_lock(fd, F_SETLK, F_UNLCK, 2, 1) == 0);
+    usleep(100000);
+    assert(set_lock(fd, F_SETLKW, F_WRLCK, 1, 1) == -1 && errno == EDEADLK);
+    assert(set_lock(fd, F_SETLK, F_UNLCK, 0, 1) == 0);
+    int status;
+    assert(waitpid(pid, &status, 0) == pid);
+    assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+    close(fds[0]);
+    close(fds[1]);
+    close(fd);
+}
+
+void on_alarm(int signo) {}
+
+void wait_interrupted()
+{
+    struct sigaction sa = {.sa_handler = on_alarm};
+    assert(sigaction(SIGALRM, &sa, NULL) == 0);
+    struct itimerval timer = {.it_value = {.tv_usec = 100000}};
+    assert(setitimer(ITIMER_REAL, &timer, NULL) == 0);
+    assert(set_lock(fd, F_SETLKW, F_RDLCK, 0, 1) == -1 && errno == EINTR);
+    assert(setitimer(ITIMER_REAL, &timer, NULL) == 0);
+    assert(flock(fd, LOCK_EX) == -1 && errno == EINTR);
+}
+
+void test_interrupt()
+{
+    // `fd` is still write locked by the parent, and the file is flocked by `fd2`
+    int fd2 = open(PATH, O_RDWR);
+    assert(flock(fd2, LOCK_EX) == 0);
+    in_child(wait_interrupted);
+    close(fd2);
+    close(fd);
+}
+
+int main(int argc, char **argv)
+{
+    int fd = open(PATH, O_CREAT | O_RDWR, 0644);
+    assert(fd >= 0);
+    close(fd);
+    test_flock();
+    test_record();
+    test_ofd();
+    test_deadlock();
+    test_interrupt();
+    test_deadlock_threads();
+    printf("flock test passed\n");
+    return 0;
+}
@@ -0,0 +1,162 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdint.h>
+#include <unistd.h>
+#include <errno.h>
+#include <assert.h>
+#include <pthread.h>
+#include <time.h>
+#include <linux/futex.h>
+#include <sys/mman.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+
+static long futex(int *uaddr, int op, int val, uintptr_t val2, int *uaddr2, int val3)
+{
+    return syscall(SYS_futex, uaddr, op, val, val2, uaddr2, val3);
+}
+
+static int word = 0;
+
+void *wake_later(void *arg)
+{
+    usleep(10000);
+    __atomic_store_n(&word, 1, __ATOMIC_SEQ_CST);
+    futex(&word, FUTEX_WAKE_PRIVATE, 1, 0, NULL, 0);
+    return NULL;
+}
+
+void test_wait_wake()
+{
+    // the value does not match
+    assert(futex(&word, FUTEX_WAIT_PRIVATE, 1, 0, NULL, 0) == -1 && errno == EAGAIN);
+
+    // a relative timeout expires
+    struct timespec timeout = {.tv_sec = 0, .tv_nsec = 10000000};
+    assert(futex(&word, FUTEX_WAIT_PRIVATE, 0, (uintptr_t)&timeout, NULL, 0) == -1 && errno == ETIMEDOUT);
+
+    // an absolute timeout expires
+    struct timespec now;
+    clock_gettime(CLOCK_MONOTONIC, &now);
+    now.tv_nsec += 10000000;
+    if (now.tv_nsec >= 1000000000)
+    {
+        now.tv_sec += 1;
+        now.tv_nsec -= 1000000000;
+    }
+    assert(futex(&word, FUTEX_WAIT_BITSET_PRIVATE, 0, (uintptr_t)&now, NULL, FUTEX_BITSET_MATCH_ANY) == -1 && errno == ETIMEDOUT);
+    assert(futex(&word, FUTEX_WAIT_BITSET_PRIVATE, 0, 0, NULL, 0) == -1 && errno == EINVAL);
+
+    // woken by another thread
+    pthread_t thread;
+    assert(pthread_create(&thread, NULL, wake_later, NULL) == 0);
+    while (__atomic_load_n(&word, __ATOMIC_SEQ_CST) == 0)
+    {
+        long ret = futex(&word, FUTEX_WAIT_PRIVATE, 0, 0, NULL, 0);
+        assert(ret == 0 || errno == EAGAIN);
+    }
+    assert(pthread_join(thread, NULL) == 0);
+
+    // process-shared futexes work on private memory too
+    assert(futex(&word, FUTEX_WAKE, 1, 0, NULL, 0) == 0);
+    assert(futex(&word, FUTEX_WAKE_BITSET_PRIVATE, 1, 0, NULL, 2) == 0);
+}
+
+void test_requeue()
+{
+    int a = 1, b = 0;
+    assert(futex(&a, FUTEX_CMP_REQUEUE_PRIVATE, 1, 1, &b, 0) == -1 && errno == EAGAIN);
+    assert(futex(&a, FUTEX_CMP_REQUEUE_PRIVATE, 1, 1, &b, 1) == 0);
+    assert(futex(&a, FUTEX_REQUEUE_PRIVATE, 1, 1, &b, 0) == 0);
+}
+
+void test_wake_op()
+{
+    int a = 0, b = 5;
+    // b += 3, and wake waiters on b if the old value was 5
+    int op = FUTEX_OP(FUTEX_OP_ADD, 3, FUTEX_OP_CMP_EQ, 5);
+    assert(futex(&a, FUTEX_WAKE_OP_PRIVATE, 1, 1, &b, op) == 0);
+    assert(b == 8);
+    // b = 1 << 4
+    op = FUTEX_OP(FUTEX_OP_SET | FUTEX_OP_OPARG_SHIFT, 4, FUTEX_OP_CMP_GT, 0);
+    assert(futex(&a, FUTEX_WAKE_OP_PRIVATE, 1, 1, &b, op) == 0);
+    assert(b == 16);
+}
+
+static pthread_mutex_t robust_mutex;
+
+void *lock_and_exit(void *arg)
+{
+    assert(pthread_mutex_lock(&robust_mutex) == 0);
+    return NULL;
+}
+
+static pthread_mutex_t *shared_mutex;
+
+void *lock_and_block(void *arg)
+{
+    int *fds = arg;
+    char c;
+    assert(pthread_mutex_lock(shared_mutex) == 0);
+    assert(write(fds[1], "", 1) == 1);
+    // blocked until the process exits
+    read(fds[2], &c, 1);
+    return NULL;
+}
+
+void test_robust()
+{
+    struct robust_list_head head = {.list = {.next = (struct robust_list *)&head}, .futex_offset = 0, .list_op_pending = NULL};
+    struct robust_list_head *old_head;
+    size_t len;
+    assert(syscall(SYS_get_robust_list, 0, &old_head, &len) == 0);
+    assert(syscall(SYS_set_robust_list, &head, sizeof(head) + 1) == -1 && errno == EINVAL);
+    assert(syscall(SYS_set_robust_list, &head, sizeof(head)) == 0);
+    struct robust_list_head *got;
+    assert(syscall(SYS_get_robust_list, 0, &got, &len) == 0);
+    assert(got == &head && len == sizeof(head));
+    assert(syscall(SYS_set_robust_list, old_head, sizeof(head)) == 0);
+
+    // the owner died while holding the mutex
+    pthread_mutexattr_t attr;
+    pthread_mutexattr_init(&attr);
+    pthread_mutexattr_setrobust(&attr, PTHREAD_MUTEX_ROBUST);
+    assert(pthread_mutex_init(&robust_mutex, &attr) == 0);
+    pthread_t thread;
+    assert(pthread_create(&thread, NULL, lock_and_exit, NULL) == 0);
+    assert(pthread_join(thread, NULL) == 0);
+    assert(pthread_mutex_lock(&robust_mutex) == EOWNERDEAD);
+    assert(pthread_mutex_consistent(&robust_mutex) == 0);
+    assert(pthread_mutex_unlock(&robust_mutex) == 0);
+
+    // the mutexes of all threads are released when the process exits
+    shared_mutex = mmap(NULL, sizeof(*shared_mutex), PROT_READ | PROT_WRITE, MAP_SHARED | MAP_ANONYMOUS, -1, 0);
+    assert(shared_mutex != MAP_FAILED);
+    pthread_mutexattr_setpshared(&attr, PTHREAD_PROCESS_SHARED);
+    assert(pthread_mutex_init(shared_mutex, &attr) == 0);
+    pid_t pid = fork();
+    if (pid == 0)
+    {
+        int fds[4];
+        char c;
+        assert(pipe(fds) == 0 && pipe(fds + 2) == 0);
+        assert(pthread_create(&thread, NULL, lock_and_block, fds) == 0);
+        assert(read(fds[0], &c, 1) == 1);
+        _exit(0);
+    }
+    int status;
+    assert(waitpid(pid, &status, 0) == pid && status == 0);
+    assert(pthread_mutex_lock(shared_mutex) == EOWNERDEAD);
+    assert(pthread_mutex_consistent(shared_mutex) == 0);
+    assert(pthread_mutex_unlock(shared_mutex) == 0);
+}
+
+int main(int argc, char **argv)
+{
+    test_wait_wake();
+    test_requeue();
+    test_wake_op();
+    test_robust();
+    printf("futex test passed\n");
+    return 0;
+}
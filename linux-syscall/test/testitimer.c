@@ -0,0 +1,190 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include <errno.h>
+#include <assert.h>
+#include <signal.h>
+#include <time.h>
+#include <sys/time.h>
+#include <sys/syscall.h>
+
+static volatile int alarms = 0;
+static volatile int vtalarms = 0;
+static volatile int profs = 0;
+static volatile int timer_signals = 0;
+static volatile int timer_code = 0;
+static volatile int timer_value = 0;
+
+static void handler(int signum)
+{
+    switch (signum)
+    {
+    case SIGALRM:
+        alarms++;
+        break;
+    case SIGVTALRM:
+        vtalarms++;
+        break;
+    case SIGPROF:
+        profs++;
+        break;
+    }
+}
+
+static void timer_handler(int signum, siginfo_t *info, void *ucontext)
+{
+    timer_signals++;
+    timer_code = info->si_code;
+    timer_value = info->si_value.sival_int;
+}
+
+static void wait_for(volatile int *count, int n)
+{
+    while (*count < n)
+        usleep(1000);
+}
+
+void test_real()
+{
+    struct itimerval it = {.it_value = {.tv_usec = 20000}};
+    struct itimerval old;
+    assert(setitimer(ITIMER_REAL, &it, NULL) == 0);
+    assert(getitimer(ITIMER_REAL, &old) == 0);
+    assert(old.it_value.tv_sec == 0 && old.it_value.tv_usec <= 20000);
+    wait_for(&alarms, 1);
+    // a one-shot timer is disarmed after it expires
+    assert(getitimer(ITIMER_REAL, &old) == 0);
+    assert(old.it_value.tv_sec == 0 && old.it_value.tv_usec == 0);
+
+    // a periodic timer is rearmed
+    it.it_interval.tv_usec = 10000;
+    assert(setitimer(ITIMER_REAL, &it, NULL) == 0);
+    wait_for(&alarms, 4);
+    memset(&it, 0, sizeof(it));
+    assert(setitimer(ITIMER_REAL, &it, &old) == 0);
+    assert(old.it_interval.tv_sec == 0 && old.it_interval.tv_usec == 10000);
+
+    // invalid arguments
+    it.it_value.tv_usec = 1000000;
+    assert(setitimer(ITIMER_REAL, &it, NULL) == -1 && errno == EINVAL);
+    assert(getitimer(3, &old) == -1 && errno == EINVAL);
+}
+
+void test_alarm()
+{
+    assert(alarm(10) == 0);
+    assert(alarm(0) == 10);
+    int count = alarms;
+    assert(alarm(1) == 0);
+    wait_for(&alarms, count + 1);
+}
+
+void test_cpu()
+{
+    // CPU time is only consumed while running
+    struct itimerval it = {.it_value = {.tv_usec = 10000}};
+    assert(setitimer(ITIMER_VIRTUAL, &it, NULL) == 0);
+    assert(setitimer(ITIMER_PROF, &it, NULL) == 0);
+    while (vtalarms == 0 || profs == 0)
+        getpid();
+    struct itimerval old;
+    assert(getitimer(ITIMER_VIRTUAL, &old) == 0);
+    assert(old.it_value.tv_sec == 0 && old.it_value.tv_usec == 0);
+}
+
+void test_posix_timer()
+{
+    struct sigaction sa;
+    memset(&sa, 0, sizeof(sa));
+    sa.sa_sigaction = timer_handler;
+    sa.sa_flags = SA_SIGINFO;
+    assert(sigaction(SIGUSR1, &sa, NULL) == 0);
+
+    struct sigevent sev;
+    memset(&sev, 0, sizeof(sev));
+    sev.sigev_notify = SIGEV_SIGNAL;
+    sev.sigev_signo = SIGUSR1;
+    sev.sigev_value.sival_int = 42;
+    timer_t timer;
+    assert(timer_create(CLOCK_MONOTONIC, &sev, &timer) == 0);
+
+    struct itimerspec its = {.it_value = {.tv_nsec = 20000000}};
+    struct itimerspec old;
+    assert(timer_settime(timer, 0, &its, NULL) == 0);
+    assert(timer_gettime(timer, &old) == 0);
+    assert(old.it_value.tv_sec == 0 && old.it_value.tv_nsec > 0);
+    wait_for(&timer_signals, 1);
+    assert(timer_code == SI_TIMER && timer_value == 42);
+    assert(timer_getoverrun(timer) == 0);
+    assert(timer_gettime(timer, &old) == 0);
+    assert(old.it_value.tv_sec == 0 && old.it_value.tv_nsec == 0);
+
+    // the first expiration at an absolute time
+    struct timespec now;
+    assert(clock_gettime(CLOCK_MONOTONIC, &now) == 0);
+    its.it_value = now;
+    its.it_value.tv_nsec += 20000000;
+    if (its.it_value.tv_nsec >= 1000000000)
+    {
+        its.it_value.tv_sec++;
+        its.it_value.tv_nsec -= 1000000000;
+    }
+    its.it_interval.tv_nsec = 10000000;
+    assert(timer_settime(timer, TIMER_ABSTIME, &its, NULL) == 0);
+    wait_for(&timer_signals, 3);
+    memset(&its, 0, sizeof(its));
+    assert(timer_settime(timer, 0, &its, &old) == 0);
+    assert(old.it_interval.tv_nsec == 10000000);
+
+    its.it_value.tv_nsec = 1000000000;
+    assert(timer_settime(timer, 0, &its, NULL) == -1 && errno == EINVAL);
+    assert(timer_delete(timer) == 0);
+    assert(timer_delete(timer) == -1 && errno == EINVAL);
+}
+
+/* the layout of `struct sigevent` in the kernel */
+struct kernel_sigevent
+{
+    union sigval value;
+    int signo;
+    int notify;
+    int tid;
+    int pad[11];
+};
+
+void test_thread_timer()
+{
+    int count = timer_signals;
+    struct kernel_sigevent sev = {
+        .value = {.sival_int = 7},
+        .signo = SIGUSR1,
+        .notify = SIGEV_THREAD_ID,
+        .tid = syscall(SYS_gettid),
+    };
+    int timer;
+    assert(syscall(SYS_timer_create, CLOCK_REALTIME, &sev, &timer) == 0);
+    struct itimerspec its = {.it_value = {.tv_nsec = 10000000}};
+    assert(syscall(SYS_timer_settime, timer, 0, &its, NULL) == 0);
+    wait_for(&timer_signals, count + 1);
+    assert(timer_value == 7);
+    assert(syscall(SYS_timer_delete, timer) == 0);
+
+    // the thread must be in the process
+    sev.tid = 0;
+    assert(syscall(SYS_timer_create, CLOCK_REALTIME, &sev, &timer) == -1 && errno == EINVAL);
+}
+
+int main(int argc, char **argv)
+{
+    signal(SIGALRM, handler);
+    signal(SIGVTALRM, handler);
+    signal(SIGPROF, handler);
+    test_real();
+    test_alarm();
+    test_cpu();
+    test_posix_timer();
+    test_thread_timer();
+    printf("itimer test passed\n");
+    return 0;
+}
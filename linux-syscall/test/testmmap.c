@@ -0,0 +1,120 @@
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include <errno.h>
+#include <assert.h>
+#include <fcntl.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+
+#define PAGE 4096
+
+void test_shared_anonymous()
+{
+    int *shared = mmap(NULL, PAGE, PROT_READ | PROT_WRITE, MAP_SHARED | MAP_ANONYMOUS, -1, 0);
+    int *private = mmap(NULL, PAGE, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    assert(shared != MAP_FAILED && private != MAP_FAILED);
+
+    // only the writes to the shared mapping are seen by the parent
+    pid_t pid = fork();
+    if (pid == 0)
+    {
+        *shared = 42;
+        *private = 42;
+        _exit(0);
+    }
+    int status;
+    assert(waitpid(pid, &status, 0) == pid && status == 0);
+    assert(*shared == 42);
+    assert(*private == 0);
+    assert(munmap(shared, PAGE) == 0);
+    assert(munmap(private, PAGE) == 0);
+}
+
+void test_shared_file()
+{
+    const char *path = "/tmp/testmmap";
+    int fd = open(path, O_RDWR | O_CREAT | O_TRUNC, 0644);
+    assert(fd >= 0);
+    assert(write(fd, "hello world", 11) == 11);
+
+    // mappings of the same file share pages
+    char *a = mmap(NULL, PAGE, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    char *b = mmap(NULL, PAGE, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    assert(a != MAP_FAILED && b != MAP_FAILED && a != b);
+    assert(memcmp(a, "hello world", 11) == 0);
+    memcpy(a, "HELLO", 5);
+    assert(memcmp(b, "HELLO world", 11) == 0);
+
+    // read and write see the pages of the shared mappings
+    char word[8];
+    assert(pread(fd, word, 5, 0) == 5 && memcmp(word, "HELLO", 5) == 0);
+    assert(pwrite(fd, "W", 1, 6) == 1);
+    assert(b[6] == 'W');
+    assert(pwrite(fd, "w", 1, 6) == 1);
+
+    // a private mapping is copied on write, and is not written back
+    char *c = mmap(NULL, PAGE, PROT_READ | PROT_WRITE, MAP_PRIVATE, fd, 0);
+    assert(c != MAP_FAILED);
+    assert(memcmp(c, "HELLO world", 11) == 0);
+    memcpy(c, "xxxxx", 5);
+    assert(memcmp(a, "HELLO world", 11) == 0);
+
+    // msync writes the shared pages back to the file
+    assert(msync(a, PAGE, MS_SYNC) == 0);
+    char buf[16];
+    assert(pread(fd, buf, sizeof(buf), 0) == 11);
+    assert(memcmp(buf, "HELLO world", 11) == 0);
+    assert(msync(a, PAGE, MS_SYNC | MS_ASYNC) == -1 && errno == EINVAL);
+    assert(msync(a + 1, PAGE, MS_SYNC) == -1 && errno == EINVAL);
+
+    // the writes of a child are written back by munmap
+    pid_t pid = fork();
+    if (pid == 0)
+    {
+        memcpy(b + 6, "WORLD", 5);
+        munmap(b, PAGE);
+        _exit(0);
+    }
+    int status;
+    assert(waitpid(pid, &status, 0) == pid && status == 0);
+    assert(memcmp(a, "HELLO WORLD", 11) == 0);
+    assert(munmap(a, PAGE) == 0);
+    assert(munmap(b, PAGE) == 0);
+    assert(munmap(c, PAGE) == 0);
+    assert(pread(fd, buf, sizeof(buf), 0) == 11);
+    assert(memcmp(buf, "HELLO WORLD", 11) == 0);
+
+    // the file is not extended by the mapping
+    assert(lseek(fd, 0, SEEK_END) == 11);
+    close(fd);
+
+    // the mapping requires read access, and write access if it is shared and writable
+    fd = open(path, O_WRONLY);
+    assert(mmap(NULL, PAGE, PROT_READ, MAP_PRIVATE, fd, 0) == MAP_FAILED && errno == EACCES);
+    close(fd);
+    fd = open(path, O_RDONLY);
+    assert(mmap(NULL, PAGE, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0) == MAP_FAILED && errno == EACCES);
+    a = mmap(NULL, PAGE, PROT_READ | PROT_WRITE, MAP_PRIVATE, fd, 0);
+    assert(a != MAP_FAILED);
+    assert(munmap(a, PAGE) == 0);
+    close(fd);
+
+    // a private mapping at an offset
+    fd = open(path, O_RDWR);
+    assert(pwrite(fd, "page", 4, PAGE) == 4);
+    a = mmap(NULL, PAGE, PROT_READ, MAP_PRIVATE, fd, PAGE);
+    assert(a != MAP_FAILED);
+    assert(memcmp(a, "page", 4) == 0 && a[4] == 0);
+    assert(munmap(a, PAGE) == 0);
+    close(fd);
+    assert(unlink(path) == 0);
+}
+
+int main(int argc, char **argv)
+{
+    test_shared_anonymous();
+    test_shared_file();
+    printf("mmap test passed\n");
+    return 0;
+}
@@ -0,0 +1,91 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <assert.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <sys/statfs.h>
+
+#define TMPFS_MAGIC 0x01021994
+
+int mounted(const char *target)
+{
+    char buf[4096];
+    int fd = open("/proc/mounts", O_RDONLY);
+    assert(fd >= 0);
+    int len = read(fd, buf, sizeof(buf) - 1);
+    assert(len > 0);
+    buf[len] = 0;
+    close(fd);
+    char pattern[256];
+    snprintf(pattern, sizeof(pattern), " %s tmpfs ", target);
+    return strstr(buf, pattern) != NULL;
+}
+
+int main(int argc, char **argv)
+{
+    const char *dir = "/tmp/testmount";
+    mkdir(dir, 0755);
+
+    // mount a tmpfs
+    assert(mount("none", dir, "nosuchfs", 0, NULL) == -1 && errno == ENODEV);
+    assert(mount("none", dir, "tmpfs", 0, NULL) == 0);
+    assert(mount("none", dir, "tmpfs", 0, NULL) == -1 && errno == EBUSY);
+    assert(mounted(dir));
+
+    struct statfs st;
+    assert(statfs(dir, &st) == 0);
+    assert(st.f_type == TMPFS_MAGIC);
+    assert((st.f_flags & ST_RDONLY) == 0);
+    printf("mount ok\n");
+
+    // files live in the mounted file system
+    char path[256];
+    snprintf(path, sizeof(path), "%s/file", dir);
+    int fd = open(path, O_CREAT | O_WRONLY, 0644);
+    assert(fd >= 0);
+    assert(write(fd, "hello", 5) == 5);
+    assert(fstatfs(fd, &st) == 0 && st.f_type == TMPFS_MAGIC);
+    close(fd);
+    assert(access(path, F_OK) == 0);
+
+    // change flags
+    assert(mount(NULL, dir, NULL, MS_REMOUNT | MS_RDONLY, NULL) == 0);
+    assert(statfs(dir, &st) == 0 && (st.f_flags & ST_RDONLY));
+    printf("remount ok\n");
+
+    // a read-only file system can be read, but not changed
+    char other[256];
+    snprintf(other, sizeof(other), "%s/other", dir);
+    fd = open(path, O_RDONLY);
+    assert(fd >= 0);
+    close(fd);
+    assert(open(path, O_WRONLY) == -1 && errno == EROFS);
+    assert(open(path, O_RDONLY | O_TRUNC) == -1 && errno == EROFS);
+    assert(open(other, O_CREAT | O_WRONLY, 0644) == -1 && errno == EROFS);
+    assert(mkdir(other, 0755) == -1 && errno == EROFS);
+    assert(symlink(path, other) == -1 && errno == EROFS);
+    assert(rename(path, other) == -1 && errno == EROFS);
+    assert(unlink(path) == -1 && errno == EROFS);
+    assert(truncate(path, 0) == -1 && errno == EROFS);
+    assert(chmod(path, 0600) == -1 && errno == EROFS);
+    printf("read-only ok\n");
+
+    // unmount shows the covered directory again
+    assert(umount2(dir, 0) == 0);
+    assert(access(path, F_OK) == -1 && errno == ENOENT);
+    assert(!mounted(dir));
+    assert(umount2(dir, 0) == -1 && errno == EINVAL);
+
+    // mount again at the same place gets an empty file system
+    assert(mount("none", dir, "tmpfs", 0, NULL) == 0);
+    assert(access(path, F_OK) == -1 && errno == ENOENT);
+    assert(umount(dir) == 0);
+    printf("umount ok\n");
+
+    rmdir(dir);
+    return 0;
+}
@@ -0,0 +1,57 @@
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include <errno.h>
+#include <assert.h>
+#include <fcntl.h>
+#include <sys/socket.h>
+#include <sys/uio.h>
+#include <netinet/in.h>
+
+int main(int argc, char **argv)
+{
+    int sock = socket(AF_INET, SOCK_DGRAM, 0);
+    assert(sock >= 0);
+
+    // nothing has been received yet
+    char buf[16];
+    struct iovec iov[2] = {
+        {.iov_base = buf, .iov_len = 8},
+        {.iov_base = buf + 8, .iov_len = 8},
+    };
+    struct sockaddr_in addr;
+    struct msghdr msg = {
+        .msg_name = &addr,
+        .msg_namelen = sizeof(addr),
+        .msg_iov = iov,
+        .msg_iovlen = 2,
+    };
+    assert(recvmsg(sock, &msg, MSG_DONTWAIT) == -1 && errno == EAGAIN);
+    assert(recv(sock, buf, sizeof(buf), MSG_DONTWAIT | MSG_PEEK) == -1 && errno == EAGAIN);
+
+    // file descriptors can only be passed over unix sockets
+    int fd = open("/dev/null", O_RDONLY);
+    assert(fd >= 0);
+    char control[CMSG_SPACE(sizeof(int))];
+    memset(control, 0, sizeof(control));
+    msg.msg_control = control;
+    msg.msg_controllen = sizeof(control);
+    struct cmsghdr *cmsg = CMSG_FIRSTHDR(&msg);
+    cmsg->cmsg_level = SOL_SOCKET;
+    cmsg->cmsg_type = SCM_RIGHTS;
+    cmsg->cmsg_len = CMSG_LEN(sizeof(int));
+    memcpy(CMSG_DATA(cmsg), &fd, sizeof(int));
+    addr.sin_family = AF_INET;
+    addr.sin_port = htons(10000);
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    assert(sendmsg(sock, &msg, 0) == -1 && errno == EINVAL);
+
+    // a malformed control message is rejected
+    cmsg->cmsg_len = sizeof(control) + 1;
+    assert(sendmsg(sock, &msg, 0) == -1 && errno == EINVAL);
+
+    close(fd);
+    close(sock);
+    printf("sendmsg/recvmsg test passed\n");
+    return 0;
+}
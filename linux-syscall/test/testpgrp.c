@@ -0,0 +1,78 @@
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <errno.h>
+#include <assert.h>
+#include <sys/ioctl.h>
+#include <sys/types.h>
+#include <sys/wait.h>
+
+static volatile int handled = 0;
+
+void handler(int signum)
+{
+    handled = signum;
+}
+
+int main(int argc, char **argv)
+{
+    pid_t pgrp = getpgrp();
+    assert(getpgid(0) == pgrp);
+    assert(getpgid(getpid()) == pgrp);
+    assert(getsid(0) > 0);
+    assert(getpgid(-1) == -1 && errno == ESRCH);
+
+    // a process group leader can not create a new session
+    if (pgrp == getpid()) {
+        assert(setsid() == -1 && errno == EPERM);
+    }
+
+    // signals to a process group reach the child in its own group
+    int fds[2];
+    assert(pipe(fds) == 0);
+    pid_t pid = fork();
+    if (pid == 0) {
+        signal(SIGUSR1, handler);
+        assert(setpgid(0, 0) == 0);
+        assert(getpgrp() == getpid());
+        char c = 0;
+        write(fds[1], &c, 1);
+        while (!handled) {
+            usleep(1000);
+        }
+        exit(handled == SIGUSR1 ? 0 : 1);
+    }
+    char c;
+    assert(read(fds[0], &c, 1) == 1);
+    assert(getpgid(pid) == pid);
+    assert(getsid(pid) == getsid(0));
+    assert(kill(-pid, SIGUSR1) == 0);
+    int status;
+    assert(waitpid(pid, &status, 0) == pid);
+    assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+    assert(kill(-pid, SIGUSR1) == -1 && errno == ESRCH);
+    printf("process group ok\n");
+
+    // a new session leads a new process group and can take the terminal
+    pid = fork();
+    if (pid == 0) {
+        pid_t self = getpid();
+        assert(setsid() == self);
+        assert(getsid(0) == self);
+        assert(getpgrp() == self);
+        assert(setsid() == -1 && errno == EPERM);
+        assert(setpgid(0, pgrp) == -1 && errno == EPERM);
+        assert(ioctl(0, TIOCSCTTY, 1) == 0);
+        assert(tcgetpgrp(0) == self);
+        assert(tcsetpgrp(0, self) == 0);
+        assert(tcsetpgrp(0, pgrp) == -1 && errno == EPERM);
+        exit(0);
+    }
+    assert(waitpid(pid, &status, 0) == pid);
+    assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+    printf("session ok\n");
+
+    return 0;
+}
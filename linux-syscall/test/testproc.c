@@ -0,0 +1,118 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <fcntl.h>
+#include <dirent.h>
+#include <assert.h>
+#include <sys/types.h>
+#include <sys/wait.h>
+
+static char buf[4096];
+
+static int read_file(const char *path)
+{
+    int fd = open(path, O_RDONLY);
+    if (fd < 0) {
+        return -1;
+    }
+    int len = 0, n;
+    while ((n = read(fd, buf + len, sizeof(buf) - 1 - len)) > 0) {
+        len += n;
+    }
+    close(fd);
+    buf[len] = 0;
+    return len;
+}
+
+static int has_entry(const char *dir, const char *name)
+{
+    DIR *d = opendir(dir);
+    assert(d != NULL);
+    struct dirent *entry;
+    int found = 0;
+    while ((entry = readdir(d)) != NULL) {
+        if (strcmp(entry->d_name, name) == 0) {
+            found = 1;
+        }
+    }
+    closedir(d);
+    return found;
+}
+
+int main(int argc, char **argv)
+{
+    char path[64], name[16];
+    pid_t pid = getpid();
+
+    // /proc/self is a link to the directory of the calling process
+    assert(readlink("/proc/self", buf, sizeof(buf)) > 0);
+    assert(atoi(buf) == pid);
+    sprintf(name, "%d", pid);
+    assert(has_entry("/proc", name));
+
+    sprintf(path, "/proc/%d/stat", pid);
+    assert(read_file(path) > 0);
+    int stat_pid, ppid;
+    char state;
+    assert(sscanf(buf, "%d (%15[^)]) %c %d", &stat_pid, name, &state, &ppid) == 4);
+    assert(stat_pid == pid && ppid == getppid());
+    assert(strcmp(name, "testproc") == 0);
+
+    assert(read_file("/proc/self/status") > 0);
+    assert(strstr(buf, "Name:\ttestproc\n") != NULL);
+    sprintf(path, "\nPid:\t%d\n", pid);
+    assert(strstr(buf, path) != NULL);
+
+    assert(read_file("/proc/self/cmdline") > 0);
+    assert(strcmp(buf, argv[0]) == 0);
+
+    assert(read_file("/proc/self/maps") > 0);
+    assert(strstr(buf, "[stack]") != NULL);
+
+    // the links of opened files
+    int fd = open("/proc/self/stat", O_RDONLY);
+    assert(fd >= 0);
+    sprintf(path, "/proc/self/fd/%d", fd);
+    int len = readlink(path, buf, sizeof(buf) - 1);
+    assert(len > 0);
+    buf[len] = 0;
+    assert(strcmp(buf, "/proc/self/stat") == 0);
+    sprintf(name, "%d", fd);
+    assert(has_entry("/proc/self/fd", name));
+    close(fd);
+    assert(access(path, F_OK) == -1);
+
+    // a child shows up until it is reaped
+    int fds[2];
+    assert(pipe(fds) == 0);
+    pid_t child = fork();
+    if (child == 0) {
+        char c;
+        read(fds[0], &c, 1);
+        exit(0);
+    }
+    sprintf(path, "/proc/%d/status", child);
+    assert(read_file(path) > 0);
+    sprintf(path, "\nPPid:\t%d\n", pid);
+    assert(strstr(buf, path) != NULL);
+    write(fds[1], "x", 1);
+    int status;
+    assert(waitpid(child, &status, 0) == child);
+    sprintf(name, "%d", child);
+    assert(!has_entry("/proc", name));
+
+    // information about the system
+    assert(read_file("/proc/meminfo") > 0);
+    assert(strncmp(buf, "MemTotal:", 9) == 0);
+    assert(read_file("/proc/cpuinfo") > 0);
+    assert(strncmp(buf, "processor", 9) == 0);
+    assert(read_file("/proc/uptime") > 0);
+    assert(read_file("/proc/loadavg") > 0);
+    assert(read_file("/proc/mounts") > 0);
+    assert(strstr(buf, "proc /proc proc") != NULL);
+    assert(sysconf(_SC_NPROCESSORS_ONLN) >= 1);
+
+    printf("procfs ok\n");
+    return 0;
+}
@@ -0,0 +1,91 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <errno.h>
+#include <assert.h>
+#include <sys/resource.h>
+#include <sys/sysinfo.h>
+#include <sys/times.h>
+#include <sys/wait.h>
+
+static void spin()
+{
+    volatile unsigned long sum = 0;
+    for (unsigned long i = 0; i < 20000000; i++)
+        sum += i;
+}
+
+static int timeval_nonzero(struct timeval *tv)
+{
+    return tv->tv_sec > 0 || tv->tv_usec > 0;
+}
+
+void test_self()
+{
+    spin();
+    for (int i = 0; i < 10000; i++)
+        getppid();
+    char *buf = malloc(1 << 20);
+    memset(buf, 1, 1 << 20);
+
+    struct rusage usage;
+    assert(getrusage(RUSAGE_SELF, &usage) == 0);
+    assert(timeval_nonzero(&usage.ru_utime));
+    assert(timeval_nonzero(&usage.ru_stime));
+    assert(usage.ru_maxrss >= 1024);
+
+    struct rusage thread;
+    assert(getrusage(RUSAGE_THREAD, &thread) == 0);
+    assert(timeval_nonzero(&thread.ru_utime));
+    assert(thread.ru_minflt <= usage.ru_minflt);
+    free(buf);
+
+    assert(getrusage(5, &usage) == -1 && errno == EINVAL);
+}
+
+void test_children()
+{
+    struct rusage usage;
+    assert(getrusage(RUSAGE_CHILDREN, &usage) == 0);
+    assert(!timeval_nonzero(&usage.ru_utime));
+
+    pid_t pid = fork();
+    if (pid == 0)
+    {
+        spin();
+        _exit(0);
+    }
+    // not counted until the child is waited for
+    int status;
+    assert(waitpid(pid, &status, 0) == pid);
+    assert(getrusage(RUSAGE_CHILDREN, &usage) == 0);
+    assert(timeval_nonzero(&usage.ru_utime));
+    assert(usage.ru_maxrss > 0);
+
+    struct tms tms;
+    clock_t ticks = times(&tms);
+    assert(ticks != (clock_t)-1);
+    assert(tms.tms_utime > 0);
+    assert(tms.tms_cutime > 0);
+}
+
+void test_sysinfo()
+{
+    struct sysinfo info;
+    assert(sysinfo(&info) == 0);
+    assert(info.totalram > 0);
+    assert(info.freeram > 0 && info.freeram <= info.totalram);
+    assert(info.procs >= 1);
+    assert(info.mem_unit == 1);
+    assert(info.totalswap == 0);
+}
+
+int main(int argc, char **argv)
+{
+    test_self();
+    test_children();
+    test_sysinfo();
+    printf("rusage test passed\n");
+    return 0;
+}
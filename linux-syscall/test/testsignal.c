@@ -0,0 +1,175 @@
+#include <errno.h>
+#include <pthread.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <assert.h>
+#include <time.h>
+#include <sys/time.h>
+#include <sys/types.h>
+#include <sys/wait.h>
+
+static volatile int handled = 0;
+static volatile int info_pid = 0;
+static volatile int on_altstack = 0;
+static char altstack[SIGSTKSZ];
+
+void handler(int signum)
+{
+    handled = signum;
+}
+
+void info_handler(int signum, siginfo_t *info, void *ucontext)
+{
+    handled = signum;
+    info_pid = info->si_pid;
+}
+
+void altstack_handler(int signum)
+{
+    int local;
+    on_altstack = (char *)&local >= altstack && (char *)&local < altstack + sizeof(altstack);
+    handled = signum;
+}
+
+void *blocked_reader(void *arg)
+{
+    char c;
+    int *fds = arg;
+    int ret = read(fds[0], &c, 1);
+    return (void *)(long)(ret == -1 && errno == EINTR);
+}
+
+void alarm_after(long usec)
+{
+    struct itimerval it = {.it_value = {.tv_usec = usec}};
+    assert(setitimer(ITIMER_REAL, &it, NULL) == 0);
+}
+
+int main(int argc, char **argv)
+{
+    // deliver to a simple handler
+    struct sigaction sa;
+    memset(&sa, 0, sizeof(sa));
+    sa.sa_handler = handler;
+    assert(sigaction(SIGUSR1, &sa, NULL) == 0);
+    assert(kill(getpid(), SIGUSR1) == 0);
+    assert(handled == SIGUSR1);
+    printf("handler ok\n");
+
+    // SA_SIGINFO
+    handled = 0;
+    sa.sa_sigaction = info_handler;
+    sa.sa_flags = SA_SIGINFO;
+    assert(sigaction(SIGUSR2, &sa, NULL) == 0);
+    assert(raise(SIGUSR2) == 0);
+    assert(handled == SIGUSR2);
+    assert(info_pid == getpid());
+    printf("siginfo ok\n");
+
+    // blocked signals stay pending until unblocked
+    handled = 0;
+    sigset_t set;
+    sigemptyset(&set);
+    sigaddset(&set, SIGUSR1);
+    assert(sigprocmask(SIG_BLOCK, &set, NULL) == 0);
+    assert(kill(getpid(), SIGUSR1) == 0);
+    assert(handled == 0);
+    assert(sigprocmask(SIG_UNBLOCK, &set, NULL) == 0);
+    assert(handled == SIGUSR1);
+    printf("mask ok\n");
+
+    // alternate signal stack
+    handled = 0;
+    stack_t ss;
+    ss.ss_sp = altstack;
+    ss.ss_size = sizeof(altstack);
+    ss.ss_flags = 0;
+    assert(sigaltstack(&ss, NULL) == 0);
+    sa.sa_handler = altstack_handler;
+    sa.sa_flags = SA_ONSTACK;
+    assert(sigaction(SIGUSR1, &sa, NULL) == 0);
+    assert(kill(getpid(), SIGUSR1) == 0);
+    assert(handled == SIGUSR1);
+    assert(on_altstack);
+    printf("sigaltstack ok\n");
+
+    // ignored signals are discarded
+    signal(SIGUSR2, SIG_IGN);
+    assert(kill(getpid(), SIGUSR2) == 0);
+
+    // signal 0 checks existence only
+    assert(kill(getpid(), 0) == 0);
+
+    // default action terminates the child
+    pid_t pid = fork();
+    if (pid == 0) {
+        signal(SIGUSR1, SIG_DFL);
+        kill(getpid(), SIGUSR1);
+        exit(1);
+    }
+    int status;
+    assert(waitpid(pid, &status, 0) == pid);
+    printf("default action ok\n");
+
+    // a fault kills the process even if SIGSEGV is ignored or blocked
+    for (int i = 0; i < 2; i++) {
+        pid = fork();
+        if (pid == 0) {
+            if (i == 0) {
+                signal(SIGSEGV, SIG_IGN);
+            } else {
+                sigemptyset(&set);
+                sigaddset(&set, SIGSEGV);
+                sigprocmask(SIG_BLOCK, &set, NULL);
+            }
+            *(volatile int *)0 = 1;
+            exit(1);
+        }
+        assert(waitpid(pid, &status, 0) == pid);
+        assert(WIFSIGNALED(status) && WTERMSIG(status) == SIGSEGV);
+    }
+    printf("forced SIGSEGV ok\n");
+
+    // a signal interrupts blocking syscalls without SA_RESTART
+    handled = 0;
+    sa.sa_handler = handler;
+    sa.sa_flags = 0;
+    assert(sigaction(SIGALRM, &sa, NULL) == 0);
+    int fds[2];
+    char c;
+    assert(pipe(fds) == 0);
+    alarm_after(20000);
+    assert(read(fds[0], &c, 1) == -1 && errno == EINTR);
+    assert(handled == SIGALRM);
+    handled = 0;
+    struct timespec ts = {.tv_sec = 5};
+    alarm_after(20000);
+    assert(nanosleep(&ts, NULL) == -1 && errno == EINTR);
+    assert(handled == SIGALRM);
+    pid = fork();
+    if (pid == 0) {
+        read(fds[0], &c, 1);
+        exit(0);
+    }
+    alarm_after(20000);
+    assert(waitpid(pid, &status, 0) == -1 && errno == EINTR);
+    assert(kill(pid, SIGKILL) == 0);
+    assert(waitpid(pid, &status, 0) == pid);
+
+    // a thread-directed signal wakes the target thread
+    pthread_t thread;
+    void *interrupted;
+    sa.sa_handler = handler;
+    assert(sigaction(SIGUSR1, &sa, NULL) == 0);
+    assert(pthread_create(&thread, NULL, blocked_reader, fds) == 0);
+    usleep(20000);
+    assert(pthread_kill(thread, SIGUSR1) == 0);
+    assert(pthread_join(thread, &interrupted) == 0);
+    assert(interrupted);
+    printf("EINTR ok\n");
+
+    return 0;
+}
@@ -0,0 +1,145 @@
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <assert.h>
+#include <sys/stat.h>
+
+#define DIR "/tmp/testsymlink"
+
+void test_create()
+{
+    char buf[256];
+    int fd = open(DIR "/file", O_CREAT | O_WRONLY, 0644);
+    assert(fd >= 0);
+    assert(write(fd, "hello", 5) == 5);
+    close(fd);
+
+    assert(symlink("file", DIR "/link") == 0);
+    assert(readlink(DIR "/link", buf, sizeof(buf)) == 4);
+    assert(memcmp(buf, "file", 4) == 0);
+    assert(symlink("other", DIR "/link") == -1 && errno == EEXIST);
+    assert(symlink("", DIR "/empty") == -1 && errno == ENOENT);
+    assert(symlinkat("file", AT_FDCWD, DIR "/nodir/link") == -1 && errno == ENOENT);
+
+    // a dangling link can be created, but not followed
+    assert(symlink("nothing", DIR "/dangling") == 0);
+    assert(open(DIR "/dangling", O_RDONLY) == -1 && errno == ENOENT);
+    // but O_CREAT creates its target, unless with O_EXCL
+    assert(open(DIR "/dangling", O_CREAT | O_WRONLY | O_EXCL, 0644) == -1 && errno == EEXIST);
+    fd = open(DIR "/dangling", O_CREAT | O_WRONLY, 0644);
+    assert(fd >= 0);
+    close(fd);
+    assert(access(DIR "/nothing", F_OK) == 0);
+}
+
+void test_follow()
+{
+    char buf[16];
+    struct stat st;
+
+    // a chain of links, relative and absolute
+    assert(symlink("link", DIR "/link2") == 0);
+    assert(symlink(DIR "/link2", DIR "/link3") == 0);
+    int fd = open(DIR "/link3", O_RDONLY);
+    assert(fd >= 0);
+    assert(read(fd, buf, sizeof(buf)) == 5);
+    assert(memcmp(buf, "hello", 5) == 0);
+    close(fd);
+
+    // links in the intermediate components
+    assert(mkdir(DIR "/dir", 0755) == 0);
+    assert(symlink("dir", DIR "/dirlink") == 0);
+    assert(symlink("../file", DIR "/dir/up") == 0);
+    fd = open(DIR "/dirlink/up", O_RDONLY);
+    assert(fd >= 0);
+    close(fd);
+    fd = open(DIR "/dirlink/new", O_CREAT | O_WRONLY, 0644);
+    assert(fd >= 0);
+    close(fd);
+    assert(access(DIR "/dir/new", F_OK) == 0);
+    assert(symlinkat("../file", AT_FDCWD, DIR "/dirlink/up2") == 0);
+    assert(access(DIR "/dir/up2", F_OK) == 0);
+
+    // a trailing slash requires a directory
+    assert(stat(DIR "/dirlink/", &st) == 0 && S_ISDIR(st.st_mode));
+    assert(stat(DIR "/link/", &st) == -1 && errno == ENOTDIR);
+
+    // stat follows the last link, lstat does not
+    assert(stat(DIR "/link3", &st) == 0 && S_ISREG(st.st_mode) && st.st_size == 5);
+    assert(lstat(DIR "/link3", &st) == 0 && S_ISLNK(st.st_mode));
+    assert(fstatat(AT_FDCWD, DIR "/link3", &st, AT_SYMLINK_NOFOLLOW) == 0 && S_ISLNK(st.st_mode));
+    assert(lstat(DIR "/dirlink/up", &st) == 0 && S_ISLNK(st.st_mode));
+
+    // relative to a directory file descriptor
+    int dirfd = open(DIR, O_RDONLY);
+    assert(dirfd >= 0);
+    assert(symlinkat("file", dirfd, "link4") == 0);
+    assert(readlinkat(dirfd, "link4", buf, sizeof(buf)) == 4);
+    assert(fstatat(dirfd, "link4", &st, 0) == 0 && S_ISREG(st.st_mode));
+    close(dirfd);
+}
+
+void test_nofollow()
+{
+    assert(open(DIR "/link", O_RDONLY | O_NOFOLLOW) == -1 && errno == ELOOP);
+    // only the last component is not followed
+    int fd = open(DIR "/dirlink/new", O_RDONLY | O_NOFOLLOW);
+    assert(fd >= 0);
+    close(fd);
+
+    // link does not follow oldpath unless asked
+    struct stat st;
+    assert(link(DIR "/link", DIR "/hard") == 0);
+    assert(lstat(DIR "/hard", &st) == 0 && S_ISLNK(st.st_mode));
+    assert(linkat(AT_FDCWD, DIR "/link", AT_FDCWD, DIR "/hard2", AT_SYMLINK_FOLLOW) == 0);
+    assert(lstat(DIR "/hard2", &st) == 0 && S_ISREG(st.st_mode));
+}
+
+void test_loop()
+{
+    char path[256], target[256];
+    struct stat st;
+
+    assert(symlink("loop", DIR "/loop") == 0);
+    assert(open(DIR "/loop", O_RDONLY) == -1 && errno == ELOOP);
+    assert(stat(DIR "/loop/file", &st) == -1 && errno == ELOOP);
+    assert(lstat(DIR "/loop", &st) == 0 && S_ISLNK(st.st_mode));
+
+    assert(symlink("ping", DIR "/pong") == 0);
+    assert(symlink("pong", DIR "/ping") == 0);
+    assert(stat(DIR "/ping", &st) == -1 && errno == ELOOP);
+
+    // 40 links are followed, but not 41
+    assert(symlink("file", DIR "/chain0") == 0);
+    for (int i = 1; i <= 40; i++) {
+        snprintf(path, sizeof(path), DIR "/chain%d", i);
+        snprintf(target, sizeof(target), "chain%d", i - 1);
+        assert(symlink(target, path) == 0);
+    }
+    assert(stat(DIR "/chain39", &st) == 0 && S_ISREG(st.st_mode));
+    assert(stat(DIR "/chain40", &st) == -1 && errno == ELOOP);
+}
+
+void test_unlink()
+{
+    struct stat st;
+    assert(unlink(DIR "/link") == 0);
+    assert(lstat(DIR "/link", &st) == -1 && errno == ENOENT);
+    assert(stat(DIR "/file", &st) == 0 && S_ISREG(st.st_mode));
+    // the chain is broken
+    assert(stat(DIR "/link3", &st) == -1 && errno == ENOENT);
+}
+
+int main(int argc, char **argv)
+{
+    assert(mkdir(DIR, 0755) == 0);
+    test_create();
+    test_follow();
+    test_nofollow();
+    test_loop();
+    test_unlink();
+    printf("symlink test passed\n");
+    return 0;
+}
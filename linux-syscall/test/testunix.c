@@ -0,0 +1,274 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stddef.h>
+#include <string.h>
+#include <unistd.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <assert.h>
+#include <poll.h>
+#include <sys/socket.h>
+#include <sys/stat.h>
+#include <sys/un.h>
+#include <sys/wait.h>
+
+void test_stream_pair()
+{
+    int sv[2];
+    assert(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+
+    // a stream merges writes
+    assert(write(sv[0], "hello ", 6) == 6);
+    assert(send(sv[0], "world", 5, 0) == 5);
+    struct pollfd pfd = {.fd = sv[1], .events = POLLIN};
+    assert(poll(&pfd, 1, 0) == 1 && (pfd.revents & POLLIN));
+    char buf[32];
+    assert(recv(sv[1], buf, sizeof(buf), MSG_PEEK) == 11);
+    assert(read(sv[1], buf, sizeof(buf)) == 11);
+    assert(memcmp(buf, "hello world", 11) == 0);
+    assert(recv(sv[1], buf, sizeof(buf), MSG_DONTWAIT) == -1 && errno == EAGAIN);
+
+    // the reader gets the end of file after the writer is closed
+    pid_t pid = fork();
+    if (pid == 0)
+    {
+        close(sv[1]);
+        assert(write(sv[0], "child", 5) == 5);
+        close(sv[0]);
+        _exit(0);
+    }
+    close(sv[0]);
+    assert(read(sv[1], buf, sizeof(buf)) == 5);
+    assert(memcmp(buf, "child", 5) == 0);
+    int status;
+    assert(waitpid(pid, &status, 0) == pid && status == 0);
+    assert(read(sv[1], buf, sizeof(buf)) == 0);
+    close(sv[1]);
+}
+
+void test_dgram_pair()
+{
+    int sv[2];
+    assert(socketpair(AF_UNIX, SOCK_DGRAM, 0, sv) == 0);
+
+    // message boundaries are kept, and the rest of a datagram is discarded
+    assert(send(sv[0], "first", 5, 0) == 5);
+    assert(send(sv[0], "second", 6, 0) == 6);
+    char buf[32];
+    assert(recv(sv[1], buf, 3, 0) == 3);
+    assert(memcmp(buf, "fir", 3) == 0);
+    assert(recv(sv[1], buf, 3, MSG_TRUNC) == 6);
+    assert(memcmp(buf, "sec", 3) == 0);
+    close(sv[0]);
+    close(sv[1]);
+}
+
+void test_rights()
+{
+    int sv[2], fds[2];
+    assert(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+    assert(pipe(fds) == 0);
+
+    char data = 'x';
+    struct iovec iov = {.iov_base = &data, .iov_len = 1};
+    char control[CMSG_SPACE(sizeof(int))];
+    memset(control, 0, sizeof(control));
+    struct msghdr msg = {
+        .msg_iov = &iov,
+        .msg_iovlen = 1,
+        .msg_control = control,
+        .msg_controllen = sizeof(control),
+    };
+    struct cmsghdr *cmsg = CMSG_FIRSTHDR(&msg);
+    cmsg->cmsg_level = SOL_SOCKET;
+    cmsg->cmsg_type = SCM_RIGHTS;
+    cmsg->cmsg_len = CMSG_LEN(sizeof(int));
+    memcpy(CMSG_DATA(cmsg), &fds[1], sizeof(int));
+    assert(sendmsg(sv[0], &msg, 0) == 1);
+
+    memset(control, 0, sizeof(control));
+    data = 0;
+    assert(recvmsg(sv[1], &msg, MSG_CMSG_CLOEXEC) == 1 && data == 'x');
+    assert(!(msg.msg_flags & MSG_CTRUNC));
+    cmsg = CMSG_FIRSTHDR(&msg);
+    assert(cmsg && cmsg->cmsg_level == SOL_SOCKET && cmsg->cmsg_type == SCM_RIGHTS);
+    int fd;
+    memcpy(&fd, CMSG_DATA(cmsg), sizeof(int));
+    assert(fcntl(fd, F_GETFD) == FD_CLOEXEC);
+
+    // both descriptors refer to the same open file description
+    assert(fcntl(fd, F_SETFL, O_NONBLOCK) == 0);
+    assert(fcntl(fds[1], F_GETFL) & O_NONBLOCK);
+    close(fds[1]);
+
+    // the received file is the write end of the pipe
+    assert(write(fd, "pipe", 4) == 4);
+    char buf[8];
+    assert(read(fds[0], buf, sizeof(buf)) == 4);
+    assert(memcmp(buf, "pipe", 4) == 0);
+    close(fd);
+    close(fds[0]);
+
+    // sockets can be passed too
+    int sp[2];
+    assert(socketpair(AF_UNIX, SOCK_STREAM, 0, sp) == 0);
+    msg.msg_controllen = sizeof(control);
+    cmsg = CMSG_FIRSTHDR(&msg);
+    cmsg->cmsg_level = SOL_SOCKET;
+    cmsg->cmsg_type = SCM_RIGHTS;
+    cmsg->cmsg_len = CMSG_LEN(sizeof(int));
+    memcpy(CMSG_DATA(cmsg), &sp[0], sizeof(int));
+    assert(sendmsg(sv[0], &msg, 0) == 1);
+    close(sp[0]);
+    memset(control, 0, sizeof(control));
+    assert(recvmsg(sv[1], &msg, 0) == 1);
+    cmsg = CMSG_FIRSTHDR(&msg);
+    assert(cmsg && cmsg->cmsg_type == SCM_RIGHTS);
+    memcpy(&fd, CMSG_DATA(cmsg), sizeof(int));
+    assert(write(fd, "sock", 4) == 4);
+    assert(read(sp[1], buf, sizeof(buf)) == 4);
+    assert(memcmp(buf, "sock", 4) == 0);
+    close(fd);
+    close(sp[1]);
+    close(sv[0]);
+    close(sv[1]);
+}
+
+void test_credentials()
+{
+    int sv[2];
+    assert(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+
+    struct ucred cred;
+    socklen_t len = sizeof(cred);
+    assert(getsockopt(sv[0], SOL_SOCKET, SO_PEERCRED, &cred, &len) == 0);
+    assert(len == sizeof(cred));
+    assert(cred.pid == getpid() && cred.uid == geteuid() && cred.gid == getegid());
+
+    int on = 1;
+    assert(setsockopt(sv[1], SOL_SOCKET, SO_PASSCRED, &on, sizeof(on)) == 0);
+    assert(send(sv[0], "c", 1, 0) == 1);
+    char data;
+    struct iovec iov = {.iov_base = &data, .iov_len = 1};
+    char control[CMSG_SPACE(sizeof(struct ucred))];
+    struct msghdr msg = {
+        .msg_iov = &iov,
+        .msg_iovlen = 1,
+        .msg_control = control,
+        .msg_controllen = sizeof(control),
+    };
+    assert(recvmsg(sv[1], &msg, 0) == 1 && data == 'c');
+    struct cmsghdr *cmsg = CMSG_FIRSTHDR(&msg);
+    assert(cmsg && cmsg->cmsg_level == SOL_SOCKET && cmsg->cmsg_type == SCM_CREDENTIALS);
+    memcpy(&cred, CMSG_DATA(cmsg), sizeof(cred));
+    assert(cred.pid == getpid() && cred.uid == geteuid() && cred.gid == getegid());
+    close(sv[0]);
+    close(sv[1]);
+}
+
+void test_path()
+{
+    const char *path = "/tmp/testunix.sock";
+    unlink(path);
+    struct sockaddr_un addr = {.sun_family = AF_UNIX};
+    strcpy(addr.sun_path, path);
+
+    int server = socket(AF_UNIX, SOCK_STREAM, 0);
+    assert(server >= 0);
+    assert(bind(server, (struct sockaddr *)&addr, sizeof(addr)) == 0);
+    struct stat st;
+    assert(stat(path, &st) == 0 && S_ISSOCK(st.st_mode));
+    assert(listen(server, 1) == 0);
+
+    // the name is in use while the socket file exists
+    int other = socket(AF_UNIX, SOCK_STREAM, 0);
+    assert(bind(other, (struct sockaddr *)&addr, sizeof(addr)) == -1 && errno == EADDRINUSE);
+    close(other);
+
+    int client = socket(AF_UNIX, SOCK_STREAM, 0);
+    assert(connect(client, (struct sockaddr *)&addr, sizeof(addr)) == 0);
+    int conn = accept(server, NULL, NULL);
+    assert(conn >= 0);
+    assert(write(client, "ping", 4) == 4);
+    char buf[8];
+    assert(read(conn, buf, sizeof(buf)) == 4);
+    assert(memcmp(buf, "ping", 4) == 0);
+    close(conn);
+    close(client);
+    close(server);
+
+    // nobody listens after the server is closed
+    client = socket(AF_UNIX, SOCK_STREAM, 0);
+    assert(connect(client, (struct sockaddr *)&addr, sizeof(addr)) == -1 && errno == ECONNREFUSED);
+    close(client);
+    assert(unlink(path) == 0);
+}
+
+void test_abstract()
+{
+    struct sockaddr_un addr = {.sun_family = AF_UNIX};
+    const char name[] = "\0testunix";
+    memcpy(addr.sun_path, name, sizeof(name) - 1);
+    socklen_t addr_len = offsetof(struct sockaddr_un, sun_path) + sizeof(name) - 1;
+
+    int receiver = socket(AF_UNIX, SOCK_DGRAM, 0);
+    assert(bind(receiver, (struct sockaddr *)&addr, addr_len) == 0);
+    int other = socket(AF_UNIX, SOCK_DGRAM, 0);
+    assert(bind(other, (struct sockaddr *)&addr, addr_len) == -1 && errno == EADDRINUSE);
+    close(other);
+
+    int sender = socket(AF_UNIX, SOCK_DGRAM | SOCK_NONBLOCK, 0);
+    assert(sendto(sender, "abstract", 8, 0, (struct sockaddr *)&addr, addr_len) == 8);
+    char buf[16];
+    struct sockaddr_un from;
+    socklen_t from_len = sizeof(from);
+    assert(recvfrom(receiver, buf, sizeof(buf), 0, (struct sockaddr *)&from, &from_len) == 8);
+    assert(memcmp(buf, "abstract", 8) == 0);
+    assert(from_len == offsetof(struct sockaddr_un, sun_path));
+    assert(recv(sender, buf, sizeof(buf), 0) == -1 && errno == EAGAIN);
+    close(sender);
+    close(receiver);
+}
+
+void test_blocking_send()
+{
+    int sv[2];
+    assert(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+    static char buf[4096];
+    while (send(sv[0], buf, sizeof(buf), MSG_DONTWAIT) > 0)
+        ;
+    assert(errno == EAGAIN);
+    struct pollfd pfd = {.fd = sv[0], .events = POLLOUT};
+    assert(poll(&pfd, 1, 0) == 0);
+
+    // the writer waits until the reader makes room
+    pid_t pid = fork();
+    if (pid == 0)
+    {
+        usleep(10000);
+        assert(read(sv[1], buf, sizeof(buf)) == sizeof(buf));
+        usleep(10000);
+        assert(read(sv[1], buf, sizeof(buf)) == sizeof(buf));
+        _exit(0);
+    }
+    assert(poll(&pfd, 1, -1) == 1 && (pfd.revents & POLLOUT));
+    assert(send(sv[0], buf, sizeof(buf), 0) == sizeof(buf));
+    assert(write(sv[0], buf, sizeof(buf)) == sizeof(buf));
+    int status;
+    assert(waitpid(pid, &status, 0) == pid && status == 0);
+    close(sv[0]);
+    close(sv[1]);
+}
+
+int main(int argc, char **argv)
+{
+    test_stream_pair();
+    test_dgram_pair();
+    test_blocking_send();
+    test_rights();
+    test_credentials();
+    test_path();
+    test_abstract();
+    printf("unix socket test passed\n");
+    return 0;
+}
@@ -0,0 +1,163 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include <errno.h>
+#include <assert.h>
+#include <sched.h>
+#include <signal.h>
+#include <sys/wait.h>
+
+static volatile sig_atomic_t sigchld_count = 0;
+
+static void handle_sigchld(int sig)
+{
+    sigchld_count++;
+}
+
+void test_exit_status()
+{
+    pid_t pid = fork();
+    if (pid == 0)
+        _exit(42);
+    int status;
+    assert(waitpid(pid, &status, 0) == pid);
+    assert(WIFEXITED(status) && WEXITSTATUS(status) == 42);
+    // the child is reaped
+    assert(waitpid(pid, &status, WNOHANG) == -1 && errno == ECHILD);
+}
+
+void test_signaled()
+{
+    pid_t pid = fork();
+    if (pid == 0)
+    {
+        for (;;)
+            pause();
+    }
+    assert(kill(pid, SIGKILL) == 0);
+    int status;
+    assert(waitpid(pid, &status, 0) == pid);
+    assert(WIFSIGNALED(status) && WTERMSIG(status) == SIGKILL);
+}
+
+void test_stop_continue()
+{
+    pid_t pid = fork();
+    if (pid == 0)
+    {
+        for (;;)
+            pause();
+    }
+    int status;
+    assert(kill(pid, SIGSTOP) == 0);
+    assert(waitpid(pid, &status, WUNTRACED) == pid);
+    assert(WIFSTOPPED(status) && WSTOPSIG(status) == SIGSTOP);
+    // the stop is only reported once
+    assert(waitpid(pid, &status, WUNTRACED | WNOHANG) == 0);
+
+    assert(kill(pid, SIGCONT) == 0);
+    assert(waitpid(pid, &status, WCONTINUED) == pid);
+    assert(WIFCONTINUED(status));
+
+    assert(kill(pid, SIGKILL) == 0);
+    assert(waitpid(pid, &status, 0) == pid);
+}
+
+void test_waitid()
+{
+    pid_t pid = fork();
+    if (pid == 0)
+        _exit(7);
+
+    // the child stays waitable with WNOWAIT
+    siginfo_t info;
+    memset(&info, 0, sizeof(info));
+    assert(waitid(P_PID, pid, &info, WEXITED | WNOWAIT) == 0);
+    assert(info.si_signo == SIGCHLD && info.si_pid == pid);
+    assert(info.si_code == CLD_EXITED && info.si_status == 7);
+    assert(info.si_uid == getuid());
+
+    memset(&info, 0, sizeof(info));
+    assert(waitid(P_ALL, 0, &info, WEXITED) == 0);
+    assert(info.si_pid == pid && info.si_code == CLD_EXITED && info.si_status == 7);
+
+    // at least one state change must be selected
+    assert(waitid(P_ALL, 0, &info, WNOHANG) == -1 && errno == EINVAL);
+    assert(waitid(P_ALL, 0, &info, WEXITED) == -1 && errno == ECHILD);
+
+    // no child has changed state
+    pid = fork();
+    if (pid == 0)
+    {
+        for (;;)
+            pause();
+    }
+    info.si_pid = 1;
+    assert(waitid(P_PID, pid, &info, WEXITED | WNOHANG) == 0);
+    assert(info.si_pid == 0);
+    assert(kill(pid, SIGKILL) == 0);
+    assert(waitid(P_PID, pid, &info, WEXITED) == 0);
+    assert(info.si_code == CLD_KILLED && info.si_status == SIGKILL);
+}
+
+void test_process_group()
+{
+    pid_t pid = fork();
+    if (pid == 0)
+    {
+        for (;;)
+            pause();
+    }
+    assert(setpgid(pid, pid) == 0);
+    int status;
+    // the child is in another process group
+    assert(waitpid(0, &status, WNOHANG) == -1 && errno == ECHILD);
+    assert(waitpid(-pid, &status, WNOHANG) == 0);
+    assert(kill(pid, SIGKILL) == 0);
+    assert(waitpid(-pid, &status, 0) == pid);
+
+    pid = fork();
+    if (pid == 0)
+        _exit(3);
+    assert(waitpid(0, &status, 0) == pid && WEXITSTATUS(status) == 3);
+}
+
+void test_sigchld()
+{
+    struct sigaction sa;
+    memset(&sa, 0, sizeof(sa));
+    sa.sa_handler = handle_sigchld;
+    assert(sigaction(SIGCHLD, &sa, NULL) == 0);
+    sigchld_count = 0;
+    pid_t pid = fork();
+    if (pid == 0)
+        _exit(0);
+    int status;
+    assert(waitpid(pid, &status, 0) == pid);
+    while (sigchld_count == 0)
+        sched_yield();
+
+    // children are reaped on termination with SA_NOCLDWAIT
+    sa.sa_handler = SIG_DFL;
+    sa.sa_flags = SA_NOCLDWAIT;
+    assert(sigaction(SIGCHLD, &sa, NULL) == 0);
+    pid = fork();
+    if (pid == 0)
+        _exit(0);
+    assert(waitpid(pid, &status, 0) == -1 && errno == ECHILD);
+    sa.sa_flags = 0;
+    assert(sigaction(SIGCHLD, &sa, NULL) == 0);
+}
+
+int main(int argc, char **argv)
+{
+    test_exit_status();
+    test_signaled();
+    test_stop_continue();
+    test_waitid();
+    test_process_group();
+    test_sigchld();
+    printf("wait test passed\n");
+    return 0;
+}
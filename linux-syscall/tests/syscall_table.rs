@@ -0,0 +1,76 @@
+//! Check that every syscall number in the headers has an arm in the dispatcher,
+//! so that no known syscall ends up in `unknown_syscall` and kills the process.
+
+use std::collections::BTreeSet;
+
+const LIB_RS: &str = include_str!("../src/lib.rs");
+
+/// Parse names of `#define __NR_xxx nnn` in a syscall header.
+fn header_names(header: &str) -> Vec<String> {
+    header
+        .lines()
+        .filter(|line| line.starts_with("#define"))
+        .map(|line| {
+            let name = line.split_whitespace().nth(1).unwrap();
+            name.trim_start_matches("__NR_").to_uppercase()
+        })
+        .collect()
+}
+
+/// Collect all `Sys::XXX` in `code`, ignoring comments.
+fn handled_names(code: &str) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    for line in code.lines() {
+        let line = line.split("//").next().unwrap();
+        for part in line.split("Sys::").skip(1) {
+            let name: String = part
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+                .collect();
+            names.insert(name);
+        }
+    }
+    names
+}
+
+/// Returns the code of the generic dispatcher and the arch-specific one.
+fn dispatcher(arch_fn: &str) -> (&'static str, &'static str) {
+    let x86_start = LIB_RS.find("async fn x86_64_syscall").unwrap();
+    let riscv_start = LIB_RS.find("async fn riscv64_syscall").unwrap();
+    let end = LIB_RS.find("fn unknown_syscall").unwrap();
+    let arch = match arch_fn {
+        "x86_64_syscall" => &LIB_RS[x86_start..riscv_start],
+        "riscv64_syscall" => &LIB_RS[riscv_start..end],
+        _ => unreachable!(),
+    };
+    (&LIB_RS[..x86_start], arch)
+}
+
+fn check(header: &str, arch_fn: &str) {
+    let (generic, arch) = dispatcher(arch_fn);
+    let mut handled = handled_names(generic);
+    handled.extend(handled_names(arch));
+    let missing: Vec<_> = header_names(header)
+        .into_iter()
+        .filter(|name| !handled.contains(name))
+        .collect();
+    assert!(
+        missing.is_empty(),
+        "syscalls without handler in {}: {:?}",
+        arch_fn,
+        missing
+    );
+}
+
+#[test]
+fn x86_64_syscall_table() {
+    check(include_str!("../src/syscall.h.in"), "x86_64_syscall");
+}
+
+#[test]
+fn riscv64_syscall_table() {
+    check(
+        include_str!("../src/riscv64_syscall.h.in"),
+        "riscv64_syscall",
+    );
+}
@@ -1,10 +1,15 @@
 //! Run Linux process and manage trap/interrupt/syscall.
 
 use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
-use core::{future::Future, pin::Pin};
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
 
 use kernel_hal::context::{TrapReason, UserContext, UserContextField};
 use linux_object::fs::{vfs::FileSystem, INodeExt};
+use linux_object::signal::{SigInfo, Signal, SignalCode};
 use linux_object::thread::{CurrentThreadExt, ThreadExt};
 use linux_object::{loader::LinuxElfLoader, process::ProcessExt};
 use zircon_object::task::{CurrentThread, Job, Process, Thread, ThreadState};
@@ -26,11 +31,16 @@ pub fn run(args: Vec<String>, envs: Vec<String>, rootfs: Arc<dyn FileSystem>) ->
     let inode = rootfs.root_inode().lookup(&args[0]).unwrap();
     let data = inode.read_as_vec().unwrap();
     let path = args[0].clone();
+    proc.linux().set_execute_path(&path);
+    proc.linux().set_args(&args, &envs);
 
     let pg_token = kernel_hal::vm::current_vmtoken();
     debug!("current pgt = {:#x}", pg_token);
     //调用zircon-object/src/task/thread.start设置好要执行的thread
-    let (entry, sp) = loader.load(&proc.vmar(), &data, args, envs, path).unwrap();
+    let (entry, sp, heap_start, vdso_base) =
+        loader.load(&proc.vmar(), &data, args, envs, path).unwrap();
+    proc.linux().init_heap(heap_start);
+    proc.linux().init_vdso(vdso_base);
 
     thread
         .start_with_entry(entry, sp, 0, 0, thread_fn)
@@ -47,6 +57,7 @@ fn thread_fn(thread: CurrentThread) -> Pin<Box<dyn Future<Output = ()> + Send +
 /// loop:
 /// - wait for the thread to be ready
 /// - get user thread context
+/// - deliver pending signals
 /// - enter user mode
 /// - handle trap/interrupt/syscall according to the return value
 /// - return the context to the user thread
@@ -59,16 +70,53 @@ async fn run_user(thread: CurrentThread) {
             break;
         }
 
+        // deliver pending signals
+        if !thread.handle_signal(&mut ctx) {
+            thread.put_context(ctx);
+            continue;
+        }
+
         // run
         trace!("go to user: {:#x?}", ctx);
+        let start = kernel_hal::timer::timer_now();
         ctx.enter_uspace();
+        let time = kernel_hal::timer::timer_now().saturating_sub(start);
+        thread.time_add(time.as_nanos());
+        thread.proc().linux().account_user_time(time);
         trace!("back from user: {:#x?}", ctx);
 
         // handle trap/interrupt/syscall
-        if let Err(err) = handle_user_trap(&thread, ctx).await {
+        let handler = SystemTime {
+            thread: &thread,
+            future: Box::pin(handle_user_trap(&thread, ctx)),
+        };
+        if let Err(err) = handler.await {
             thread.exit_linux(err as i32);
         }
     }
+    // the thread may be terminated with its process, rather than by `exit`
+    thread.exit_linux(0);
+}
+
+/// A future accounting the time spent polling `future` as the system time of `thread`,
+/// so the time blocked in a syscall is not counted.
+#[must_use = "future does nothing unless polled/`await`-ed"]
+struct SystemTime<'a, F> {
+    thread: &'a CurrentThread,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for SystemTime<'_, F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let start = kernel_hal::timer::timer_now();
+        let ret = self.future.as_mut().poll(cx);
+        let time = kernel_hal::timer::timer_now().saturating_sub(start);
+        self.thread.system_time_add(time.as_nanos());
+        self.thread.proc().linux().account_system_time(time);
+        ret
+    }
 }
 
 async fn handle_user_trap(thread: &CurrentThread, mut ctx: Box<UserContext>) -> ZxResult {
@@ -104,7 +152,7 @@ async fn handle_user_trap(thread: &CurrentThread, mut ctx: Box<UserContext>) ->
                 vaddr, flags, pid
             );
             let vmar = thread.proc().vmar();
-            vmar.handle_page_fault(vaddr, flags).map_err(|err| {
+            if let Err(err) = vmar.handle_page_fault(vaddr, flags) {
                 error!(
                     "failed to handle page fault from user mode @ {:#x}({:?}): {:?}\n{:#x?}",
                     vaddr,
@@ -112,8 +160,12 @@ async fn handle_user_trap(thread: &CurrentThread, mut ctx: Box<UserContext>) ->
                     err,
                     thread.context_cloned(),
                 );
-                err
-            })
+                thread.force_signal(SigInfo::new(Signal::SIGSEGV, SignalCode::KERNEL));
+            } else {
+                thread.lock_linux().minor_faults += 1;
+                thread.proc().linux().account_minor_fault();
+            }
+            Ok(())
         }
         _ => {
             error!(
@@ -3,7 +3,7 @@
 //! Reference: <https://fuchsia.googlesource.com/fuchsia/+/3c234f79f71/zircon/kernel/lib/userabi/userboot.cc>
 
 use alloc::{boxed::Box, sync::Arc, vec::Vec};
-use core::{future::Future, pin::Pin};
+use core::{future::Future, pin::Pin, time::Duration};
 
 use xmas_elf::ElfFile;
 
@@ -15,7 +15,8 @@ use zircon_object::kcounter;
 use zircon_object::object::{Handle, KernelObject, Rights};
 use zircon_object::task::{CurrentThread, ExceptionType, Job, Process, Thread, ThreadState};
 use zircon_object::util::elf_loader::{ElfExt, VmarExt};
-use zircon_object::vm::{VmObject, VmarFlags};
+use zircon_object::vm::{wait_for_pages, VmObject, VmarFlags};
+use zircon_object::ZxError;
 
 // These describe userboot itself
 const K_PROC_SELF: usize = 0;
@@ -111,7 +112,12 @@ pub fn run_userboot(zbi: impl AsRef<[u8]>, cmdline: &str) -> Arc<Process> {
         let elf = ElfFile::new(userboot).unwrap();
         let size = elf.load_segment_size();
         let vmar = vmar
-            .allocate(None, size, VmarFlags::CAN_MAP_RXW, PAGE_SIZE)
+            .allocate(
+                None,
+                size,
+                VmarFlags::CAN_MAP_RXW | VmarFlags::CAN_MAP_SPECIFIC,
+                PAGE_SIZE,
+            )
             .unwrap();
         vmar.load_from_elf(&elf).unwrap();
         (vmar.addr() + elf.header.pt2.entry_point() as usize, size)
@@ -127,7 +133,7 @@ pub fn run_userboot(zbi: impl AsRef<[u8]>, cmdline: &str) -> Arc<Process> {
             .allocate_at(
                 userboot_size,
                 size,
-                VmarFlags::CAN_MAP_RXW | VmarFlags::SPECIFIC,
+                VmarFlags::CAN_MAP_RXW | VmarFlags::CAN_MAP_SPECIFIC | VmarFlags::SPECIFIC,
                 PAGE_SIZE,
             )
             .unwrap();
@@ -310,7 +316,19 @@ async fn handler_user_trap(
             EXCEPTIONS_PGFAULT.add(1);
             info!("page fault from user mode @ {:#x}({:?})", vaddr, flags);
             let vmar = thread.proc().vmar();
-            vmar.handle_page_fault(vaddr, flags).map_err(|err| {
+            let mut res = vmar.handle_page_fault(vaddr, flags);
+            if res == Err(ZxError::SHOULD_WAIT) {
+                // block until the pager supplies the page
+                let future = wait_for_pages(|| vmar.try_handle_page_fault(vaddr, flags));
+                let deadline = Duration::from_nanos(u64::MAX);
+                res = thread
+                    .blocking_run(future, ThreadState::BlockedPager, deadline, None)
+                    .await;
+                if res == Err(ZxError::STOP) {
+                    return Err(ExceptionType::ThreadExiting);
+                }
+            }
+            res.map_err(|err| {
                 error!(
                     "failed to handle page fault from user mode @ {:#x}({:?}): {:?}\n{:#x?}",
                     vaddr,
@@ -153,3 +153,98 @@ async fn test_select() {
 async fn test_poll() {
     assert_eq!(test("/bin/testpoll").await, 0);
 }
+
+#[async_std::test]
+async fn test_signal() {
+    assert_eq!(test("/bin/testsignal").await, 0);
+}
+
+#[async_std::test]
+async fn test_epoll() {
+    assert_eq!(test("/bin/testepoll").await, 0);
+}
+
+#[async_std::test]
+async fn test_brk() {
+    assert_eq!(test("/bin/testbrk").await, 0);
+}
+
+#[async_std::test]
+async fn test_cred() {
+    assert_eq!(test("/bin/testcred").await, 0);
+}
+
+#[async_std::test]
+async fn test_pgrp() {
+    assert_eq!(test("/bin/testpgrp").await, 0);
+}
+
+#[async_std::test]
+async fn test_mount() {
+    assert_eq!(test("/bin/testmount").await, 0);
+}
+
+#[async_std::test]
+async fn test_proc() {
+    assert_eq!(test("/bin/testproc").await, 0);
+}
+
+#[async_std::test]
+async fn test_msg() {
+    assert_eq!(test("/bin/testmsg").await, 0);
+}
+
+#[async_std::test]
+async fn test_unix() {
+    assert_eq!(test("/bin/testunix").await, 0);
+}
+
+#[async_std::test]
+async fn test_clone() {
+    assert_eq!(test("/bin/testclone").await, 0);
+}
+
+#[async_std::test]
+async fn test_futex() {
+    assert_eq!(test("/bin/testfutex").await, 0);
+}
+
+#[async_std::test]
+async fn test_mmap() {
+    assert_eq!(test("/bin/testmmap").await, 0);
+}
+
+#[async_std::test]
+async fn test_wait() {
+    assert_eq!(test("/bin/testwait").await, 0);
+}
+
+#[async_std::test]
+async fn test_itimer() {
+    assert_eq!(test("/bin/testitimer").await, 0);
+}
+
+#[async_std::test]
+async fn test_eventfd() {
+    assert_eq!(test("/bin/testeventfd").await, 0);
+}
+
+#[async_std::test]
+async fn test_rusage() {
+    assert_eq!(test("/bin/testrusage").await, 0);
+}
+
+#[async_std::test]
+async fn test_symlink() {
+    assert_eq!(test("/bin/testsymlink").await, 0);
+}
+
+#[async_std::test]
+async fn test_cloexec() {
+    assert_eq!(test("/bin/testcloexec").await, 0);
+}
+
+#[async_std::test]
+async fn test_flock() {
+    assert_eq!(test("/bin/testflock").await, 0);
+}
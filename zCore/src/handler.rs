@@ -17,6 +17,10 @@ impl KernelHandler for ZcoreKernelHandler {
         memory::frame_dealloc(paddr)
     }
 
+    fn frame_stats(&self) -> (usize, usize) {
+        memory::frame_stats()
+    }
+
     fn handle_page_fault(&self, fault_vaddr: usize, access_flags: MMUFlags) {
         panic!(
             "page fault from kernel mode @ {:#x}({:?})",
@@ -1,6 +1,7 @@
 //! Define physical frame allocation and dynamic memory allocation.
 
 use core::ops::Range;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 use bitmap_allocator::BitAlloc;
 use kernel_hal::PhysAddr;
@@ -22,6 +23,12 @@ const PAGE_SIZE: usize = 4096;
 /// Global physical frame allocator
 static FRAME_ALLOCATOR: Mutex<FrameAlloc> = Mutex::new(FrameAlloc::DEFAULT);
 
+/// Number of frames added to the allocator
+static TOTAL_FRAMES: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of allocated frames
+static ALLOCATED_FRAMES: AtomicUsize = AtomicUsize::new(0);
+
 fn phys_addr_to_frame_idx(addr: PhysAddr) -> usize {
     (addr - PHYS_MEMORY_BASE) / PAGE_SIZE
 }
@@ -38,6 +45,7 @@ pub fn init_frame_allocator(regions: &[Range<PhysAddr>]) {
         let frame_end = phys_addr_to_frame_idx(region.end - 1) + 1;
         if frame_start < frame_end {
             ba.insert(frame_start..frame_end);
+            TOTAL_FRAMES.fetch_add(frame_end - frame_start, Ordering::Relaxed);
             info!(
                 "Frame allocator: add range {:#x?}",
                 frame_idx_to_phys_addr(frame_start)..frame_idx_to_phys_addr(frame_end),
@@ -50,6 +58,9 @@ pub fn init_frame_allocator(regions: &[Range<PhysAddr>]) {
 pub fn frame_alloc() -> Option<PhysAddr> {
     let ret = FRAME_ALLOCATOR.lock().alloc().map(frame_idx_to_phys_addr);
     trace!("frame_alloc(): {:x?}", ret);
+    if ret.is_some() {
+        ALLOCATED_FRAMES.fetch_add(1, Ordering::Relaxed);
+    }
     ret
 }
 
@@ -64,6 +75,9 @@ pub fn frame_alloc_contiguous(frame_count: usize, align_log2: usize) -> Option<P
         ret.map(|x| x + frame_count),
         align_log2,
     );
+    if ret.is_some() {
+        ALLOCATED_FRAMES.fetch_add(frame_count, Ordering::Relaxed);
+    }
     ret
 }
 
@@ -71,7 +85,14 @@ pub fn frame_dealloc(target: PhysAddr) {
     trace!("frame_dealloc(): {:x}", target);
     FRAME_ALLOCATOR
         .lock()
-        .dealloc(phys_addr_to_frame_idx(target))
+        .dealloc(phys_addr_to_frame_idx(target));
+    ALLOCATED_FRAMES.fetch_sub(1, Ordering::Relaxed);
+}
+
+/// Returns the number of total and free frames.
+pub fn frame_stats() -> (usize, usize) {
+    let total = TOTAL_FRAMES.load(Ordering::Relaxed);
+    (total, total - ALLOCATED_FRAMES.load(Ordering::Relaxed))
 }
 
 cfg_if! {
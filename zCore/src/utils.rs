@@ -119,7 +119,7 @@ pub fn wait_for_exit(proc: Option<Arc<Process>>) -> ! {
 #[cfg(not(feature = "libos"))]
 pub fn wait_for_exit(proc: Option<Arc<Process>>) -> ! {
     loop {
-        let has_task = executor::run_until_idle();
+        let has_task = kernel_hal::thread::run_until_idle();
         if cfg!(feature = "baremetal-test") && !has_task {
             proc.map(check_exit_code);
             kernel_hal::cpu::reset();
@@ -121,7 +121,7 @@ impl GuestPhysMemorySetTrait for GuestPhysMemorySet {
         if let Some(mapping) = self.vmar.find_mapping(gpaddr) {
             mapping
                 .handle_page_fault(gpaddr, mapping.get_flags(gpaddr).unwrap())
-                .map_err(From::from)
+                .map_err(|err| ZxError::from(err).into())
         } else {
             Err(RvmError::NotFound)
         }
@@ -158,6 +158,12 @@ bitflags! {
 
         /// BASIC | IO | EXECUTE | SIGNAL
         const DEFAULT_VCPU = Self::BASIC.bits | Self::IO.bits | Self::EXECUTE.bits | Self::SIGNAL.bits;
+
+        /// BASIC & !WAIT
+        const DEFAULT_PAGER = Self::BASIC.bits & !Self::WAIT.bits;
+
+        /// BASIC | APPLY_PROFILE
+        const DEFAULT_PROFILE = Self::BASIC.bits | Self::APPLY_PROFILE.bits;
     }
 }
 
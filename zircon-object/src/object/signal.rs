@@ -45,6 +45,7 @@ bitflags! {
 
         // for Linux
         const SIGCHLD                       = 1 << 6;
+        const PROCESS_EXECVE                = 1 << 7;
 
         // for user
         const USER_SIGNAL_0                 = 1 << 24;
@@ -118,66 +118,28 @@ impl Futex {
         thread: Option<Arc<Thread>>,
         new_owner: Option<Arc<Thread>>,
     ) -> impl Future<Output = ZxResult> {
-        #[must_use = "wait does nothing unless polled/`await`-ed"]
-        struct FutexFuture {
-            waiter: Arc<Waiter>,
-            current_value: i32,
-            new_owner: Option<Arc<Thread>>,
-        }
-        impl Future for FutexFuture {
-            type Output = ZxResult;
-
-            fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-                let mut inner = self.waiter.inner.lock();
-                // check wakeup
-                if inner.woken {
-                    // set new owner on success
-                    inner.futex.inner.lock().set_owner(self.new_owner.clone());
-                    return Poll::Ready(Ok(()));
-                }
-                // first time?
-                if inner.waker.is_none() {
-                    // check value
-                    let value = inner.futex.value.load(Ordering::SeqCst);
-                    if value != self.current_value {
-                        return Poll::Ready(Err(ZxError::BAD_STATE));
-                    }
-                    // check new owner
-                    let mut futex = inner.futex.inner.lock();
-                    if !futex.is_valid_new_owner(&self.new_owner) {
-                        return Poll::Ready(Err(ZxError::INVALID_ARGS));
-                    }
-                    futex.waiter_queue.push_back(self.waiter.clone());
-                    drop(futex);
-                    inner.waker.replace(cx.waker().clone());
-                }
-                Poll::Pending
-            }
-        }
-        // The FutexFuture will be dropped when the thread is no longer waiting
-        // if we wake without be woken, remove myself from the waiter_queue
-        impl Drop for FutexFuture {
-            fn drop(&mut self) {
-                let inner = self.waiter.inner.lock();
-                if !inner.woken {
-                    let futex = inner.futex.clone();
-                    let queue = &mut futex.inner.lock().waiter_queue;
-                    if let Some(pos) = queue.iter().position(|x| Arc::ptr_eq(x, &self.waiter)) {
-                        // Nobody cares about the order of queue, so just remove faster
-                        queue.swap_remove_back(pos);
-                    }
-                }
-            }
-        }
+        self.wait_inner(self.value, current_value, thread, new_owner, u32::MAX)
+    }
+
+    fn wait_inner(
+        self: &Arc<Self>,
+        value: &'static AtomicI32,
+        current_value: i32,
+        thread: Option<Arc<Thread>>,
+        new_owner: Option<Arc<Thread>>,
+        bitset: u32,
+    ) -> FutexFuture {
         FutexFuture {
             waiter: Arc::new(Waiter {
                 thread,
+                bitset,
                 inner: Mutex::new(WaiterInner {
                     waker: None,
                     woken: false,
                     futex: self.clone(),
                 }),
             }),
+            value,
             current_value,
             new_owner,
         }
@@ -228,29 +190,106 @@ impl Futex {
         if self.value.load(Ordering::SeqCst) != current_value {
             return Err(ZxError::BAD_STATE);
         }
+        let mut new_inner = requeue_futex.inner.lock();
+        inner.wake_requeue(&mut new_inner, wake_count, requeue_count, requeue_futex);
+        // set owner
+        inner.set_owner(None);
+        new_inner.set_owner(new_requeue_owner);
+        Ok(())
+    }
+
+    // ------ Advanced APIs for Linux ------
+
+    /// Wait on a futex until woken by [`wake_bitset`] with a bitset intersecting `bitset`.
+    ///
+    /// This atomically verifies that `value` still contains `current_value`.
+    /// The value is read from `value` rather than the one given on creation,
+    /// because a futex shared by processes may be mapped at different addresses.
+    ///
+    /// [`wake_bitset`]: Futex::wake_bitset
+    pub fn wait_bitset(
+        self: &Arc<Self>,
+        value: &'static AtomicI32,
+        current_value: i32,
+        bitset: u32,
+    ) -> impl Future<Output = ZxResult> {
+        self.wait_inner(value, current_value, None, None, bitset)
+    }
+
+    /// Wake at most `wake_count` waiters whose bitset intersects `bitset`.
+    ///
+    /// Return the number of waiters that were woken up.
+    pub fn wake_bitset(&self, wake_count: usize, bitset: u32) -> usize {
+        let mut inner = self.inner.lock();
+        inner.set_owner(None);
+        let mut count = 0;
+        inner.waiter_queue.retain(|waiter| {
+            if count < wake_count && waiter.bitset & bitset != 0 {
+                waiter.wake();
+                count += 1;
+                false
+            } else {
+                true
+            }
+        });
+        count
+    }
+
+    /// Wake at most `wake_count` waiters, and move at most `requeue_count` of
+    /// the others to `requeue_futex`.
+    ///
+    /// Unlike [`requeue`], the value is not verified, which is up to the caller.
+    /// Return the number of waiters that were woken up and requeued.
+    ///
+    /// [`requeue`]: Futex::requeue
+    pub fn wake_requeue(
+        &self,
+        wake_count: usize,
+        requeue_count: usize,
+        requeue_futex: &Arc<Futex>,
+    ) -> (usize, usize) {
+        if core::ptr::eq(self, requeue_futex.as_ref()) {
+            // waiters requeued to the same futex stay where they are
+            let woken = self.wake(wake_count);
+            let requeued = requeue_count.min(self.inner.lock().waiter_queue.len());
+            return (woken, requeued);
+        }
+        let mut inner = self.inner.lock();
+        inner.set_owner(None);
+        let mut new_inner = requeue_futex.inner.lock();
+        inner.wake_requeue(&mut new_inner, wake_count, requeue_count, requeue_futex)
+    }
+}
+
+impl FutexInner {
+    /// Wake `wake_count` waiters and requeue `requeue_count` waiters to `requeue_futex`,
+    /// whose inner is `new_inner`.
+    fn wake_requeue(
+        &mut self,
+        new_inner: &mut FutexInner,
+        wake_count: usize,
+        requeue_count: usize,
+        requeue_futex: &Arc<Futex>,
+    ) -> (usize, usize) {
         // wake
-        for _ in 0..wake_count {
-            if let Some(waiter) = inner.waiter_queue.pop_front() {
+        let mut woken = 0;
+        while woken < wake_count {
+            if let Some(waiter) = self.waiter_queue.pop_front() {
                 waiter.wake();
+                woken += 1;
             } else {
                 break;
             }
         }
         // requeue
-        let mut new_inner = requeue_futex.inner.lock();
-        let requeue_count = requeue_count.min(inner.waiter_queue.len());
-        for waiter in inner.waiter_queue.drain(..requeue_count) {
+        let requeue_count = requeue_count.min(self.waiter_queue.len());
+        for waiter in self.waiter_queue.drain(..requeue_count) {
             waiter.reset_futex(requeue_futex.clone());
             new_inner.waiter_queue.push_back(waiter);
         }
-        // set owner
-        inner.set_owner(None);
-        new_inner.set_owner(new_requeue_owner);
-        Ok(())
+        (woken, requeue_count)
     }
-}
 
-impl FutexInner {
     fn is_valid_new_owner(&self, new_owner: &Option<Arc<Thread>>) -> bool {
         // TODO: check whether the thread has been started yet
         if let Some(new_owner) = &new_owner {
@@ -272,9 +311,68 @@ impl FutexInner {
     }
 }
 
+/// The future returned by [`Futex::wait_with_owner`] and [`Futex::wait_bitset`].
+#[must_use = "wait does nothing unless polled/`await`-ed"]
+struct FutexFuture {
+    waiter: Arc<Waiter>,
+    /// The futex word to verify
+    value: &'static AtomicI32,
+    current_value: i32,
+    new_owner: Option<Arc<Thread>>,
+}
+
+impl Future for FutexFuture {
+    type Output = ZxResult;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut inner = self.waiter.inner.lock();
+        // check wakeup
+        if inner.woken {
+            // set new owner on success
+            inner.futex.inner.lock().set_owner(self.new_owner.clone());
+            return Poll::Ready(Ok(()));
+        }
+        // first time?
+        if inner.waker.is_none() {
+            // check value
+            let value = self.value.load(Ordering::SeqCst);
+            if value != self.current_value {
+                return Poll::Ready(Err(ZxError::BAD_STATE));
+            }
+            // check new owner
+            let mut futex = inner.futex.inner.lock();
+            if !futex.is_valid_new_owner(&self.new_owner) {
+                return Poll::Ready(Err(ZxError::INVALID_ARGS));
+            }
+            futex.waiter_queue.push_back(self.waiter.clone());
+            drop(futex);
+            inner.waker.replace(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+// The FutexFuture will be dropped when the thread is no longer waiting
+// if we wake without be woken, remove myself from the waiter_queue
+impl Drop for FutexFuture {
+    fn drop(&mut self) {
+        let inner = self.waiter.inner.lock();
+        if !inner.woken {
+            let futex = inner.futex.clone();
+            let queue = &mut futex.inner.lock().waiter_queue;
+            if let Some(pos) = queue.iter().position(|x| Arc::ptr_eq(x, &self.waiter)) {
+                // Nobody cares about the order of queue, so just remove faster
+                queue.swap_remove_back(pos);
+            }
+        }
+    }
+}
+
 struct Waiter {
     /// The thread waiting on the futex.
     thread: Option<Arc<Thread>>,
+    /// Only woken by a bitset intersecting it
+    bitset: u32,
     inner: Mutex<WaiterInner>,
 }
 
@@ -384,6 +482,53 @@ mod tests {
         assert_eq!(VALUE.load(Ordering::SeqCst), 2);
     }
 
+    #[async_std::test]
+    async fn bitset() {
+        static VALUE: AtomicI32 = AtomicI32::new(1);
+        let futex = Futex::new(&VALUE);
+
+        // inconsistent value should fail.
+        assert_eq!(
+            futex.wait_bitset(&VALUE, 0, 0b01).await,
+            Err(ZxError::BAD_STATE)
+        );
+
+        for &bitset in [0b01, 0b10].iter() {
+            let futex = futex.clone();
+            async_std::task::spawn(async move {
+                futex.wait_bitset(&VALUE, 1, bitset).await.unwrap();
+            });
+        }
+        async_std::task::sleep(Duration::from_millis(10)).await;
+        // only the waiters with an intersecting bitset are woken.
+        assert_eq!(futex.wake_bitset(2, 0b100), 0);
+        assert_eq!(futex.wake_bitset(2, 0b10), 1);
+        assert_eq!(futex.inner.lock().waiter_queue.len(), 1);
+        assert_eq!(futex.wake(2), 1);
+    }
+
+    #[async_std::test]
+    async fn wake_requeue() {
+        static VALUE: AtomicI32 = AtomicI32::new(1);
+        let futex = Futex::new(&VALUE);
+        static REQUEUE_VALUE: AtomicI32 = AtomicI32::new(100);
+        let requeue_futex = Futex::new(&REQUEUE_VALUE);
+
+        for _ in 0..3 {
+            let futex = futex.clone();
+            async_std::task::spawn(async move {
+                futex.wait(1).await.unwrap();
+            });
+        }
+        async_std::task::sleep(Duration::from_millis(10)).await;
+        // the value is not checked.
+        VALUE.store(2, Ordering::SeqCst);
+        assert_eq!(futex.wake_requeue(1, 1, &futex), (1, 1));
+        assert_eq!(futex.wake_requeue(1, 5, &requeue_futex), (1, 1));
+        assert_eq!(futex.inner.lock().waiter_queue.len(), 0);
+        assert_eq!(requeue_futex.wake(5), 1);
+    }
+
     #[async_std::test]
     async fn owner() {
         let root_job = Job::root();
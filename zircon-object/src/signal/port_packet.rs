@@ -41,7 +41,7 @@ pub union Payload {
     guest_io: PacketGuestIo,
     guest_vcpu: PacketGuestVcpu,
     interrupt: PacketInterrupt,
-    // TODO: PacketPageRequest
+    page_request: PacketPageRequest,
 }
 
 pub type PacketUser = [u8; 32];
@@ -159,6 +159,17 @@ pub struct PacketInterrupt {
     pub _reserved2: u64,
 }
 
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PacketPageRequest {
+    pub command: u16,
+    pub flags: u16,
+    pub _reserved0: u32,
+    pub offset: u64,
+    pub length: u64,
+    pub _reserved1: u64,
+}
+
 // Rust struct: for internal constructing and debugging
 
 /// A high-level representation of a packet sent through a port.
@@ -179,6 +190,7 @@ pub enum PayloadRepr {
     GuestIo(PacketGuestIo),
     GuestVcpu(PacketGuestVcpu),
     Interrupt(PacketInterrupt),
+    PageRequest(PacketPageRequest),
 }
 
 impl PayloadRepr {
@@ -191,6 +203,7 @@ impl PayloadRepr {
             PayloadRepr::GuestIo(_) => PacketType::GuestIo,
             PayloadRepr::GuestVcpu(_) => PacketType::GuestVcpu,
             PayloadRepr::Interrupt(_) => PacketType::Interrupt,
+            PayloadRepr::PageRequest(_) => PacketType::PageRequest,
         }
     }
     fn encode(&self) -> Payload {
@@ -202,6 +215,7 @@ impl PayloadRepr {
             PayloadRepr::GuestIo(guest_io) => Payload { guest_io },
             PayloadRepr::GuestVcpu(guest_vcpu) => Payload { guest_vcpu },
             PayloadRepr::Interrupt(interrupt) => Payload { interrupt },
+            PayloadRepr::PageRequest(page_request) => Payload { page_request },
         }
     }
     #[allow(unsafe_code)]
@@ -216,7 +230,7 @@ impl PayloadRepr {
                 PacketType::GuestIo => PayloadRepr::GuestIo(data.guest_io),
                 PacketType::GuestVcpu => PayloadRepr::GuestVcpu(data.guest_vcpu),
                 PacketType::Interrupt => PayloadRepr::Interrupt(data.interrupt),
-                _ => unimplemented!(),
+                PacketType::PageRequest => PayloadRepr::PageRequest(data.page_request),
             }
         }
     }
@@ -300,6 +314,7 @@ mod tests {
         assert_eq!(size_of::<PacketGuestIo>(), 32);
         assert_eq!(size_of::<PacketGuestVcpu>(), 32);
         assert_eq!(size_of::<PacketInterrupt>(), 32);
+        assert_eq!(size_of::<PacketPageRequest>(), 32);
     }
 
     fn test_encdec(data: PayloadRepr) {
@@ -430,9 +445,15 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "not implemented")]
     fn page_request() {
-        let data: PacketUser = [0u8; 32];
-        PayloadRepr::decode(PacketType::PageRequest, &Payload { user: data });
+        let page_request = PacketPageRequest {
+            command: 1,
+            flags: 0,
+            _reserved0: 0,
+            offset: 0x1000,
+            length: 0x2000,
+            _reserved1: 0,
+        };
+        test_encdec(PayloadRepr::PageRequest(page_request));
     }
 }
@@ -7,11 +7,12 @@ mod exception;
 mod job;
 mod job_policy;
 mod process;
+mod profile;
 mod suspend_token;
 mod thread;
 
 pub use {
-    self::exception::*, self::job::*, self::job_policy::*, self::process::*,
+    self::exception::*, self::job::*, self::job_policy::*, self::process::*, self::profile::*,
     self::suspend_token::*, self::thread::*,
 };
 
@@ -58,7 +58,7 @@ pub struct Process {
     _counter: CountHelper,
     job: Arc<Job>,
     policy: JobPolicy,
-    vmar: Arc<VmAddressRegion>,
+    vmar: Mutex<Arc<VmAddressRegion>>,
     ext: Box<dyn Any + Send + Sync>,
     exceptionate: Arc<Exceptionate>,
     debug_exceptionate: Arc<Exceptionate>,
@@ -125,7 +125,7 @@ impl Process {
             _counter: CountHelper::new(),
             job: job.clone(),
             policy: job.policy(),
-            vmar: VmAddressRegion::new_root(),
+            vmar: Mutex::new(VmAddressRegion::new_root()),
             ext: Box::new(ext),
             exceptionate: Exceptionate::new(ExceptionChannelType::Process),
             debug_exceptionate: Exceptionate::new(ExceptionChannelType::Debugger),
@@ -310,7 +310,15 @@ impl Process {
 
     /// Get the `VmAddressRegion` of the process.
     pub fn vmar(&self) -> Arc<VmAddressRegion> {
-        self.vmar.clone()
+        self.vmar.lock().clone()
+    }
+
+    /// Replace the root `VmAddressRegion` of the process.
+    ///
+    /// Threads of the process switch to the new address space
+    /// the next time they enter user mode.
+    pub fn set_vmar(&self, vmar: Arc<VmAddressRegion>) {
+        *self.vmar.lock() = vmar;
     }
 
     /// Get the job of the process.
@@ -0,0 +1,225 @@
+use {
+    super::*,
+    crate::object::*,
+    bitflags::bitflags,
+    core::{convert::TryInto, time::Duration},
+    kernel_hal::thread::{SchedClass, SchedInfo, HIGHEST_PRIORITY, LOWEST_PRIORITY},
+};
+
+/// Scheduling parameters applied to threads
+///
+/// ## SYNOPSIS
+///
+/// A profile holds a priority or deadline scheduling class, and a CPU affinity mask.
+/// Applying it to a thread with [`Thread::set_profile`] changes the parameters of the
+/// thread given by the profile, which take effect the next time the thread is scheduled.
+pub struct Profile {
+    base: KObjectBase,
+    class: Option<SchedClass>,
+    affinity: Option<u64>,
+}
+
+impl_kobject!(Profile);
+
+impl Profile {
+    /// Create a new profile from `info`.
+    pub fn new(info: &ProfileInfo) -> ZxResult<Arc<Self>> {
+        let flags = ProfileInfoFlags::from_bits(info.flags).ok_or(ZxError::INVALID_ARGS)?;
+        if flags.is_empty()
+            || flags.contains(ProfileInfoFlags::PRIORITY | ProfileInfoFlags::DEADLINE)
+        {
+            return Err(ZxError::INVALID_ARGS);
+        }
+        let class = if flags.contains(ProfileInfoFlags::PRIORITY) {
+            let priority = info.priority();
+            if !(LOWEST_PRIORITY as i32..=HIGHEST_PRIORITY as i32).contains(&priority) {
+                return Err(ZxError::INVALID_ARGS);
+            }
+            Some(SchedClass::Fair {
+                priority: priority as u8,
+            })
+        } else if flags.contains(ProfileInfoFlags::DEADLINE) {
+            let [capacity, relative_deadline, period] = info.deadline_params();
+            if capacity <= 0 || capacity > relative_deadline || relative_deadline > period {
+                return Err(ZxError::INVALID_ARGS);
+            }
+            Some(SchedClass::Deadline {
+                capacity: Duration::from_nanos(capacity as u64),
+                relative_deadline: Duration::from_nanos(relative_deadline as u64),
+                period: Duration::from_nanos(period as u64),
+            })
+        } else {
+            None
+        };
+        let affinity = if flags.contains(ProfileInfoFlags::CPU_MASK) {
+            // only the first 64 CPUs are supported
+            match info.cpu_affinity_mask[0] {
+                0 => return Err(ZxError::INVALID_ARGS),
+                mask => Some(mask),
+            }
+        } else {
+            None
+        };
+        Ok(Arc::new(Profile {
+            base: KObjectBase::new(),
+            class,
+            affinity,
+        }))
+    }
+
+    /// Apply the parameters in the profile to `info`.
+    pub(super) fn apply(&self, info: &mut SchedInfo) {
+        if let Some(class) = self.class {
+            info.class = class;
+        }
+        if let Some(affinity) = self.affinity {
+            info.affinity = affinity;
+        }
+    }
+}
+
+bitflags! {
+    /// Flags of [`ProfileInfo`], telling which parameters are set.
+    pub struct ProfileInfoFlags: u32 {
+        #[allow(clippy::identity_op)]
+        /// The priority is set.
+        const PRIORITY = 1 << 0;
+        /// The CPU affinity mask is set.
+        const CPU_MASK = 1 << 1;
+        /// The deadline parameters are set.
+        const DEADLINE = 1 << 2;
+    }
+}
+
+/// Parameters to create a [`Profile`].
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ProfileInfo {
+    /// Combination of [`ProfileInfoFlags`].
+    pub flags: u32,
+    padding1: [u8; 4],
+    /// The priority as an `i32`, or the capacity, relative deadline and period
+    /// of deadline scheduling as `i64`s in ns.
+    params: [u8; 24],
+    /// Bit `i` for the CPU `i`.
+    pub cpu_affinity_mask: [u64; 8],
+}
+
+impl ProfileInfo {
+    /// Parameters of a priority profile.
+    pub fn with_priority(priority: i32) -> Self {
+        let mut info = ProfileInfo {
+            flags: ProfileInfoFlags::PRIORITY.bits(),
+            ..Default::default()
+        };
+        info.params[..4].copy_from_slice(&priority.to_ne_bytes());
+        info
+    }
+
+    /// Parameters of a deadline profile, durations are in ns.
+    pub fn with_deadline(capacity: i64, relative_deadline: i64, period: i64) -> Self {
+        let mut info = ProfileInfo {
+            flags: ProfileInfoFlags::DEADLINE.bits(),
+            ..Default::default()
+        };
+        for (i, x) in [capacity, relative_deadline, period].iter().enumerate() {
+            info.params[i * 8..(i + 1) * 8].copy_from_slice(&x.to_ne_bytes());
+        }
+        info
+    }
+
+    fn priority(&self) -> i32 {
+        i32::from_ne_bytes(self.params[..4].try_into().unwrap())
+    }
+
+    fn deadline_params(&self) -> [i64; 3] {
+        let param =
+            |i: usize| i64::from_ne_bytes(self.params[i * 8..(i + 1) * 8].try_into().unwrap());
+        [param(0), param(1), param(2)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel_hal::thread::CPU_MASK_ALL;
+
+    #[test]
+    fn create() {
+        assert!(Profile::new(&ProfileInfo::with_priority(0)).is_ok());
+        assert!(Profile::new(&ProfileInfo::with_priority(31)).is_ok());
+        assert_eq!(
+            Profile::new(&ProfileInfo::with_priority(32)).err(),
+            Some(ZxError::INVALID_ARGS)
+        );
+        assert_eq!(
+            Profile::new(&ProfileInfo::with_priority(-1)).err(),
+            Some(ZxError::INVALID_ARGS)
+        );
+        assert_eq!(
+            Profile::new(&ProfileInfo::default()).err(),
+            Some(ZxError::INVALID_ARGS)
+        );
+
+        assert!(Profile::new(&ProfileInfo::with_deadline(1000, 2000, 2000)).is_ok());
+        assert_eq!(
+            Profile::new(&ProfileInfo::with_deadline(0, 2000, 2000)).err(),
+            Some(ZxError::INVALID_ARGS)
+        );
+        assert_eq!(
+            Profile::new(&ProfileInfo::with_deadline(3000, 2000, 4000)).err(),
+            Some(ZxError::INVALID_ARGS)
+        );
+        assert_eq!(
+            Profile::new(&ProfileInfo::with_deadline(1000, 3000, 2000)).err(),
+            Some(ZxError::INVALID_ARGS)
+        );
+
+        let mut info = ProfileInfo::with_priority(1);
+        info.flags |= ProfileInfoFlags::DEADLINE.bits();
+        assert_eq!(Profile::new(&info).err(), Some(ZxError::INVALID_ARGS));
+        let mut info = ProfileInfo {
+            flags: ProfileInfoFlags::CPU_MASK.bits(),
+            ..Default::default()
+        };
+        assert_eq!(Profile::new(&info).err(), Some(ZxError::INVALID_ARGS));
+        info.cpu_affinity_mask[0] = 1;
+        assert!(Profile::new(&info).is_ok());
+    }
+
+    #[test]
+    fn set_profile() {
+        let job = Job::root();
+        let proc = Process::create(&job, "proc").expect("failed to create process");
+        let thread = Thread::create(&proc, "thread").expect("failed to create thread");
+        assert_eq!(thread.sched_info(), SchedInfo::default());
+
+        let profile = Profile::new(&ProfileInfo::with_priority(24)).unwrap();
+        thread.set_profile(&profile);
+        let info = thread.sched_info();
+        assert_eq!(info.class, SchedClass::Fair { priority: 24 });
+        assert_eq!(info.affinity, CPU_MASK_ALL);
+
+        // only the parameters set in the profile are changed
+        let mut info = ProfileInfo {
+            flags: ProfileInfoFlags::CPU_MASK.bits(),
+            ..Default::default()
+        };
+        info.cpu_affinity_mask[0] = 0b10;
+        thread.set_profile(&Profile::new(&info).unwrap());
+        let info = thread.sched_info();
+        assert_eq!(info.class, SchedClass::Fair { priority: 24 });
+        assert_eq!(info.affinity, 0b10);
+
+        let profile = Profile::new(&ProfileInfo::with_deadline(1000, 2000, 3000)).unwrap();
+        thread.set_profile(&profile);
+        assert_eq!(
+            thread.sched_info().class,
+            SchedClass::Deadline {
+                capacity: Duration::from_nanos(1000),
+                relative_deadline: Duration::from_nanos(2000),
+                period: Duration::from_nanos(3000),
+            }
+        );
+    }
+}
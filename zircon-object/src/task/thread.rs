@@ -10,10 +10,11 @@ use core::{any::Any, future::Future, pin::Pin};
 use bitflags::bitflags;
 use futures::{channel::oneshot::*, future::FutureExt, pin_mut, select_biased};
 use kernel_hal::context::UserContext;
+use kernel_hal::thread::{SchedHandle, SchedInfo};
 use spin::Mutex;
 
 use self::thread_state::ContextAccessState;
-use super::{exception::*, Process, Task};
+use super::{exception::*, Process, Profile, Task};
 use crate::object::{KObjectBase, KoID, Signal};
 use crate::{define_count_helper, impl_kobject, ZxError, ZxResult};
 
@@ -88,6 +89,8 @@ pub struct Thread {
     ext: Box<dyn Any + Send + Sync>,
     inner: Mutex<ThreadInner>,
     exceptionate: Arc<Exceptionate>,
+    /// Scheduling parameters shared with the scheduler
+    sched: Arc<SchedHandle>,
 }
 
 impl_kobject!(Thread
@@ -121,8 +124,10 @@ struct ThreadInner {
     first_thread: bool,
     /// Should The ThreadExiting exception do not block this thread
     killed: bool,
-    /// The time this thread has run on cpu
+    /// The time this thread has run on cpu in user mode
     time: u128,
+    /// The time this thread has run on cpu in kernel mode
+    system_time: u128,
     flags: ThreadFlag,
 }
 
@@ -211,6 +216,7 @@ impl Thread {
             proc: proc.clone(),
             ext: Box::new(ext),
             exceptionate: Exceptionate::new(ExceptionChannelType::Thread),
+            sched: Arc::new(SchedHandle::default()),
             inner: Mutex::new(ThreadInner {
                 context: Some(Box::new(UserContext::new())),
                 ..Default::default()
@@ -257,7 +263,10 @@ impl Thread {
             .change_state(ThreadState::Running, &self.base);
         let current = CurrentThread(self.clone());
         let future = thread_fn(current);
-        kernel_hal::thread::spawn(ThreadSwitchFuture::new(self.clone(), future));
+        kernel_hal::thread::spawn_sched(
+            ThreadSwitchFuture::new(self.clone(), future),
+            self.sched.clone(),
+        );
         Ok(())
     }
 
@@ -334,8 +343,22 @@ impl Thread {
         context.write_state(kind, buf)
     }
 
+    /// Apply the scheduling parameters in `profile` to the thread.
+    ///
+    /// They take effect the next time the thread is scheduled.
+    pub fn set_profile(&self, profile: &Profile) {
+        self.sched.update(|info| profile.apply(info));
+    }
+
+    /// Get the scheduling parameters of the thread.
+    pub fn sched_info(&self) -> SchedInfo {
+        self.sched.get()
+    }
+
     /// Get the thread's information.
     pub fn get_thread_info(&self) -> ThreadInfo {
+        let mut cpu_affinity_mask = [0u64; 8];
+        cpu_affinity_mask[0] = self.sched.get().affinity;
         let inner = self.inner.lock();
         ThreadInfo {
             state: inner.state() as u32,
@@ -343,7 +366,7 @@ impl Thread {
                 .exception
                 .as_ref()
                 .map_or(0, |exception| exception.current_channel_type() as u32),
-            cpu_affinity_mask: [0u64; 8],
+            cpu_affinity_mask,
         }
     }
 
@@ -362,16 +385,32 @@ impl Thread {
         self.inner.lock().state()
     }
 
-    /// Add the parameter to the time this thread has run on cpu.
+    /// Add the parameter to the time this thread has run on cpu in user mode.
     pub fn time_add(&self, time: u128) {
         self.inner.lock().time += time;
     }
 
+    /// Add the parameter to the time this thread has run on cpu in kernel mode.
+    pub fn system_time_add(&self, time: u128) {
+        self.inner.lock().system_time += time;
+    }
+
     /// Get the time this thread has run on cpu.
     pub fn get_time(&self) -> u64 {
+        let inner = self.inner.lock();
+        (inner.time + inner.system_time) as u64
+    }
+
+    /// Get the time this thread has run on cpu in user mode.
+    pub fn get_user_time(&self) -> u64 {
         self.inner.lock().time as u64
     }
 
+    /// Get the time this thread has run on cpu in kernel mode.
+    pub fn get_system_time(&self) -> u64 {
+        self.inner.lock().system_time as u64
+    }
+
     /// Set this thread as the first thread of a process.
     pub(super) fn set_first_thread(&self) {
         self.inner.lock().first_thread = true;
@@ -946,5 +985,9 @@ mod tests {
         assert_eq!(thread.get_time(), 0);
         thread.time_add(10);
         assert_eq!(thread.get_time(), 10);
+        thread.system_time_add(5);
+        assert_eq!(thread.get_time(), 15);
+        assert_eq!(thread.get_user_time(), 10);
+        assert_eq!(thread.get_system_time(), 5);
     }
 }
@@ -0,0 +1,748 @@
+use {
+    super::*,
+    crate::object::*,
+    crate::signal::{PacketPageRequest, PayloadRepr, Port, PortPacketRepr},
+    alloc::{
+        collections::{BTreeMap, BTreeSet},
+        sync::{Arc, Weak},
+        vec::Vec,
+    },
+    core::{
+        future::Future,
+        marker::PhantomData,
+        ops::Range,
+        pin::Pin,
+        task::{Context, Poll, Waker},
+    },
+    lazy_static::lazy_static,
+    spin::Mutex,
+};
+
+/// Create VMOs whose pages are supplied by user space
+///
+/// ## SYNOPSIS
+///
+/// A pager allows a user space service, such as a filesystem, to provide the
+/// content of VMOs on demand. When an absent page of a pager-backed VMO is
+/// accessed, a `ZX_PKT_TYPE_PAGE_REQUEST` packet is queued on the port of the
+/// VMO, and the accessing thread blocks until the pager supplies the page.
+pub struct Pager {
+    base: KObjectBase,
+    /// The VMOs created by the pager, detached when the pager is destroyed.
+    sources: Mutex<Vec<Weak<PagerSource>>>,
+}
+
+impl_kobject!(Pager);
+
+/// The command of a page request packet.
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageRequestCommand {
+    /// The pages in the range are requested.
+    Read = 0,
+    /// The VMO is detached from the pager, no more requests will be sent.
+    Complete = 1,
+}
+
+/// An operation of `Pager::op_range`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PagerOp {
+    /// Fail the outstanding page requests in the range with the error.
+    Fail(ZxError),
+    /// Mark the pages in the range dirty.
+    Dirty,
+    /// Begin writing back the dirty pages in the range.
+    WritebackBegin,
+    /// Finish writing back the pages in the range, they become clean unless
+    /// written again since the writeback began.
+    WritebackEnd,
+}
+
+/// An absent page of a pager-backed VMO, which an access waits for to be supplied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PageRequest {
+    /// The address identifying the source of the VMO.
+    source: usize,
+    page_idx: usize,
+}
+
+/// The error of committing pages, which may be absent in a pager-backed VMO.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PagedError {
+    /// The access fails with the error.
+    Failed(ZxError),
+    /// The access should be retried when the requested page is supplied.
+    Wait(PageRequest),
+}
+
+/// The result of committing pages, which may be absent in a pager-backed VMO.
+pub type PagedResult<T = ()> = Result<T, PagedError>;
+
+impl From<ZxError> for PagedError {
+    fn from(err: ZxError) -> Self {
+        PagedError::Failed(err)
+    }
+}
+
+impl From<PagedError> for ZxError {
+    fn from(err: PagedError) -> Self {
+        match err {
+            PagedError::Failed(err) => err,
+            PagedError::Wait(_) => ZxError::SHOULD_WAIT,
+        }
+    }
+}
+
+/// The writeback state of a page modified since it was supplied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DirtyState {
+    /// The page is modified and not being written back.
+    Dirty,
+    /// The page is being written back.
+    AwaitingClean,
+}
+
+/// The link between a pager-backed VMO and its pager.
+pub struct PagerSource {
+    pager: Weak<Pager>,
+    port: Arc<Port>,
+    key: u64,
+    inner: Mutex<PagerSourceInner>,
+}
+
+#[derive(Default)]
+struct PagerSourceInner {
+    detached: bool,
+    /// Pages requested and not supplied yet.
+    pending: BTreeSet<usize>,
+    /// Pages whose requests failed, with the error for the next access.
+    failed: BTreeMap<usize, ZxError>,
+    /// Pages modified since they were supplied or written back.
+    dirty: BTreeMap<usize, DirtyState>,
+}
+
+impl Pager {
+    /// Create a new `Pager`.
+    pub fn new() -> Arc<Self> {
+        Arc::new(Pager {
+            base: KObjectBase::new(),
+            sources: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Create a VMO of `pages` pages, whose page requests are sent to `port` with `key`.
+    pub fn create_vmo(
+        self: &Arc<Self>,
+        pages: usize,
+        port: Arc<Port>,
+        key: u64,
+        resizable: bool,
+    ) -> Arc<VmObject> {
+        let source = Arc::new(PagerSource {
+            pager: Arc::downgrade(self),
+            port,
+            key,
+            inner: Mutex::new(PagerSourceInner::default()),
+        });
+        let mut sources = self.sources.lock();
+        sources.retain(|s| s.strong_count() != 0);
+        sources.push(Arc::downgrade(&source));
+        VmObject::new_pager_backed(resizable, pages, source)
+    }
+
+    /// Get the source of `vmo`, which must be created by this pager.
+    fn source(&self, vmo: &VmObject) -> ZxResult<Arc<PagerSource>> {
+        let source = vmo.pager_source().ok_or(ZxError::INVALID_ARGS)?;
+        if !core::ptr::eq(source.pager.as_ptr(), self) {
+            return Err(ZxError::INVALID_ARGS);
+        }
+        Ok(source)
+    }
+
+    /// Detach `vmo` from the pager.
+    ///
+    /// Outstanding and further accesses to absent pages fail with `BAD_STATE`.
+    pub fn detach_vmo(&self, vmo: &VmObject) -> ZxResult {
+        self.source(vmo)?.detach();
+        Ok(())
+    }
+
+    /// Supply the pages in `[offset, offset + len)` of `vmo`, moving the pages
+    /// from `aux_offset` of `aux_vmo`, which are decommitted.
+    ///
+    /// The pages of `aux_vmo` must be committed, or it fails with `BAD_STATE`.
+    /// Pages already present in `vmo` are not replaced.
+    pub fn supply_pages(
+        &self,
+        vmo: &VmObject,
+        offset: usize,
+        len: usize,
+        aux_vmo: &VmObject,
+        aux_offset: usize,
+    ) -> ZxResult {
+        let source = self.source(vmo)?;
+        if source.inner.lock().detached {
+            return Err(ZxError::BAD_STATE);
+        }
+        if !page_aligned(offset) || !page_aligned(len) || !page_aligned(aux_offset) {
+            return Err(ZxError::INVALID_ARGS);
+        }
+        let in_range = |offset: usize, vmo: &VmObject| {
+            offset
+                .checked_add(len)
+                .map_or(false, |end| end <= vmo.len())
+        };
+        if !in_range(offset, vmo) || !in_range(aux_offset, aux_vmo) {
+            return Err(ZxError::OUT_OF_RANGE);
+        }
+        let frames = aux_vmo.take_pages(aux_offset, len)?;
+        vmo.supply_pages(offset, frames)?;
+        source.notify(offset / PAGE_SIZE..pages(offset + len));
+        Ok(())
+    }
+
+    /// Apply `op` to the pages in `[offset, offset + len)` of `vmo`.
+    pub fn op_range(&self, vmo: &VmObject, op: PagerOp, offset: usize, len: usize) -> ZxResult {
+        let source = self.source(vmo)?;
+        if source.inner.lock().detached {
+            return Err(ZxError::BAD_STATE);
+        }
+        if !page_aligned(offset) || !page_aligned(len) {
+            return Err(ZxError::INVALID_ARGS);
+        }
+        if offset.checked_add(len).map_or(true, |end| end > vmo.len()) {
+            return Err(ZxError::OUT_OF_RANGE);
+        }
+        vmo.pager_op(op, offset, len)?;
+        if let PagerOp::Fail(_) = op {
+            source.notify(offset / PAGE_SIZE..pages(offset + len));
+        }
+        Ok(())
+    }
+
+    /// Get the ranges of dirty pages in `[offset, offset + len)` of `vmo`,
+    /// as `(offset, len)` pairs in bytes.
+    pub fn query_dirty_ranges(
+        &self,
+        vmo: &VmObject,
+        offset: usize,
+        len: usize,
+    ) -> ZxResult<Vec<(usize, usize)>> {
+        let source = self.source(vmo)?;
+        let range = offset / PAGE_SIZE..pages(offset.saturating_add(len));
+        let inner = source.inner.lock();
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for &idx in inner.dirty.range(range).map(|(idx, _)| idx) {
+            match ranges.last_mut() {
+                Some((start, len)) if *start + *len == idx * PAGE_SIZE => *len += PAGE_SIZE,
+                _ => ranges.push((idx * PAGE_SIZE, PAGE_SIZE)),
+            }
+        }
+        Ok(ranges)
+    }
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        for source in self.sources.get_mut().iter().filter_map(Weak::upgrade) {
+            source.detach();
+        }
+    }
+}
+
+impl PagerSource {
+    /// Request the absent page `page_idx`, called with the VMO locked.
+    ///
+    /// Returns the error for the access, or the request to wait for if the page
+    /// is to be supplied.
+    pub(super) fn request_page(&self, page_idx: usize) -> PagedError {
+        let mut inner = self.inner.lock();
+        if inner.detached {
+            return ZxError::BAD_STATE.into();
+        }
+        if let Some(err) = inner.failed.remove(&page_idx) {
+            return err.into();
+        }
+        if inner.pending.insert(page_idx) {
+            self.send(PageRequestCommand::Read, page_idx * PAGE_SIZE, PAGE_SIZE);
+        }
+        PagedError::Wait(PageRequest {
+            source: self.addr(),
+            page_idx,
+        })
+    }
+
+    /// Mark the page `page_idx` dirty on write, called with the VMO locked.
+    pub(super) fn page_written(&self, page_idx: usize) {
+        self.inner.lock().dirty.insert(page_idx, DirtyState::Dirty);
+    }
+
+    /// The pages in `range` are supplied, called with the VMO locked.
+    pub(super) fn pages_supplied(&self, range: Range<usize>) {
+        let mut inner = self.inner.lock();
+        for idx in range {
+            inner.pending.remove(&idx);
+            inner.failed.remove(&idx);
+        }
+    }
+
+    /// Apply `op` to the pages in `range`, called with the VMO locked.
+    pub(super) fn op_range(
+        &self,
+        op: PagerOp,
+        range: Range<usize>,
+        present: impl Fn(usize) -> bool,
+    ) -> ZxResult {
+        let mut inner = self.inner.lock();
+        match op {
+            PagerOp::Fail(err) => {
+                for idx in range {
+                    if inner.pending.remove(&idx) {
+                        inner.failed.insert(idx, err);
+                    }
+                }
+            }
+            PagerOp::Dirty => {
+                if !range.clone().all(present) {
+                    return Err(ZxError::NOT_FOUND);
+                }
+                for idx in range {
+                    inner.dirty.insert(idx, DirtyState::Dirty);
+                }
+            }
+            PagerOp::WritebackBegin => {
+                for (_, state) in inner.dirty.range_mut(range) {
+                    *state = DirtyState::AwaitingClean;
+                }
+            }
+            PagerOp::WritebackEnd => {
+                inner
+                    .dirty
+                    .retain(|idx, state| !range.contains(idx) || *state == DirtyState::Dirty);
+            }
+        }
+        Ok(())
+    }
+
+    /// Detach from the pager, failing the outstanding requests.
+    fn detach(&self) {
+        let mut inner = self.inner.lock();
+        if inner.detached {
+            return;
+        }
+        inner.detached = true;
+        inner.pending.clear();
+        inner.failed.clear();
+        self.send(PageRequestCommand::Complete, 0, 0);
+        drop(inner);
+        self.notify(0..usize::MAX);
+    }
+
+    /// Wake up the waiters for the pages in `range`.
+    fn notify(&self, range: Range<usize>) {
+        let source = self.addr();
+        let mut waiters = PAGE_WAITERS.lock();
+        waiters.generation += 1;
+        let start = PageRequest {
+            source,
+            page_idx: range.start,
+        };
+        let end = PageRequest {
+            source,
+            page_idx: range.end,
+        };
+        let keys = waiters
+            .wakers
+            .range(start..end)
+            .map(|(&key, _)| key)
+            .collect::<Vec<_>>();
+        for key in keys {
+            for waker in waiters.wakers.remove(&key).unwrap() {
+                waker.wake();
+            }
+        }
+    }
+
+    /// The address identifying the source in the page waiters.
+    fn addr(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    fn send(&self, command: PageRequestCommand, offset: usize, length: usize) {
+        self.port.push(PortPacketRepr {
+            key: self.key,
+            status: ZxError::OK,
+            data: PayloadRepr::PageRequest(PacketPageRequest {
+                command: command as u16,
+                flags: 0,
+                _reserved0: 0,
+                offset: offset as u64,
+                length: length as u64,
+                _reserved1: 0,
+            }),
+        });
+    }
+}
+
+impl Drop for PagerSource {
+    /// The pager is notified when the VMO is destroyed.
+    fn drop(&mut self) {
+        self.detach();
+    }
+}
+
+/// Waiters for pages of pager-backed VMOs to be supplied.
+#[derive(Default)]
+struct PageWaiters {
+    /// Increased whenever the state of a pager-backed VMO changes.
+    generation: u64,
+    /// Wakers of the waiters, by the pages they wait for.
+    wakers: BTreeMap<PageRequest, Vec<Waker>>,
+}
+
+lazy_static! {
+    static ref PAGE_WAITERS: Mutex<PageWaiters> = Mutex::new(PageWaiters::default());
+}
+
+/// Retry `f` until it does not fail with a page request, waiting for the
+/// requested pages of pager-backed VMOs to be supplied between the attempts.
+pub fn wait_for_pages<T, F>(f: F) -> impl Future<Output = ZxResult<T>> + Unpin
+where
+    F: FnMut() -> PagedResult<T> + Unpin,
+{
+    #[must_use = "wait_for_pages does nothing unless polled/`await`-ed"]
+    struct PagesFuture<F, T> {
+        f: F,
+        _marker: PhantomData<fn() -> T>,
+    }
+
+    impl<T, F: FnMut() -> PagedResult<T> + Unpin> Future for PagesFuture<F, T> {
+        type Output = ZxResult<T>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+            loop {
+                let generation = PAGE_WAITERS.lock().generation;
+                let request = match (self.f)() {
+                    Err(PagedError::Wait(request)) => request,
+                    ret => return Poll::Ready(ret.map_err(ZxError::from)),
+                };
+                let mut waiters = PAGE_WAITERS.lock();
+                // retry if pages are supplied meanwhile
+                if waiters.generation == generation {
+                    waiters
+                        .wakers
+                        .entry(request)
+                        .or_default()
+                        .push(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+
+    PagesFuture {
+        f,
+        _marker: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn next_request(port: &Arc<Port>) -> PortPacketRepr {
+        PortPacketRepr::from(&port.wait().await)
+    }
+
+    fn page_request(
+        key: u64,
+        command: PageRequestCommand,
+        offset: usize,
+        length: usize,
+    ) -> PortPacketRepr {
+        PortPacketRepr {
+            key,
+            status: ZxError::OK,
+            data: PayloadRepr::PageRequest(PacketPageRequest {
+                command: command as u16,
+                flags: 0,
+                _reserved0: 0,
+                offset: offset as u64,
+                length: length as u64,
+                _reserved1: 0,
+            }),
+        }
+    }
+
+    fn aux_vmo(byte: u8) -> Arc<VmObject> {
+        let aux = VmObject::new_paged(1);
+        aux.write(0, &[byte; PAGE_SIZE]).unwrap();
+        aux
+    }
+
+    #[async_std::test]
+    async fn request_and_supply() {
+        let pager = Pager::new();
+        let port = Port::new(0).unwrap();
+        let vmo = pager.create_vmo(2, port.clone(), 42, false);
+        assert!(vmo.get_info().flags.contains(VmoInfoFlags::PAGER_BACKED));
+
+        let mut buf = [0u8; 4];
+        assert_eq!(vmo.read(PAGE_SIZE, &mut buf), Err(ZxError::SHOULD_WAIT));
+        assert_eq!(
+            next_request(&port).await,
+            page_request(42, PageRequestCommand::Read, PAGE_SIZE, PAGE_SIZE)
+        );
+        // an outstanding request is not sent again
+        assert_eq!(vmo.read(PAGE_SIZE, &mut buf), Err(ZxError::SHOULD_WAIT));
+        assert!(!port.signal().contains(Signal::READABLE));
+
+        let aux = aux_vmo(1);
+        let paddr = aux.commit_page(0, MMUFlags::READ).unwrap();
+        pager
+            .supply_pages(&vmo, PAGE_SIZE, PAGE_SIZE, &aux, 0)
+            .unwrap();
+        vmo.read(PAGE_SIZE, &mut buf).unwrap();
+        assert_eq!(buf, [1; 4]);
+        // the aux pages are moved out
+        assert_eq!(aux.committed_pages_in_range(0, 1), 0);
+        assert_eq!(vmo.commit_page(1, MMUFlags::READ), Ok(paddr));
+        // absent aux pages are not supplied
+        assert_eq!(
+            pager.supply_pages(&vmo, 0, PAGE_SIZE, &aux, 0),
+            Err(ZxError::BAD_STATE)
+        );
+        // supplied pages do not replace present ones
+        pager
+            .supply_pages(&vmo, PAGE_SIZE, PAGE_SIZE, &aux_vmo(2), 0)
+            .unwrap();
+        vmo.read(PAGE_SIZE, &mut buf).unwrap();
+        assert_eq!(buf, [1; 4]);
+
+        // only the owning pager can supply pages
+        let other = Pager::new();
+        assert_eq!(
+            other.supply_pages(&vmo, 0, PAGE_SIZE, &aux, 0),
+            Err(ZxError::INVALID_ARGS)
+        );
+        assert_eq!(
+            pager.supply_pages(&vmo, 0, PAGE_SIZE * 3, &aux, 0),
+            Err(ZxError::OUT_OF_RANGE)
+        );
+        assert_eq!(
+            pager.supply_pages(&vmo, 1, PAGE_SIZE, &aux, 0),
+            Err(ZxError::INVALID_ARGS)
+        );
+        // pager-backed VMOs can not be snapshot or decommitted
+        assert_eq!(
+            vmo.create_clone(CloneType::Snapshot, false, 0, PAGE_SIZE)
+                .err(),
+            Some(ZxError::NOT_SUPPORTED)
+        );
+        assert_eq!(vmo.decommit(0, PAGE_SIZE), Err(ZxError::NOT_SUPPORTED));
+    }
+
+    #[test]
+    fn clone() {
+        let pager = Pager::new();
+        let port = Port::new(0).unwrap();
+        let vmo = pager.create_vmo(2, port, 1, false);
+        pager
+            .supply_pages(&vmo, 0, PAGE_SIZE, &aux_vmo(1), 0)
+            .unwrap();
+        let child = vmo
+            .create_clone(CloneType::SnapshotAtLeastOnWrite, false, 0, PAGE_SIZE * 3)
+            .unwrap();
+        assert!(child.get_info().flags.contains(VmoInfoFlags::IS_COW_CLONE));
+        // absent pages are requested for the child too
+        let mut buf = [0u8; 1];
+        assert_eq!(child.read(PAGE_SIZE, &mut buf), Err(ZxError::SHOULD_WAIT));
+        // pages beyond the parent are zero
+        assert_eq!(child.test_read(2), 0);
+
+        // unmodified pages are shared with the parent
+        vmo.test_write(0, 2);
+        assert_eq!(child.test_read(0), 2);
+        assert_eq!(child.get_info().committed_bytes, 0);
+        child.test_write(0, 3);
+        assert_eq!(vmo.test_read(0), 2);
+        assert_eq!(child.test_read(0), 3);
+        vmo.test_write(0, 4);
+        assert_eq!(child.test_read(0), 3);
+
+        // clones of a clone of pager-backed VMO can not be snapshot either
+        assert_eq!(
+            child
+                .create_clone(CloneType::Snapshot, false, 0, PAGE_SIZE)
+                .err(),
+            Some(ZxError::NOT_SUPPORTED)
+        );
+        // but the modified pages can
+        let snapshot = child
+            .create_clone(CloneType::SnapshotModified, false, 0, PAGE_SIZE)
+            .unwrap();
+        child.test_write(0, 5);
+        assert_eq!(snapshot.test_read(0), 3);
+        snapshot.test_write(0, 6);
+        assert_eq!(child.test_read(0), 5);
+        assert_eq!(vmo.test_read(0), 4);
+
+        // the parent lives until its clones are closed
+        drop(child);
+        drop(vmo);
+        assert_eq!(snapshot.test_read(0), 6);
+    }
+
+    #[async_std::test]
+    async fn wait_for_supply() {
+        let pager = Pager::new();
+        let port = Port::new(0).unwrap();
+        let vmo = pager.create_vmo(1, port.clone(), 1, false);
+        let reader = {
+            let vmo = vmo.clone();
+            async_std::task::spawn(async move {
+                let mut buf = [0u8; 4];
+                let res = wait_for_pages(|| {
+                    vmo.commit_range(0, buf.len(), MMUFlags::READ)?;
+                    Ok(vmo.read(0, &mut buf)?)
+                })
+                .await;
+                res.map(|_| buf)
+            })
+        };
+        assert_eq!(
+            next_request(&port).await,
+            page_request(1, PageRequestCommand::Read, 0, PAGE_SIZE)
+        );
+        pager
+            .supply_pages(&vmo, 0, PAGE_SIZE, &aux_vmo(3), 0)
+            .unwrap();
+        assert_eq!(reader.await, Ok([3; 4]));
+    }
+
+    #[test]
+    fn wake_waiters_of_page() {
+        let pager = Pager::new();
+        let port = Port::new(0).unwrap();
+        let vmo = pager.create_vmo(2, port.clone(), 1, false);
+        let mut buf = [0u8; 4];
+        let key = PageRequest {
+            source: vmo.pager_source().unwrap().addr(),
+            page_idx: 0,
+        };
+        assert_eq!(
+            vmo.commit_page(0, MMUFlags::READ),
+            Err(PagedError::Wait(key))
+        );
+        let mut reader = wait_for_pages(|| {
+            vmo.commit_range(0, buf.len(), MMUFlags::READ)?;
+            Ok(vmo.read(0, &mut buf)?)
+        });
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert!(Pin::new(&mut reader).poll(&mut cx).is_pending());
+        assert_eq!(PAGE_WAITERS.lock().wakers.get(&key).map(Vec::len), Some(1));
+
+        // supplying other pages does not wake up the waiter
+        pager
+            .supply_pages(&vmo, PAGE_SIZE, PAGE_SIZE, &aux_vmo(1), 0)
+            .unwrap();
+        assert!(PAGE_WAITERS.lock().wakers.contains_key(&key));
+        pager
+            .supply_pages(&vmo, 0, PAGE_SIZE, &aux_vmo(2), 0)
+            .unwrap();
+        assert!(!PAGE_WAITERS.lock().wakers.contains_key(&key));
+        assert_eq!(Pin::new(&mut reader).poll(&mut cx), Poll::Ready(Ok(())));
+    }
+
+    #[async_std::test]
+    async fn fail_and_detach() {
+        let pager = Pager::new();
+        let port = Port::new(0).unwrap();
+        let vmo = pager.create_vmo(2, port.clone(), 7, false);
+        let mut buf = [0u8; 4];
+        assert_eq!(vmo.read(0, &mut buf), Err(ZxError::SHOULD_WAIT));
+        next_request(&port).await;
+        pager
+            .op_range(&vmo, PagerOp::Fail(ZxError::IO), 0, PAGE_SIZE)
+            .unwrap();
+        let res = wait_for_pages(|| vmo.commit_page(0, MMUFlags::READ)).await;
+        assert_eq!(res, Err(ZxError::IO));
+
+        // failed once, the page is requested again
+        assert_eq!(vmo.read(0, &mut buf), Err(ZxError::SHOULD_WAIT));
+        next_request(&port).await;
+        pager.detach_vmo(&vmo).unwrap();
+        assert_eq!(
+            next_request(&port).await,
+            page_request(7, PageRequestCommand::Complete, 0, 0)
+        );
+        assert_eq!(vmo.read(0, &mut buf), Err(ZxError::BAD_STATE));
+        assert_eq!(
+            pager.supply_pages(&vmo, 0, PAGE_SIZE, &aux_vmo(0), 0),
+            Err(ZxError::BAD_STATE)
+        );
+
+        // the pager is notified when a VMO is destroyed
+        let vmo = pager.create_vmo(1, port.clone(), 8, false);
+        drop(vmo);
+        assert_eq!(
+            next_request(&port).await,
+            page_request(8, PageRequestCommand::Complete, 0, 0)
+        );
+        // and VMOs are detached when the pager is destroyed
+        let vmo = pager.create_vmo(1, port.clone(), 9, false);
+        drop(pager);
+        assert_eq!(
+            next_request(&port).await,
+            page_request(9, PageRequestCommand::Complete, 0, 0)
+        );
+        assert_eq!(vmo.read(0, &mut buf), Err(ZxError::BAD_STATE));
+    }
+
+    #[test]
+    fn dirty_and_writeback() {
+        let pager = Pager::new();
+        let port = Port::new(0).unwrap();
+        let vmo = pager.create_vmo(4, port, 0, false);
+        for i in 0..4 {
+            pager
+                .supply_pages(&vmo, i * PAGE_SIZE, PAGE_SIZE, &aux_vmo(0), 0)
+                .unwrap();
+        }
+        assert_eq!(pager.query_dirty_ranges(&vmo, 0, 4 * PAGE_SIZE), Ok(vec![]));
+
+        vmo.write(PAGE_SIZE, &[1; 8]).unwrap();
+        vmo.write(2 * PAGE_SIZE, &[1; 8]).unwrap();
+        let all = 4 * PAGE_SIZE;
+        assert_eq!(
+            pager.query_dirty_ranges(&vmo, 0, all),
+            Ok(vec![(PAGE_SIZE, 2 * PAGE_SIZE)])
+        );
+
+        // a page written during the writeback stays dirty
+        pager
+            .op_range(&vmo, PagerOp::WritebackBegin, 0, all)
+            .unwrap();
+        vmo.write(2 * PAGE_SIZE, &[2; 8]).unwrap();
+        pager.op_range(&vmo, PagerOp::WritebackEnd, 0, all).unwrap();
+        assert_eq!(
+            pager.query_dirty_ranges(&vmo, 0, all),
+            Ok(vec![(2 * PAGE_SIZE, PAGE_SIZE)])
+        );
+
+        pager.op_range(&vmo, PagerOp::Dirty, 0, PAGE_SIZE).unwrap();
+        assert_eq!(
+            pager.query_dirty_ranges(&vmo, 0, all),
+            Ok(vec![(0, PAGE_SIZE), (2 * PAGE_SIZE, PAGE_SIZE)])
+        );
+        assert_eq!(
+            pager.op_range(&vmo, PagerOp::Dirty, 0, PAGE_SIZE + 1),
+            Err(ZxError::INVALID_ARGS)
+        );
+        assert_eq!(
+            pager.op_range(&vmo, PagerOp::Dirty, 0, 5 * PAGE_SIZE),
+            Err(ZxError::OUT_OF_RANGE)
+        );
+    }
+}
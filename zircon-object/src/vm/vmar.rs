@@ -49,6 +49,8 @@ pub struct VmAddressRegion {
     _counter: CountHelper,
     addr: VirtAddr,
     size: usize,
+    /// Whether the non-specific allocations are placed randomly (ASLR).
+    aslr: bool,
     parent: Option<Arc<VmAddressRegion>>,
     page_table: Arc<Mutex<dyn GenericPageTable>>,
     /// If inner is None, this region is destroyed, all operations are invalid.
@@ -67,7 +69,14 @@ struct VmarInner {
 
 impl VmAddressRegion {
     /// Create a new root VMAR.
+    ///
+    /// The placement of allocations is randomized if the feature `aslr` is enabled.
+    /// It is opt-in, enabled by the `aslr` feature of zCore.
     pub fn new_root() -> Arc<Self> {
+        Self::new_root_ext(cfg!(feature = "aslr"))
+    }
+
+    fn new_root_ext(aslr: bool) -> Arc<Self> {
         #[cfg(feature = "aspace-separate")]
         let (addr, size) = {
             use core::sync::atomic::*;
@@ -83,6 +92,7 @@ impl VmAddressRegion {
             _counter: CountHelper::new(),
             addr,
             size,
+            aslr,
             parent: None,
             page_table: Arc::new(Mutex::new(PageTable::from_current().clone_kernel())), //hal PageTable
             inner: Mutex::new(Some(VmarInner::default())),
@@ -99,6 +109,7 @@ impl VmAddressRegion {
             _counter: CountHelper::new(),
             addr: kernel_vmar_base,
             size: kernel_vmar_size,
+            aslr: false,
             parent: None,
             page_table: Arc::new(Mutex::new(PageTable::from_current().clone_kernel())),
             inner: Mutex::new(Some(VmarInner::default())),
@@ -116,6 +127,7 @@ impl VmAddressRegion {
             _counter: CountHelper::new(),
             addr: guest_vmar_base,
             size: guest_vmar_size,
+            aslr: false,
             parent: None,
             page_table: Arc::new(Mutex::new(crate::hypervisor::VmmPageTable::new())),
             inner: Mutex::new(Some(VmarInner::default())),
@@ -134,6 +146,10 @@ impl VmAddressRegion {
     }
 
     /// Create a child VMAR with optional `offset`.
+    ///
+    /// The child can not be given more `CAN_MAP_*` permissions than this VMAR.
+    /// With `SPECIFIC_OVERWRITE` in `flags`, the mappings in the range are replaced
+    /// by the child, but it fails if the range overlaps any sub-region.
     pub fn allocate(
         self: &Arc<Self>,
         offset: Option<usize>,
@@ -141,15 +157,29 @@ impl VmAddressRegion {
         flags: VmarFlags,
         align: usize,
     ) -> ZxResult<Arc<Self>> {
+        if !self.flags.contains(flags & VmarFlags::CAN_MAP_RXW) {
+            return Err(ZxError::ACCESS_DENIED);
+        }
+        if offset.is_some() && !self.flags.contains(VmarFlags::CAN_MAP_SPECIFIC) {
+            return Err(ZxError::ACCESS_DENIED);
+        }
+        let overwrite = flags.contains(VmarFlags::SPECIFIC_OVERWRITE);
+        if overwrite && offset.is_none() {
+            return Err(ZxError::INVALID_ARGS);
+        }
         let mut guard = self.inner.lock();
         let inner = guard.as_mut().ok_or(ZxError::BAD_STATE)?;
-        let offset = self.determine_offset(inner, offset, len, align)?;
+        let offset = self.determine_offset(inner, offset, len, align, overwrite)?;
+        if overwrite {
+            self.unmap_inner(self.addr + offset, len, inner)?;
+        }
         let child = Arc::new(VmAddressRegion {
             flags,
             base: KObjectBase::new(),
             _counter: CountHelper::new(),
             addr: self.addr + offset,
             size: len,
+            aslr: self.aslr,
             parent: Some(self.clone()),
             page_table: self.page_table.clone(),
             inner: Mutex::new(Some(VmarInner::default())),
@@ -192,6 +222,9 @@ impl VmAddressRegion {
     }
 
     /// Map the `vmo` into this VMAR.
+    ///
+    /// With `overwrite`, the existing mappings in the range are replaced atomically,
+    /// but it fails if the range overlaps any sub-region.
     #[allow(clippy::too_many_arguments)]
     pub fn map_ext(
         &self,
@@ -203,33 +236,86 @@ impl VmAddressRegion {
         flags: MMUFlags,
         overwrite: bool,
         map_range: bool,
+    ) -> ZxResult<VirtAddr> {
+        self.map_inner(
+            vmar_offset,
+            vmo,
+            vmo_offset,
+            len,
+            permissions,
+            flags,
+            overwrite,
+            map_range,
+            false,
+        )
+    }
+
+    /// Map the `vmo` into this VMAR, which is shared with the forked VMAR
+    /// instead of being copied by [`fork_from`].
+    ///
+    /// [`fork_from`]: VmAddressRegion::fork_from
+    pub fn map_shared(
+        &self,
+        vmar_offset: Option<usize>,
+        vmo: Arc<VmObject>,
+        vmo_offset: usize,
+        len: usize,
+        flags: MMUFlags,
+    ) -> ZxResult<VirtAddr> {
+        self.map_inner(
+            vmar_offset,
+            vmo,
+            vmo_offset,
+            len,
+            MMUFlags::RXW,
+            flags,
+            false,
+            true,
+            true,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn map_inner(
+        &self,
+        vmar_offset: Option<usize>,
+        vmo: Arc<VmObject>,
+        vmo_offset: usize,
+        len: usize,
+        permissions: MMUFlags,
+        flags: MMUFlags,
+        overwrite: bool,
+        map_range: bool,
+        shared: bool,
     ) -> ZxResult<VirtAddr> {
         if !page_aligned(vmo_offset) || !page_aligned(len) || vmo_offset.overflowing_add(len).1 {
             return Err(ZxError::INVALID_ARGS);
         }
-        if !permissions.contains(flags & MMUFlags::RXW) {
+        if !permissions.contains(flags & MMUFlags::RXW) || !self.can_map(flags) {
             return Err(ZxError::ACCESS_DENIED);
         }
+        if vmar_offset.is_some() && !self.flags.contains(VmarFlags::CAN_MAP_SPECIFIC) {
+            return Err(ZxError::ACCESS_DENIED);
+        }
+        if overwrite && vmar_offset.is_none() {
+            return Err(ZxError::INVALID_ARGS);
+        }
         // TODO: allow the mapping extends past the end of vmo
         if vmo_offset > vmo.len() || len > vmo.len() - vmo_offset {
             return Err(ZxError::INVALID_ARGS);
         }
         let mut guard = self.inner.lock();
         let inner = guard.as_mut().ok_or(ZxError::BAD_STATE)?;
-        let offset = self.determine_offset(inner, vmar_offset, len, PAGE_SIZE)?;
+        let offset = self.determine_offset(inner, vmar_offset, len, PAGE_SIZE, overwrite)?;
         let addr = self.addr + offset;
         let mut flags = flags;
         // if vmo != 0
         {
             flags |= MMUFlags::from_bits_truncate(vmo.cache_policy() as usize);
         }
-        // align = 1K? 2K? 4K? 8K? ...
-        if !self.test_map(inner, offset, len, PAGE_SIZE) {
-            if overwrite {
-                self.unmap_inner(addr, len, inner)?;
-            } else {
-                return Err(ZxError::NO_MEMORY);
-            }
+        // all checks are done with the lock held, so the range is replaced atomically
+        if overwrite {
+            self.unmap_inner(addr, len, inner)?;
         }
         // TODO: Fix map_range bugs and remove this line
         let map_range = map_range || vmo.name() != "";
@@ -240,6 +326,7 @@ impl VmAddressRegion {
             vmo_offset,
             permissions,
             flags,
+            shared,
             self.page_table.clone(),
         );
         if map_range {
@@ -345,6 +432,55 @@ impl VmAddressRegion {
         Ok(())
     }
 
+    /// Apply `op` to the pages mapped in `[addr, addr + len)`.
+    ///
+    /// The range must be wholly covered by mappings, in this VMAR or its sub-regions.
+    pub fn op_range(&self, op: VmarOp, addr: VirtAddr, len: usize) -> ZxResult {
+        if !page_aligned(addr) || !page_aligned(len) || len == 0 {
+            return Err(ZxError::INVALID_ARGS);
+        }
+        if self.is_dead() {
+            return Err(ZxError::BAD_STATE);
+        }
+        let end = addr.checked_add(len).ok_or(ZxError::OUT_OF_RANGE)?;
+        if addr < self.addr || end > self.end_addr() {
+            return Err(ZxError::OUT_OF_RANGE);
+        }
+        let mut mappings = Vec::new();
+        self.for_each_mapping(&mut |map| {
+            if map.overlap(addr, end) {
+                mappings.push(map.clone());
+            }
+        });
+        mappings.sort_unstable_by_key(|map| map.addr());
+        // check there is no gap in the range
+        let mut mapped_end = addr;
+        for map in mappings.iter() {
+            if map.addr() > mapped_end {
+                return Err(ZxError::BAD_STATE);
+            }
+            mapped_end = map.end_addr();
+        }
+        if mapped_end < end {
+            return Err(ZxError::BAD_STATE);
+        }
+        let ranges = mappings
+            .iter()
+            .map(|map| (addr.max(map.addr()), end.min(map.end_addr())));
+        if matches!(op, VmarOp::Commit | VmarOp::Decommit)
+            && mappings
+                .iter()
+                .zip(ranges.clone())
+                .any(|(map, (begin, end))| !map.is_writable(begin, end))
+        {
+            return Err(ZxError::ACCESS_DENIED);
+        }
+        for (map, (begin, end)) in mappings.iter().zip(ranges) {
+            map.op_range(op, begin, end)?;
+        }
+        Ok(())
+    }
+
     /// Unmap all mappings within the VMAR, and destroy all sub-regions of the region.
     pub fn destroy(self: &Arc<Self>) -> ZxResult {
         self.destroy_internal()?;
@@ -419,17 +555,23 @@ impl VmAddressRegion {
     }
 
     /// Determine final address with given input `offset` and `len`.
+    ///
+    /// With `overwrite`, the existing mappings at `offset` do not matter.
     fn determine_offset(
         &self,
         inner: &VmarInner,
         offset: Option<usize>,
         len: usize,
         align: usize,
+        overwrite: bool,
     ) -> ZxResult<VirtAddr> {
         if !check_aligned(len, align) {
             Err(ZxError::INVALID_ARGS)
         } else if let Some(offset) = offset {
-            if check_aligned(offset, align) && self.test_map(inner, offset, len, align) {
+            if check_aligned(offset, align)
+                && (overwrite && self.test_overwrite(inner, offset, len)
+                    || self.test_map(inner, offset, len, align))
+            {
                 Ok(offset)
             } else {
                 Err(ZxError::INVALID_ARGS)
@@ -463,7 +605,23 @@ impl VmAddressRegion {
         true
     }
 
+    /// Test if can replace the mappings at `offset` with `len`,
+    /// which must be in this VMAR and not overlap any sub-region.
+    fn test_overwrite(&self, inner: &VmarInner, offset: usize, len: usize) -> bool {
+        match offset.checked_add(len) {
+            Some(end) if end <= self.size => {
+                let (begin, end) = (self.addr + offset, self.addr + end);
+                !inner.children.iter().any(|vmar| vmar.overlap(begin, end))
+            }
+            _ => false,
+        }
+    }
+
     /// Find a free area with `len`.
+    ///
+    /// Without ASLR, the first area fits is chosen. Otherwise the area is chosen
+    /// randomly, from the ones adjacent to the existing allocations if possible
+    /// when this VMAR is `COMPACT`.
     fn find_free_area(
         &self,
         inner: &VmarInner,
@@ -471,15 +629,77 @@ impl VmAddressRegion {
         len: usize,
         align: usize,
     ) -> Option<usize> {
-        // TODO: randomize
         debug_assert!(check_aligned(offset_hint, align));
         debug_assert!(check_aligned(len, align));
-        // brute force:
-        // try each area's end address as the start
-        core::iter::once(offset_hint)
-            .chain(inner.children.iter().map(|map| map.end_addr() - self.addr))
-            .chain(inner.mappings.iter().map(|map| map.end_addr() - self.addr))
-            .find(|&offset| self.test_map(inner, offset, len, align))
+        let used = inner
+            .children
+            .iter()
+            .map(|vmar| (vmar.addr - self.addr, vmar.end_addr() - self.addr))
+            .chain(
+                inner
+                    .mappings
+                    .iter()
+                    .map(|map| (map.addr() - self.addr, map.end_addr() - self.addr)),
+            );
+        if !self.aslr {
+            // brute force:
+            // try each area's end address as the start
+            return core::iter::once(offset_hint)
+                .chain(used.map(|(_, end)| end))
+                .find(|&offset| self.test_map(inner, offset, len, align));
+        }
+        let mut used = used.collect::<Vec<_>>();
+        if self.flags.contains(VmarFlags::COMPACT) {
+            // try the areas right before and after each allocation
+            let offsets = used
+                .iter()
+                .flat_map(|&(begin, end)| {
+                    let before = begin.checked_sub(len).map(|x| x / align * align);
+                    before.into_iter().chain(Some(ceil(end, align) * align))
+                })
+                .filter(|&offset| self.test_map(inner, offset, len, align))
+                .collect::<Vec<_>>();
+            if !offsets.is_empty() {
+                return Some(offsets[random_usize() % offsets.len()]);
+            }
+        }
+        // choose uniformly from the aligned offsets in the free gaps,
+        // each gap is recorded as its first offset and the number of offsets
+        used.sort_unstable();
+        used.push((self.size, self.size));
+        let mut gaps = Vec::new();
+        let mut gap_begin = 0;
+        for (begin, end) in used {
+            let first = ceil(gap_begin, align) * align;
+            if first <= begin && len <= begin - first {
+                gaps.push((first, (begin - first - len) / align + 1));
+            }
+            gap_begin = gap_begin.max(end);
+        }
+        let total = gaps.iter().map(|&(_, n)| n).sum::<usize>();
+        if total == 0 {
+            return None;
+        }
+        let mut index = random_usize() % total;
+        for (first, n) in gaps {
+            if index < n {
+                return Some(first + index * align);
+            }
+            index -= n;
+        }
+        unreachable!()
+    }
+
+    /// Whether a mapping with `flags` can be created in this VMAR.
+    fn can_map(&self, flags: MMUFlags) -> bool {
+        let mut required = VmarFlags::empty();
+        required.set(VmarFlags::CAN_MAP_READ, flags.contains(MMUFlags::READ));
+        required.set(VmarFlags::CAN_MAP_WRITE, flags.contains(MMUFlags::WRITE));
+        required.set(
+            VmarFlags::CAN_MAP_EXECUTE,
+            flags.contains(MMUFlags::EXECUTE),
+        );
+        self.flags.contains(required)
     }
 
     fn end_addr(&self) -> VirtAddr {
@@ -552,18 +772,24 @@ impl VmAddressRegion {
     ///
     /// The fault virtual address is `vaddr` and the reason is in `flags`.
     pub fn handle_page_fault(&self, vaddr: VirtAddr, flags: MMUFlags) -> ZxResult {
+        Ok(self.try_handle_page_fault(vaddr, flags)?)
+    }
+
+    /// Handle the page fault, failing with the request of the page if it is
+    /// absent in a pager-backed VMO.
+    pub fn try_handle_page_fault(&self, vaddr: VirtAddr, flags: MMUFlags) -> PagedResult {
         let guard = self.inner.lock();
         let inner = guard.as_ref().unwrap();
         if !self.contains(vaddr) {
-            return Err(ZxError::NOT_FOUND);
+            return Err(ZxError::NOT_FOUND.into());
         }
         if let Some(child) = inner.children.iter().find(|ch| ch.contains(vaddr)) {
-            return child.handle_page_fault(vaddr, flags);
+            return child.try_handle_page_fault(vaddr, flags);
         }
         if let Some(mapping) = inner.mappings.iter().find(|map| map.contains(vaddr)) {
             return mapping.handle_page_fault(vaddr, flags);
         }
-        Err(ZxError::NOT_FOUND)
+        Err(ZxError::NOT_FOUND.into())
     }
 
     fn for_each_mapping(&self, f: &mut impl FnMut(&Arc<VmMapping>)) {
@@ -577,11 +803,43 @@ impl VmAddressRegion {
         }
     }
 
+    /// Returns information of all mappings recursively, sorted by address.
+    pub fn get_mappings(&self) -> Vec<MappingInfo> {
+        let mut mappings = Vec::new();
+        self.for_each_mapping(&mut |map| mappings.push(map.get_info()));
+        mappings.sort_unstable_by_key(|info| info.addr);
+        mappings
+    }
+
     /// Clone the entire address space and VMOs from source VMAR. (For Linux fork)
-    pub fn fork_from(&self, src: &Arc<Self>) -> ZxResult {
+    ///
+    /// The sub-regions are cloned at the same addresses.
+    pub fn fork_from(self: &Arc<Self>, src: &Arc<Self>) -> ZxResult {
+        let src_guard = src.inner.lock();
+        let src_inner = src_guard.as_ref().ok_or(ZxError::BAD_STATE)?;
         let mut guard = self.inner.lock();
-        let inner = guard.as_mut().unwrap();
-        inner.fork_from(src, &self.page_table)
+        let inner = guard.as_mut().ok_or(ZxError::BAD_STATE)?;
+        for child in src_inner.children.iter() {
+            let new_child = Arc::new(VmAddressRegion {
+                flags: child.flags,
+                base: KObjectBase::new(),
+                _counter: CountHelper::new(),
+                addr: child.addr,
+                size: child.size,
+                aslr: child.aslr,
+                parent: Some(self.clone()),
+                page_table: self.page_table.clone(),
+                inner: Mutex::new(Some(VmarInner::default())),
+            });
+            new_child.fork_from(child)?;
+            inner.children.push(new_child);
+        }
+        for map in src_inner.mappings.iter() {
+            let mapping = map.clone_map(self.page_table.clone())?;
+            mapping.map()?;
+            inner.mappings.push(mapping);
+        }
+        Ok(())
     }
 
     /// Returns statistics about memory used by a task.
@@ -619,6 +877,13 @@ impl VmAddressRegion {
         Ok(actual_size)
     }
 
+    /// Find the direct sub-region containing `vaddr`.
+    pub fn find_child(&self, vaddr: VirtAddr) -> Option<Arc<Self>> {
+        let guard = self.inner.lock();
+        let inner = guard.as_ref()?;
+        inner.children.iter().find(|ch| ch.contains(vaddr)).cloned()
+    }
+
     /// Find mapping of vaddr
     pub fn find_mapping(&self, vaddr: usize) -> Option<Arc<VmMapping>> {
         let guard = self.inner.lock();
@@ -632,6 +897,13 @@ impl VmAddressRegion {
         None
     }
 
+    /// Returns the VMO mapped at `vaddr` and the offset of `vaddr` in it.
+    pub fn get_vmo_offset(&self, vaddr: VirtAddr) -> Option<(Arc<VmObject>, usize)> {
+        let map = self.find_mapping(vaddr)?;
+        let inner = map.inner.lock();
+        Some((map.vmo.clone(), vaddr - inner.addr + inner.vmo_offset))
+    }
+
     #[cfg(test)]
     fn count(&self) -> usize {
         let mut guard = self.inner.lock();
@@ -649,25 +921,25 @@ impl VmAddressRegion {
     }
 }
 
-impl VmarInner {
-    /// Clone the entire address space and VMOs from source VMAR. (For Linux fork)
-    fn fork_from(
-        &mut self,
-        src: &Arc<VmAddressRegion>,
-        page_table: &Arc<Mutex<dyn GenericPageTable>>,
-    ) -> ZxResult {
-        let src_guard = src.inner.lock();
-        let src_inner = src_guard.as_ref().unwrap();
-        for child in src_inner.children.iter() {
-            self.fork_from(child, page_table)?;
-        }
-        for map in src_inner.mappings.iter() {
-            let mapping = map.clone_map(page_table.clone())?;
-            mapping.map()?;
-            self.mappings.push(mapping);
-        }
-        Ok(())
-    }
+fn random_usize() -> usize {
+    let mut buf = [0u8; core::mem::size_of::<usize>()];
+    kernel_hal::rand::fill_random(&mut buf);
+    usize::from_ne_bytes(buf)
+}
+
+/// Operations on the pages mapped in a range of VmAddressRegion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VmarOp {
+    /// Commit the pages of the mapped VMOs, the mappings must be writable.
+    Commit,
+    /// Decommit the pages of the mapped VMOs, the mappings must be writable.
+    Decommit,
+    /// Map the committed pages, without committing the others.
+    MapRange,
+    /// Prefetch the pages, committing and mapping them for read.
+    AlwaysNeed,
+    /// A hint that the pages will not be needed.
+    DontNeed,
 }
 
 /// Information of a VmAddressRegion.
@@ -679,10 +951,29 @@ pub struct VmarInfo {
     // pg_token: usize,
 }
 
+/// Information of a mapping in a VmAddressRegion.
+#[derive(Debug, Clone)]
+pub struct MappingInfo {
+    /// Start address of the mapping
+    pub addr: VirtAddr,
+    /// Size of the mapping in bytes
+    pub size: usize,
+    /// Flags of the first page
+    pub flags: MMUFlags,
+    /// The mapped VMO
+    pub vmo: Arc<VmObject>,
+    /// Offset of the mapping in the VMO
+    pub vmo_offset: usize,
+    /// Number of bytes committed in the mapped range
+    pub committed_bytes: usize,
+}
+
 /// Virtual Memory Mapping
 pub struct VmMapping {
     /// The permission limitation of the vmar
     permissions: MMUFlags,
+    /// Whether the VMO is shared rather than copied on fork
+    shared: bool,
     vmo: Arc<VmObject>,
     page_table: Arc<Mutex<dyn GenericPageTable>>,
     inner: Mutex<VmMappingInner>,
@@ -729,6 +1020,7 @@ impl VmMapping {
         vmo_offset: usize,
         permissions: MMUFlags,
         flags: MMUFlags,
+        shared: bool,
         page_table: Arc<Mutex<dyn GenericPageTable>>,
     ) -> Arc<Self> {
         let mapping = Arc::new(VmMapping {
@@ -739,6 +1031,7 @@ impl VmMapping {
                 vmo_offset,
             }),
             permissions,
+            shared,
             page_table,
             vmo: vmo.clone(),
         });
@@ -771,6 +1064,61 @@ impl VmMapping {
         })
     }
 
+    /// Map the pages in `[begin, end)` for read.
+    ///
+    /// Only the committed pages are mapped, unless `commit` is true. Absent pages
+    /// of a pager-backed VMO are requested from the pager, but not waited for.
+    fn map_range(&self, begin: VirtAddr, end: VirtAddr, commit: bool) -> ZxResult {
+        let (addr, vmo_offset) = {
+            let inner = self.inner.lock();
+            (inner.addr, inner.vmo_offset)
+        };
+        let start_idx = (vmo_offset + begin - addr) / PAGE_SIZE;
+        let end_idx = (vmo_offset + end - addr) / PAGE_SIZE;
+        let to_map: Vec<usize> = (start_idx..end_idx)
+            .filter(|&idx| commit || self.vmo.committed_pages_in_range(idx, idx + 1) != 0)
+            .collect();
+        self.vmo.commit_pages_with(&mut |commit_page| {
+            let inner = self.inner.lock();
+            let mut page_table = self.page_table.lock();
+            for &idx in to_map.iter() {
+                let vaddr = inner.addr + idx * PAGE_SIZE - inner.vmo_offset;
+                let mut flags = inner.flags[(vaddr - inner.addr) / PAGE_SIZE];
+                if !flags.contains(MMUFlags::READ) {
+                    continue;
+                }
+                let paddr = match commit_page(idx, MMUFlags::READ) {
+                    Ok(paddr) => paddr,
+                    Err(PagedError::Wait(_)) => continue,
+                    Err(PagedError::Failed(err)) => return Err(err),
+                };
+                // pages are mapped read-only, so that copy-on-write still works
+                flags.remove(MMUFlags::WRITE);
+                let page = Page::new_aligned(vaddr, PageSize::Size4K);
+                match page_table.map(page, paddr, flags) {
+                    Ok(()) | Err(PagingError::AlreadyMapped) => {}
+                    Err(_) => return Err(ZxError::NO_MEMORY),
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Apply `op` to the pages mapped in `[begin, end)`.
+    fn op_range(&self, op: VmarOp, begin: VirtAddr, end: VirtAddr) -> ZxResult {
+        let vmo_offset = {
+            let inner = self.inner.lock();
+            inner.vmo_offset + begin - inner.addr
+        };
+        match op {
+            VmarOp::Commit => self.vmo.commit(vmo_offset, end - begin),
+            VmarOp::Decommit => self.vmo.decommit(vmo_offset, end - begin),
+            VmarOp::MapRange => self.map_range(begin, end, false),
+            VmarOp::AlwaysNeed => self.map_range(begin, end, true),
+            VmarOp::DontNeed => Ok(()),
+        }
+    }
+
     fn unmap(&self) {
         let inner = self.inner.lock();
         // TODO inner.vmo_offset unused?
@@ -780,6 +1128,25 @@ impl VmMapping {
             .expect("failed to unmap")
     }
 
+    fn get_info(&self) -> MappingInfo {
+        let (addr, size, flags, vmo_offset) = {
+            let inner = self.inner.lock();
+            (inner.addr, inner.size, inner.flags[0], inner.vmo_offset)
+        };
+        let start_idx = vmo_offset / PAGE_SIZE;
+        let committed_pages = self
+            .vmo
+            .committed_pages_in_range(start_idx, start_idx + size / PAGE_SIZE);
+        MappingInfo {
+            addr,
+            size,
+            flags,
+            vmo: self.vmo.clone(),
+            vmo_offset,
+            committed_bytes: committed_pages * PAGE_SIZE,
+        }
+    }
+
     fn fill_in_task_status(&self, task_stats: &mut TaskStatsInfo) {
         let (start_idx, end_idx) = {
             let inner = self.inner.lock();
@@ -846,6 +1213,7 @@ impl VmMapping {
             let new_flags_range = (pages(inner.size) - pages(new_len2))..pages(inner.size);
             let new_mapping = Arc::new(VmMapping {
                 permissions: self.permissions,
+                shared: self.shared,
                 vmo: self.vmo.clone(),
                 page_table: self.page_table.clone(),
                 inner: Mutex::new(VmMappingInner {
@@ -871,6 +1239,15 @@ impl VmMapping {
         inner.addr <= vaddr && vaddr < inner.end_addr()
     }
 
+    fn is_writable(&self, begin: VirtAddr, end: VirtAddr) -> bool {
+        let inner = self.inner.lock();
+        let start_index = (begin - inner.addr) / PAGE_SIZE;
+        let end_index = pages(end - inner.addr);
+        inner.flags[start_index..end_index]
+            .iter()
+            .all(|flags| flags.contains(MMUFlags::WRITE))
+    }
+
     fn is_valid_mapping_flags(&self, flags: MMUFlags) -> bool {
         self.permissions.contains(flags & MMUFlags::RXW)
     }
@@ -947,7 +1324,7 @@ impl VmMapping {
     }
 
     /// Handle page fault happened on this VmMapping.
-    pub(crate) fn handle_page_fault(&self, vaddr: VirtAddr, access_flags: MMUFlags) -> ZxResult {
+    pub(crate) fn handle_page_fault(&self, vaddr: VirtAddr, access_flags: MMUFlags) -> PagedResult {
         let vaddr = round_down_pages(vaddr);
         let (vmo_offset, mut flags) = {
             let inner = self.inner.lock();
@@ -955,7 +1332,7 @@ impl VmMapping {
             (offset + inner.vmo_offset, inner.flags[offset / PAGE_SIZE])
         };
         if !flags.contains(access_flags) {
-            return Err(ZxError::ACCESS_DENIED);
+            return Err(ZxError::ACCESS_DENIED.into());
         }
         if !access_flags.contains(MMUFlags::WRITE) {
             // 注意一下!
@@ -975,10 +1352,15 @@ impl VmMapping {
     /// Clone VMO and map it to a new page table. (For Linux)
     fn clone_map(&self, page_table: Arc<Mutex<dyn GenericPageTable>>) -> ZxResult<Arc<Self>> {
         //这里调用hal protect后,protect()好像会破坏页表
-        let new_vmo = self.vmo.create_child(false, 0, self.vmo.len())?;
+        let new_vmo = if self.shared {
+            self.vmo.clone()
+        } else {
+            self.vmo.create_child(false, 0, self.vmo.len())?
+        };
         let mapping = Arc::new(VmMapping {
             inner: Mutex::new(self.inner.lock().clone()),
             permissions: self.permissions,
+            shared: self.shared,
             page_table,
             vmo: new_vmo.clone(),
         });
@@ -1017,8 +1399,9 @@ mod tests {
     #[test]
     fn create_child() {
         let root_vmar = VmAddressRegion::new_root();
+        let flags = VmarFlags::CAN_MAP_RXW | VmarFlags::CAN_MAP_SPECIFIC;
         let child = root_vmar
-            .allocate_at(0, 0x2000, VmarFlags::CAN_MAP_RXW, PAGE_SIZE)
+            .allocate_at(0, 0x2000, flags, PAGE_SIZE)
             .expect("failed to create child VMAR");
 
         // test invalid argument
@@ -1106,9 +1489,8 @@ mod tests {
     impl Sample {
         fn new() -> Self {
             let root = VmAddressRegion::new_root();
-            let child1 = root
-                .allocate_at(0, 0x2000, VmarFlags::CAN_MAP_RXW, PAGE_SIZE)
-                .unwrap();
+            let flags = VmarFlags::CAN_MAP_RXW | VmarFlags::CAN_MAP_SPECIFIC;
+            let child1 = root.allocate_at(0, 0x2000, flags, PAGE_SIZE).unwrap();
             let child2 = root
                 .allocate_at(0x2000, 0x1000, VmarFlags::CAN_MAP_RXW, PAGE_SIZE)
                 .unwrap();
@@ -1241,4 +1623,238 @@ mod tests {
             assert_eq!((vmar.addr() as *const u8).read(), 2);
         }
     }
+
+    #[test]
+    fn fork_shared_mapping() {
+        let vmar = VmAddressRegion::new_root();
+        let flags = MMUFlags::READ | MMUFlags::WRITE;
+        let shared = vmar
+            .map_shared(None, VmObject::new_paged(1), 0, PAGE_SIZE, flags)
+            .unwrap();
+        let private = vmar
+            .map(None, VmObject::new_paged(1), 0, PAGE_SIZE, flags)
+            .unwrap();
+        let child = VmAddressRegion::new_root();
+        child.fork_from(&vmar).unwrap();
+        child.write_memory(shared, &[1]).unwrap();
+        child.write_memory(private, &[1]).unwrap();
+
+        // only the writes to the shared mapping are seen by the parent
+        let mut buf = [0];
+        vmar.read_memory(shared, &mut buf).unwrap();
+        assert_eq!(buf, [1]);
+        vmar.read_memory(private, &mut buf).unwrap();
+        assert_eq!(buf, [0]);
+    }
+
+    #[test]
+    fn fork_sub_region() {
+        let vmar = VmAddressRegion::new_root();
+        let flags = VmarFlags::CAN_MAP_RXW | VmarFlags::CAN_MAP_SPECIFIC;
+        let child_vmar = vmar
+            .allocate(None, 4 * PAGE_SIZE, flags, PAGE_SIZE)
+            .unwrap();
+        let addr = child_vmar
+            .map_at(0, VmObject::new_paged(1), 0, PAGE_SIZE, MMUFlags::READ)
+            .unwrap();
+        let forked = VmAddressRegion::new_root();
+        forked.fork_from(&vmar).unwrap();
+
+        // the sub-region is kept, with its mappings and free space
+        let forked_child = forked.find_child(addr).unwrap();
+        assert_eq!(forked_child.addr(), child_vmar.addr());
+        assert!(forked_child.find_mapping(addr).is_some());
+        assert!(forked
+            .map_at(
+                addr + PAGE_SIZE,
+                VmObject::new_paged(1),
+                0,
+                PAGE_SIZE,
+                MMUFlags::READ
+            )
+            .is_err());
+        forked_child
+            .map_at(
+                PAGE_SIZE,
+                VmObject::new_paged(1),
+                0,
+                PAGE_SIZE,
+                MMUFlags::READ,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    #[allow(unsafe_code)]
+    fn op_range() {
+        let vmar = VmAddressRegion::new_root();
+        let base = vmar.addr();
+        let vmo = VmObject::new_paged(2);
+        let flags = MMUFlags::READ | MMUFlags::WRITE;
+        vmar.map_ext(
+            Some(0),
+            vmo.clone(),
+            0,
+            2 * PAGE_SIZE,
+            MMUFlags::RXW,
+            flags,
+            false,
+            false,
+        )
+        .unwrap();
+        vmar.map_ext(
+            Some(3 * PAGE_SIZE),
+            VmObject::new_paged(1),
+            0,
+            PAGE_SIZE,
+            MMUFlags::RXW,
+            MMUFlags::READ,
+            false,
+            false,
+        )
+        .unwrap();
+
+        // the range must be page aligned, mapped without gaps, and writable to commit
+        assert_eq!(
+            vmar.op_range(VmarOp::Commit, base + 1, PAGE_SIZE),
+            Err(ZxError::INVALID_ARGS)
+        );
+        assert_eq!(
+            vmar.op_range(VmarOp::Commit, base, 3 * PAGE_SIZE),
+            Err(ZxError::BAD_STATE)
+        );
+        assert_eq!(
+            vmar.op_range(VmarOp::Commit, base + 3 * PAGE_SIZE, PAGE_SIZE),
+            Err(ZxError::ACCESS_DENIED)
+        );
+
+        // only the committed pages are mapped
+        vmo.test_write(0, 1);
+        vmar.op_range(VmarOp::MapRange, base, 2 * PAGE_SIZE)
+            .unwrap();
+        unsafe {
+            assert_eq!((base as *const u8).read(), 1);
+        }
+        vmar.op_range(VmarOp::AlwaysNeed, base, 2 * PAGE_SIZE)
+            .unwrap();
+        unsafe {
+            assert_eq!(((base + PAGE_SIZE) as *const u8).read(), 0);
+        }
+        assert_eq!(vmo.get_info().committed_bytes as usize, PAGE_SIZE);
+
+        vmar.op_range(VmarOp::Commit, base, 2 * PAGE_SIZE).unwrap();
+        assert_eq!(vmo.get_info().committed_bytes as usize, 2 * PAGE_SIZE);
+        vmar.op_range(VmarOp::Decommit, base, 2 * PAGE_SIZE)
+            .unwrap();
+        assert_eq!(vmo.get_info().committed_bytes, 0);
+        vmar.op_range(VmarOp::DontNeed, base, PAGE_SIZE).unwrap();
+    }
+
+    #[test]
+    fn map_overwrite() {
+        let vmar = VmAddressRegion::new_root();
+        let base = vmar.addr();
+        let flags = MMUFlags::READ | MMUFlags::WRITE;
+        let vmo1 = VmObject::new_paged(3);
+        vmo1.test_write(PAGE_SIZE, 1);
+        let vmo2 = VmObject::new_paged(1);
+        vmo2.test_write(0, 2);
+        vmar.map_at(0, vmo1, 0, 3 * PAGE_SIZE, flags).unwrap();
+        let child = vmar
+            .allocate_at(4 * PAGE_SIZE, PAGE_SIZE, VmarFlags::CAN_MAP_RXW, PAGE_SIZE)
+            .unwrap();
+
+        let map = |offset: Option<usize>, overwrite: bool| {
+            let (perms, vmo) = (MMUFlags::RXW, vmo2.clone());
+            vmar.map_ext(offset, vmo, 0, PAGE_SIZE, perms, flags, overwrite, true)
+        };
+        assert_eq!(map(Some(PAGE_SIZE), false), Err(ZxError::INVALID_ARGS));
+        assert_eq!(map(None, true), Err(ZxError::INVALID_ARGS));
+        // never overwrite through a sub-region
+        assert_eq!(map(Some(4 * PAGE_SIZE), true), Err(ZxError::INVALID_ARGS));
+        assert!(child.is_alive());
+
+        let mut buf = [0u8];
+        vmar.read_memory(base + PAGE_SIZE, &mut buf).unwrap();
+        assert_eq!(buf, [1]);
+        assert_eq!(map(Some(PAGE_SIZE), true), Ok(base + PAGE_SIZE));
+        assert_eq!(vmar.count(), 4);
+        assert_eq!(vmar.used_size(), 4 * PAGE_SIZE);
+        vmar.read_memory(base + PAGE_SIZE, &mut buf).unwrap();
+        assert_eq!(buf, [2]);
+
+        // a child VMAR can replace the mappings too
+        let flags = VmarFlags::CAN_MAP_RXW | VmarFlags::SPECIFIC_OVERWRITE;
+        vmar.allocate_at(0, 2 * PAGE_SIZE, flags, PAGE_SIZE)
+            .unwrap();
+        assert_eq!(vmar.count(), 3);
+        assert_eq!(vmar.used_size(), 4 * PAGE_SIZE);
+    }
+
+    #[test]
+    fn can_map_permissions() {
+        let vmar = VmAddressRegion::new_root();
+        let child = vmar
+            .allocate(None, 4 * PAGE_SIZE, VmarFlags::CAN_MAP_READ, PAGE_SIZE)
+            .unwrap();
+        let vmo = VmObject::new_paged(1);
+
+        let flags = VmarFlags::CAN_MAP_READ | VmarFlags::CAN_MAP_WRITE;
+        assert_eq!(
+            child.allocate(None, PAGE_SIZE, flags, PAGE_SIZE).err(),
+            Some(ZxError::ACCESS_DENIED)
+        );
+        assert_eq!(
+            child
+                .allocate_at(0, PAGE_SIZE, VmarFlags::CAN_MAP_READ, PAGE_SIZE)
+                .err(),
+            Some(ZxError::ACCESS_DENIED)
+        );
+        let flags = MMUFlags::READ | MMUFlags::WRITE;
+        assert_eq!(
+            child.map(None, vmo.clone(), 0, PAGE_SIZE, flags),
+            Err(ZxError::ACCESS_DENIED)
+        );
+        assert_eq!(
+            child.map_at(0, vmo.clone(), 0, PAGE_SIZE, MMUFlags::READ),
+            Err(ZxError::ACCESS_DENIED)
+        );
+        assert!(child.map(None, vmo, 0, PAGE_SIZE, MMUFlags::READ).is_ok());
+        assert!(child
+            .allocate(None, PAGE_SIZE, VmarFlags::CAN_MAP_READ, PAGE_SIZE)
+            .is_ok());
+    }
+
+    #[test]
+    fn aslr() {
+        // only allocate sub-regions, since the random addresses may be used by the host
+        let root = VmAddressRegion::new_root_ext(true);
+        let flags = VmarFlags::CAN_MAP_RXW | VmarFlags::COMPACT;
+        let child = root.allocate(None, 0x100_0000, flags, PAGE_SIZE).unwrap();
+        let other = root.allocate(None, 0x100_0000, flags, PAGE_SIZE).unwrap();
+        assert!(!child.overlap(other.addr(), other.end_addr()));
+        assert!(child.within(root.addr(), root.end_addr()));
+        assert!(other.within(root.addr(), root.end_addr()));
+        assert_ne!(
+            (child.addr(), other.addr()),
+            (root.addr(), child.end_addr()),
+            "allocations should not be placed in the first fit areas"
+        );
+
+        // allocations in a compact VMAR are placed next to each other
+        let flags = VmarFlags::CAN_MAP_RXW;
+        let a = child.allocate(None, PAGE_SIZE, flags, PAGE_SIZE).unwrap();
+        let b = child
+            .allocate(None, 2 * PAGE_SIZE, flags, PAGE_SIZE)
+            .unwrap();
+        assert!(b.end_addr() == a.addr() || b.addr() == a.end_addr());
+
+        // all space can be allocated
+        let full = a.allocate(None, PAGE_SIZE, flags, PAGE_SIZE).unwrap();
+        assert_eq!(full.addr(), a.addr());
+        assert_eq!(
+            a.allocate(None, PAGE_SIZE, flags, PAGE_SIZE).err(),
+            Some(ZxError::NO_MEMORY)
+        );
+    }
 }
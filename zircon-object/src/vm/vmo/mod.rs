@@ -8,7 +8,7 @@ use {
     },
     bitflags::bitflags,
     core::ops::Deref,
-    kernel_hal::CachePolicy,
+    kernel_hal::{mem::PhysFrame, CachePolicy},
     spin::Mutex,
 };
 
@@ -24,6 +24,22 @@ pub fn vmo_page_bytes() -> usize {
     (VMO_PAGE_ALLOC.get() - VMO_PAGE_DEALLOC.get()) * PAGE_SIZE
 }
 
+/// The semantics of a copy-on-write child VMO.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloneType {
+    /// A point-in-time snapshot of the parent.
+    ///
+    /// Not supported on pager-backed VMOs and their clones.
+    Snapshot,
+    /// Changes of the parent may be seen by the child until the child writes the page.
+    SnapshotAtLeastOnWrite,
+    /// A snapshot of the pages modified in a clone of a pager-backed VMO,
+    /// the unmodified pages are shared at least until written.
+    ///
+    /// The same as `Snapshot` for anonymous VMOs.
+    SnapshotModified,
+}
+
 /// Virtual Memory Object Trait
 #[allow(clippy::len_without_is_empty)]
 pub trait VMObjectTrait: Sync + Send {
@@ -42,15 +58,15 @@ pub trait VMObjectTrait: Sync + Send {
     /// Set the length of VMO.
     fn set_len(&self, len: usize) -> ZxResult;
 
-    /// Commit a page.
-    fn commit_page(&self, page_idx: usize, flags: MMUFlags) -> ZxResult<PhysAddr>;
+    /// Commit a page, failing with a page request if it is absent in a pager-backed VMO.
+    fn commit_page(&self, page_idx: usize, flags: MMUFlags) -> PagedResult<PhysAddr>;
 
     /// Commit pages with an external function f.
     /// the vmo is internally locked before it calls f,
     /// allowing `VmMapping` to avoid deadlock
     fn commit_pages_with(
         &self,
-        f: &mut dyn FnMut(&mut dyn FnMut(usize, MMUFlags) -> ZxResult<PhysAddr>) -> ZxResult,
+        f: &mut dyn FnMut(&mut dyn FnMut(usize, MMUFlags) -> PagedResult<PhysAddr>) -> ZxResult,
     ) -> ZxResult;
 
     /// Commit allocating physical memory.
@@ -59,8 +75,22 @@ pub trait VMObjectTrait: Sync + Send {
     /// Decommit allocated physical memory.
     fn decommit(&self, offset: usize, len: usize) -> ZxResult;
 
-    /// Create a child VMO.
-    fn create_child(&self, offset: usize, len: usize) -> ZxResult<Arc<dyn VMObjectTrait>>;
+    /// Create a copy-on-write child VMO.
+    fn create_child(
+        &self,
+        type_: CloneType,
+        offset: usize,
+        len: usize,
+    ) -> ZxResult<Arc<dyn VMObjectTrait>>;
+
+    /// Commit the pages in the range for an access with `flags`, failing with
+    /// the request of the first page absent in a pager-backed VMO.
+    pub fn commit_range(&self, offset: usize, len: usize, flags: MMUFlags) -> PagedResult {
+        for idx in offset / PAGE_SIZE..pages(offset + len) {
+            self.trait_.commit_page(idx, flags)?;
+        }
+        Ok(())
+    }
 
     /// Append a mapping to the VMO's mapping list.
     fn append_mapping(&self, _mapping: Weak<VmMapping>) {}
@@ -99,6 +129,27 @@ pub trait VMObjectTrait: Sync + Send {
     fn is_paged(&self) -> bool {
         false
     }
+
+    /// Get the pager supplying the pages, if the VMO is pager-backed.
+    fn pager_source(&self) -> Option<Arc<PagerSource>> {
+        None
+    }
+
+    /// Take the frames of the pages in `[offset, offset + len)` out of the VMO,
+    /// which are decommitted. Fails with `BAD_STATE` if any page is absent.
+    fn take_pages(&self, _offset: usize, _len: usize) -> ZxResult<Vec<PhysFrame>> {
+        Err(ZxError::NOT_SUPPORTED)
+    }
+
+    /// Supply `frames` to the absent pages from `offset` of a pager-backed VMO.
+    fn supply_pages(&self, _offset: usize, _frames: Vec<PhysFrame>) -> ZxResult {
+        Err(ZxError::NOT_SUPPORTED)
+    }
+
+    /// Apply a pager operation to the given range of a pager-backed VMO.
+    fn pager_op(&self, _op: PagerOp, _offset: usize, _len: usize) -> ZxResult {
+        Err(ZxError::NOT_SUPPORTED)
+    }
 }
 
 /// Virtual memory containers
@@ -144,6 +195,21 @@ impl VmObject {
         })
     }
 
+    /// Create a new VMO whose pages are supplied by a pager through `source`.
+    pub(super) fn new_pager_backed(
+        resizable: bool,
+        pages: usize,
+        source: Arc<PagerSource>,
+    ) -> Arc<Self> {
+        Arc::new(VmObject {
+            base: KObjectBase::with_signal(Signal::VMO_ZERO_CHILDREN),
+            resizable,
+            _counter: CountHelper::new(),
+            trait_: VMObjectPaged::new_pager_backed(pages, source),
+            inner: Mutex::new(VmObjectInner::default()),
+        })
+    }
+
     /// Create a new VMO representing a piece of contiguous physical memory.
     pub fn new_physical(paddr: PhysAddr, pages: usize) -> Arc<Self> {
         Arc::new(VmObject {
@@ -167,16 +233,27 @@ impl VmObject {
         Ok(vmo)
     }
 
-    /// Create a child VMO.
+    /// Create a child VMO, which sees the changes of its parent at least until written.
     pub fn create_child(
         self: &Arc<Self>,
         resizable: bool,
         offset: usize,
         len: usize,
+    ) -> ZxResult<Arc<Self>> {
+        self.create_clone(CloneType::SnapshotAtLeastOnWrite, resizable, offset, len)
+    }
+
+    /// Create a copy-on-write child VMO with the semantics of `type_`.
+    pub fn create_clone(
+        self: &Arc<Self>,
+        type_: CloneType,
+        resizable: bool,
+        offset: usize,
+        len: usize,
     ) -> ZxResult<Arc<Self>> {
         let base = KObjectBase::with_signal(Signal::VMO_ZERO_CHILDREN);
         base.set_name(&self.base.name());
-        let trait_ = self.trait_.create_child(offset, len)?;
+        let trait_ = self.trait_.create_child(type_, offset, len)?;
         let child = Arc::new(VmObject {
             base,
             resizable,
@@ -329,6 +406,24 @@ impl VmObject {
         self.trait_.set_cache_policy(policy)
     }
 
+    /// Flush the cache of the committed pages in the range.
+    ///
+    /// Pages of a physical VMO are always committed.
+    pub fn cache_flush(&self, offset: usize, len: usize) -> ZxResult {
+        let end = offset.checked_add(len).ok_or(ZxError::OUT_OF_RANGE)?;
+        if end > self.trait_.len() {
+            return Err(ZxError::OUT_OF_RANGE);
+        }
+        for idx in offset / PAGE_SIZE..pages(end) {
+            if self.trait_.is_paged() && self.trait_.committed_pages_in_range(idx, idx + 1) == 0 {
+                continue;
+            }
+            let paddr = self.trait_.commit_page(idx, MMUFlags::READ)?;
+            kernel_hal::mem::frame_flush(paddr);
+        }
+        Ok(())
+    }
+
     /// Append a mapping to the VMO's mapping list.
     pub fn append_mapping(&self, mapping: Weak<VmMapping>) {
         self.inner.lock().mapping_count += 1;
@@ -93,6 +93,8 @@ struct VMObjectPagedInner {
     self_ref: WeakRef,
     /// Sum of pin_count
     pin_count: usize,
+    /// The pager supplying absent pages, if pager-backed
+    pager: Option<Arc<PagerSource>>,
 }
 
 /// Page state in VMO.
@@ -172,11 +174,19 @@ impl VMObjectPaged {
                 contiguous: false,
                 self_ref: Default::default(),
                 pin_count: 0,
+                pager: None,
             },
             None,
         )
     }
 
+    /// Create a new VMO whose pages are supplied by a pager.
+    pub fn new_pager_backed(pages: usize, source: Arc<PagerSource>) -> Arc<Self> {
+        let vmo = Self::new(pages);
+        vmo.get_inner_mut().1.pager = Some(source);
+        vmo
+    }
+
     /// Create a new VMO backing on contiguous pages.
     pub fn new_contiguous(pages: usize, align_log2: usize) -> ZxResult<Arc<Self>> {
         let vmo = Self::new(pages);
@@ -283,13 +293,13 @@ impl VMObjectTrait for VMObjectPaged {
         Ok(())
     }
 
-    fn commit_page(&self, page_idx: usize, flags: MMUFlags) -> ZxResult<PhysAddr> {
+    fn commit_page(&self, page_idx: usize, flags: MMUFlags) -> PagedResult<PhysAddr> {
         self.get_inner_mut().1.commit_page(page_idx, flags)
     }
 
     fn commit_pages_with(
         &self,
-        f: &mut dyn FnMut(&mut dyn FnMut(usize, MMUFlags) -> ZxResult<PhysAddr>) -> ZxResult,
+        f: &mut dyn FnMut(&mut dyn FnMut(usize, MMUFlags) -> PagedResult<PhysAddr>) -> ZxResult,
     ) -> ZxResult {
         let (_guard, mut inner) = self.get_inner_mut();
         f(&mut |page_idx, flags| inner.commit_page(page_idx, flags))
@@ -307,7 +317,7 @@ impl VMObjectTrait for VMObjectPaged {
 
     fn decommit(&self, offset: usize, len: usize) -> ZxResult {
         let (_guard, mut inner) = self.get_inner_mut();
-        if inner.parent.is_some() {
+        if inner.parent.is_some() || inner.pager.is_some() {
             return Err(ZxError::NOT_SUPPORTED);
         }
         let start_page = offset / PAGE_SIZE;
@@ -315,14 +325,23 @@ impl VMObjectTrait for VMObjectPaged {
         for i in 0..pages {
             inner.decommit(start_page + i);
         }
+        // the freed frames must not be accessed through the mappings any more
+        for map in inner.mappings.iter().filter_map(Weak::upgrade) {
+            map.range_change(start_page, pages, RangeChangeOp::Unmap);
+        }
         Ok(())
     }
 
-    fn create_child(&self, offset: usize, len: usize) -> ZxResult<Arc<dyn VMObjectTrait>> {
+    fn create_child(
+        &self,
+        type_: CloneType,
+        offset: usize,
+        len: usize,
+    ) -> ZxResult<Arc<dyn VMObjectTrait>> {
         assert!(page_aligned(offset));
         assert!(page_aligned(len));
         let (_guard, mut inner) = self.get_inner_mut();
-        let child = inner.create_child(offset, len, &self.lock)?;
+        let child = inner.create_child(type_, offset, len, &self.lock)?;
         Ok(child)
     }
 
@@ -437,6 +456,59 @@ impl VMObjectTrait for VMObjectPaged {
     fn is_paged(&self) -> bool {
         true
     }
+
+    fn pager_source(&self) -> Option<Arc<PagerSource>> {
+        self.get_inner().1.pager.clone()
+    }
+
+    fn take_pages(&self, offset: usize, len: usize) -> ZxResult<Vec<PhysFrame>> {
+        let (_guard, mut inner) = self.get_inner_mut();
+        if inner.parent.is_some() || inner.pager.is_some() {
+            return Err(ZxError::NOT_SUPPORTED);
+        }
+        let range = offset / PAGE_SIZE..pages(offset + len);
+        // absent pages are not supplied as zero pages
+        let absent_or_pinned = |idx| inner.frames.get(&idx).map_or(true, |p| p.pin_count > 0);
+        if range.clone().any(absent_or_pinned) {
+            return Err(ZxError::BAD_STATE);
+        }
+        // the moved frames must not be accessed through the mappings any more
+        for map in inner.mappings.iter().filter_map(Weak::upgrade) {
+            map.range_change(range.start, range.len(), RangeChangeOp::Unmap);
+        }
+        let frames = range
+            .map(|idx| inner.frames.remove(&idx).unwrap().take())
+            .collect();
+        Ok(frames)
+    }
+
+    fn supply_pages(&self, offset: usize, frames: Vec<PhysFrame>) -> ZxResult {
+        let (_guard, mut inner) = self.get_inner_mut();
+        let pager = inner.pager.clone().ok_or(ZxError::NOT_SUPPORTED)?;
+        let start = offset / PAGE_SIZE;
+        let end = start + frames.len();
+        for (idx, frame) in (start..end).zip(frames) {
+            // pages already present are kept, and the supplied ones discarded
+            if idx < inner.size / PAGE_SIZE && !inner.frames.contains_key(&idx) {
+                inner.frames.insert(idx, PageState::new(frame));
+            }
+        }
+        pager.pages_supplied(start..end);
+        Ok(())
+    }
+
+    fn pager_op(&self, op: PagerOp, offset: usize, len: usize) -> ZxResult {
+        let (_guard, inner) = self.get_inner();
+        let pager = inner.pager.as_ref().ok_or(ZxError::NOT_SUPPORTED)?;
+        let range = offset / PAGE_SIZE..pages(offset + len);
+        if matches!(op, PagerOp::WritebackBegin) {
+            // writes during the writeback fault again and mark the pages dirty
+            for map in inner.mappings.iter().filter_map(Weak::upgrade) {
+                map.range_change(range.start, range.len(), RangeChangeOp::RemoveWrite);
+            }
+        }
+        pager.op_range(op, range, |idx| inner.frames.contains_key(&idx))
+    }
 }
 
 enum CommitResult {
@@ -492,7 +564,7 @@ impl VMObjectPagedInner {
         Ok(())
     }
 
-    fn commit_page(&mut self, page_idx: usize, flags: MMUFlags) -> ZxResult<PhysAddr> {
+    fn commit_page(&mut self, page_idx: usize, flags: MMUFlags) -> PagedResult<PhysAddr> {
         let ret = match self.commit_page_internal(page_idx, flags, &Weak::new())? {
             CommitResult::Ref(paddr) => Ok(paddr),
             _ => unreachable!(),
@@ -508,7 +580,7 @@ impl VMObjectPagedInner {
         page_idx: usize,
         flags: MMUFlags,
         child: &WeakRef,
-    ) -> ZxResult<CommitResult> {
+    ) -> PagedResult<CommitResult> {
         // special case
         let no_parent = self.parent.is_none();
         let no_frame = !self.frames.contains_key(&page_idx);
@@ -521,6 +593,12 @@ impl VMObjectPagedInner {
         if no_frame {
             // if out_of_range
             if out_of_range || no_parent {
+                // absent pages of a pager-backed VMO are requested from the pager
+                if let Some(pager) = &self.pager {
+                    if !out_of_range {
+                        return Err(pager.request_page(page_idx));
+                    }
+                }
                 if !flags.contains(MMUFlags::WRITE) {
                     // read-only, just return zero frame
                     return Ok(CommitResult::Ref(kernel_hal::mem::ZERO_FRAME.paddr()));
@@ -540,7 +618,31 @@ impl VMObjectPagedInner {
                 // recursively find a frame in parent
                 let mut parent = self.parent.as_ref().unwrap().inner.borrow_mut();
                 let parent_idx = page_idx + self.parent_offset / PAGE_SIZE;
-                match parent.commit_page_internal(parent_idx, flags, &self.self_ref)? {
+                let result = if parent.type_.is_hidden() {
+                    parent.commit_page_internal(parent_idx, flags, &self.self_ref)?
+                } else {
+                    // the parent is pager-backed, whose pages are shared until written
+                    let paddr = match parent.commit_page_internal(
+                        parent_idx,
+                        MMUFlags::READ,
+                        &self.self_ref,
+                    )? {
+                        CommitResult::Ref(paddr) => paddr,
+                        _ => unreachable!(),
+                    };
+                    if !flags.contains(MMUFlags::WRITE) {
+                        return Ok(CommitResult::Ref(paddr));
+                    }
+                    let frame = PhysFrame::new().ok_or(ZxError::NO_MEMORY)?;
+                    kernel_hal::mem::pmem_copy(frame.paddr(), paddr, PAGE_SIZE);
+                    let result = CommitResult::CopyOnWrite(frame, true);
+                    if self.type_.is_hidden() {
+                        // pass the copy down, the other child keeps sharing the page of the parent
+                        return Ok(result);
+                    }
+                    result
+                };
+                match result {
                     CommitResult::NewPage(frame) if !self.type_.is_hidden() => {
                         self.frames.insert(page_idx, PageState::new(frame));
                     }
@@ -611,6 +713,11 @@ impl VMObjectPagedInner {
             return Ok(CommitResult::CopyOnWrite(target_frame, true));
         }
         // otherwise already committed
+        if flags.contains(MMUFlags::WRITE) {
+            if let Some(pager) = &self.pager {
+                pager.page_written(page_idx);
+            }
+        }
         Ok(CommitResult::Ref(frame.frame.paddr()))
     }
 
@@ -722,19 +829,44 @@ impl VMObjectPagedInner {
         child.parent_offset += self.parent_offset;
         child.parent_limit += self.parent_offset;
         if let Some(parent) = &self.parent {
-            parent.inner.borrow_mut().replace_child(
-                &self.self_ref,
-                self.owner,
-                other_child,
-                Some((child.parent_offset, child.parent_limit)),
-            );
+            let mut parent = parent.inner.borrow_mut();
+            // a pager-backed parent does not track its children
+            if parent.type_.is_hidden() {
+                parent.replace_child(
+                    &self.self_ref,
+                    self.owner,
+                    other_child,
+                    Some((child.parent_offset, child.parent_limit)),
+                );
+            }
         }
         child.parent = self.parent.take();
     }
 
-    /// Create a snapshot child VMO.
+    /// Whether the VMO is pager-backed or a clone of a pager-backed VMO.
+    fn is_pager_backed(&self) -> bool {
+        if self.pager.is_some() {
+            return true;
+        }
+        let mut current = self.parent.clone();
+        while let Some(vmop) = current {
+            let inner = vmop.inner.borrow();
+            if inner.pager.is_some() {
+                return true;
+            }
+            current = inner.parent.clone();
+        }
+        false
+    }
+
+    /// Create a copy-on-write child VMO.
+    ///
+    /// A child of a pager-backed VMO refers to it directly, and copies its pages
+    /// on write. Otherwise the pages are moved to a hidden node shared by both VMOs,
+    /// which makes a point-in-time snapshot of the chain of ancestors.
     fn create_child(
         &mut self,
+        type_: CloneType,
         offset: usize,
         len: usize,
         lock_ref: &Arc<Mutex<()>>,
@@ -744,9 +876,14 @@ impl VMObjectPagedInner {
         if self.is_contiguous() {
             return Err(ZxError::INVALID_ARGS);
         }
+        // pages supplied by the pager may change under a snapshot
+        if type_ == CloneType::Snapshot && self.is_pager_backed() {
+            return Err(ZxError::NOT_SUPPORTED);
+        }
         if self.cache_policy != CachePolicy::Cached || self.pin_count != 0 {
             return Err(ZxError::BAD_STATE);
         }
+        let pager_backed = self.pager.is_some();
         // create child VMO
         let child = VMObjectPaged::wrap(
             VMObjectPagedInner {
@@ -762,9 +899,14 @@ impl VMObjectPagedInner {
                 contiguous: false,
                 self_ref: Default::default(),
                 pin_count: 0,
+                pager: None,
             },
             Some(lock_ref.clone()),
         );
+        if pager_backed {
+            child.inner.borrow_mut().parent = Some(self.self_ref.upgrade().unwrap());
+            return Ok(child);
+        }
         // construct a hidden VMO as shared parent
         let hidden = VMObjectPaged::wrap(
             VMObjectPagedInner {
@@ -783,6 +925,7 @@ impl VMObjectPagedInner {
                 contiguous: self.contiguous,
                 self_ref: Default::default(),
                 pin_count: self.pin_count,
+                pager: None,
             },
             Some(lock_ref.clone()),
         );
@@ -909,6 +1052,9 @@ impl VMObjectPagedInner {
         if self.is_contiguous() {
             info.flags |= VmoInfoFlags::CONTIGUOUS;
         }
+        if self.pager.is_some() {
+            info.flags |= VmoInfoFlags::PAGER_BACKED;
+        }
         // info.num_children = if self.type_.is_hidden() { 2 } else { 0 };
         info.num_mappings = self.mappings.len() as u64; // FIXME remove weak ptr
         info.share_count = self.mappings.len() as u64; // FIXME share_count should be the count of unique aspace
@@ -921,6 +1067,10 @@ impl VMObjectPagedInner {
         let mut child = self.self_ref.clone();
         while let Some(parent) = option_parent {
             let mut parent_inner = parent.inner.borrow_mut();
+            // pages of a pager-backed parent are never owned by its children
+            if !parent_inner.type_.is_hidden() {
+                break;
+            }
             let (tag, other) = parent_inner.type_.get_tag_and_other(&child);
             let arc_other = other.upgrade().unwrap();
             let mut other_inner = arc_other.inner.borrow_mut();
@@ -1112,6 +1262,76 @@ mod tests {
         assert_eq!(vmo2.get_info().committed_bytes as usize, PAGE_SIZE);
     }
 
+    #[test]
+    fn snapshot_chain() {
+        let vmo0 = VmObject::new_paged(2);
+        vmo0.test_write(0, 1);
+        let vmo1 = vmo0
+            .create_clone(CloneType::Snapshot, false, 0, 2 * PAGE_SIZE)
+            .unwrap();
+        vmo1.test_write(1, 2);
+        let vmo2 = vmo1
+            .create_clone(CloneType::Snapshot, false, 0, 2 * PAGE_SIZE)
+            .unwrap();
+        let vmo3 = vmo2
+            .create_clone(CloneType::Snapshot, false, 0, 2 * PAGE_SIZE)
+            .unwrap();
+
+        // changes after a snapshot are not seen by it at any depth
+        vmo0.test_write(0, 3);
+        vmo1.test_write(0, 4);
+        vmo2.test_write(1, 5);
+        let vmos = [&vmo0, &vmo1, &vmo2, &vmo3];
+        let page0: Vec<u8> = vmos.iter().map(|vmo| vmo.test_read(0)).collect();
+        let page1: Vec<u8> = vmos.iter().map(|vmo| vmo.test_read(1)).collect();
+        assert_eq!(page0, [3, 4, 1, 1]);
+        assert_eq!(page1, [0, 2, 5, 2]);
+
+        // closing the nodes in the middle keeps the pages of the snapshot
+        drop(vmo1);
+        drop(vmo2);
+        assert_eq!(vmo3.test_read(0), 1);
+        assert_eq!(vmo3.test_read(1), 2);
+        vmo3.test_write(0, 6);
+        assert_eq!(vmo0.test_read(0), 3);
+        assert_eq!(vmo3.test_read(0), 6);
+    }
+
+    #[test]
+    fn snapshot_range() {
+        let vmo = VmObject::new_paged(2);
+        vmo.test_write(0, 1);
+        vmo.test_write(1, 2);
+        let child = vmo
+            .create_clone(CloneType::Snapshot, false, PAGE_SIZE, 2 * PAGE_SIZE)
+            .unwrap();
+        // pages beyond the parent are zero
+        assert_eq!(child.test_read(0), 2);
+        assert_eq!(child.test_read(1), 0);
+        child.test_write(1, 3);
+        vmo.test_write(1, 4);
+        assert_eq!(child.test_read(0), 2);
+        assert_eq!(child.test_read(1), 3);
+        // pages out of the range of the child are not copied
+        vmo.test_write(0, 5);
+        assert_eq!(vmo.test_read(0), 5);
+        assert_eq!(vmo.get_info().committed_bytes as usize, 2 * PAGE_SIZE);
+    }
+
+    #[test]
+    fn snapshot_resize() {
+        let vmo = VmObject::new_paged_with_resizable(true, 2);
+        vmo.test_write(1, 1);
+        let child = vmo
+            .create_clone(CloneType::Snapshot, false, 0, 2 * PAGE_SIZE)
+            .unwrap();
+        // shrinking the parent hands its pages over to the child
+        vmo.set_len(PAGE_SIZE).unwrap();
+        vmo.set_len(2 * PAGE_SIZE).unwrap();
+        assert_eq!(vmo.test_read(1), 0);
+        assert_eq!(child.test_read(1), 1);
+    }
+
     impl VmObject {
         pub fn test_write(&self, page: usize, value: u8) {
             self.write(page * PAGE_SIZE, &[value]).unwrap();
@@ -65,13 +65,13 @@ impl VMObjectTrait for VMObjectPhysical {
         unimplemented!()
     }
 
-    fn commit_page(&self, page_idx: usize, _flags: MMUFlags) -> ZxResult<PhysAddr> {
+    fn commit_page(&self, page_idx: usize, _flags: MMUFlags) -> PagedResult<PhysAddr> {
         Ok(self.paddr + page_idx * PAGE_SIZE)
     }
 
     fn commit_pages_with(
         &self,
-        f: &mut dyn FnMut(&mut dyn FnMut(usize, MMUFlags) -> ZxResult<PhysAddr>) -> ZxResult,
+        f: &mut dyn FnMut(&mut dyn FnMut(usize, MMUFlags) -> PagedResult<PhysAddr>) -> ZxResult,
     ) -> ZxResult {
         f(&mut |page_idx, _flags| Ok(self.paddr + page_idx * PAGE_SIZE))
     }
@@ -86,7 +86,12 @@ impl VMObjectTrait for VMObjectPhysical {
         Ok(())
     }
 
-    fn create_child(&self, _offset: usize, _len: usize) -> ZxResult<Arc<dyn VMObjectTrait>> {
+    fn create_child(
+        &self,
+        _type: CloneType,
+        _offset: usize,
+        _len: usize,
+    ) -> ZxResult<Arc<dyn VMObjectTrait>> {
         Err(ZxError::NOT_SUPPORTED)
     }
 
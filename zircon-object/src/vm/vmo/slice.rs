@@ -50,14 +50,14 @@ impl VMObjectTrait for VMObjectSlice {
         unimplemented!()
     }
 
-    fn commit_page(&self, page_idx: usize, flags: MMUFlags) -> ZxResult<usize> {
+    fn commit_page(&self, page_idx: usize, flags: MMUFlags) -> PagedResult<PhysAddr> {
         self.parent
             .commit_page(page_idx + self.offset / PAGE_SIZE, flags)
     }
 
     fn commit_pages_with(
         &self,
-        f: &mut dyn FnMut(&mut dyn FnMut(usize, MMUFlags) -> ZxResult<PhysAddr>) -> ZxResult,
+        f: &mut dyn FnMut(&mut dyn FnMut(usize, MMUFlags) -> PagedResult<PhysAddr>) -> ZxResult,
     ) -> ZxResult {
         self.parent.commit_pages_with(f)
     }
@@ -70,7 +70,12 @@ impl VMObjectTrait for VMObjectSlice {
         self.parent.decommit(offset + self.offset, len)
     }
 
-    fn create_child(&self, _offset: usize, _len: usize) -> ZxResult<Arc<dyn VMObjectTrait>> {
+    fn create_child(
+        &self,
+        _type: CloneType,
+        _offset: usize,
+        _len: usize,
+    ) -> ZxResult<Arc<dyn VMObjectTrait>> {
         Err(ZxError::NOT_SUPPORTED)
     }
 
@@ -36,8 +36,10 @@ mod handle;
 #[cfg(feature = "hypervisor")]
 mod hypervisor;
 mod object;
+mod pager;
 mod pci;
 mod port;
+mod profile;
 mod resource;
 mod signal;
 mod socket;
@@ -216,12 +218,19 @@ impl Syscall<'_> {
             }
             Sys::FUTEX_WAKE_SINGLE_OWNER => self.sys_futex_wake_single_owner(a0.into()),
             Sys::VMO_CREATE => self.sys_vmo_create(a0 as _, a1 as _, a2.into()),
-            Sys::VMO_READ => self.sys_vmo_read(a0 as _, a1.into(), a2 as _, a3 as _),
-            Sys::VMO_WRITE => self.sys_vmo_write(a0 as _, a1.into(), a2 as _, a3 as _),
+            Sys::VMO_READ => {
+                self.sys_vmo_read(a0 as _, a1.into(), a2 as _, a3 as _)
+                    .await
+            }
+            Sys::VMO_WRITE => {
+                self.sys_vmo_write(a0 as _, a1.into(), a2 as _, a3 as _)
+                    .await
+            }
             Sys::VMO_GET_SIZE => self.sys_vmo_get_size(a0 as _, a1.into()),
             Sys::VMO_SET_SIZE => self.sys_vmo_set_size(a0 as _, a1 as _),
             Sys::VMO_OP_RANGE => {
                 self.sys_vmo_op_range(a0 as _, a1 as _, a2 as _, a3 as _, a4.into(), a5 as _)
+                    .await
             }
             Sys::VMO_REPLACE_AS_EXECUTABLE => {
                 self.sys_vmo_replace_as_executable(a0 as _, a1 as _, a2.into())
@@ -236,6 +245,17 @@ impl Syscall<'_> {
                 self.sys_vmo_create_contiguous(a0 as _, a1 as _, a2 as _, a3.into())
             }
             Sys::VMO_SET_CACHE_POLICY => self.sys_vmo_cache_policy(a0 as _, a1 as _),
+            Sys::PAGER_CREATE => self.sys_pager_create(a0 as _, a1.into()),
+            Sys::PAGER_CREATE_VMO => {
+                self.sys_pager_create_vmo(a0 as _, a1 as _, a2 as _, a3 as _, a4 as _, a5.into())
+            }
+            Sys::PAGER_DETACH_VMO => self.sys_pager_detach_vmo(a0 as _, a1 as _),
+            Sys::PAGER_SUPPLY_PAGES => {
+                self.sys_pager_supply_pages(a0 as _, a1 as _, a2 as _, a3 as _, a4 as _, a5 as _)
+            }
+            Sys::PAGER_OP_RANGE => {
+                self.sys_pager_op_range(a0 as _, a1 as _, a2 as _, a3 as _, a4 as _, a5 as _)
+            }
             Sys::VMAR_MAP => self.sys_vmar_map(
                 a0 as _,
                 a1 as _,
@@ -250,6 +270,9 @@ impl Syscall<'_> {
                 self.sys_vmar_allocate(a0 as _, a1 as _, a2 as _, a3 as _, a4.into(), a5.into())
             }
             Sys::VMAR_PROTECT => self.sys_vmar_protect(a0 as _, a1 as _, a2 as _, a3 as _),
+            Sys::VMAR_OP_RANGE => {
+                self.sys_vmar_op_range(a0 as _, a1 as _, a2 as _, a3 as _, a4.into(), a5 as _)
+            }
             Sys::VMAR_DESTROY => self.sys_vmar_destroy(a0 as _),
             Sys::CPRNG_DRAW_ONCE => self.sys_cprng_draw_once(a0.into(), a1 as _),
             Sys::NANOSLEEP => self.sys_nanosleep(a0.into()).await,
@@ -316,6 +339,8 @@ impl Syscall<'_> {
             Sys::OBJECT_GET_CHILD => {
                 self.sys_object_get_child(a0 as _, a1 as _, a2 as _, a3.into())
             }
+            Sys::OBJECT_SET_PROFILE => self.sys_object_set_profile(a0 as _, a1 as _, a2 as _),
+            Sys::PROFILE_CREATE => self.sys_profile_create(a0 as _, a1 as _, a2.into(), a3.into()),
             Sys::PC_FIRMWARE_TABLES => self.sys_pc_firmware_tables(a0 as _, a1.into(), a2.into()),
             Sys::PCI_ADD_SUBTRACT_IO_RANGE => {
                 self.sys_pci_add_subtract_io_range(a0 as _, a1 != 0, a2 as _, a3 as _, a4 != 0)
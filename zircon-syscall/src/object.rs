@@ -495,6 +495,27 @@ impl Syscall<'_> {
         out.write(child_handle)?;
         Ok(())
     }
+
+    /// Apply a scheduling profile to a thread.
+    pub fn sys_object_set_profile(
+        &self,
+        handle: HandleValue,
+        profile: HandleValue,
+        options: u32,
+    ) -> ZxResult {
+        info!(
+            "object.set_profile: handle={:#x}, profile={:#x}, options={:#x}",
+            handle, profile, options
+        );
+        if options != 0 {
+            return Err(ZxError::INVALID_ARGS);
+        }
+        let proc = self.thread.proc();
+        let thread = proc.get_object_with_rights::<Thread>(handle, Rights::MANAGE_THREAD)?;
+        let profile = proc.get_object_with_rights::<Profile>(profile, Rights::APPLY_PROFILE)?;
+        thread.set_profile(&profile);
+        Ok(())
+    }
 }
 
 numeric_enum! {
@@ -0,0 +1,148 @@
+use {
+    super::*,
+    zircon_object::{signal::Port, task::ThreadState, vm::*},
+};
+
+impl Syscall<'_> {
+    /// Create a new pager object.
+    pub fn sys_pager_create(&self, options: u32, mut out: UserOutPtr<HandleValue>) -> ZxResult {
+        info!("pager.create: options={:#x}", options);
+        if options != 0 {
+            return Err(ZxError::INVALID_ARGS);
+        }
+        let handle = Handle::new(Pager::new(), Rights::DEFAULT_PAGER);
+        out.write(self.thread.proc().add_handle(handle))?;
+        Ok(())
+    }
+
+    /// Create a pager owned VMO, whose page requests are sent to `port` with `key`.
+    pub fn sys_pager_create_vmo(
+        &self,
+        pager: HandleValue,
+        options: u32,
+        port: HandleValue,
+        key: u64,
+        size: u64,
+        mut out: UserOutPtr<HandleValue>,
+    ) -> ZxResult {
+        info!(
+            "pager.create_vmo: pager={:#x}, options={:#x}, port={:#x}, key={:#x}, size={:#x}",
+            pager, options, port, key, size
+        );
+        if options & !VMO_RESIZABLE != 0 {
+            return Err(ZxError::INVALID_ARGS);
+        }
+        let resizable = options & VMO_RESIZABLE != 0;
+        let size = size as usize;
+        if roundup_pages(size) < size {
+            return Err(ZxError::OUT_OF_RANGE);
+        }
+        let proc = self.thread.proc();
+        let pager = proc.get_object::<Pager>(pager)?;
+        let port = proc.get_object_with_rights::<Port>(port, Rights::WRITE)?;
+        let vmo = pager.create_vmo(pages(size), port, key, resizable);
+        out.write(proc.add_handle(Handle::new(vmo, Rights::DEFAULT_VMO)))?;
+        Ok(())
+    }
+
+    /// Detach a VMO from its pager.
+    pub fn sys_pager_detach_vmo(&self, pager: HandleValue, vmo: HandleValue) -> ZxResult {
+        info!("pager.detach_vmo: pager={:#x}, vmo={:#x}", pager, vmo);
+        let proc = self.thread.proc();
+        let pager = proc.get_object::<Pager>(pager)?;
+        let vmo = proc.get_object::<VmObject>(vmo)?;
+        pager.detach_vmo(&vmo)
+    }
+
+    /// Supply pages into a pager owned VMO, moving them from an auxiliary VMO.
+    pub fn sys_pager_supply_pages(
+        &self,
+        pager: HandleValue,
+        pager_vmo: HandleValue,
+        offset: usize,
+        length: usize,
+        aux_vmo: HandleValue,
+        aux_offset: usize,
+    ) -> ZxResult {
+        info!(
+            "pager.supply_pages: pager={:#x}, pager_vmo={:#x}, offset={:#x}, length={:#x}, aux_vmo={:#x}, aux_offset={:#x}",
+            pager, pager_vmo, offset, length, aux_vmo, aux_offset
+        );
+        let proc = self.thread.proc();
+        let pager = proc.get_object::<Pager>(pager)?;
+        let pager_vmo = proc.get_object::<VmObject>(pager_vmo)?;
+        let aux_vmo =
+            proc.get_object_with_rights::<VmObject>(aux_vmo, Rights::READ | Rights::WRITE)?;
+        if Arc::ptr_eq(&pager_vmo, &aux_vmo) {
+            return Err(ZxError::INVALID_ARGS);
+        }
+        pager.supply_pages(&pager_vmo, offset, length, &aux_vmo, aux_offset)
+    }
+
+    /// Perform an operation on a range of a pager owned VMO.
+    pub fn sys_pager_op_range(
+        &self,
+        pager: HandleValue,
+        op: u32,
+        pager_vmo: HandleValue,
+        offset: usize,
+        length: usize,
+        data: u64,
+    ) -> ZxResult {
+        info!(
+            "pager.op_range: pager={:#x}, op={:#x}, pager_vmo={:#x}, offset={:#x}, length={:#x}, data={:#x}",
+            pager, op, pager_vmo, offset, length, data
+        );
+        let op = match op {
+            PAGER_OP_FAIL => {
+                let err = [
+                    ZxError::IO,
+                    ZxError::IO_DATA_INTEGRITY,
+                    ZxError::BAD_STATE,
+                    ZxError::NO_SPACE,
+                    ZxError::BUFFER_TOO_SMALL,
+                ]
+                .iter()
+                .find(|&&err| err as i64 == data as i64)
+                .ok_or(ZxError::INVALID_ARGS)?;
+                PagerOp::Fail(*err)
+            }
+            PAGER_OP_DIRTY => PagerOp::Dirty,
+            PAGER_OP_WRITEBACK_BEGIN => PagerOp::WritebackBegin,
+            PAGER_OP_WRITEBACK_END => PagerOp::WritebackEnd,
+            _ => return Err(ZxError::NOT_SUPPORTED),
+        };
+        let proc = self.thread.proc();
+        let pager = proc.get_object::<Pager>(pager)?;
+        let pager_vmo = proc.get_object::<VmObject>(pager_vmo)?;
+        pager.op_range(&pager_vmo, op, offset, length)
+    }
+
+    /// Retry `f` on a VMO until the pages it requests are supplied by the pager,
+    /// blocking the thread meanwhile.
+    pub(crate) async fn wait_for_pages<T>(
+        &self,
+        mut f: impl FnMut() -> PagedResult<T> + Unpin,
+    ) -> ZxResult<T> {
+        match f() {
+            Err(PagedError::Wait(_)) => {
+                self.thread
+                    .blocking_run(
+                        wait_for_pages(f),
+                        ThreadState::BlockedPager,
+                        Deadline::forever().into(),
+                        None,
+                    )
+                    .await
+            }
+            ret => Ok(ret?),
+        }
+    }
+}
+
+const VMO_RESIZABLE: u32 = 1 << 1;
+
+const PAGER_OP_FAIL: u32 = 1;
+const PAGER_OP_DIRTY: u32 = 2;
+const PAGER_OP_WRITEBACK_BEGIN: u32 = 3;
+const PAGER_OP_WRITEBACK_END: u32 = 4;
@@ -0,0 +1,34 @@
+use {
+    super::*,
+    zircon_object::task::{Job, PolicyCondition, Profile, ProfileInfo},
+};
+
+impl Syscall<'_> {
+    /// Create a scheduler profile, which can only be done with the root job.
+    pub fn sys_profile_create(
+        &self,
+        root_job: HandleValue,
+        options: u32,
+        info: UserInPtr<ProfileInfo>,
+        mut out: UserOutPtr<HandleValue>,
+    ) -> ZxResult {
+        info!(
+            "profile.create: root_job={:#x}, options={:#x}",
+            root_job, options
+        );
+        let proc = self.thread.proc();
+        proc.check_policy(PolicyCondition::NewProfile)?;
+        if options != 0 {
+            return Err(ZxError::INVALID_ARGS);
+        }
+        let job = proc.get_object_with_rights::<Job>(root_job, Rights::MANAGE_PROCESS)?;
+        if job.parent().is_some() {
+            return Err(ZxError::ACCESS_DENIED);
+        }
+        let info = info.read()?;
+        debug!("profile.create: {:x?}", info);
+        let profile = Profile::new(&info)?;
+        out.write(proc.add_handle(Handle::new(profile, Rights::DEFAULT_PROFILE)))?;
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use {super::*, bitflags::bitflags, zircon_object::vm::*};
+use {super::*, bitflags::bitflags, numeric_enum_macro::numeric_enum, zircon_object::vm::*};
 
 fn amount_of_alignments(options: u32) -> ZxResult<usize> {
     let mut align_pow2 = (options >> 24) as usize;
@@ -42,6 +42,7 @@ impl Syscall<'_> {
         let vmar_flags = vm_options.to_flags();
         if vmar_flags.intersects(
             !(VmarFlags::SPECIFIC
+                | VmarFlags::SPECIFIC_OVERWRITE
                 | VmarFlags::CAN_MAP_SPECIFIC
                 | VmarFlags::COMPACT
                 | VmarFlags::CAN_MAP_RXW),
@@ -53,10 +54,8 @@ impl Syscall<'_> {
         let align = amount_of_alignments(options)?;
 
         // get offest with options
-        let offset = if vm_options.contains(VmOptions::SPECIFIC) {
+        let offset = if vm_options.intersects(VmOptions::SPECIFIC | VmOptions::SPECIFIC_OVERWRITE) {
             Some(offset as usize)
-        } else if vm_options.contains(VmOptions::SPECIFIC_OVERWRITE) {
-            unimplemented!()
         } else {
             if offset != 0 {
                 return Err(ZxError::INVALID_ARGS);
@@ -139,7 +138,7 @@ impl Syscall<'_> {
             "mmuflags: {:?}, is_specific {:?}, overwrite {:?}, map_range {:?}",
             mapping_flags, is_specific, overwrite, map_range
         );
-        if map_range && overwrite {
+        if options.contains(VmOptions::MAP_RANGE) && overwrite {
             return Err(ZxError::INVALID_ARGS);
         }
         // Note: we should reject non-page-aligned length here,
@@ -221,6 +220,45 @@ impl Syscall<'_> {
         vmar.unmap(addr, pages(len) * PAGE_SIZE)?;
         Ok(())
     }
+
+    /// Perform an operation on VMOs mapped into this VMAR.
+    pub fn sys_vmar_op_range(
+        &self,
+        handle_value: HandleValue,
+        op: u32,
+        addr: usize,
+        len: usize,
+        _buffer: UserInOutPtr<u8>,
+        _buffer_size: usize,
+    ) -> ZxResult {
+        info!(
+            "vmar.op_range: handle={:#x}, op={:#x}, addr={:#x}, len={:#x}",
+            handle_value, op, addr, len
+        );
+        let op = VmarOpType::try_from(op).or(Err(ZxError::INVALID_ARGS))?;
+        let (op, rights) = match op {
+            VmarOpType::Commit => (VmarOp::Commit, Rights::WRITE),
+            VmarOpType::Decommit => (VmarOp::Decommit, Rights::WRITE),
+            VmarOpType::MapRange => (VmarOp::MapRange, Rights::READ),
+            VmarOpType::AlwaysNeed => (VmarOp::AlwaysNeed, Rights::READ),
+            VmarOpType::DontNeed => (VmarOp::DontNeed, Rights::empty()),
+        };
+        let proc = self.thread.proc();
+        let vmar = proc.get_object_with_rights::<VmAddressRegion>(handle_value, rights)?;
+        vmar.op_range(op, addr, roundup_pages(len))
+    }
+}
+
+numeric_enum! {
+    #[repr(u32)]
+    /// VMAR Opcodes (for vmar_op_range)
+    pub enum VmarOpType {
+        Commit = 1,
+        Decommit = 2,
+        MapRange = 3,
+        AlwaysNeed = 11,
+        DontNeed = 12,
+    }
 }
 
 bitflags! {
@@ -30,7 +30,7 @@ impl Syscall<'_> {
     }
 
     /// Read bytes from a VMO.
-    pub fn sys_vmo_read(
+    pub async fn sys_vmo_read(
         &self,
         handle_value: HandleValue,
         mut buf: UserOutPtr<u8>,
@@ -49,13 +49,18 @@ impl Syscall<'_> {
         }
         // TODO: optimize
         let mut buffer = vec![0u8; buf_size];
-        vmo.read(offset as usize, &mut buffer)?;
+        let offset = offset as usize;
+        self.wait_for_pages(|| {
+            vmo.commit_range(offset, buf_size, MMUFlags::READ)?;
+            Ok(vmo.read(offset, &mut buffer)?)
+        })
+        .await?;
         buf.write_array(&buffer)?;
         Ok(())
     }
 
     /// Write bytes to a VMO.
-    pub fn sys_vmo_write(
+    pub async fn sys_vmo_write(
         &self,
         handle_value: HandleValue,
         buf: UserInPtr<u8>,
@@ -71,7 +76,13 @@ impl Syscall<'_> {
         if offset as usize > vmo.len() || buf_size > vmo.len() - (offset as usize) {
             return Err(ZxError::OUT_OF_RANGE);
         }
-        vmo.write(offset as usize, buf.as_slice(buf_size)?)
+        let buf = buf.as_slice(buf_size)?;
+        let offset = offset as usize;
+        self.wait_for_pages(|| {
+            vmo.commit_range(offset, buf_size, MMUFlags::WRITE)?;
+            Ok(vmo.write(offset, buf)?)
+        })
+        .await
     }
 
     /// Add execute rights to a VMO.
@@ -135,6 +146,9 @@ impl Syscall<'_> {
         if child_size < size {
             return Err(ZxError::OUT_OF_RANGE);
         }
+        if !page_aligned(offset) {
+            return Err(ZxError::INVALID_ARGS);
+        }
         info!("size of child vmo: {:#x}", child_size);
 
         let proc = self.thread.proc();
@@ -149,20 +163,21 @@ impl Syscall<'_> {
                 vmo.create_slice(offset, child_size)
             }
         } else {
-            // TODO: ZX_VMO_CHILD_SNAPSHOT
-            if !options.contains(VmoCloneFlags::SNAPSHOT_AT_LEAST_ON_WRITE) {
-                return Err(ZxError::NOT_SUPPORTED);
-            }
-            vmo.create_child(resizable, offset as usize, child_size)
+            // exactly one type of clone is given
+            let type_ = match options - VmoCloneFlags::RESIZABLE {
+                VmoCloneFlags::SNAPSHOT => CloneType::Snapshot,
+                VmoCloneFlags::SNAPSHOT_AT_LEAST_ON_WRITE => CloneType::SnapshotAtLeastOnWrite,
+                VmoCloneFlags::SNAPSHOT_MODIFIED => CloneType::SnapshotModified,
+                _ => return Err(ZxError::INVALID_ARGS),
+            };
+            vmo.create_clone(type_, resizable, offset, child_size)
         }?;
         // generate rights
         let mut child_rights = parent_rights;
         child_rights.insert(Rights::GET_PROPERTY | Rights::SET_PROPERTY);
         if no_write {
             child_rights.remove(Rights::WRITE);
-        } else if options.contains(VmoCloneFlags::SNAPSHOT)
-            || options.contains(VmoCloneFlags::SNAPSHOT_AT_LEAST_ON_WRITE)
-        {
+        } else if !options.contains(VmoCloneFlags::SLICE) {
             child_rights.remove(Rights::EXECUTE);
             child_rights.insert(Rights::WRITE);
         };
@@ -251,7 +266,8 @@ impl Syscall<'_> {
     /// Perform an operation on a range of a VMO.
     ///
     /// Performs cache and memory operations against pages held by the VMO.
-    pub fn sys_vmo_op_range(
+    /// Committing absent pages of a pager-backed VMO blocks until they are supplied.
+    pub async fn sys_vmo_op_range(
         &self,
         handle_value: HandleValue,
         op: u32,
@@ -267,33 +283,65 @@ impl Syscall<'_> {
         let op = VmoOpType::try_from(op).or(Err(ZxError::INVALID_ARGS))?;
         let proc = self.thread.proc();
         let (vmo, rights) = proc.get_object_and_rights::<VmObject>(handle_value)?;
-        match op {
-            VmoOpType::Commit => {
-                if !rights.contains(Rights::WRITE) {
+        let required_rights = match op {
+            VmoOpType::Commit
+            | VmoOpType::Decommit
+            | VmoOpType::Zero
+            | VmoOpType::CacheInvalidate => Rights::WRITE,
+            VmoOpType::CacheSync | VmoOpType::CacheClean | VmoOpType::CacheCleanInvalidate => {
+                Rights::READ
+            }
+            // either READ or WRITE is required
+            VmoOpType::Lock
+            | VmoOpType::TryLock
+            | VmoOpType::Unlock
+            | VmoOpType::AlwaysNeed
+            | VmoOpType::DontNeed => {
+                if !rights.intersects(Rights::READ | Rights::WRITE) {
                     return Err(ZxError::ACCESS_DENIED);
                 }
+                Rights::empty()
+            }
+        };
+        if !rights.contains(required_rights) {
+            return Err(ZxError::ACCESS_DENIED);
+        }
+        match offset.checked_add(len) {
+            Some(end) if end <= vmo.len() => {}
+            _ => return Err(ZxError::OUT_OF_RANGE),
+        }
+        match op {
+            VmoOpType::Commit => {
                 if !page_aligned(offset) || !page_aligned(len) {
                     return Err(ZxError::INVALID_ARGS);
                 }
-                vmo.commit(offset, len)?;
-                Ok(())
+                self.wait_for_pages(|| {
+                    vmo.commit_range(offset, len, MMUFlags::READ)?;
+                    Ok(vmo.commit(offset, len)?)
+                })
+                .await
             }
             VmoOpType::Decommit => {
-                if !rights.contains(Rights::WRITE) {
-                    return Err(ZxError::ACCESS_DENIED);
-                }
                 if !page_aligned(offset) || !page_aligned(len) {
                     return Err(ZxError::INVALID_ARGS);
                 }
                 vmo.decommit(offset, len)
             }
             VmoOpType::Zero => {
-                if !rights.contains(Rights::WRITE) {
-                    return Err(ZxError::ACCESS_DENIED);
-                }
-                vmo.zero(offset, len)
+                self.wait_for_pages(|| {
+                    vmo.commit_range(offset, len, MMUFlags::READ)?;
+                    Ok(vmo.zero(offset, len)?)
+                })
+                .await
             }
-            _ => unimplemented!(),
+            // only discardable VMOs can be locked, which are not supported
+            VmoOpType::Lock | VmoOpType::TryLock | VmoOpType::Unlock => Err(ZxError::NOT_SUPPORTED),
+            VmoOpType::CacheSync
+            | VmoOpType::CacheInvalidate
+            | VmoOpType::CacheClean
+            | VmoOpType::CacheCleanInvalidate => vmo.cache_flush(offset, len),
+            // hints of the access pattern
+            VmoOpType::AlwaysNeed | VmoOpType::DontNeed => Ok(()),
         }
     }
 
@@ -314,6 +362,7 @@ bitflags! {
         const SLICE                      = 1 << 3;
         const SNAPSHOT_AT_LEAST_ON_WRITE = 1 << 4;
         const NO_WRITE                   = 1 << 5;
+        const SNAPSHOT_MODIFIED          = 1 << 6;
     }
 }
 
@@ -325,10 +374,13 @@ numeric_enum! {
         Decommit = 2,
         Lock = 3,
         Unlock = 4,
+        TryLock = 5,
         CacheSync = 6,
         CacheInvalidate = 7,
         CacheClean = 8,
         CacheCleanInvalidate = 9,
         Zero = 10,
+        AlwaysNeed = 11,
+        DontNeed = 12,
     }
 }